@@ -0,0 +1,40 @@
+// Using Tron as a scripting layer from Rust: `cargo run --example embedding`
+use std::time::Duration;
+use tron_lang::{Interpreter, Sandbox, TronType};
+
+fn main() {
+    let mut tron = Interpreter::new();
+
+    tron.register_function("@greet", 1, |args| match args[0].as_str() {
+        Some(name) => TronType::string(format!("hello, {}", name)),
+        None => TronType::Null,
+    });
+    tron.register_native("@clamp", |x: f64, max: Option<f64>| {
+        x.min(max.unwrap_or(1000.0))
+    });
+    tron.set_global("retries", TronType::Number(3.0));
+
+    tron.run(
+        r#"
+        let name: string = "tron";
+        fn backoff(attempt: number): number = attempt * 250;
+        "#,
+    )
+    .expect("config script failed");
+
+    let greeting = tron.eval("@greet(name);").unwrap();
+    let delay = tron.eval("@clamp(backoff(retries), 600);").unwrap();
+    println!("{}", greeting.as_str().unwrap_or_default());
+    println!("{}ms", delay.as_number().unwrap_or_default());
+    println!("name = {:?}", tron.get_global("name"));
+
+    // code from users gets no `@cmd`, files, `@args` or `@ask`, and a budget
+    tron.set_sandbox(Sandbox {
+        max_steps: Some(10_000),
+        timeout: Some(Duration::from_millis(100)),
+        ..Sandbox::strict()
+    })
+    .expect("sandbox refused");
+    let doubled = tron.eval("retries * 2;").unwrap();
+    println!("sandboxed: {:?}", doubled.as_number());
+}
@@ -0,0 +1,934 @@
+// what editors show about a file without running it, for `tron lsp`: the errors scanning, parsing
+// and resolving it report, the names it declares, the types they are declared with, and where
+// each of them is used
+//
+// Names are bound the way the resolver scopes them. Statements only know their line, so how far a
+// block reaches is taken from the braces around it in the tokens.
+use crate::environment::Environment;
+use crate::expressions::{Arity, CallableImpl, Expression, TronType};
+use crate::library::standard_library;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::{get_keywords_hashmap, Parameter, Scanner, Span, Statement, Token, TokenType};
+use crate::utils::{collect_errors, TronError};
+use std::collections::{HashMap, HashSet};
+
+// the types a declaration can be checked against before the program runs
+pub const TYPES: [&str; 8] = [
+    "number", "string", "boolean", "array", "object", "function", "null", "error",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    // what may only go wrong at run time, like a name from a library that wasn't found
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub code: String,
+    pub message: String,
+    pub severity: Severity,
+    pub line: usize,
+    // the text it is about, when that is known more precisely than the line
+    pub span: Option<Span>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+    // from the standard library
+    Builtin,
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // the declared type of variables and parameters, the output type of functions
+    pub type_name: String,
+    // how it is declared, like `let x: number` or `fn add(a: number, b: number): number`
+    pub detail: String,
+    // its name where it is declared; empty for builtins
+    pub span: Span,
+    // from its name to the end of its block, where it can be used; `None` for globals and
+    // builtins, which can be used anywhere
+    pub scope: Option<Span>,
+    // a function's name up to the end of its body, any other symbol's name
+    pub extent: Span,
+    // the function it is declared in
+    pub parent: Option<usize>,
+    // the used file it is declared in, as an index into `Analysis::libraries`; `None` when it is
+    // declared in the analysed file
+    pub library: Option<usize>,
+    // how many arguments a function or builtin takes
+    pub arity: Option<Arity>,
+}
+
+// a file named by a `use` statement, whose globals the analysed file can use
+#[derive(Clone, Debug)]
+pub struct Library {
+    pub path: String,
+    pub source: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub symbols: Vec<Symbol>,
+    // every use of a symbol in the analysed file, by the symbol's index
+    pub references: Vec<(Span, usize)>,
+    pub libraries: Vec<Library>,
+}
+
+impl Analysis {
+    // `load` finds the files `use` statements name, returning the path they were found at and
+    // their source
+    pub fn new(source: &str, mut load: impl FnMut(&str) -> Option<(String, String)>) -> Self {
+        let mut analysis = Analysis::default();
+        let ((tokens, statements), errors) = collect_errors(|| {
+            let tokens = Scanner::new(source).scan_tokens().unwrap_or_default();
+            let statements = Parser::new(tokens.clone()).parse().unwrap_or_default();
+            let _ = Resolver::new().resolve(&statements.iter().collect());
+            (tokens, statements)
+        });
+        analysis
+            .diagnostics
+            .extend(errors.into_iter().map(|error| Diagnostic {
+                code: error.code,
+                message: error.message,
+                severity: Severity::Error,
+                line: error.line,
+                span: error.span,
+            }));
+        for builtin in builtins() {
+            analysis.symbols.push(builtin);
+        }
+        let missing = analysis.load_libraries(&statements, &mut load);
+
+        let mut binder = Binder::new(&mut analysis, &tokens, source.len(), None);
+        binder.hoist(&statements);
+        for statement in &statements {
+            binder.statement(statement);
+        }
+        let unresolved = std::mem::take(&mut binder.unresolved);
+        // a library that wasn't found could declare any name
+        if !missing {
+            for name in unresolved {
+                let mut diagnostic = error("E4011", name.line_number, vec![name.lexeme]);
+                diagnostic.severity = Severity::Warning;
+                diagnostic.span = Some(name.span);
+                analysis.diagnostics.push(diagnostic);
+            }
+        }
+        analysis
+    }
+
+    // declares the globals of the files `statements` use and those they use in turn; true when
+    // one of them wasn't found
+    fn load_libraries(
+        &mut self,
+        statements: &[Statement],
+        load: &mut impl FnMut(&str) -> Option<(String, String)>,
+    ) -> bool {
+        let mut missing = false;
+        let mut seen = HashSet::new();
+        let mut pending: Vec<(String, Option<usize>)> = uses(statements)
+            .into_iter()
+            .map(|(path, line)| (path, Some(line)))
+            .collect();
+        while let Some((path, line)) = pending.pop() {
+            let Some((found, source)) = load(&path) else {
+                missing = true;
+                if let Some(line) = line {
+                    let mut diagnostic = error("E4005", line, vec![path]);
+                    diagnostic.severity = Severity::Warning;
+                    self.diagnostics.push(diagnostic);
+                }
+                continue;
+            };
+            if !seen.insert(found.clone()) {
+                continue;
+            }
+            let ((tokens, statements), _) = collect_errors(|| {
+                let tokens = Scanner::new(&source).scan_tokens().unwrap_or_default();
+                let statements = Parser::new(tokens.clone()).parse().unwrap_or_default();
+                (tokens, statements)
+            });
+            let library = self.libraries.len();
+            self.libraries.push(Library {
+                path: found,
+                source: source.clone(),
+            });
+            Binder::new(self, &tokens, source.len(), Some(library)).hoist(&statements);
+            pending.extend(uses(&statements).into_iter().map(|(path, _)| (path, None)));
+        }
+        missing
+    }
+
+    // the symbol declared or used at `offset`, counting the end of a name as on it
+    pub fn symbol_at(&self, offset: usize) -> Option<usize> {
+        let on = |span: &Span| span.start <= offset && offset <= span.end && span.start < span.end;
+        self.references
+            .iter()
+            .find(|(span, _)| on(span))
+            .map(|(_, symbol)| *symbol)
+            .or_else(|| {
+                self.symbols
+                    .iter()
+                    .position(|symbol| symbol.library.is_none() && on(&symbol.span))
+            })
+    }
+
+    // where `symbol` is used in the analysed file
+    pub fn references_to(&self, symbol: usize) -> impl Iterator<Item = Span> + '_ {
+        self.references
+            .iter()
+            .filter(move |(_, index)| *index == symbol)
+            .map(|(span, _)| *span)
+    }
+
+    // the symbols that can be used at `offset`, one for each name: the innermost local, then the
+    // globals of the file, then those of its libraries, then the builtins
+    pub fn visible_at(&self, offset: usize) -> Vec<&Symbol> {
+        let mut visible: Vec<(usize, usize, &Symbol)> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| match (symbol.scope, symbol.library, symbol.kind) {
+                (Some(scope), _, _) if scope.start <= offset && offset <= scope.end => {
+                    Some((0, usize::MAX - scope.start, symbol))
+                }
+                (Some(_), _, _) => None,
+                (None, None, SymbolKind::Builtin) => Some((3, 0, symbol)),
+                (None, None, _) => Some((1, 0, symbol)),
+                (None, Some(_), _) => Some((2, 0, symbol)),
+            })
+            .collect();
+        visible.sort_by_key(|(tier, inner, _)| (*tier, *inner));
+        let mut names = HashSet::new();
+        visible
+            .into_iter()
+            .filter(|(_, _, symbol)| names.insert(symbol.name.clone()))
+            .map(|(_, _, symbol)| symbol)
+            .collect()
+    }
+}
+
+// the keywords of the language, sorted
+pub fn keywords() -> Vec<&'static str> {
+    let mut keywords: Vec<&str> = get_keywords_hashmap().into_keys().collect();
+    keywords.sort();
+    keywords
+}
+
+// the functions every program starts with, sorted by name
+pub fn builtins() -> Vec<Symbol> {
+    let mut environment = Environment::new(HashMap::new());
+    standard_library(&mut environment);
+    let mut builtins: Vec<Symbol> = environment
+        .values
+        .borrow()
+        .iter()
+        .filter_map(|(name, value)| match value {
+            TronType::Callable(CallableImpl::StdFunction(function)) => Some(Symbol {
+                name: name.clone(),
+                kind: SymbolKind::Builtin,
+                type_name: "function".to_string(),
+                detail: format!("`{}` is a builtin that {}", name, takes(function.arity)),
+                span: Span::default(),
+                scope: None,
+                extent: Span::default(),
+                parent: None,
+                library: None,
+                arity: Some(function.arity),
+            }),
+            _ => None,
+        })
+        .collect();
+    builtins.sort_by(|a, b| a.name.cmp(&b.name));
+    builtins
+}
+
+fn takes(arity: Arity) -> String {
+    let arguments = |count: usize| match count {
+        1 => "1 argument".to_string(),
+        count => format!("{} arguments", count),
+    };
+    match (arity.min, arity.max) {
+        (0, Some(0)) => "takes no arguments".to_string(),
+        (min, Some(max)) if min == max => format!("takes {}", arguments(min)),
+        (min, Some(max)) => format!("takes {} to {}", min, arguments(max)),
+        (0, None) => "takes any number of arguments".to_string(),
+        (min, None) => format!("takes at least {}", arguments(min)),
+    }
+}
+
+// a diagnostic worded as the error the program would stop with
+fn error(code: &str, line: usize, args: Vec<String>) -> Diagnostic {
+    let (_, errors) = collect_errors(|| TronError::throw(code, line, args));
+    let message = errors
+        .into_iter()
+        .next()
+        .map(|error| error.message)
+        .unwrap_or_default();
+    Diagnostic {
+        code: code.to_string(),
+        message,
+        severity: Severity::Error,
+        line,
+        span: None,
+    }
+}
+
+// the files `statements` use by a string literal, with the line of the `use`
+fn uses(statements: &[Statement]) -> Vec<(String, usize)> {
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::UseStatement {
+                expression:
+                    Expression::Literal {
+                        value: TronType::StringValue(path),
+                        ..
+                    },
+                line,
+            } => Some((path.to_string(), *line)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn signature(name: &str, params: &[Parameter], output_type: &Token) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| match param.rest {
+            true => format!("...{}: {}", param.name.lexeme, param.type_token.lexeme),
+            false => format!("{}: {}", param.name.lexeme, param.type_token.lexeme),
+        })
+        .collect();
+    format!("fn {}({}): {}", name, params.join(", "), output_type.lexeme)
+}
+
+// binds the names of one file to their declarations, adding both to an `Analysis`
+struct Binder<'a> {
+    analysis: &'a mut Analysis,
+    tokens: &'a [Token],
+    source_length: usize,
+    library: Option<usize>,
+    // every pair of braces, from the opening one to the closing one or the end of the file
+    braces: Vec<Span>,
+    // the locals of each scope the walk is in, innermost last
+    scopes: Vec<HashMap<String, usize>>,
+    // the globals declared so far
+    globals: HashMap<String, usize>,
+    // the first declaration of each global, for functions using globals declared after them
+    hoisted: HashMap<String, usize>,
+    // the function declaration the walk is in
+    function: Option<usize>,
+    // the output type of the function, or function expression, the walk is in
+    output_type: Option<String>,
+    // names used but declared nowhere
+    unresolved: Vec<Token>,
+}
+
+impl<'a> Binder<'a> {
+    fn new(
+        analysis: &'a mut Analysis,
+        tokens: &'a [Token],
+        source_length: usize,
+        library: Option<usize>,
+    ) -> Self {
+        let mut braces = vec![];
+        let mut open = vec![];
+        for token in tokens {
+            match token.token_type {
+                TokenType::LeftBrace => open.push(token.span.start),
+                TokenType::RightBrace => {
+                    if let Some(start) = open.pop() {
+                        braces.push(Span {
+                            start,
+                            end: token.span.end,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        braces.extend(open.into_iter().map(|start| Span {
+            start,
+            end: source_length,
+        }));
+        Binder {
+            analysis,
+            tokens,
+            source_length,
+            library,
+            braces,
+            scopes: vec![],
+            globals: HashMap::new(),
+            hoisted: HashMap::new(),
+            function: None,
+            output_type: None,
+            unresolved: vec![],
+        }
+    }
+
+    // declares the globals of a file before walking it, as functions can use globals declared
+    // after them; top level `case` bodies declare globals too
+    fn hoist(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::VariableStatement {
+                    name, value_type, ..
+                } => {
+                    self.global(name, SymbolKind::Variable, &value_type.lexeme, name.span);
+                }
+                Statement::FunctionStatement {
+                    name,
+                    params,
+                    output_type,
+                    ..
+                } => {
+                    let end = self.body_end(self.paren_after(name.span.end));
+                    let symbol = self.global(
+                        name,
+                        SymbolKind::Function,
+                        &output_type.lexeme,
+                        Span {
+                            start: name.span.start,
+                            end,
+                        },
+                    );
+                    self.analysis.symbols[symbol].detail =
+                        signature(&name.lexeme, params, output_type);
+                    self.analysis.symbols[symbol].arity = Some(Arity::of(params));
+                }
+                Statement::SwitchStatement {
+                    case_branches,
+                    default_branch,
+                    ..
+                } => {
+                    for (_, body) in case_branches {
+                        self.hoist(body);
+                    }
+                    if let Some(body) = default_branch {
+                        self.hoist(body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn global(&mut self, name: &Token, kind: SymbolKind, type_name: &str, extent: Span) -> usize {
+        let symbol = self.symbol(name, kind, type_name, None, extent);
+        self.hoisted.entry(name.lexeme.clone()).or_insert(symbol);
+        symbol
+    }
+
+    fn symbol(
+        &mut self,
+        name: &Token,
+        kind: SymbolKind,
+        type_name: &str,
+        scope: Option<Span>,
+        extent: Span,
+    ) -> usize {
+        let detail = match kind {
+            SymbolKind::Variable => format!("let {}: {}", name.lexeme, type_name),
+            _ => format!("{}: {}", name.lexeme, type_name),
+        };
+        self.analysis.symbols.push(Symbol {
+            name: name.lexeme.clone(),
+            kind,
+            type_name: type_name.to_string(),
+            detail,
+            span: name.span,
+            scope,
+            extent,
+            parent: self.function,
+            library: self.library,
+            arity: None,
+        });
+        self.analysis.symbols.len() - 1
+    }
+
+    // declares `name` where the walk is: a global was already declared by `hoist`
+    fn declare(&mut self, name: &Token, kind: SymbolKind, type_name: &str, extent: Span) -> usize {
+        if self.scopes.is_empty() {
+            let hoisted = self.analysis.symbols.iter().position(|symbol| {
+                symbol.library == self.library && symbol.scope.is_none() && symbol.span == name.span
+            });
+            let symbol = hoisted.unwrap_or_else(|| self.global(name, kind, type_name, extent));
+            self.globals.insert(name.lexeme.clone(), symbol);
+            return symbol;
+        }
+        let scope = Span {
+            start: name.span.start,
+            end: self.block_end(name.span.start),
+        };
+        let symbol = self.symbol(name, kind, type_name, Some(scope), extent);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), symbol);
+        }
+        symbol
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.globals.get(name))
+            .or_else(|| self.hoisted.get(name))
+            .copied()
+            .or_else(|| {
+                // the globals of used files, then the builtins
+                self.analysis.symbols.iter().position(|symbol| {
+                    symbol.name == name
+                        && symbol.scope.is_none()
+                        && (symbol.library.is_some() || symbol.kind == SymbolKind::Builtin)
+                })
+            })
+    }
+
+    fn reference(&mut self, name: &Token) {
+        match self.lookup(&name.lexeme) {
+            Some(symbol) => self.analysis.references.push((name.span, symbol)),
+            None => self.unresolved.push(name.clone()),
+        }
+    }
+
+    // the end of the innermost braces around `offset`, or of the file
+    fn block_end(&self, offset: usize) -> usize {
+        self.braces
+            .iter()
+            .filter(|braces| braces.start < offset && offset < braces.end)
+            .min_by_key(|braces| braces.end - braces.start)
+            .map_or(self.source_length, |braces| braces.end)
+    }
+
+    // the `(` of the first parameter list after `offset`
+    fn paren_after(&self, offset: usize) -> Option<usize> {
+        self.tokens.iter().position(|token| {
+            token.span.start >= offset && token.token_type == TokenType::LeftParen
+        })
+    }
+
+    // the `(` of the parameter list `param` is the first of
+    fn paren_before(&self, param: &Token) -> Option<usize> {
+        let index = self
+            .tokens
+            .iter()
+            .position(|token| token.span == param.span)?;
+        self.tokens[..index]
+            .iter()
+            .rposition(|token| token.token_type == TokenType::LeftParen)
+    }
+
+    // where the body of the function with its parameter list at `paren` ends: at its closing
+    // brace, or the `;` after a `=` body
+    fn body_end(&self, paren: Option<usize>) -> usize {
+        let Some(paren) = paren else {
+            return self.source_length;
+        };
+        let mut depth = 0;
+        let mut in_body = false;
+        for token in &self.tokens[paren..] {
+            match token.token_type {
+                TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                    depth -= 1;
+                    if depth == 0 && in_body && token.token_type == TokenType::RightBrace {
+                        return token.span.end;
+                    }
+                    // the parameters end here and the body begins
+                    in_body |= depth == 0;
+                }
+                TokenType::Semicolon if depth == 0 && in_body => return token.span.end,
+                _ => {}
+            }
+        }
+        self.source_length
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn statements(&mut self, statements: &[Box<Statement>]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::ExpressionStatement { expression, .. }
+            | Statement::UseStatement { expression, .. } => self.expression(expression),
+            Statement::VariableStatement {
+                name,
+                value_type,
+                value,
+                line,
+            } => {
+                // the initializer can't use the variable it declares
+                self.expression(value);
+                self.declare(name, SymbolKind::Variable, &value_type.lexeme, name.span);
+                self.check_type(value, &value_type.lexeme, |found| {
+                    let mut diagnostic = error(
+                        "E4003",
+                        *line,
+                        vec![
+                            "variable".to_string(),
+                            name.lexeme.clone(),
+                            value_type.lexeme.clone(),
+                            found.to_string(),
+                        ],
+                    );
+                    diagnostic.span = Some(name.span);
+                    diagnostic
+                });
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.begin_scope();
+                self.statements(statements);
+                self.end_scope();
+            }
+            Statement::WhileStatement {
+                conditions, body, ..
+            } => {
+                for condition in conditions {
+                    self.expression(condition);
+                }
+                self.statement(body);
+            }
+            Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches,
+                else_branch,
+                ..
+            } => {
+                for condition in conditions {
+                    self.expression(condition);
+                }
+                self.statement(then_branch);
+                for (conditions, branch) in elif_branches {
+                    for condition in conditions {
+                        self.expression(condition);
+                    }
+                    self.statement(branch);
+                }
+                if let Some(branch) = else_branch {
+                    self.statement(branch);
+                }
+            }
+            Statement::FunctionStatement {
+                name,
+                params,
+                body,
+                output_type,
+                ..
+            } => {
+                let paren = self.paren_after(name.span.end);
+                let extent = Span {
+                    start: name.span.start,
+                    end: self.body_end(paren),
+                };
+                // declared before its body, which can call it
+                let symbol = self.declare(name, SymbolKind::Function, &output_type.lexeme, extent);
+                self.analysis.symbols[symbol].detail = signature(&name.lexeme, params, output_type);
+                self.analysis.symbols[symbol].arity = Some(Arity::of(params));
+                let enclosing = self.function.replace(symbol);
+                self.function_body(paren, params, body, output_type);
+                self.function = enclosing;
+            }
+            Statement::ReturnStatement { keyword, value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                let Some(output_type) = self.output_type.clone() else {
+                    return;
+                };
+                let line = keyword.line_number;
+                let span = (keyword.span.start < keyword.span.end).then_some(keyword.span);
+                match value {
+                    Some(value) => self.check_type(value, &output_type, |_| {
+                        let mut diagnostic = error("E4017", line, vec![]);
+                        diagnostic.span = span;
+                        diagnostic
+                    }),
+                    None if output_type != "null" && TYPES.contains(&output_type.as_str()) => {
+                        let mut diagnostic = error("E4017", line, vec![]);
+                        diagnostic.span = span;
+                        self.analysis.diagnostics.push(diagnostic);
+                    }
+                    None => {}
+                }
+            }
+            Statement::BreakStatement { .. } => {}
+            Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                ..
+            } => {
+                self.expression(condition);
+                for (value, body) in case_branches {
+                    self.expression(value);
+                    self.case(body);
+                }
+                if let Some(body) = default_branch {
+                    self.case(body);
+                }
+            }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.statement(body);
+                if let Some((name, block)) = catch {
+                    // the error is in scope until the end of the catch block
+                    let end = self
+                        .braces
+                        .iter()
+                        .filter(|braces| braces.start > name.span.start)
+                        .min_by_key(|braces| braces.start)
+                        .map_or(self.source_length, |braces| braces.end);
+                    self.begin_scope();
+                    let symbol = self.symbol(
+                        name,
+                        SymbolKind::Parameter,
+                        "error",
+                        Some(Span {
+                            start: name.span.start,
+                            end,
+                        }),
+                        name.span,
+                    );
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.insert(name.lexeme.clone(), symbol);
+                    }
+                    self.statement(block);
+                    self.end_scope();
+                }
+                if let Some(finally) = finally {
+                    self.statement(finally);
+                }
+            }
+            Statement::ThrowStatement { value, .. } => self.expression(value),
+        }
+    }
+
+    // as in the resolver, `case` bodies only get a scope inside another one
+    fn case(&mut self, body: &[Statement]) {
+        let scoped = !self.scopes.is_empty();
+        if scoped {
+            self.begin_scope();
+        }
+        for statement in body {
+            self.statement(statement);
+        }
+        if scoped {
+            self.end_scope();
+        }
+    }
+
+    // the parameters and body of a function with its parameter list at `paren`
+    fn function_body(
+        &mut self,
+        paren: Option<usize>,
+        params: &[Parameter],
+        body: &[Box<Statement>],
+        output_type: &Token,
+    ) {
+        let end = self.body_end(paren);
+        let enclosing = self.output_type.replace(output_type.lexeme.clone());
+        self.begin_scope();
+        for param in params {
+            if let Some(default) = &param.default {
+                self.expression(default);
+            }
+            let symbol = self.symbol(
+                &param.name,
+                SymbolKind::Parameter,
+                &param.type_token.lexeme,
+                Some(Span {
+                    start: param.name.span.start,
+                    end,
+                }),
+                param.name.span,
+            );
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert(param.name.lexeme.clone(), symbol);
+            }
+        }
+        self.statements(body);
+        self.end_scope();
+        self.output_type = enclosing;
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Object { properties, .. } => {
+                for (_, value) in properties {
+                    self.expression(value);
+                }
+            }
+            Expression::ObjectCall { name, .. } => self.reference(name),
+            Expression::Array { elements, .. } => {
+                for element in elements {
+                    self.expression(element);
+                }
+            }
+            Expression::Index { array, index, .. } => {
+                self.expression(array);
+                self.expression(index);
+            }
+            Expression::Assign { name, value, .. } => {
+                self.expression(value);
+                self.reference(name);
+            }
+            Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Call {
+                callee,
+                arguments,
+                named,
+                ..
+            } => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+                for (_, value) in named {
+                    self.expression(value);
+                }
+            }
+            Expression::Grouping { expression, .. } => self.expression(expression),
+            Expression::Unary { right, .. } => self.expression(right),
+            Expression::Literal { .. } => {}
+            Expression::Variable { name, .. } => self.reference(name),
+            Expression::Function {
+                params,
+                body,
+                output_type,
+                ..
+            } => {
+                // without parameters, there is nothing the parameter list is needed for
+                let paren = params
+                    .first()
+                    .and_then(|param| self.paren_before(&param.name));
+                self.function_body(paren, params, body, output_type);
+            }
+        }
+    }
+
+    // reports `mismatch` with the type `expression` was found to have, when it is known and
+    // isn't `expected`
+    fn check_type(
+        &mut self,
+        expression: &Expression,
+        expected: &str,
+        mismatch: impl FnOnce(&str) -> Diagnostic,
+    ) {
+        if !TYPES.contains(&expected) {
+            return;
+        }
+        if let Some(found) = self.type_of(expression) {
+            if found != expected {
+                let diagnostic = mismatch(&found);
+                self.analysis.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    // the type `expression` evaluates to, when that is certain without running it
+    fn type_of(&self, expression: &Expression) -> Option<String> {
+        let known = |name: &str| Some(name.to_string());
+        match expression {
+            Expression::Literal { value, .. } => known(value.to_type()),
+            Expression::Array { .. } => known("array"),
+            Expression::Object { .. } => known("object"),
+            Expression::Function { .. } => known("function"),
+            Expression::Grouping { expression, .. } => self.type_of(expression),
+            Expression::Unary {
+                operator, right, ..
+            } => match operator.token_type {
+                TokenType::Bang => known("boolean"),
+                TokenType::Minus if self.type_of(right).as_deref() == Some("number") => {
+                    known("number")
+                }
+                _ => None,
+            },
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.type_of(left)?;
+                let right = self.type_of(right)?;
+                match (left.as_str(), operator.token_type, right.as_str()) {
+                    (_, TokenType::EqualEqual | TokenType::BangEqual, _) => known("boolean"),
+                    ("number", TokenType::Plus, "number") => known("number"),
+                    ("string", TokenType::Plus, "string" | "number")
+                    | ("number", TokenType::Plus, "string") => known("string"),
+                    ("number", TokenType::Minus | TokenType::Star | TokenType::Slash, "number") => {
+                        known("number")
+                    }
+                    (
+                        "number",
+                        TokenType::Greater
+                        | TokenType::GreaterEqual
+                        | TokenType::Less
+                        | TokenType::LessEqual,
+                        "number",
+                    )
+                    | (
+                        "string",
+                        TokenType::Greater
+                        | TokenType::GreaterEqual
+                        | TokenType::Less
+                        | TokenType::LessEqual,
+                        "string",
+                    ) => known("boolean"),
+                    _ => None,
+                }
+            }
+            Expression::Variable { name, .. } => {
+                let symbol = &self.analysis.symbols[self.lookup(&name.lexeme)?];
+                match symbol.kind {
+                    SymbolKind::Function | SymbolKind::Builtin => known("function"),
+                    _ if TYPES.contains(&symbol.type_name.as_str()) => known(&symbol.type_name),
+                    _ => None,
+                }
+            }
+            Expression::Call { callee, .. } => match callee.as_ref() {
+                Expression::Variable { name, .. } => {
+                    let symbol = &self.analysis.symbols[self.lookup(&name.lexeme)?];
+                    match symbol.kind {
+                        SymbolKind::Function if TYPES.contains(&symbol.type_name.as_str()) => {
+                            known(&symbol.type_name)
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,590 @@
+// the statements a file parses to, as json for `tron ast --json` and other tools, or as an
+// indented tree for people
+//
+// Every node is an object whose "kind" names it: statements carry the "line" they start on, and
+// expressions the "id" the resolver keys its locals by. Names, types and operators are tokens,
+// `{"text", "line", "start", "end"}` with byte offsets into the source. Fields are only added
+// within a `SCHEMA_VERSION`; renaming or removing one is a new version.
+use crate::expressions::{Expression, TronType};
+use crate::scanner::{Parameter, Statement, Token};
+use crate::utils::json::Json;
+
+pub const SCHEMA_VERSION: usize = 1;
+
+// `{"version", "statements"}`
+pub fn to_json(statements: &[Statement]) -> Json {
+    Json::object([
+        ("version", SCHEMA_VERSION.into()),
+        (
+            "statements",
+            statements.iter().map(statement).collect::<Vec<_>>().into(),
+        ),
+    ])
+}
+
+fn node(kind: &str, mut fields: Vec<(&str, Json)>) -> Json {
+    fields.insert(0, ("kind", kind.into()));
+    Json::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+fn token(token: &Token) -> Json {
+    Json::object([
+        ("text", token.lexeme.as_str().into()),
+        ("line", token.line_number.into()),
+        ("start", token.span.start.into()),
+        ("end", token.span.end.into()),
+    ])
+}
+
+fn statements<'a>(statements: impl IntoIterator<Item = &'a Statement>) -> Json {
+    statements
+        .into_iter()
+        .map(statement)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn expressions<'a>(expressions: impl IntoIterator<Item = &'a Expression>) -> Json {
+    expressions
+        .into_iter()
+        .map(expression)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn parameters(params: &[Parameter]) -> Json {
+    params
+        .iter()
+        .map(|param| {
+            node(
+                "Parameter",
+                vec![
+                    ("name", token(&param.name)),
+                    ("type", token(&param.type_token)),
+                    (
+                        "default",
+                        param.default.as_ref().map_or(Json::Null, expression),
+                    ),
+                    ("rest", param.rest.into()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn statement(stmt: &Statement) -> Json {
+    let line = ("line", stmt.get_line().into());
+    match stmt {
+        Statement::ExpressionStatement {
+            expression: expr, ..
+        } => node(
+            "ExpressionStatement",
+            vec![line, ("expression", expression(expr))],
+        ),
+        Statement::UseStatement {
+            expression: expr, ..
+        } => node("UseStatement", vec![line, ("path", expression(expr))]),
+        Statement::VariableStatement {
+            name,
+            value_type,
+            value,
+            ..
+        } => node(
+            "VariableStatement",
+            vec![
+                line,
+                ("name", token(name)),
+                ("type", token(value_type)),
+                ("value", expression(value)),
+            ],
+        ),
+        Statement::BlockStatement {
+            statements: body, ..
+        } => node(
+            "BlockStatement",
+            vec![line, ("statements", statements(body.iter().map(|s| &**s)))],
+        ),
+        Statement::WhileStatement {
+            conditions, body, ..
+        } => node(
+            "WhileStatement",
+            vec![
+                line,
+                ("conditions", expressions(conditions)),
+                ("body", statement(body)),
+            ],
+        ),
+        Statement::IfStatement {
+            conditions,
+            then_branch,
+            elif_branches,
+            else_branch,
+            ..
+        } => node(
+            "IfStatement",
+            vec![
+                line,
+                ("conditions", expressions(conditions)),
+                ("then", statement(then_branch)),
+                (
+                    "elif",
+                    elif_branches
+                        .iter()
+                        .map(|(conditions, body)| {
+                            node(
+                                "ElifBranch",
+                                vec![
+                                    ("conditions", expressions(conditions)),
+                                    ("body", statement(body)),
+                                ],
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+                ("else", else_branch.as_deref().map_or(Json::Null, statement)),
+            ],
+        ),
+        Statement::FunctionStatement {
+            name,
+            params,
+            body,
+            output_type,
+            ..
+        } => node(
+            "FunctionStatement",
+            vec![
+                line,
+                ("name", token(name)),
+                ("parameters", parameters(params)),
+                ("output_type", token(output_type)),
+                ("body", statements(body.iter().map(|s| &**s))),
+            ],
+        ),
+        Statement::ReturnStatement { value, .. } => node(
+            "ReturnStatement",
+            vec![
+                line,
+                ("value", value.as_ref().map_or(Json::Null, expression)),
+            ],
+        ),
+        Statement::BreakStatement { .. } => node("BreakStatement", vec![line]),
+        Statement::SwitchStatement {
+            condition,
+            case_branches,
+            default_branch,
+            ..
+        } => node(
+            "SwitchStatement",
+            vec![
+                line,
+                ("condition", expression(condition)),
+                (
+                    "cases",
+                    case_branches
+                        .iter()
+                        .map(|(value, body)| {
+                            node(
+                                "CaseBranch",
+                                vec![("value", expression(value)), ("body", statements(body))],
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+                (
+                    "default",
+                    default_branch.as_ref().map_or(Json::Null, statements),
+                ),
+            ],
+        ),
+        Statement::TryStatement {
+            body,
+            catch,
+            finally,
+            ..
+        } => node(
+            "TryStatement",
+            vec![
+                line,
+                ("body", statement(body)),
+                (
+                    "catch",
+                    catch.as_ref().map_or(Json::Null, |(name, block)| {
+                        node(
+                            "CatchClause",
+                            vec![("name", token(name)), ("body", statement(block))],
+                        )
+                    }),
+                ),
+                ("finally", finally.as_deref().map_or(Json::Null, statement)),
+            ],
+        ),
+        Statement::ThrowStatement { value, .. } => {
+            node("ThrowStatement", vec![line, ("value", expression(value))])
+        }
+    }
+}
+
+fn literal(value: &TronType) -> Json {
+    match value {
+        // through its shortest text, so 0.1 isn't 0.10000000149011612
+        TronType::Number(number) => Json::Number(number.to_string().parse().unwrap_or_default()),
+        TronType::StringValue(text) => text.as_ref().into(),
+        TronType::True => true.into(),
+        TronType::False => false.into(),
+        _ => Json::Null,
+    }
+}
+
+fn expression(expr: &Expression) -> Json {
+    let id = ("id", expr.get_id().into());
+    match expr {
+        Expression::Object { properties, .. } => node(
+            "Object",
+            vec![
+                id,
+                (
+                    "properties",
+                    properties
+                        .iter()
+                        .map(|(key, value)| {
+                            node(
+                                "Property",
+                                vec![("key", token(key)), ("value", expression(value))],
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            ],
+        ),
+        Expression::ObjectCall { key, name, .. } => node(
+            "ObjectCall",
+            vec![id, ("object", token(name)), ("key", token(key))],
+        ),
+        Expression::Array { elements, .. } => node(
+            "Array",
+            vec![id, ("elements", expressions(elements.iter().map(|e| &**e)))],
+        ),
+        Expression::Index { array, index, .. } => node(
+            "Index",
+            vec![
+                id,
+                ("array", expression(array)),
+                ("index", expression(index)),
+            ],
+        ),
+        Expression::Assign { name, value, .. } => node(
+            "Assign",
+            vec![id, ("name", token(name)), ("value", expression(value))],
+        ),
+        Expression::Binary {
+            left,
+            operator,
+            right,
+            ..
+        } => node(
+            "Binary",
+            vec![
+                id,
+                ("left", expression(left)),
+                ("operator", token(operator)),
+                ("right", expression(right)),
+            ],
+        ),
+        Expression::Logical {
+            left,
+            operator,
+            right,
+            ..
+        } => node(
+            "Logical",
+            vec![
+                id,
+                ("left", expression(left)),
+                ("operator", token(operator)),
+                ("right", expression(right)),
+            ],
+        ),
+        Expression::Call {
+            callee,
+            arguments,
+            named,
+            ..
+        } => node(
+            "Call",
+            vec![
+                id,
+                ("callee", expression(callee)),
+                ("arguments", expressions(arguments)),
+                (
+                    "named",
+                    named
+                        .iter()
+                        .map(|(name, value)| {
+                            node(
+                                "NamedArgument",
+                                vec![("name", token(name)), ("value", expression(value))],
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            ],
+        ),
+        Expression::Grouping {
+            expression: inner, ..
+        } => node("Grouping", vec![id, ("expression", expression(inner))]),
+        Expression::Literal { value, .. } => node(
+            "Literal",
+            vec![
+                id,
+                ("type", value.to_type().into()),
+                ("value", literal(value)),
+            ],
+        ),
+        Expression::Unary {
+            operator, right, ..
+        } => node(
+            "Unary",
+            vec![
+                id,
+                ("operator", token(operator)),
+                ("right", expression(right)),
+            ],
+        ),
+        Expression::Variable { name, .. } => node("Variable", vec![id, ("name", token(name))]),
+        Expression::Function {
+            name,
+            params,
+            body,
+            output_type,
+            ..
+        } => node(
+            "FunctionExpression",
+            vec![
+                id,
+                ("name", token(name)),
+                ("parameters", parameters(params)),
+                ("output_type", token(output_type)),
+                ("body", statements(body.iter().map(|s| &**s))),
+            ],
+        ),
+    }
+}
+
+// the json of `to_json` as an indented tree: a line per node, headed by its kind, id and line,
+// with tokens and other values next to the field that holds them
+pub fn dump(json: &Json) -> String {
+    let mut out = String::new();
+    if let Some(Json::Array(statements)) = json.get("statements") {
+        for statement in statements {
+            dump_into(&mut out, None, statement, 0);
+        }
+    }
+    out
+}
+
+fn dump_into(out: &mut String, label: Option<&str>, json: &Json, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let label = label.map_or(String::new(), |label| format!("{}: ", label));
+    match json {
+        // tokens
+        Json::Object(_) if json.get("text").is_some() => {
+            let text = json.get("text").and_then(Json::as_str).unwrap_or_default();
+            out.push_str(&format!("{}{}{}\n", indent, label, text));
+        }
+        Json::Object(fields) => {
+            let mut header = json
+                .get("kind")
+                .and_then(Json::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(id) = json.get("id") {
+                header.push_str(&format!(" #{}", id));
+            }
+            if let Some(line) = json.get("line") {
+                header.push_str(&format!(" (line {})", line));
+            }
+            out.push_str(&format!("{}{}{}\n", indent, label, header));
+            for (key, value) in fields {
+                if !matches!(key.as_str(), "kind" | "id" | "line") {
+                    dump_into(out, Some(key), value, depth + 1);
+                }
+            }
+        }
+        Json::Array(items) if items.is_empty() => {}
+        Json::Array(items) => {
+            out.push_str(&format!("{}{}\n", indent, label.trim_end()));
+            for item in items {
+                dump_into(out, None, item, depth + 1);
+            }
+        }
+        Json::Null => {}
+        value => out.push_str(&format!("{}{}{}\n", indent, label, value)),
+    }
+}
+
+// the expressions that name a variable, which the resolver gives a slot when they are local:
+// variables, assignments and keys read from a variable, in source order, with the token that
+// names it
+pub fn references(statements: &[Statement]) -> Vec<(usize, &Token)> {
+    let mut found = vec![];
+    for stmt in statements {
+        walk_statement(stmt, &mut found);
+    }
+    found.sort_by_key(|(_, name)| name.span.start);
+    found
+}
+
+fn walk_statement<'a>(stmt: &'a Statement, found: &mut Vec<(usize, &'a Token)>) {
+    match stmt {
+        Statement::ExpressionStatement { expression, .. }
+        | Statement::UseStatement { expression, .. }
+        | Statement::VariableStatement {
+            value: expression, ..
+        } => walk_expression(expression, found),
+        Statement::BlockStatement { statements, .. } => {
+            for stmt in statements {
+                walk_statement(stmt, found);
+            }
+        }
+        Statement::FunctionStatement { params, body, .. } => walk_function(params, body, found),
+        Statement::WhileStatement {
+            conditions, body, ..
+        } => {
+            for condition in conditions {
+                walk_expression(condition, found);
+            }
+            walk_statement(body, found);
+        }
+        Statement::IfStatement {
+            conditions,
+            then_branch,
+            elif_branches,
+            else_branch,
+            ..
+        } => {
+            for condition in conditions {
+                walk_expression(condition, found);
+            }
+            walk_statement(then_branch, found);
+            for (conditions, body) in elif_branches {
+                for condition in conditions {
+                    walk_expression(condition, found);
+                }
+                walk_statement(body, found);
+            }
+            if let Some(else_branch) = else_branch {
+                walk_statement(else_branch, found);
+            }
+        }
+        Statement::ReturnStatement { value, .. } => {
+            if let Some(value) = value {
+                walk_expression(value, found);
+            }
+        }
+        Statement::BreakStatement { .. } => {}
+        Statement::SwitchStatement {
+            condition,
+            case_branches,
+            default_branch,
+            ..
+        } => {
+            walk_expression(condition, found);
+            for (value, body) in case_branches {
+                walk_expression(value, found);
+                for stmt in body {
+                    walk_statement(stmt, found);
+                }
+            }
+            for stmt in default_branch.iter().flatten() {
+                walk_statement(stmt, found);
+            }
+        }
+        Statement::TryStatement {
+            body,
+            catch,
+            finally,
+            ..
+        } => {
+            walk_statement(body, found);
+            if let Some((_, block)) = catch {
+                walk_statement(block, found);
+            }
+            if let Some(finally) = finally {
+                walk_statement(finally, found);
+            }
+        }
+        Statement::ThrowStatement { value, .. } => walk_expression(value, found),
+    }
+}
+
+fn walk_expression<'a>(expr: &'a Expression, found: &mut Vec<(usize, &'a Token)>) {
+    let id = expr.get_id();
+    match expr {
+        Expression::Object { properties, .. } => {
+            for (_, value) in properties {
+                walk_expression(value, found);
+            }
+        }
+        Expression::ObjectCall { name, .. } | Expression::Variable { name, .. } => {
+            found.push((id, name))
+        }
+        Expression::Array { elements, .. } => {
+            for element in elements {
+                walk_expression(element, found);
+            }
+        }
+        Expression::Index { array, index, .. } => {
+            walk_expression(array, found);
+            walk_expression(index, found);
+        }
+        Expression::Assign { name, value, .. } => {
+            walk_expression(value, found);
+            found.push((id, name));
+        }
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            walk_expression(left, found);
+            walk_expression(right, found);
+        }
+        Expression::Call {
+            callee,
+            arguments,
+            named,
+            ..
+        } => {
+            walk_expression(callee, found);
+            for argument in arguments {
+                walk_expression(argument, found);
+            }
+            for (_, value) in named {
+                walk_expression(value, found);
+            }
+        }
+        Expression::Grouping { expression, .. } => walk_expression(expression, found),
+        Expression::Literal { .. } => {}
+        Expression::Unary { right, .. } => walk_expression(right, found),
+        Expression::Function { params, body, .. } => walk_function(params, body, found),
+    }
+}
+
+fn walk_function<'a>(
+    params: &'a [Parameter],
+    body: &'a [Box<Statement>],
+    found: &mut Vec<(usize, &'a Token)>,
+) {
+    for default in params.iter().filter_map(|param| param.default.as_ref()) {
+        walk_expression(default, found);
+    }
+    for stmt in body {
+        walk_statement(stmt, found);
+    }
+}
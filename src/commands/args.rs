@@ -0,0 +1,344 @@
+// command line parsing: `tron [flags] <command> [flags] [args] [-- script args]`
+use std::{collections::HashMap, path::Path};
+
+pub struct FlagSpec {
+    pub name: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub args: &'static [&'static str],
+    pub about: &'static str,
+    pub flags: &'static [FlagSpec],
+}
+
+pub const GLOBAL_FLAGS: &[FlagSpec] = &[
+    FlagSpec {
+        name: "help",
+        short: Some('h'),
+        value: None,
+        help: "print help for tron or a command",
+    },
+    FlagSpec {
+        name: "eval",
+        short: Some('e'),
+        value: Some("code"),
+        help: "run the given code instead of a file",
+    },
+    FlagSpec {
+        name: "no-color",
+        short: None,
+        value: None,
+        help: "disable colored output, which is also off when stderr isn't a terminal or NO_COLOR is set",
+    },
+    FlagSpec {
+        name: "quiet",
+        short: Some('q'),
+        value: None,
+        help: "only print program output and errors",
+    },
+    FlagSpec {
+        name: "error-format",
+        short: None,
+        value: Some("human|json|sarif"),
+        help: "how diagnostics are written to stderr",
+    },
+    FlagSpec {
+        name: "max-steps",
+        short: None,
+        value: Some("n"),
+        help: "stop the program after executing n statements",
+    },
+    FlagSpec {
+        name: "max-depth",
+        short: None,
+        value: Some("n"),
+        help: "report a stack overflow past n nested calls (default 10000)",
+    },
+    FlagSpec {
+        name: "max-memory",
+        short: None,
+        value: Some("size"),
+        help: "stop the program when the heap grows past size bytes (k, m and g suffixes)",
+    },
+    FlagSpec {
+        name: "timeout",
+        short: None,
+        value: Some("duration"),
+        help: "stop the program after a wall-clock duration such as 500ms, 2s or 1m",
+    },
+    FlagSpec {
+        name: "sandbox",
+        short: None,
+        value: None,
+        help: "deny the program @cmd, file access, @args and @ask",
+    },
+    FlagSpec {
+        name: "allow",
+        short: None,
+        value: Some("cmd,fs,env,ask"),
+        help: "capabilities to grant back in the sandbox",
+    },
+    FlagSpec {
+        name: "engine",
+        short: None,
+        value: Some("tree|vm"),
+        help: "run programs with the tree-walking interpreter or the bytecode vm",
+    },
+    FlagSpec {
+        name: "no-optimize",
+        short: None,
+        value: None,
+        help: "run programs as written, without folding constants or dropping dead branches first",
+    },
+];
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "run",
+        args: &["<filename>"],
+        about: "run tron files (`-` reads stdin), passing everything after `--` to the script",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "debug",
+        args: &["<filename>"],
+        about: "run a tron file in the step debugger, which reads commands from stdin",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "dap",
+        args: &[],
+        about: "serve the Debug Adapter Protocol on stdin and stdout, for editors to debug with",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "lsp",
+        args: &[],
+        about: "serve the Language Server Protocol on stdin and stdout, for editors to check and navigate code with",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "fmt",
+        args: &["[paths...]"],
+        about: "format tron files, or every tron file under the given directories (default: the current one)",
+        flags: &[FlagSpec {
+            name: "check",
+            short: None,
+            value: None,
+            help: "list the files that aren't formatted instead of rewriting them, and fail if there are any",
+        }],
+    },
+    CommandSpec {
+        name: "lint",
+        args: &["[paths...]"],
+        about: "check tron files, or every tron file under the given directories (default: the current one), for likely mistakes",
+        flags: &[FlagSpec {
+            name: "fix",
+            short: None,
+            value: None,
+            help: "apply the fixes that can't change what the program does",
+        }],
+    },
+    CommandSpec {
+        name: "tokens",
+        args: &["<filename>"],
+        about: "print the tokens the scanner reads from a tron file, with their line and column",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "ast",
+        args: &["<filename>"],
+        about: "print the syntax tree the parser builds from a tron file",
+        flags: &[FlagSpec {
+            name: "json",
+            short: None,
+            value: None,
+            help: "print it as json, in a schema that only gains fields within a version",
+        }],
+    },
+    CommandSpec {
+        name: "resolve",
+        args: &["<filename>"],
+        about: "print where the resolver finds each variable a tron file uses: how many scopes out and at which slot, or global",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "explain",
+        args: &["<code>"],
+        about: "explain an error code, like E4003, with an example of the mistake and its fix",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "bench",
+        args: &["[path]"],
+        about: "time tron programs (default: the `benches` directory) on both engines",
+        flags: &[FlagSpec {
+            name: "runs",
+            short: None,
+            value: Some("n"),
+            help: "run every program n times and report the fastest (default 3)",
+        }],
+    },
+    CommandSpec {
+        name: "version",
+        args: &[],
+        about: "installed version",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "update",
+        args: &[],
+        about: "install the latest version",
+        flags: &[
+            FlagSpec {
+                name: "from",
+                short: None,
+                value: Some("archive|dir"),
+                help: "install a verified local release bundle",
+            },
+            FlagSpec {
+                name: "rollback",
+                short: None,
+                value: None,
+                help: "restore the previously installed version",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "uninstall",
+        args: &[],
+        about: "remove the installed files",
+        flags: &[],
+    },
+    CommandSpec {
+        name: "help",
+        args: &["[command]"],
+        about: "print help for tron or a command",
+        flags: &[],
+    },
+];
+
+pub struct Matches {
+    pub command: Option<&'static CommandSpec>,
+    pub positionals: Vec<String>,
+    pub script_args: Vec<String>,
+    flags: HashMap<&'static str, String>,
+}
+
+impl Matches {
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(|value| value.as_str())
+    }
+}
+
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+pub fn parse_args(args: &[String]) -> Result<Matches, String> {
+    let mut matches = Matches {
+        command: None,
+        positionals: vec![],
+        script_args: vec![],
+        flags: HashMap::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            matches.script_args = args.by_ref().cloned().collect();
+        } else if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline_value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let spec = lookup(matches.command, |flag| flag.name == name)
+                .ok_or_else(|| format!("unknown flag --{}", name))?;
+            let value = flag_value(spec, inline_value, &mut args)?;
+            matches.flags.insert(spec.name, value);
+        } else if arg.len() == 2 && arg.starts_with('-') && arg != "--" {
+            let short = arg.chars().nth(1);
+            let spec = lookup(matches.command, |flag| flag.short == short)
+                .ok_or_else(|| format!("unknown flag {}", arg))?;
+            let value = flag_value(spec, None, &mut args)?;
+            matches.flags.insert(spec.name, value);
+        } else if matches.command.is_none() {
+            match find_command(arg) {
+                Some(command) => matches.command = Some(command),
+                // `tron script.tron a b`, which is how a `#!/usr/bin/env tron` line invokes us
+                None if Path::new(arg).is_file() => {
+                    matches.command = find_command("run");
+                    matches.positionals.push(arg.clone());
+                    matches.script_args = args.by_ref().cloned().collect();
+                }
+                None => return Err(format!("unknown command {}", arg)),
+            }
+        } else {
+            matches.positionals.push(arg.clone());
+        }
+    }
+    if let Some(command) = matches.command {
+        if !matches.flag("help") {
+            check_positionals(command, &matches.positionals)?;
+        }
+    }
+    Ok(matches)
+}
+
+fn lookup(
+    command: Option<&'static CommandSpec>,
+    predicate: impl Fn(&FlagSpec) -> bool,
+) -> Option<&'static FlagSpec> {
+    let command_flags = command.map(|command| command.flags).unwrap_or(&[]);
+    command_flags
+        .iter()
+        .chain(GLOBAL_FLAGS.iter())
+        .find(|flag| predicate(flag))
+}
+
+fn flag_value<'a>(
+    spec: &FlagSpec,
+    inline_value: Option<String>,
+    rest: &mut impl Iterator<Item = &'a String>,
+) -> Result<String, String> {
+    match (spec.value, inline_value) {
+        (None, None) => Ok(String::new()),
+        (None, Some(_)) => Err(format!("--{} doesn't take a value", spec.name)),
+        (Some(_), Some(value)) => Ok(value),
+        (Some(placeholder), None) => rest
+            .next()
+            .cloned()
+            .ok_or_else(|| format!("--{} requires <{}>", spec.name, placeholder)),
+    }
+}
+
+fn check_positionals(command: &CommandSpec, positionals: &[String]) -> Result<(), String> {
+    let required = command
+        .args
+        .iter()
+        .filter(|arg| arg.starts_with('<'))
+        .count();
+    if positionals.len() < required {
+        Err(format!(
+            "{} requires {}",
+            command.name,
+            command.args[positionals.len()]
+        ))
+    } else if positionals.len() > command.args.len()
+        && !command.args.last().is_some_and(|arg| arg.ends_with("...]"))
+    {
+        Err(format!(
+            "unexpected argument {} for {}",
+            positionals[command.args.len()],
+            command.name
+        ))
+    } else {
+        Ok(())
+    }
+}
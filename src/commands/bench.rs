@@ -1 +1,69 @@
-// run benchmarks
+// run benchmarks: times every program with both engines and keeps the fastest of `--runs` runs
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tron_lang::utils::options::inform;
+use tron_lang::utils::{exit, TronError};
+
+const ENGINES: &[&str] = &["tree", "vm"];
+
+fn programs(target: &Path) -> Vec<PathBuf> {
+    if target.is_file() {
+        return vec![target.to_path_buf()];
+    }
+    let mut programs: Vec<PathBuf> = match target.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "tron"))
+            .collect(),
+        Err(_) => {
+            TronError::throw("E0001", 0, vec![]);
+            exit(1);
+        }
+    };
+    programs.sort();
+    programs
+}
+
+// each run is a fresh `tron run`, so startup is included just like for a user
+fn time_run(program: &Path, engine: &str) -> Option<Duration> {
+    let exe = std::env::current_exe().ok()?;
+    let start = Instant::now();
+    let status = Command::new(exe)
+        .args(["--quiet", "--engine", engine, "run"])
+        .arg(program)
+        .stdout(Stdio::null())
+        .status()
+        .ok()?;
+    let elapsed = start.elapsed();
+    status.success().then_some(elapsed)
+}
+
+pub fn cli_bench(target: Option<&str>, runs: Option<&str>) {
+    let runs: usize = match runs.map(str::parse) {
+        None => 3,
+        Some(Ok(runs)) if runs > 0 => runs,
+        Some(_) => {
+            TronError::throw(
+                "E0008",
+                0,
+                vec!["--runs expects a positive number".to_string()],
+            );
+            exit(64);
+        }
+    };
+    let programs = programs(Path::new(target.unwrap_or("benches")));
+    inform(&format!("fastest of {} runs", runs));
+    for program in programs {
+        let mut line = format!("{:<40}", program.display());
+        for engine in ENGINES {
+            let fastest = (0..runs).map(|_| time_run(&program, engine)).min();
+            let result = match fastest.flatten() {
+                Some(time) => format!("{:>10.1}ms", time.as_secs_f64() * 1000.0),
+                None => format!("{:>12}", "failed"),
+            };
+            line.push_str(&format!("  {} {}", engine, result));
+        }
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,546 @@
+// `tron dap`: a Debug Adapter Protocol server on stdin and stdout, which is how editors such as
+// VS Code debug Tron programs
+//
+// A thread reads the client's requests into a channel. Until the client has sent both `launch`
+// and `configurationDone` they are answered here; then the program runs under `tron_lang::debugger`
+// and the `Adapter` answers them each time it pauses, or between statements while it runs.
+use crate::commands::run::on_program_thread;
+use std::cell::RefCell;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::{env, fs, thread};
+use tron_lang::debugger::{self, Breakpoints, Frontend, Pause, Reason, Resume};
+use tron_lang::library::redirect_output;
+use tron_lang::utils::exit;
+use tron_lang::utils::json::Json;
+use tron_lang::utils::options::{options, set_options, Options};
+use tron_lang::utils::protocol::{read_message, write_message};
+use tron_lang::{Interpreter, TronType};
+
+// the interpreter runs programs on a single thread
+const THREAD_ID: usize = 1;
+
+// writes messages to the client from any thread, numbering them in the order they are sent
+#[derive(Clone)]
+struct Client {
+    seq: Arc<Mutex<usize>>,
+}
+
+impl Client {
+    fn send(&self, kind: &str, mut fields: Vec<(String, Json)>) {
+        let mut seq = self.seq.lock().unwrap();
+        *seq += 1;
+        fields.insert(0, ("seq".to_string(), (*seq).into()));
+        fields.insert(1, ("type".to_string(), kind.into()));
+        let _ = write_message(&mut io::stdout().lock(), &Json::Object(fields));
+    }
+    fn respond(&self, request: &Json, body: Json) {
+        self.send(
+            "response",
+            vec![
+                ("request_seq".to_string(), seq_of(request)),
+                ("success".to_string(), true.into()),
+                ("command".to_string(), command_of(request).into()),
+                ("body".to_string(), body),
+            ],
+        );
+    }
+    fn fail(&self, request: &Json, message: &str) {
+        self.send(
+            "response",
+            vec![
+                ("request_seq".to_string(), seq_of(request)),
+                ("success".to_string(), false.into()),
+                ("command".to_string(), command_of(request).into()),
+                ("message".to_string(), message.into()),
+            ],
+        );
+    }
+    fn event(&self, event: &str, body: Json) {
+        self.send(
+            "event",
+            vec![
+                ("event".to_string(), event.into()),
+                ("body".to_string(), body),
+            ],
+        );
+    }
+}
+
+fn seq_of(request: &Json) -> Json {
+    request.get("seq").cloned().unwrap_or(Json::Null)
+}
+
+fn command_of(request: &Json) -> &str {
+    request.get("command").and_then(Json::as_str).unwrap_or("")
+}
+
+fn argument<'a>(request: &'a Json, name: &str) -> Option<&'a Json> {
+    request.get("arguments")?.get(name)
+}
+
+// files are named relative to the working directory, as `use` names libraries, while clients use
+// absolute paths
+fn relative(path: &str) -> String {
+    let cwd = env::current_dir().unwrap_or_default();
+    match Path::new(path).strip_prefix(&cwd) {
+        Ok(relative) => relative.to_string_lossy().to_string(),
+        Err(_) => path.to_string(),
+    }
+}
+
+fn absolute(file: &str) -> PathBuf {
+    env::current_dir().unwrap_or_default().join(file)
+}
+
+struct Adapter {
+    requests: Receiver<Json>,
+    client: Client,
+    // what `variables` requests refer to while paused: variables by reference, counted from 1
+    references: Vec<Vec<(String, TronType)>>,
+    // a `pause` request came in while the program ran
+    pause_requested: bool,
+    // the client ended the session while the program ran
+    stopping: bool,
+}
+
+impl Adapter {
+    // requests answered the same whether the program runs or not; false for any other
+    fn common(&mut self, request: &Json, breakpoints: &mut Breakpoints) -> bool {
+        match command_of(request) {
+            "setBreakpoints" => {
+                let file = argument(request, "source")
+                    .and_then(|source| source.get("path"))
+                    .and_then(Json::as_str)
+                    .map(relative)
+                    .unwrap_or_default();
+                breakpoints.clear(&file);
+                let lines: Vec<usize> = argument(request, "breakpoints")
+                    .and_then(Json::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|breakpoint| breakpoint.get("line")?.as_f64())
+                    .map(|line| line as usize)
+                    .collect();
+                let verified = lines
+                    .iter()
+                    .map(|line| {
+                        breakpoints.add(&file, *line);
+                        Json::object([("verified", true.into()), ("line", (*line).into())])
+                    })
+                    .collect();
+                self.client.respond(
+                    request,
+                    Json::object([("breakpoints", Json::Array(verified))]),
+                );
+            }
+            "setExceptionBreakpoints" => self.client.respond(request, Json::object([])),
+            "threads" => {
+                let thread = Json::object([("id", THREAD_ID.into()), ("name", "main".into())]);
+                self.client
+                    .respond(request, Json::object([("threads", vec![thread].into())]));
+            }
+            _ => return false,
+        }
+        true
+    }
+    fn reference(&mut self, variables: Vec<(String, TronType)>) -> usize {
+        self.references.push(variables);
+        self.references.len()
+    }
+    // arrays and objects get a reference to their elements
+    fn variable(&mut self, name: &str, value: &TronType) -> Json {
+        let reference = match value {
+            TronType::ArrayValue(items) => self.reference(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| (format!("[{}]", index), item.clone()))
+                    .collect(),
+            ),
+            TronType::Object(fields) => {
+                let mut fields: Vec<(String, TronType)> = fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), field.clone()))
+                    .collect();
+                fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+                self.reference(fields)
+            }
+            _ => 0,
+        };
+        Json::object([
+            ("name", name.into()),
+            ("value", value.to_string().into()),
+            ("type", value.to_type().into()),
+            ("variablesReference", reference.into()),
+        ])
+    }
+    fn stack_trace(&self, request: &Json, pause: &Pause) {
+        let frames: Vec<Json> = pause
+            .stack()
+            .iter()
+            .enumerate()
+            .map(|(id, frame)| {
+                let mut fields = vec![
+                    ("id".to_string(), id.into()),
+                    ("name".to_string(), frame.function.to_string().into()),
+                    ("line".to_string(), frame.line.into()),
+                    ("column".to_string(), 1.into()),
+                ];
+                if let Some(file) = &frame.file {
+                    let name = Path::new(&**file)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let path = absolute(file).to_string_lossy().to_string();
+                    let source = Json::object([("name", name.into()), ("path", path.into())]);
+                    fields.push(("source".to_string(), source));
+                }
+                Json::Object(fields)
+            })
+            .collect();
+        let total = frames.len();
+        self.client.respond(
+            request,
+            Json::object([
+                ("stackFrames", frames.into()),
+                ("totalFrames", total.into()),
+            ]),
+        );
+    }
+    // the paused frame sees its scopes and the globals; the frames below it only the globals
+    fn scopes(&mut self, request: &Json, pause: &Pause) {
+        let frame = argument(request, "frameId").and_then(Json::as_f64);
+        let mut scopes = vec![];
+        if frame == Some(0.0) {
+            for (depth, variables) in pause.scopes().into_iter().enumerate() {
+                let name = match depth {
+                    0 => "Locals".to_string(),
+                    depth => format!("Outer scope {}", depth),
+                };
+                let reference = self.reference(variables);
+                scopes.push(scope(&name, reference));
+            }
+        }
+        let reference = self.reference(pause.globals());
+        scopes.push(scope("Globals", reference));
+        self.client
+            .respond(request, Json::object([("scopes", scopes.into())]));
+    }
+    fn variables(&mut self, request: &Json) {
+        let reference = argument(request, "variablesReference")
+            .and_then(Json::as_f64)
+            .unwrap_or(0.0) as usize;
+        let Some(variables) = reference
+            .checked_sub(1)
+            .and_then(|index| self.references.get(index))
+            .cloned()
+        else {
+            return self.client.fail(request, "unknown variables reference");
+        };
+        let variables: Vec<Json> = variables
+            .iter()
+            .map(|(name, value)| self.variable(name, value))
+            .collect();
+        self.client
+            .respond(request, Json::object([("variables", variables.into())]));
+    }
+    fn evaluate(&mut self, request: &Json, pause: &Pause) {
+        let expression = argument(request, "expression")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+        match pause.evaluate(&format!("{};", expression.trim_end_matches(';'))) {
+            Ok(value) => {
+                let variable = self.variable("", &value);
+                let reference = variable.get("variablesReference").cloned();
+                self.client.respond(
+                    request,
+                    Json::object([
+                        ("result", value.to_string().into()),
+                        ("variablesReference", reference.unwrap_or(Json::Null)),
+                    ]),
+                );
+            }
+            Err(_) => self
+                .client
+                .fail(request, &format!("couldn't evaluate {}", expression)),
+        }
+    }
+}
+
+fn scope(name: &str, reference: usize) -> Json {
+    Json::object([
+        ("name", name.into()),
+        ("variablesReference", reference.into()),
+        ("expensive", false.into()),
+    ])
+}
+
+// shared with `serve`, which needs the adapter back once the program has ended
+struct Session(Rc<RefCell<Adapter>>);
+
+impl Frontend for Session {
+    fn paused(&mut self, pause: &mut Pause) -> Resume {
+        let mut adapter = self.0.borrow_mut();
+        if adapter.stopping {
+            return Resume::Stop;
+        }
+        adapter.pause_requested = false;
+        adapter.references.clear();
+        let reason = match pause.reason {
+            Reason::Entry => "entry",
+            Reason::Breakpoint => "breakpoint",
+            Reason::Step => "step",
+            Reason::Pause => "pause",
+        };
+        adapter.client.event(
+            "stopped",
+            Json::object([
+                ("reason", reason.into()),
+                ("threadId", THREAD_ID.into()),
+                ("allThreadsStopped", true.into()),
+            ]),
+        );
+        loop {
+            let Ok(request) = adapter.requests.recv() else {
+                return Resume::Stop;
+            };
+            let resume = match command_of(&request) {
+                "continue" => Resume::Continue,
+                "next" => Resume::StepOver,
+                "stepIn" => Resume::StepIn,
+                "stepOut" => Resume::StepOut,
+                "disconnect" | "terminate" => Resume::Stop,
+                "stackTrace" => {
+                    adapter.stack_trace(&request, pause);
+                    continue;
+                }
+                "scopes" => {
+                    adapter.scopes(&request, pause);
+                    continue;
+                }
+                "variables" => {
+                    adapter.variables(&request);
+                    continue;
+                }
+                "evaluate" => {
+                    adapter.evaluate(&request, pause);
+                    continue;
+                }
+                "pause" => {
+                    adapter.client.respond(&request, Json::object([]));
+                    continue;
+                }
+                _ => {
+                    if !adapter.common(&request, pause.breakpoints) {
+                        adapter.client.fail(&request, "not supported");
+                    }
+                    continue;
+                }
+            };
+            let body = match resume {
+                Resume::Continue => Json::object([("allThreadsContinued", true.into())]),
+                _ => Json::object([]),
+            };
+            adapter.client.respond(&request, body);
+            if resume == Resume::Stop {
+                adapter.stopping = true;
+            }
+            return resume;
+        }
+    }
+    fn running(&mut self, breakpoints: &mut Breakpoints) -> bool {
+        let mut adapter = self.0.borrow_mut();
+        while let Ok(request) = adapter.requests.try_recv() {
+            match command_of(&request) {
+                "pause" => {
+                    adapter.client.respond(&request, Json::object([]));
+                    adapter.pause_requested = true;
+                }
+                "disconnect" | "terminate" => {
+                    adapter.client.respond(&request, Json::object([]));
+                    adapter.stopping = true;
+                }
+                _ => {
+                    if !adapter.common(&request, breakpoints) {
+                        adapter.client.fail(&request, "the program is running");
+                    }
+                }
+            }
+        }
+        adapter.pause_requested || adapter.stopping
+    }
+}
+
+// what `launch` asked for
+struct Launch {
+    file: String,
+    source: String,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+fn launch(request: &Json) -> Result<Launch, String> {
+    if let Some(cwd) = argument(request, "cwd").and_then(Json::as_str) {
+        env::set_current_dir(cwd).map_err(|_| format!("can't change to {}", cwd))?;
+    }
+    let program = argument(request, "program")
+        .and_then(Json::as_str)
+        .ok_or("launch needs a program")?;
+    let source = fs::read_to_string(program).map_err(|_| format!("can't read {}", program))?;
+    let args = argument(request, "args")
+        .and_then(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|arg| arg.as_str().map(str::to_string))
+        .collect();
+    Ok(Launch {
+        file: relative(program),
+        source,
+        args,
+        stop_on_entry: argument(request, "stopOnEntry")
+            .and_then(Json::as_bool)
+            .unwrap_or(false),
+    })
+}
+
+fn serve() -> Result<(), String> {
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || {
+        let mut input = io::stdin().lock();
+        while let Some(message) = read_message(&mut input) {
+            if let Ok(message) = message {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    let client = Client {
+        seq: Arc::new(Mutex::new(0)),
+    };
+    let adapter = Rc::new(RefCell::new(Adapter {
+        requests,
+        client: client.clone(),
+        references: vec![],
+        pause_requested: false,
+        stopping: false,
+    }));
+    let mut breakpoints = Breakpoints::default();
+    let mut launched = None;
+    let mut configured = false;
+    while launched.is_none() || !configured {
+        let Ok(request) = adapter.borrow().requests.recv() else {
+            return Ok(());
+        };
+        match command_of(&request) {
+            "initialize" => {
+                client.respond(
+                    &request,
+                    Json::object([
+                        ("supportsConfigurationDoneRequest", true.into()),
+                        ("supportsEvaluateForHovers", true.into()),
+                        ("supportsTerminateRequest", true.into()),
+                    ]),
+                );
+                client.event("initialized", Json::object([]));
+            }
+            "launch" => match launch(&request) {
+                Ok(program) => {
+                    launched = Some(program);
+                    client.respond(&request, Json::object([]));
+                }
+                Err(message) => client.fail(&request, &message),
+            },
+            "configurationDone" => {
+                configured = true;
+                client.respond(&request, Json::object([]));
+            }
+            "disconnect" | "terminate" => {
+                client.respond(&request, Json::object([]));
+                return Ok(());
+            }
+            _ => {
+                if !adapter.borrow_mut().common(&request, &mut breakpoints) {
+                    client.fail(&request, "launch the program first");
+                }
+            }
+        }
+    }
+    let Launch {
+        file,
+        source,
+        args,
+        stop_on_entry,
+    } = launched.unwrap();
+
+    // stdout carries the protocol, so the program's output goes to the client as events and it
+    // can't read stdin with `@ask`; an error that ends it comes back here rather than ending the
+    // adapter
+    let output = client.clone();
+    redirect_output(move |text| {
+        output.event(
+            "output",
+            Json::object([("category", "stdout".into()), ("output", text.into())]),
+        )
+    });
+    let mut sandbox = options().sandbox;
+    sandbox.capabilities.ask = false;
+    set_options(Options {
+        sandbox,
+        report_errors: false,
+        ..options()
+    });
+    debugger::attach(
+        Box::new(Session(adapter.clone())),
+        breakpoints,
+        stop_on_entry,
+    );
+    let mut interpreter = Interpreter::new();
+    interpreter.set_file(&file);
+    interpreter.set_script_args(args);
+    let result = interpreter.run(&source);
+    debugger::detach();
+
+    let exit_code = match (&result, adapter.borrow().stopping) {
+        (Err(error), false) => {
+            client.event(
+                "output",
+                Json::object([
+                    ("category", "stderr".into()),
+                    ("output", format!("{}\n", error.summary()).into()),
+                ]),
+            );
+            1
+        }
+        _ => 0,
+    };
+    client.event("exited", Json::object([("exitCode", exit_code.into())]));
+    client.event("terminated", Json::object([]));
+    let mut adapter = adapter.borrow_mut();
+    let mut breakpoints = Breakpoints::default();
+    while let Ok(request) = adapter.requests.recv() {
+        match command_of(&request) {
+            "disconnect" | "terminate" => {
+                client.respond(&request, Json::object([]));
+                break;
+            }
+            _ => {
+                if !adapter.common(&request, &mut breakpoints) {
+                    client.fail(&request, "the program has ended");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn cli_dap() {
+    match on_program_thread(serve) {
+        Ok(()) => exit(0),
+        Err(_) => exit(1),
+    }
+}
@@ -0,0 +1,193 @@
+// `tron debug <file>`: runs a program with the tree-walking interpreter, pausing before its first
+// statement, at breakpoints and after steps to take commands from stdin
+use crate::commands::run::on_program_thread;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use tron_lang::debugger::{self, Breakpoints, Frontend, Pause, Reason, Resume};
+use tron_lang::utils::{exit, format_frame, TronError};
+use tron_lang::{Interpreter, TronType};
+
+const HELP: &str = "commands:
+  break <line> | <file>:<line>   pause at a line (b)
+  delete <line> | <file>:<line>  remove a breakpoint (d)
+  breakpoints                    list the breakpoints
+  continue                       run to the next breakpoint (c)
+  step                           run to the next statement, inside calls as well (s)
+  next                           run to the next statement of this function (n)
+  out                            run until this function returns (o)
+  stack                          list the calls that led here (bt)
+  locals                         print the variables of each scope, innermost first (l)
+  globals                        print the global variables (g)
+  print <expression>             evaluate an expression here (p)
+  list                           show the code around this line
+  quit                           end the program (q)";
+
+// lines of code shown before and after the paused line by `list`
+const LIST_CONTEXT: usize = 3;
+
+struct Terminal {
+    // source lines by file, read when first shown
+    sources: HashMap<String, Vec<String>>,
+    // set once the user ends the program, which isn't an error
+    quit: Rc<Cell<bool>>,
+}
+
+impl Terminal {
+    fn source_line(&mut self, file: &str, line: usize) -> Option<String> {
+        let lines = self.sources.entry(file.to_string()).or_insert_with(|| {
+            fs::read_to_string(file)
+                .map(|source| source.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+        lines.get(line.checked_sub(1)?).cloned()
+    }
+    fn show(&mut self, file: &str, line: usize, current: usize) {
+        if let Some(source) = self.source_line(file, line) {
+            let marker = if line == current { ">" } else { " " };
+            println!("{} {:>4} | {}", marker, line, source);
+        }
+    }
+    // runs a command that doesn't resume the program
+    fn command(&mut self, pause: &mut Pause, command: &str, argument: &str) {
+        match command {
+            "b" | "break" => match location(&pause.file, argument) {
+                Some((file, line)) => {
+                    pause.breakpoints.add(&file, line);
+                    println!("breakpoint at {}:{}", file, line);
+                }
+                None => println!("expected a line or <file>:<line>, got {:?}", argument),
+            },
+            "d" | "delete" => match location(&pause.file, argument) {
+                Some((file, line)) if pause.breakpoints.remove(&file, line) => {
+                    println!("deleted the breakpoint at {}:{}", file, line)
+                }
+                Some((file, line)) => println!("no breakpoint at {}:{}", file, line),
+                None => println!("expected a line or <file>:<line>, got {:?}", argument),
+            },
+            "breakpoints" => {
+                for (file, line) in pause.breakpoints.iter() {
+                    println!("{}:{}", file, line);
+                }
+            }
+            "bt" | "stack" => {
+                for (index, frame) in pause.stack().iter().enumerate() {
+                    println!("#{} {}", index, format_frame(frame));
+                }
+            }
+            "l" | "locals" => {
+                for (depth, scope) in pause.scopes().iter().enumerate() {
+                    println!("scope {}:", depth);
+                    print_variables(scope);
+                }
+            }
+            "g" | "globals" => print_variables(&pause.globals()),
+            "p" | "print" => {
+                let source = argument.trim_end_matches(';');
+                match pause.evaluate(&format!("{};", source)) {
+                    Ok(value) => println!("{}", value.to_string()),
+                    Err(message) if !message.is_empty() => println!("{}", message),
+                    Err(_) => {}
+                }
+            }
+            "list" => {
+                let first = pause.line.saturating_sub(LIST_CONTEXT).max(1);
+                for line in first..=pause.line + LIST_CONTEXT {
+                    let file = pause.file.clone();
+                    self.show(&file, line, pause.line);
+                }
+            }
+            "h" | "help" => println!("{}", HELP),
+            "" => {}
+            _ => println!("unknown command {}, `help` lists them", command),
+        }
+    }
+}
+
+impl Frontend for Terminal {
+    fn paused(&mut self, pause: &mut Pause) -> Resume {
+        let reason = match pause.reason {
+            Reason::Entry => "paused before the first statement",
+            Reason::Breakpoint => "paused at a breakpoint",
+            Reason::Step | Reason::Pause => "paused",
+        };
+        println!("{} at {}:{}", reason, pause.file, pause.line);
+        let file = pause.file.clone();
+        self.show(&file, pause.line, pause.line);
+        let mut input = io::stdin().lock();
+        loop {
+            print!("(tron) ");
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            // the end of the input ends the session like `quit`
+            if input.read_line(&mut line).unwrap_or(0) == 0 {
+                println!();
+                self.quit.set(true);
+                return Resume::Stop;
+            }
+            let line = line.trim();
+            let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+            match command {
+                "c" | "continue" => return Resume::Continue,
+                "s" | "step" => return Resume::StepIn,
+                "n" | "next" => return Resume::StepOver,
+                "o" | "out" => return Resume::StepOut,
+                "q" | "quit" => {
+                    self.quit.set(true);
+                    return Resume::Stop;
+                }
+                _ => self.command(pause, command, argument.trim()),
+            }
+        }
+    }
+}
+
+// `12` in the paused file or `lib/math.tron:12`
+fn location(file: &str, argument: &str) -> Option<(String, usize)> {
+    match argument.rsplit_once(':') {
+        Some((file, line)) => Some((file.to_string(), line.parse().ok()?)),
+        None => Some((file.to_string(), argument.parse().ok()?)),
+    }
+}
+
+fn print_variables(variables: &[(String, TronType)]) {
+    for (name, value) in variables {
+        println!("  {} = {}", name, value.to_string());
+    }
+}
+
+pub fn cli_debug(command: &str, path: PathBuf, script_args: Vec<String>) {
+    let Ok(source) = fs::read_to_string(path.join(command)) else {
+        TronError::throw("E0001", 0, vec![]);
+        exit(1);
+    };
+    let file = command.to_string();
+    let result = on_program_thread(move || {
+        let quit = Rc::new(Cell::new(false));
+        let terminal = Terminal {
+            sources: HashMap::new(),
+            quit: quit.clone(),
+        };
+        debugger::attach(Box::new(terminal), Breakpoints::default(), true);
+        let mut interpreter = Interpreter::new();
+        interpreter.set_file(&file);
+        interpreter.set_script_args(script_args);
+        let result = interpreter.run(&source);
+        debugger::detach();
+        match result {
+            Err(_) if quit.get() => Ok(()),
+            Err(error) => Err(error.message),
+            Ok(()) => {
+                println!("the program finished");
+                Ok(())
+            }
+        }
+    });
+    match result {
+        Ok(()) => exit(0),
+        Err(_) => exit(1),
+    }
+}
@@ -0,0 +1,38 @@
+// `tron explain <code>`: the long form of an error, with a program that reports it and its fix
+use tron_lang::utils::errors::find;
+use tron_lang::utils::options::options;
+use tron_lang::utils::{exit, TronError};
+
+fn paint(color: &str, text: &str) -> String {
+    if options().color {
+        format!("\x1B[{}m{}\x1B[0m", color, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn example(title: &str, code: &str) {
+    println!();
+    println!("{}", paint("2", title));
+    for line in code.lines() {
+        println!("    {}", line);
+    }
+}
+
+pub fn cli_explain(code: &str) {
+    let Some(error) = find(code) else {
+        TronError::throw("E0013", 0, vec![code.to_string()]);
+        exit(1);
+    };
+    println!(
+        "{}: {}",
+        paint("91;1", error.code),
+        error.template.replace('\n', "\\n")
+    );
+    println!();
+    println!("{}", error.explanation);
+    if let (Some(wrong), Some(fixed)) = (error.wrong, error.fixed) {
+        example("wrong:", wrong);
+        example("fixed:", fixed);
+    }
+}
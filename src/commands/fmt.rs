@@ -0,0 +1,78 @@
+// `tron fmt`: rewrites tron files in the formatter's style, or with `--check` lists the ones that
+// aren't in it, for pre-commit hooks and CI
+use std::fs;
+use std::path::{Path, PathBuf};
+use tron_lang::formatter::format;
+use tron_lang::interpreter::stack;
+use tron_lang::utils::options::inform;
+use tron_lang::utils::{exit, TronError};
+
+// the files `path` names: itself, or the tron files under it, leaving out hidden directories
+pub fn files(path: &Path, found: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        found.push(path.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut entries: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if entry.is_dir() && !hidden {
+            files(&entry, found);
+        } else if entry.extension().is_some_and(|ext| ext == "tron") {
+            found.push(entry);
+        }
+    }
+}
+
+pub fn cli_fmt(paths: &[String], check: bool) {
+    let mut found = vec![];
+    if paths.is_empty() {
+        files(Path::new("."), &mut found);
+    }
+    for path in paths {
+        files(Path::new(path), &mut found);
+    }
+    let mut failed = false;
+    for file in found {
+        let name = file.display().to_string();
+        let Ok(source) = fs::read_to_string(&file) else {
+            TronError::throw("E0010", 0, vec![name]);
+            failed = true;
+            continue;
+        };
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                stack::add_source(&name, &source);
+                for mut error in errors {
+                    error.file = Some(name.clone());
+                    error.report();
+                }
+                TronError::throw("E0010", 0, vec![name]);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", name);
+            failed = true;
+        } else if fs::write(&file, formatted).is_ok() {
+            inform(&format!("formatted {}", name));
+        } else {
+            TronError::throw("E0010", 0, vec![name]);
+            failed = true;
+        }
+    }
+    exit(i32::from(failed));
+}
@@ -1,11 +1,61 @@
-pub fn cli_help() {
-    println!(
-        "
-        \x1B[36mtron\x1B[0m \x1B[32mrun\x1B[0m \x1B[31m<filename>\x1B[0m - run tron files
-        \x1B[36mtron\x1B[0m \x1B[32mversion\x1B[0m - installed version
-        \x1B[36mtron\x1B[0m \x1B[32mupdate\x1B[0m - install the latest version
-
-             \x1B[36mTron Programming Language (3.1.0)\x1B[0m
-        "
-    );
+use super::args::{CommandSpec, FlagSpec, COMMANDS, GLOBAL_FLAGS};
+use tron_lang::utils::options::options;
+
+fn paint(color: &str, text: &str) -> String {
+    if options().color {
+        format!("\x1B[{}m{}\x1B[0m", color, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn usage(command: &CommandSpec) -> String {
+    let mut line = format!("{} {}", paint("36", "tron"), paint("32", command.name));
+    if !command.args.is_empty() {
+        line.push(' ');
+        line.push_str(&paint("31", &command.args.join(" ")));
+    }
+    line
+}
+
+fn flag_line(flag: &FlagSpec) -> String {
+    let mut name = format!("--{}", flag.name);
+    if let Some(value) = flag.value {
+        name.push_str(&format!("=<{}>", value));
+    }
+    if let Some(short) = flag.short {
+        name = format!("-{}, {}", short, name);
+    }
+    let padding = " ".repeat(28usize.saturating_sub(name.len()));
+    format!("        {}{} {}", paint("33", &name), padding, flag.help)
+}
+
+pub fn cli_help(command: Option<&CommandSpec>) {
+    match command {
+        Some(command) => {
+            println!();
+            println!("        {} - {}", usage(command), command.about);
+            println!();
+            for flag in command.flags.iter().chain(GLOBAL_FLAGS.iter()) {
+                println!("{}", flag_line(flag));
+            }
+            println!();
+        }
+        None => {
+            println!();
+            for command in COMMANDS {
+                println!("        {} - {}", usage(command), command.about);
+            }
+            println!();
+            for flag in GLOBAL_FLAGS {
+                println!("{}", flag_line(flag));
+            }
+            println!();
+            println!(
+                "             {}",
+                paint("36", "Tron Programming Language (3.1.0)")
+            );
+            println!();
+        }
+    }
 }
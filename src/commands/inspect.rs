@@ -0,0 +1,90 @@
+// `tron tokens`, `tron ast` and `tron resolve`: what the scanner, the parser and the resolver
+// make of a file, for tooling and for debugging the language
+use std::fs;
+use tron_lang::ast::{dump, references, to_json};
+use tron_lang::interpreter::stack;
+use tron_lang::parser::Parser;
+use tron_lang::resolver::Resolver;
+use tron_lang::scanner::{Scanner, Statement, Token};
+use tron_lang::utils::report::LineStarts;
+use tron_lang::utils::{collect_errors, exit, TronError};
+
+// the source of `file`, or the program ends with its errors reported
+fn read(file: &str) -> String {
+    let Ok(source) = fs::read_to_string(file) else {
+        TronError::throw("E0014", 0, vec![file.to_string()]);
+        exit(1);
+    };
+    stack::add_source(file, &source);
+    source
+}
+
+fn check<T>(file: &str, (value, errors): (T, Vec<TronError>)) -> T {
+    if errors.is_empty() {
+        return value;
+    }
+    for mut error in errors {
+        error.file = Some(file.to_string());
+        error.report();
+    }
+    exit(1);
+}
+
+fn tokens(file: &str, source: &str) -> Vec<Token> {
+    check(
+        file,
+        collect_errors(|| Scanner::new(source).scan_tokens().unwrap_or_default()),
+    )
+}
+
+fn statements(file: &str, source: &str) -> Vec<Statement> {
+    let tokens = tokens(file, source);
+    check(
+        file,
+        collect_errors(|| Parser::new(tokens).parse().unwrap_or_default()),
+    )
+}
+
+pub fn cli_tokens(file: &str) {
+    let source = read(file);
+    let lines = LineStarts::new(&source);
+    for token in tokens(file, &source) {
+        let position = lines.position(&source, token.span.start);
+        let location = format!("{}:{}", position.line, position.column);
+        let token_type = token.token_type.to_string();
+        println!("{:<8}{:<14}{:?}", location, token_type, token.lexeme);
+    }
+}
+
+pub fn cli_ast(file: &str, json: bool) {
+    let source = read(file);
+    let json_ast = to_json(&statements(file, &source));
+    if json {
+        println!("{}", json_ast);
+    } else {
+        print!("{}", dump(&json_ast));
+    }
+}
+
+pub fn cli_resolve(file: &str) {
+    let source = read(file);
+    let statements = statements(file, &source);
+    let locals = check(
+        file,
+        collect_errors(|| {
+            Resolver::new()
+                .resolve(&statements.iter().collect())
+                .unwrap_or_default()
+        }),
+    );
+    let lines = LineStarts::new(&source);
+    for (id, name) in references(&statements) {
+        let position = lines.position(&source, name.span.start);
+        let location = format!("{}:{}", position.line, position.column);
+        let slot = match locals.get(&id) {
+            Some(slot) => format!("depth {}, slot {}", slot.depth, slot.index),
+            None => "global".to_string(),
+        };
+        println!("{:<8}#{:<6}{:<16}{}", location, id, name.lexeme, slot);
+    }
+}
@@ -0,0 +1,104 @@
+// installation layout shared by `update` and `uninstall`
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tron_lang::utils::sha256::sha256_hex;
+
+pub const MANIFEST: &str = ".tron-manifest";
+pub const PREVIOUS: &str = ".tron-previous";
+pub const CHECKSUMS: &str = "SHA256SUMS";
+
+pub struct ManifestEntry {
+    pub checksum: String,
+    pub path: String,
+}
+
+// `TRON_HOME` when set, otherwise the directory holding the running binary
+pub fn install_root() -> Result<PathBuf, String> {
+    if let Ok(home) = std::env::var("TRON_HOME") {
+        return Ok(PathBuf::from(home));
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    match exe.parent() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => Err("failed to locate the installation directory".to_string()),
+    }
+}
+
+// reads `<sha256>  <path>` lines, the format written by `sha256sum`
+pub fn read_manifest(path: &Path) -> Result<Vec<ManifestEntry>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut entries = vec![];
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (checksum, file) = match line.split_once(char::is_whitespace) {
+            Some((checksum, file)) => (checksum, file.trim_start().trim_start_matches('*')),
+            None => return Err(format!("malformed line in {}: {}", path.display(), line)),
+        };
+        if !is_relative_inside(file) {
+            return Err(format!("refusing to install outside of the root: {}", file));
+        }
+        entries.push(ManifestEntry {
+            checksum: checksum.to_lowercase(),
+            path: file.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+pub fn write_manifest(path: &Path, entries: &[ManifestEntry]) -> Result<(), String> {
+    let contents: String = entries
+        .iter()
+        .map(|entry| format!("{}  {}\n", entry.checksum, entry.path))
+        .collect();
+    fs::write(path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn file_checksum(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(sha256_hex(&bytes))
+}
+
+// copies through a temporary name and renames, so replacing the running binary is safe
+pub fn install_file(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    let staging = to.with_file_name(format!(
+        ".{}.tron-new",
+        to.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    fs::copy(from, &staging).map_err(|e| format!("{}: {}", from.display(), e))?;
+    fs::rename(&staging, to).map_err(|e| {
+        let _ = fs::remove_file(&staging);
+        format!("{}: {}", to.display(), e)
+    })
+}
+
+// removes a file and then any directories it leaves empty, stopping at the root
+pub fn remove_installed(root: &Path, relative: &str) -> Result<bool, String> {
+    let path = root.join(relative);
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == root || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+    Ok(true)
+}
+
+fn is_relative_inside(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
@@ -0,0 +1,155 @@
+// `tron lint`: reports what the lint rules find in tron files, and with `--fix` applies the fixes
+// that can't change what a program does
+use crate::commands::fmt::files;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tron_lang::interpreter::stack;
+use tron_lang::lint::{apply_fixes, lint, Config, Finding, Level};
+use tron_lang::utils::options::{inform, options, ErrorFormat};
+use tron_lang::utils::report::{add_sarif, positions, Report};
+use tron_lang::utils::{exit, TronError};
+
+// the `tron.toml` in the directory of `file` or the closest one above it
+fn config_for(file: &Path) -> Result<Config, String> {
+    let directory = fs::canonicalize(file)
+        .ok()
+        .and_then(|file| file.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    for directory in directory.ancestors() {
+        let path = directory.join("tron.toml");
+        if let Ok(text) = fs::read_to_string(&path) {
+            return Config::parse(&text)
+                .map_err(|message| format!("{}: {}", path.display(), message));
+        }
+    }
+    Ok(Config::default())
+}
+
+// `use` paths are relative to the working directory, as for `tron run`, or to the file
+fn load(file: &Path) -> impl FnMut(&str) -> Option<(String, String)> {
+    let directories: Vec<PathBuf> = [PathBuf::from(".")]
+        .into_iter()
+        .chain(file.parent().map(Path::to_path_buf))
+        .collect();
+    move |library| {
+        let library = library.trim_start_matches('/');
+        directories.iter().find_map(|directory| {
+            let path = directory.join(library);
+            let source = fs::read_to_string(&path).ok()?;
+            Some((path.to_string_lossy().to_string(), source))
+        })
+    }
+}
+
+fn print(name: &str, source: &str, finding: &Finding) {
+    let (start, end) = positions(source, finding.span);
+    let column = start.column;
+    let options = options();
+    let report = Report {
+        code: finding.rule,
+        severity: finding.level.name(),
+        message: &finding.message,
+        file: Some(name),
+        line: finding.line,
+        span: Some((start, end)),
+        trace: &[],
+    };
+    match options.error_format {
+        ErrorFormat::Json => println!("{}", report.json()),
+        ErrorFormat::Sarif => add_sarif(&report),
+        ErrorFormat::Human if !options.color => println!(
+            "{}:{}:{}: {}[{}] {}",
+            name,
+            finding.line,
+            column,
+            finding.level.name(),
+            finding.rule,
+            finding.message
+        ),
+        ErrorFormat::Human => {
+            let color = match finding.level {
+                Level::Error => "91",
+                _ => "93",
+            };
+            println!(
+                "\x1B[96m{}:{}:{}\x1B[0m: \x1B[{};1m{}[{}]\x1B[0m {}",
+                name,
+                finding.line,
+                column,
+                color,
+                finding.level.name(),
+                finding.rule,
+                finding.message
+            )
+        }
+    }
+}
+
+pub fn cli_lint(paths: &[String], fix: bool) {
+    let mut found = vec![];
+    if paths.is_empty() {
+        files(Path::new("."), &mut found);
+    }
+    for path in paths {
+        files(Path::new(path), &mut found);
+    }
+    let mut failed = false;
+    for file in found {
+        let name = file.display().to_string();
+        let config = match config_for(&file) {
+            Ok(config) => config,
+            Err(message) => {
+                TronError::throw("E0012", 0, vec![message]);
+                failed = true;
+                continue;
+            }
+        };
+        let Ok(mut source) = fs::read_to_string(&file) else {
+            TronError::throw("E0011", 0, vec![name]);
+            failed = true;
+            continue;
+        };
+        let mut findings = match lint(&source, &config, load(&file)) {
+            Ok(findings) => findings,
+            Err(errors) => {
+                stack::add_source(&name, &source);
+                for mut error in errors {
+                    error.file = Some(name.clone());
+                    error.report();
+                }
+                TronError::throw("E0011", 0, vec![name]);
+                failed = true;
+                continue;
+            }
+        };
+        if fix {
+            let fixes = findings
+                .iter()
+                .filter(|finding| finding.fix.is_some())
+                .count();
+            let fixed = apply_fixes(&source, &findings);
+            if fixed != source {
+                if fs::write(&file, &fixed).is_err() {
+                    TronError::throw("E0011", 0, vec![name]);
+                    failed = true;
+                    continue;
+                }
+                inform(&format!("fixed {} in {}", problems(fixes), name));
+                source = fixed;
+                findings = lint(&source, &config, load(&file)).unwrap_or_default();
+            }
+        }
+        for finding in &findings {
+            print(&name, &source, finding);
+            failed |= finding.level == Level::Error;
+        }
+    }
+    exit(i32::from(failed));
+}
+
+fn problems(count: usize) -> String {
+    match count {
+        1 => "1 problem".to_string(),
+        count => format!("{} problems", count),
+    }
+}
@@ -0,0 +1,572 @@
+// `tron lsp`: a Language Server Protocol server on stdin and stdout, which gives any editor that
+// speaks it diagnostics, definitions, references, hovers, completion, outlines and formatting for
+// Tron files
+//
+// Documents are synced whole and analysed by `tron_lang::analysis` on every change, which is
+// quick next to typing for files of the size Tron programs are.
+use crate::commands::run::on_program_thread;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tron_lang::analysis::{keywords, Analysis, Severity, Symbol, SymbolKind, TYPES};
+use tron_lang::formatter;
+use tron_lang::scanner::Span;
+use tron_lang::utils::exit;
+use tron_lang::utils::json::Json;
+use tron_lang::utils::protocol::{read_message, write_message};
+use tron_lang::utils::report::LineStarts;
+
+// error codes of JSON-RPC
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+
+// kinds of the protocol's `CompletionItem`s and `DocumentSymbol`s
+const COMPLETION_FUNCTION: usize = 3;
+const COMPLETION_VARIABLE: usize = 6;
+const COMPLETION_KEYWORD: usize = 14;
+const SYMBOL_FUNCTION: usize = 12;
+const SYMBOL_VARIABLE: usize = 13;
+
+fn send(fields: Vec<(String, Json)>) {
+    let mut message = vec![("jsonrpc".to_string(), "2.0".into())];
+    message.extend(fields);
+    let _ = write_message(&mut io::stdout().lock(), &Json::Object(message));
+}
+
+fn respond(request: &Json, result: Json) {
+    send(vec![
+        ("id".to_string(), id_of(request)),
+        ("result".to_string(), result),
+    ]);
+}
+
+fn fail(request: &Json, code: f64, message: &str) {
+    send(vec![
+        ("id".to_string(), id_of(request)),
+        (
+            "error".to_string(),
+            Json::object([("code", Json::Number(code)), ("message", message.into())]),
+        ),
+    ]);
+}
+
+fn notify(method: &str, params: Json) {
+    send(vec![
+        ("method".to_string(), method.into()),
+        ("params".to_string(), params),
+    ]);
+}
+
+fn id_of(request: &Json) -> Json {
+    request.get("id").cloned().unwrap_or(Json::Null)
+}
+
+fn param<'a>(request: &'a Json, path: &[&str]) -> Option<&'a Json> {
+    path.iter()
+        .try_fold(request.get("params")?, |value, key| value.get(key))
+}
+
+fn uri_of_request(request: &Json) -> &str {
+    param(request, &["textDocument", "uri"])
+        .and_then(Json::as_str)
+        .unwrap_or("")
+}
+
+// `file:///home/me/my%20code/main.tron` is `/home/me/my code/main.tron`
+fn path_of(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = path
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // `/c:/code` on windows
+    match path.as_bytes() {
+        [b'/', _, b':', ..] => Some(PathBuf::from(&path[1..])),
+        _ => Some(PathBuf::from(path)),
+    }
+}
+
+fn uri_of(path: &str) -> String {
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+// protocol positions count lines from 0, and characters in UTF-16 code units
+fn position(text: &str, lines: &LineStarts, offset: usize) -> Json {
+    let offset = offset.min(text.len());
+    let (line, line_start) = lines.line(offset);
+    let character: usize = text[line_start..offset].chars().map(char::len_utf16).sum();
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+fn offset(text: &str, lines: &LineStarts, position: &Json) -> usize {
+    let number = |key: &str| {
+        position
+            .get(key)
+            .and_then(Json::as_f64)
+            .map_or(0, |number| number as usize)
+    };
+    let Some(line_start) = lines.start(number("line")) else {
+        return text.len();
+    };
+    let mut units = 0;
+    for (index, c) in text[line_start..].char_indices() {
+        if units >= number("character") || c == '\n' {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn range(text: &str, lines: &LineStarts, span: Span) -> Json {
+    Json::object([
+        ("start", position(text, lines, span.start)),
+        ("end", position(text, lines, span.end)),
+    ])
+}
+
+// the text of a line, counted from 1, without its indentation
+fn line_span(text: &str, lines: &LineStarts, line: usize) -> Span {
+    // past the end is the last line
+    let start = lines
+        .start(line.saturating_sub(1))
+        .unwrap_or_else(|| lines.line(text.len()).1);
+    let end = text[start..]
+        .find('\n')
+        .map_or(text.len(), |index| start + index);
+    let indentation = text[start..end].len() - text[start..end].trim_start().len();
+    Span {
+        start: start + indentation,
+        end,
+    }
+}
+
+struct Document {
+    text: String,
+    lines: LineStarts,
+    analysis: Analysis,
+}
+
+struct Server {
+    documents: HashMap<String, Document>,
+    // the workspace, which `use` paths are relative to as they are to the working directory of
+    // `tron run`
+    root: Option<PathBuf>,
+    shut_down: bool,
+}
+
+impl Server {
+    fn open(&mut self, uri: &str, text: String) {
+        let path = path_of(uri);
+        let directories: Vec<PathBuf> = self
+            .root
+            .iter()
+            .cloned()
+            .chain(
+                path.as_deref()
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf),
+            )
+            .collect();
+        let analysis = Analysis::new(&text, |library| {
+            let library = library.trim_start_matches('/');
+            directories.iter().find_map(|directory| {
+                let path = directory.join(library);
+                let source = fs::read_to_string(&path).ok()?;
+                Some((path.to_string_lossy().to_string(), source))
+            })
+        });
+        let lines = LineStarts::new(&text);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let span = diagnostic
+                    .span
+                    .unwrap_or_else(|| line_span(&text, &lines, diagnostic.line));
+                let severity: usize = match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                };
+                Json::object([
+                    ("range", range(&text, &lines, span)),
+                    ("severity", severity.into()),
+                    ("code", diagnostic.code.as_str().into()),
+                    ("source", "tron".into()),
+                    ("message", diagnostic.message.as_str().into()),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        notify(
+            "textDocument/publishDiagnostics",
+            Json::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+        );
+        self.documents.insert(
+            uri.to_string(),
+            Document {
+                text,
+                lines,
+                analysis,
+            },
+        );
+    }
+
+    fn close(&mut self, uri: &str) {
+        self.documents.remove(uri);
+        notify(
+            "textDocument/publishDiagnostics",
+            Json::object([("uri", uri.into()), ("diagnostics", Json::Array(vec![]))]),
+        );
+    }
+
+    // the document a request is about, and the symbol at its position
+    fn symbol(&self, request: &Json) -> Option<(&Document, usize)> {
+        let document = self.documents.get(uri_of_request(request))?;
+        let at = offset(
+            &document.text,
+            &document.lines,
+            param(request, &["position"])?,
+        );
+        Some((document, document.analysis.symbol_at(at)?))
+    }
+
+    // where a symbol is declared, `None` for builtins
+    fn location(&self, uri: &str, document: &Document, symbol: &Symbol) -> Option<Json> {
+        let (uri, declared) = match symbol.library {
+            _ if symbol.kind == SymbolKind::Builtin => return None,
+            Some(library) => {
+                let library = &document.analysis.libraries[library];
+                let lines = LineStarts::new(&library.source);
+                (
+                    uri_of(&library.path),
+                    range(&library.source, &lines, symbol.span),
+                )
+            }
+            None => (
+                uri.to_string(),
+                range(&document.text, &document.lines, symbol.span),
+            ),
+        };
+        Some(Json::object([("uri", uri.into()), ("range", declared)]))
+    }
+
+    fn hover(&self, request: &Json) -> Json {
+        let Some((document, symbol)) = self.symbol(request) else {
+            return Json::Null;
+        };
+        let symbol = &document.analysis.symbols[symbol];
+        let value = match symbol.kind {
+            SymbolKind::Builtin => symbol.detail.clone(),
+            _ => format!("```tron\n{}\n```", symbol.detail),
+        };
+        Json::object([(
+            "contents",
+            Json::object([("kind", "markdown".into()), ("value", value.into())]),
+        )])
+    }
+
+    fn definition(&self, request: &Json) -> Json {
+        let uri = uri_of_request(request);
+        self.symbol(request)
+            .and_then(|(document, symbol)| {
+                self.location(uri, document, &document.analysis.symbols[symbol])
+            })
+            .unwrap_or(Json::Null)
+    }
+
+    fn references(&self, request: &Json) -> Json {
+        let uri = uri_of_request(request);
+        let Some((document, symbol)) = self.symbol(request) else {
+            return Json::Array(vec![]);
+        };
+        let include_declaration = param(request, &["context", "includeDeclaration"])
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let declaration = &document.analysis.symbols[symbol];
+        let mut locations: Vec<Json> = vec![];
+        if include_declaration {
+            locations.extend(self.location(uri, document, declaration));
+        }
+        locations.extend(document.analysis.references_to(symbol).map(|span| {
+            Json::object([
+                ("uri", uri.into()),
+                ("range", range(&document.text, &document.lines, span)),
+            ])
+        }));
+        Json::Array(locations)
+    }
+
+    fn completion(&self, request: &Json) -> Json {
+        let Some(document) = self.documents.get(uri_of_request(request)) else {
+            return Json::Array(vec![]);
+        };
+        let Some(position) = param(request, &["position"]) else {
+            return Json::Array(vec![]);
+        };
+        let at = offset(&document.text, &document.lines, position);
+        let item = |label: &str, kind: usize, detail: &str| {
+            Json::object([
+                ("label", label.into()),
+                ("kind", kind.into()),
+                ("detail", detail.into()),
+            ])
+        };
+        let mut items: Vec<Json> = document
+            .analysis
+            .visible_at(at)
+            .into_iter()
+            .map(|symbol| {
+                let kind = match symbol.kind {
+                    SymbolKind::Function | SymbolKind::Builtin => COMPLETION_FUNCTION,
+                    SymbolKind::Variable | SymbolKind::Parameter => COMPLETION_VARIABLE,
+                };
+                item(&symbol.name, kind, &symbol.detail)
+            })
+            .collect();
+        let keywords = keywords();
+        items.extend(
+            keywords
+                .iter()
+                .map(|keyword| item(keyword, COMPLETION_KEYWORD, "keyword")),
+        );
+        items.extend(
+            TYPES
+                .iter()
+                .filter(|name| !keywords.contains(name))
+                .map(|name| item(name, COMPLETION_KEYWORD, "type")),
+        );
+        Json::Array(items)
+    }
+
+    fn document_symbols(&self, request: &Json) -> Json {
+        let Some(document) = self.documents.get(uri_of_request(request)) else {
+            return Json::Array(vec![]);
+        };
+        Json::Array(outline(document, None))
+    }
+}
+
+// the variables and functions declared in `parent`, or at the top level, with those declared in
+// each function nested under it
+fn outline(document: &Document, parent: Option<usize>) -> Vec<Json> {
+    let symbols = &document.analysis.symbols;
+    (0..symbols.len())
+        .filter(|index| {
+            let symbol = &symbols[*index];
+            symbol.library.is_none()
+                && symbol.parent == parent
+                && matches!(symbol.kind, SymbolKind::Variable | SymbolKind::Function)
+        })
+        .map(|index| {
+            let symbol = &symbols[index];
+            let kind = match symbol.kind {
+                SymbolKind::Function => SYMBOL_FUNCTION,
+                _ => SYMBOL_VARIABLE,
+            };
+            Json::object([
+                ("name", symbol.name.as_str().into()),
+                ("detail", symbol.detail.as_str().into()),
+                ("kind", kind.into()),
+                (
+                    "range",
+                    range(&document.text, &document.lines, symbol.extent),
+                ),
+                (
+                    "selectionRange",
+                    range(&document.text, &document.lines, symbol.span),
+                ),
+                ("children", outline(document, Some(index)).into()),
+            ])
+        })
+        .collect()
+}
+
+// a single edit, as the protocol wants it, replacing `span` of the document with `text`
+fn edit(document: &Document, span: Span, text: String) -> Json {
+    if document.text[span.start..span.end] == text {
+        return Json::Array(vec![]);
+    }
+    Json::Array(vec![Json::object([
+        ("range", range(&document.text, &document.lines, span)),
+        ("newText", text.into()),
+    ])])
+}
+
+// the formatter always writes its own style, whatever the editor's options; a file that doesn't
+// parse is left alone
+fn formatting(server: &Server, request: &Json) -> Json {
+    let Some(document) = server.documents.get(uri_of_request(request)) else {
+        return Json::Array(vec![]);
+    };
+    let Ok(formatted) = formatter::format(&document.text) else {
+        return Json::Array(vec![]);
+    };
+    let whole = Span {
+        start: 0,
+        end: document.text.len(),
+    };
+    edit(document, whole, formatted)
+}
+
+fn range_formatting(server: &Server, request: &Json) -> Json {
+    let Some(document) = server.documents.get(uri_of_request(request)) else {
+        return Json::Array(vec![]);
+    };
+    let (Some(start), Some(end)) = (
+        param(request, &["range", "start"]),
+        param(request, &["range", "end"]),
+    ) else {
+        return Json::Array(vec![]);
+    };
+    let selected = Span {
+        start: offset(&document.text, &document.lines, start),
+        end: offset(&document.text, &document.lines, end),
+    };
+    match formatter::format_range(&document.text, selected) {
+        Ok(Some((span, formatted))) => edit(document, span, formatted),
+        _ => Json::Array(vec![]),
+    }
+}
+
+fn initialize(server: &mut Server, request: &Json) -> Json {
+    server.root = param(request, &["rootUri"])
+        .and_then(Json::as_str)
+        .and_then(path_of);
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                (
+                    "textDocumentSync",
+                    Json::object([("openClose", true.into()), ("change", 1.into())]),
+                ),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                (
+                    "completionProvider",
+                    Json::object([("triggerCharacters", vec![Json::from("@")].into())]),
+                ),
+                ("documentSymbolProvider", true.into()),
+                ("documentFormattingProvider", true.into()),
+                ("documentRangeFormattingProvider", true.into()),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", "tron".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn serve() -> Result<(), String> {
+    let mut server = Server {
+        documents: HashMap::new(),
+        root: None,
+        shut_down: false,
+    };
+    let mut input = io::stdin().lock();
+    while let Some(message) = read_message(&mut input) {
+        let Ok(message) = message else {
+            continue;
+        };
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let is_request = message.get("id").is_some();
+        if server.shut_down && is_request {
+            fail(&message, INVALID_REQUEST, "the server has shut down");
+            continue;
+        }
+        match method {
+            "initialize" => {
+                let result = initialize(&mut server, &message);
+                respond(&message, result);
+            }
+            "shutdown" => {
+                server.shut_down = true;
+                respond(&message, Json::Null);
+            }
+            "exit" => {
+                return if server.shut_down {
+                    Ok(())
+                } else {
+                    Err(String::new())
+                }
+            }
+            "textDocument/didOpen" => {
+                let uri = uri_of_request(&message).to_string();
+                let text = param(&message, &["textDocument", "text"])
+                    .and_then(Json::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                server.open(&uri, text);
+            }
+            "textDocument/didChange" => {
+                let uri = uri_of_request(&message).to_string();
+                // documents are synced whole, so the last change has all of the text
+                let text = param(&message, &["contentChanges"])
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                if let Some(text) = text {
+                    server.open(&uri, text.to_string());
+                }
+            }
+            "textDocument/didClose" => server.close(uri_of_request(&message)),
+            "textDocument/hover" => respond(&message, server.hover(&message)),
+            "textDocument/definition" => respond(&message, server.definition(&message)),
+            "textDocument/references" => respond(&message, server.references(&message)),
+            "textDocument/completion" => respond(&message, server.completion(&message)),
+            "textDocument/documentSymbol" => respond(&message, server.document_symbols(&message)),
+            "textDocument/formatting" => respond(&message, formatting(&server, &message)),
+            "textDocument/rangeFormatting" => {
+                respond(&message, range_formatting(&server, &message))
+            }
+            _ if is_request => fail(&message, METHOD_NOT_FOUND, "not supported"),
+            // notifications the server has no use for, such as `initialized`
+            _ => {}
+        }
+        let _ = io::stdout().flush();
+    }
+    // the client went away without asking the server to exit
+    Err(String::new())
+}
+
+pub fn cli_lsp() {
+    match on_program_thread(serve) {
+        Ok(()) => exit(0),
+        Err(_) => exit(1),
+    }
+}
@@ -1,11 +1,15 @@
+pub mod args;
+pub mod bench;
+pub mod dap;
+pub mod debug;
+pub mod explain;
+pub mod fmt;
+pub mod help;
+pub mod inspect;
+pub mod install;
+pub mod lint;
+pub mod lsp;
 pub mod run;
-pub mod version;
+pub mod uninstall;
 pub mod update;
-pub mod help;
-
-
-
-
-
-
-
+pub mod version;
@@ -1,11 +1,124 @@
-use crate::run_file;
-use std::{path::PathBuf, process::exit};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use std::{fs, io::Read, path::PathBuf, thread};
+use tron_lang::interpreter::limit_stack;
+use tron_lang::sandbox::format_duration;
+use tron_lang::utils::exit;
+use tron_lang::utils::options::{options, set_options, Engine};
+use tron_lang::{utils::TronError, vm::Vm, Interpreter};
 
-pub fn cli_run(command: &str, path: PathBuf) {
+// `file` is the path as given, which stack traces show
+pub fn run_file(path: &str, file: &str, script_args: Vec<String>) -> Result<(), String> {
+    match fs::read_to_string(path) {
+        Err(msg) => {
+            TronError::throw("E0001", 0, vec![]);
+            Err(msg.to_string())
+        }
+        Ok(contents) => run(&contents, file, script_args),
+    }
+}
+
+// native stack a tron call may take in the tree-walking interpreter, with room to spare: calls
+// nested in blocks, a loop and a `try` measured 12 KiB, and several times that unoptimized
+#[cfg(not(debug_assertions))]
+const STACK_PER_CALL: usize = 24 * 1024;
+#[cfg(debug_assertions)]
+const STACK_PER_CALL: usize = 128 * 1024;
+
+// past this, recursion stops with a stack overflow (E4028) before `--max-depth`
+const MAX_STACK: usize = 256 * 1024 * 1024;
+
+// the vm keeps tron calls off the native stack, which is left to parsing and natives
+const VM_STACK: usize = 16 * 1024 * 1024;
+
+// how long past `--timeout` a program blocked in a native like `@cmd` is given before it is stopped
+const TIMEOUT_GRACE: Duration = Duration::from_millis(500);
+
+pub fn run(contents: &str, file: &str, script_args: Vec<String>) -> Result<(), String> {
+    let contents = contents.to_string();
+    let file = file.to_string();
+    on_program_thread(move || run_on_engine(&contents, &file, script_args))
+}
+
+// tree-walking tron calls recurse on the native stack, so programs run on a thread big enough for
+// `--max-depth` of them
+pub fn on_program_thread(
+    program: impl FnOnce() -> Result<(), String> + Send + 'static,
+) -> Result<(), String> {
+    let options = options();
+    let stack_size = match options.engine {
+        Engine::Tree => (options.max_depth + 1)
+            .saturating_mul(STACK_PER_CALL)
+            .min(MAX_STACK),
+        Engine::Vm => VM_STACK,
+    };
+    let (done, finished) = mpsc::channel();
+    let program = thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            set_options(options);
+            limit_stack(stack_size);
+            let result = program();
+            let _ = done.send(());
+            result
+        })
+        .unwrap_or_else(|_| {
+            TronError::throw("E0009", 0, vec![options.max_depth.to_string()]);
+            exit(1)
+        });
+    // the program checks the clock between statements, which a blocked native never reaches
+    if let Some(timeout) = options.sandbox.timeout {
+        if let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(timeout + TIMEOUT_GRACE) {
+            TronError::throw("E4029", 0, vec![format_duration(timeout)]);
+            exit(1);
+        }
+    }
+    program.join().unwrap_or_else(|_| exit(101))
+}
+
+fn run_on_engine(contents: &str, file: &str, script_args: Vec<String>) -> Result<(), String> {
+    match options().engine {
+        Engine::Tree => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_file(file);
+            interpreter.set_script_args(script_args);
+            interpreter.run(contents).map_err(|error| error.message)
+        }
+        Engine::Vm => {
+            let mut vm = Vm::new();
+            vm.set_file(file);
+            vm.set_script_args(script_args);
+            vm.run(contents).map_err(|error| error.message)
+        }
+    }
+}
+
+// `file` names the code in stack traces
+pub fn cli_eval(code: &str, file: &str, script_args: Vec<String>) {
+    match run(code, file, script_args) {
+        Ok(_) => exit(0),
+        Err(_msg) => exit(1),
+    }
+}
+
+// `tron run -` reads the whole program from stdin
+fn run_stdin(script_args: Vec<String>) {
+    let mut contents = String::new();
+    if std::io::stdin().read_to_string(&mut contents).is_err() {
+        TronError::throw("E0001", 0, vec![]);
+        exit(1);
+    }
+    cli_eval(&contents, "<stdin>", script_args)
+}
+
+pub fn cli_run(command: &str, path: PathBuf, script_args: Vec<String>) {
+    if command == "-" {
+        return run_stdin(script_args);
+    }
     let path_buf = path.join(command);
     let input = path_buf.to_str();
     match input {
-        Some(input) => match run_file(input) {
+        Some(input) => match run_file(input, command, script_args) {
             Ok(_) => exit(0),
             Err(_msg) => {
                 exit(1);
@@ -1 +1,41 @@
 // uninstall tron
+use super::install::*;
+use std::fs;
+use tron_lang::utils::{exit, options::inform, TronError};
+
+pub fn cli_uninstall() {
+    match uninstall() {
+        Ok(removed) => {
+            inform(&format!("Uninstalled tron ({} files removed)", removed));
+            exit(0);
+        }
+        Err(message) => {
+            TronError::throw("E0007", 0, vec![message]);
+            exit(1);
+        }
+    }
+}
+
+fn uninstall() -> Result<usize, String> {
+    let root = install_root()?;
+    let manifest = root.join(MANIFEST);
+    if !manifest.is_file() {
+        return Err(format!(
+            "no {} in {}, refusing to guess which files belong to tron",
+            MANIFEST,
+            root.display()
+        ));
+    }
+    let mut removed = 0;
+    for entry in read_manifest(&manifest)? {
+        if remove_installed(&root, &entry.path)? {
+            removed += 1;
+        }
+    }
+    let previous = root.join(PREVIOUS);
+    if previous.exists() {
+        fs::remove_dir_all(&previous).map_err(|e| format!("{}: {}", previous.display(), e))?;
+    }
+    fs::remove_file(&manifest).map_err(|e| format!("{}: {}", manifest.display(), e))?;
+    Ok(removed)
+}
@@ -1,11 +1,232 @@
-use std::process::Command;
+use super::install::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use tron_lang::utils::{exit, options::inform, TronError};
 
-pub fn cli_update() {
-    println!("Updating....");
-    let _output = Command::new("bash")
+pub fn cli_update(from: Option<&str>, rollback: bool) {
+    let result = match (from, rollback) {
+        (Some(_), true) => Err(failed(
+            "--from and --rollback can't be used together".to_string(),
+        )),
+        (Some(source), false) => update_from(Path::new(source)),
+        (None, true) => self::rollback(),
+        (None, false) => update_online(),
+    };
+    match result {
+        Ok(message) => {
+            inform(&message);
+            exit(0);
+        }
+        Err((code, message)) => {
+            let args = if message.is_empty() {
+                vec![]
+            } else {
+                vec![message]
+            };
+            TronError::throw(code, 0, args);
+            exit(1);
+        }
+    }
+}
+
+type UpdateResult = Result<String, (&'static str, String)>;
+
+fn failed(message: String) -> (&'static str, String) {
+    ("E0004", message)
+}
+
+fn update_online() -> UpdateResult {
+    inform("Updating....");
+    let status = Command::new("bash")
         .arg("-c")
-        .arg("curl -sSL https://tronlang.org/install.sh | bash")
-        .output()
-        .expect("Failed to execute command");
-    println!("Update completed");
+        .arg("set -o pipefail; curl -fsSL https://tronlang.org/install.sh | bash")
+        .status()
+        .map_err(|e| failed(e.to_string()))?;
+    if status.success() {
+        Ok("Update completed".to_string())
+    } else {
+        Err(failed(format!("installer exited with {}", status)))
+    }
+}
+
+fn update_from(source: &Path) -> UpdateResult {
+    let root = install_root().map_err(failed)?;
+    let staging = std::env::temp_dir().join(format!("tron-update-{}", std::process::id()));
+    let bundle = if source.is_dir() {
+        source.to_path_buf()
+    } else if source.is_file() {
+        extract(source, &staging)?
+    } else {
+        return Err(failed(format!("{} does not exist", source.display())));
+    };
+    let result = install_bundle(&bundle, &root);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn extract(archive: &Path, staging: &Path) -> Result<PathBuf, (&'static str, String)> {
+    let _ = fs::remove_dir_all(staging);
+    fs::create_dir_all(staging).map_err(|e| failed(e.to_string()))?;
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(staging)
+        .status()
+        .map_err(|e| failed(format!("failed to run tar: {}", e)))?;
+    if !status.success() {
+        return Err(failed(format!(
+            "failed to extract {} ({})",
+            archive.display(),
+            status
+        )));
+    }
+    Ok(staging.to_path_buf())
+}
+
+// release archives usually wrap everything in a single `tron-x.y.z/` directory
+fn find_checksums(bundle: &Path) -> Result<PathBuf, (&'static str, String)> {
+    if bundle.join(CHECKSUMS).is_file() {
+        return Ok(bundle.to_path_buf());
+    }
+    let entries: Vec<PathBuf> = fs::read_dir(bundle)
+        .map_err(|e| failed(e.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    match entries.as_slice() {
+        [single] if single.join(CHECKSUMS).is_file() => Ok(single.clone()),
+        _ => Err(failed(format!(
+            "{} not found in {}",
+            CHECKSUMS,
+            bundle.display()
+        ))),
+    }
+}
+
+fn install_bundle(bundle: &Path, root: &Path) -> UpdateResult {
+    let bundle = find_checksums(bundle)?;
+    let entries = read_manifest(&bundle.join(CHECKSUMS)).map_err(failed)?;
+    if entries.is_empty() {
+        return Err(failed(format!("{} lists no files", CHECKSUMS)));
+    }
+    for entry in &entries {
+        let actual = file_checksum(&bundle.join(&entry.path)).map_err(failed)?;
+        if actual != entry.checksum {
+            return Err(("E0005", entry.path.clone()));
+        }
+    }
+
+    let installed = installed_entries(root).map_err(failed)?;
+    backup(root, &installed).map_err(failed)?;
+    if let Err(message) = replace(&bundle, root, &installed, &entries) {
+        restore(root, &installed, &entries);
+        return Err(failed(message));
+    }
+    Ok(format!(
+        "Update completed: installed {} verified files into {}",
+        entries.len(),
+        root.display()
+    ))
+}
+
+// puts the bundle's files in place of the installed ones, writing the manifest last
+fn replace(
+    bundle: &Path,
+    root: &Path,
+    installed: &[ManifestEntry],
+    entries: &[ManifestEntry],
+) -> Result<(), String> {
+    for entry in entries {
+        install_file(&bundle.join(&entry.path), &root.join(&entry.path))?;
+    }
+    for old in installed {
+        if !entries.iter().any(|entry| entry.path == old.path) {
+            remove_installed(root, &old.path)?;
+        }
+    }
+    write_manifest(&root.join(MANIFEST), entries)
+}
+
+// puts back the files `backup` saved when `replace` stopped part way, removing the new ones; this
+// is as far as it can get, so failures are left for the error `replace` gave
+fn restore(root: &Path, installed: &[ManifestEntry], entries: &[ManifestEntry]) {
+    let previous = root.join(PREVIOUS);
+    for entry in entries {
+        if !installed.iter().any(|old| old.path == entry.path) {
+            let _ = remove_installed(root, &entry.path);
+        }
+    }
+    for old in installed {
+        let from = previous.join(&old.path);
+        if from.is_file() {
+            let _ = install_file(&from, &root.join(&old.path));
+        }
+    }
+}
+
+// installs made by install.sh have no manifest; the binary itself is then all we know about
+fn installed_entries(root: &Path) -> Result<Vec<ManifestEntry>, String> {
+    let manifest = root.join(MANIFEST);
+    if manifest.is_file() {
+        return read_manifest(&manifest);
+    }
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    match exe.strip_prefix(root) {
+        Ok(relative) => Ok(vec![ManifestEntry {
+            checksum: file_checksum(&exe)?,
+            path: relative.to_string_lossy().to_string(),
+        }]),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+fn backup(root: &Path, installed: &[ManifestEntry]) -> Result<(), String> {
+    let previous = root.join(PREVIOUS);
+    if previous.exists() {
+        fs::remove_dir_all(&previous).map_err(|e| e.to_string())?;
+    }
+    if installed.is_empty() {
+        return Ok(());
+    }
+    for entry in installed {
+        let from = root.join(&entry.path);
+        if from.is_file() {
+            install_file(&from, &previous.join(&entry.path))?;
+        }
+    }
+    write_manifest(&previous.join(MANIFEST), installed)
+}
+
+fn rollback() -> UpdateResult {
+    let root = install_root().map_err(failed)?;
+    let previous = root.join(PREVIOUS);
+    if !previous.join(MANIFEST).is_file() {
+        return Err(("E0006", String::new()));
+    }
+    let entries = read_manifest(&previous.join(MANIFEST)).map_err(failed)?;
+    for entry in &entries {
+        let actual = file_checksum(&previous.join(&entry.path)).map_err(failed)?;
+        if actual != entry.checksum {
+            return Err(("E0005", entry.path.clone()));
+        }
+    }
+    if root.join(MANIFEST).is_file() {
+        for current in read_manifest(&root.join(MANIFEST)).map_err(failed)? {
+            if !entries.iter().any(|entry| entry.path == current.path) {
+                remove_installed(&root, &current.path).map_err(failed)?;
+            }
+        }
+    }
+    for entry in &entries {
+        install_file(&previous.join(&entry.path), &root.join(&entry.path)).map_err(failed)?;
+    }
+    write_manifest(&root.join(MANIFEST), &entries).map_err(failed)?;
+    fs::remove_dir_all(&previous).map_err(|e| failed(e.to_string()))?;
+    Ok(format!(
+        "Rolled back to the previous version in {}",
+        root.display()
+    ))
 }
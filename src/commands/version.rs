@@ -1,3 +1,3 @@
 pub fn cli_version() {
     println!("v3.1.0");
-}
\ No newline at end of file
+}
@@ -0,0 +1,243 @@
+// pausing programs run by the tree-walking interpreter, for `tron debug` and anything else that
+// implements `Frontend`
+//
+// `Interpreter::interpret` reports every statement it is about to run to `statement`. When one is
+// on a breakpoint, or is where the last step ends, the frontend gets a `Pause` to look at the
+// paused frame through, and answers with how the program goes on.
+use crate::environment::Environment;
+use crate::expressions::{CallableImpl, TronType};
+use crate::interpreter::stack::{self, TraceFrame};
+use crate::interpreter::Interpreter;
+use crate::scanner::Statement;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resume {
+    Continue,
+    // to the next statement, inside calls as well
+    StepIn,
+    // to the next statement of this function, or of its caller once it returns
+    StepOver,
+    // to the next statement after this function returns
+    StepOut,
+    // ends the program
+    Stop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reason {
+    Entry,
+    Breakpoint,
+    Step,
+    // the frontend asked for it while the program ran
+    Pause,
+}
+
+pub trait Frontend {
+    fn paused(&mut self, pause: &mut Pause) -> Resume;
+    // called before every statement that doesn't pause, for a frontend that takes requests while
+    // the program runs; true pauses at the statement
+    fn running(&mut self, _breakpoints: &mut Breakpoints) -> bool {
+        false
+    }
+}
+
+// lines to pause at, by file
+#[derive(Clone, Debug, Default)]
+pub struct Breakpoints {
+    lines: BTreeSet<(String, usize)>,
+}
+
+impl Breakpoints {
+    // false when there already is one
+    pub fn add(&mut self, file: &str, line: usize) -> bool {
+        self.lines.insert((file.to_string(), line))
+    }
+    // false when there was none
+    pub fn remove(&mut self, file: &str, line: usize) -> bool {
+        self.lines.remove(&(file.to_string(), line))
+    }
+    pub fn clear(&mut self, file: &str) {
+        self.lines
+            .retain(|(breakpoint_file, _)| breakpoint_file != file);
+    }
+    pub fn contains(&self, file: &str, line: usize) -> bool {
+        self.lines.contains(&(file.to_string(), line))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.lines.iter().map(|(file, line)| (file.as_str(), *line))
+    }
+}
+
+// a paused program, at `line` of `file` about to run the statement there
+pub struct Pause<'a> {
+    pub reason: Reason,
+    pub file: Rc<str>,
+    pub line: usize,
+    pub breakpoints: &'a mut Breakpoints,
+    environment: &'a Environment,
+}
+
+impl Pause<'_> {
+    // the calls that led here, innermost first, as in stack traces
+    pub fn stack(&self) -> Vec<TraceFrame> {
+        let trace = stack::trace(self.line);
+        if !trace.is_empty() {
+            return trace;
+        }
+        vec![TraceFrame {
+            function: Rc::from("<script>"),
+            file: Some(self.file.clone()),
+            line: self.line,
+            times: 1,
+        }]
+    }
+    // the locals of every scope from the paused one out, up to the globals; a function's scopes
+    // are followed by those it was defined in
+    pub fn scopes(&self) -> Vec<Vec<(String, TronType)>> {
+        let mut scopes = vec![];
+        let mut environment = self.environment;
+        while let Some(enclosing) = &environment.enclosing {
+            let slots = environment.slots.borrow();
+            let variables = slots
+                .names
+                .iter()
+                .cloned()
+                .zip(slots.values.iter().cloned());
+            scopes.push(variables.collect());
+            environment = enclosing;
+        }
+        scopes
+    }
+    // the program's globals, sorted by name, without the builtins
+    pub fn globals(&self) -> Vec<(String, TronType)> {
+        let mut globals: Vec<(String, TronType)> = self
+            .environment
+            .values
+            .borrow()
+            .iter()
+            .filter(|(_, value)| !matches!(value, TronType::Callable(CallableImpl::StdFunction(_))))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        globals
+    }
+    // runs `source` with the variables visible here; it gets copies of them, so assigning to one
+    // doesn't change the program
+    pub fn evaluate(&self, source: &str) -> Result<TronType, String> {
+        let scope = Environment::new(HashMap::new());
+        {
+            let mut values = scope.values.borrow_mut();
+            // builtins included, which `globals` leaves out
+            values.extend(
+                self.environment
+                    .values
+                    .borrow()
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone())),
+            );
+            for variables in self.scopes().into_iter().rev() {
+                values.extend(variables);
+            }
+        }
+        Interpreter::with_env(scope)
+            .eval(source)
+            .map_err(|error| error.message)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Entry,
+    Run,
+    StepIn,
+    // with the call depth the step started at
+    StepOver(usize),
+    StepOut(usize),
+}
+
+struct Session {
+    frontend: Box<dyn Frontend>,
+    breakpoints: Breakpoints,
+    mode: Mode,
+}
+
+thread_local! {
+    // taken out while the frontend has the program paused, so what it evaluates runs undisturbed
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+    // checked before every statement, so running without a debugger costs next to nothing
+    static ATTACHED: Cell<bool> = const { Cell::new(false) };
+}
+
+// debugs everything the tree-walking interpreter runs on this thread from now on
+pub fn attach(frontend: Box<dyn Frontend>, breakpoints: Breakpoints, stop_on_entry: bool) {
+    let mode = if stop_on_entry {
+        Mode::Entry
+    } else {
+        Mode::Run
+    };
+    SESSION.with(|session| {
+        *session.borrow_mut() = Some(Session {
+            frontend,
+            breakpoints,
+            mode,
+        })
+    });
+    ATTACHED.with(|attached| attached.set(true));
+}
+
+pub fn detach() -> Option<Box<dyn Frontend>> {
+    ATTACHED.with(|attached| attached.set(false));
+    SESSION.with(|session| session.borrow_mut().take().map(|session| session.frontend))
+}
+
+pub(crate) fn attached() -> bool {
+    ATTACHED.with(|attached| attached.get())
+}
+
+// called with every statement before it runs; an `Err` ends the program
+pub(crate) fn statement(stmt: &Statement, environment: &Environment) -> Result<(), String> {
+    // blocks only hold the statements that pause
+    if matches!(stmt, Statement::BlockStatement { .. }) {
+        return Ok(());
+    }
+    let Some(mut session) = SESSION.with(|session| session.borrow_mut().take()) else {
+        return Ok(());
+    };
+    let file = stack::current_file();
+    let line = stmt.get_line();
+    let depth = stack::depth();
+    let reason = match session.mode {
+        Mode::Entry => Some(Reason::Entry),
+        _ if session.breakpoints.contains(&file, line) => Some(Reason::Breakpoint),
+        Mode::StepIn => Some(Reason::Step),
+        Mode::StepOver(from) if depth <= from => Some(Reason::Step),
+        Mode::StepOut(from) if depth < from => Some(Reason::Step),
+        _ if session.frontend.running(&mut session.breakpoints) => Some(Reason::Pause),
+        _ => None,
+    };
+    let mut result = Ok(());
+    if let Some(reason) = reason {
+        let mut pause = Pause {
+            reason,
+            file,
+            line,
+            breakpoints: &mut session.breakpoints,
+            environment,
+        };
+        session.mode = match session.frontend.paused(&mut pause) {
+            Resume::Continue => Mode::Run,
+            Resume::StepIn => Mode::StepIn,
+            Resume::StepOver => Mode::StepOver(depth),
+            Resume::StepOut => Mode::StepOut(depth),
+            Resume::Stop => {
+                result = Err(String::new());
+                Mode::Run
+            }
+        };
+    }
+    SESSION.with(|slot| *slot.borrow_mut() = Some(session));
+    result
+}
@@ -1,17 +1,19 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-use crate::expressions::{CallableImpl, StdFunctionImpl, TronType};
+use crate::expressions::{Arity, CallableImpl, StdFunctionImpl, TronType};
+
+use super::Globals;
 
 use super::clock_impl;
 
-pub fn get_globals() -> Rc<RefCell<HashMap<String, TronType>>> {
-    let mut env = HashMap::new();
+pub fn get_globals() -> Rc<RefCell<Globals>> {
+    let mut env = Globals::default();
     let fun_impl: StdFunctionImpl = StdFunctionImpl {
         name: "clock".to_string(),
-        arity: 0,
-        function: Rc::new(clock_impl),
+        arity: Arity::exact(0),
+        function: Rc::new(|args: Vec<TronType>| clock_impl(&args)),
     };
-    let callable_impl = CallableImpl::StdFunction(fun_impl);
-    env.insert("clock".to_string(), TronType::Callable(callable_impl));
+    let callable_impl = CallableImpl::StdFunction(Rc::new(fun_impl));
+    env.define("clock".to_string(), TronType::Callable(callable_impl));
     Rc::new(RefCell::new(env))
 }
@@ -1,26 +1,107 @@
-use crate::{expressions::TronType, utils::TronError};
+use crate::{expressions::TronType, memory::track, utils::TronError};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 mod clock_impl;
 use clock_impl::clock_impl;
 mod get_globals;
 use get_globals::get_globals;
 
+// where the resolver put a local: `depth` scopes out from its use, at `index` in that scope
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
+// the locals of a scope in declaration order, addressed by `Slot::index`, with their names for
+// the debugger
+#[derive(Debug, Default)]
+pub(crate) struct Slots {
+    pub(crate) values: Vec<TronType>,
+    pub(crate) names: Vec<String>,
+}
+
+// the globals of a program by name, each at an index that stays the same once it is defined
+#[derive(Debug, Default)]
+pub struct Globals {
+    indexes: HashMap<String, usize>,
+    names: Vec<String>,
+    values: Vec<TronType>,
+}
+
+impl Globals {
+    pub fn get(&self, name: &str) -> Option<&TronType> {
+        self.at(*self.indexes.get(name)?)
+    }
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut TronType> {
+        let index = *self.indexes.get(name)?;
+        self.values.get_mut(index)
+    }
+    pub fn define(&mut self, name: String, value: TronType) {
+        match self.indexes.get(&name) {
+            Some(&index) => self.values[index] = value,
+            None => {
+                self.indexes.insert(name.clone(), self.values.len());
+                self.names.push(name);
+                self.values.push(value);
+            }
+        }
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TronType)> {
+        self.names.iter().zip(&self.values)
+    }
+    fn index(&self, name: &str) -> Option<usize> {
+        self.indexes.get(name).copied()
+    }
+    fn at(&self, index: usize) -> Option<&TronType> {
+        self.values.get(index)
+    }
+    fn at_mut(&mut self, index: usize) -> Option<&mut TronType> {
+        self.values.get_mut(index)
+    }
+}
+
+impl Extend<(String, TronType)> for Globals {
+    fn extend<T: IntoIterator<Item = (String, TronType)>>(&mut self, values: T) {
+        for (name, value) in values {
+            self.define(name, value);
+        }
+    }
+}
+
+// what the variable an expression names is: a local the resolver found, or a global whose index
+// the first lookup by name found
+#[derive(Clone, Copy, Debug)]
+enum Binding {
+    Unknown,
+    Local(Slot),
+    Global(usize),
+}
+
 #[derive(Clone, Debug)]
 pub struct Environment {
-    pub values: Rc<RefCell<HashMap<String, TronType>>>,
+    // globals by name, shared by every scope of the program
+    pub values: Rc<RefCell<Globals>>,
+    pub(crate) slots: Rc<RefCell<Slots>>,
     pub value_types: Rc<RefCell<HashMap<String, String>>>,
-    locals: Rc<RefCell<HashMap<usize, usize>>>,
-    pub enclosing: Option<Box<Environment>>,
+    // by expression id, for the globals above
+    bindings: Rc<RefCell<Vec<Binding>>>,
+    pub enclosing: Option<Rc<Environment>>,
 }
 
 impl Environment {
-    pub fn new(locals: HashMap<usize, usize>) -> Self {
-        Self {
+    pub fn new(locals: HashMap<usize, Slot>) -> Self {
+        let environment = Self {
             values: get_globals(),
+            slots: Rc::new(RefCell::new(Slots::default())),
             value_types: Rc::new(RefCell::new(HashMap::new())),
-            locals: Rc::new(RefCell::new(locals)),
+            bindings: Rc::new(RefCell::new(vec![])),
             enclosing: None,
-        }
+        };
+        environment.resolve(locals);
+        // functions hold the environment they were defined in, so both can end up in a cycle
+        track(&environment.values);
+        track(&environment.slots);
+        environment
     }
     pub fn get_value_type(&self, name: &str) -> Option<String> {
         self.value_types.borrow().get(name).cloned()
@@ -28,78 +109,113 @@ impl Environment {
     pub fn set_value_type(&self, name: String, type_annotation: String) {
         self.value_types.borrow_mut().insert(name, type_annotation);
     }
-    pub fn resolve(&self, locals: HashMap<usize, usize>) {
-        for (key, val) in locals.iter() {
-            self.locals.borrow_mut().insert(*key, *val);
+    pub fn resolve(&self, locals: HashMap<usize, Slot>) {
+        for (expr_id, slot) in locals {
+            self.bind(expr_id, Binding::Local(slot));
+        }
+    }
+    fn bind(&self, expr_id: usize, binding: Binding) {
+        let mut bindings = self.bindings.borrow_mut();
+        if bindings.len() <= expr_id {
+            bindings.resize(expr_id + 1, Binding::Unknown);
         }
+        bindings[expr_id] = binding;
+    }
+    fn binding(&self, expr_id: usize) -> Binding {
+        let binding = self.bindings.borrow().get(expr_id).copied();
+        binding.unwrap_or(Binding::Unknown)
+    }
+    // the index of the global `name`, kept for the next time `expr_id` is run
+    fn global_index(&self, name: &str, expr_id: usize) -> Option<usize> {
+        let index = self.values.borrow().index(name)?;
+        self.bind(expr_id, Binding::Global(index));
+        Some(index)
     }
     pub fn enclose(&self) -> Environment {
+        let slots = Rc::new(RefCell::new(Slots::default()));
+        track(&slots);
         Self {
-            values: Rc::new(RefCell::new(HashMap::new())),
+            values: self.values.clone(),
+            slots,
             value_types: self.value_types.clone(),
-            locals: self.locals.clone(),
-            enclosing: Some(Box::new(self.clone())),
+            bindings: self.bindings.clone(),
+            enclosing: Some(Rc::new(self.clone())),
         }
     }
+    pub fn is_global(&self) -> bool {
+        self.enclosing.is_none()
+    }
+    // globals are stored by name; locals take the next slot, matching the order the resolver
+    // declared them in
     pub fn define(&self, name: String, value: TronType) {
-        self.values.borrow_mut().insert(name, value);
+        if self.is_global() {
+            self.values.borrow_mut().define(name, value);
+        } else {
+            let mut slots = self.slots.borrow_mut();
+            slots.values.push(value);
+            slots.names.push(name);
+        }
+    }
+    pub fn global(&self) -> &Environment {
+        match &self.enclosing {
+            Some(env) => env.global(),
+            None => self,
+        }
+    }
+    pub fn get_global(&self, name: &str) -> Option<TronType> {
+        self.values.borrow().get(name).cloned()
+    }
+    fn ancestor(&self, depth: usize) -> Option<&Environment> {
+        match depth {
+            0 => Some(self),
+            _ => self.enclosing.as_ref()?.ancestor(depth - 1),
+        }
     }
     pub fn get(&self, name: &str, expr_id: usize) -> Option<TronType> {
-        let distance = self.locals.borrow().get(&expr_id).cloned();
-        self.get_internal(name, distance)
-    }
-    fn get_internal(&self, name: &str, distance: Option<usize>) -> Option<TronType> {
-        if distance.is_none() {
-            match &self.enclosing {
-                None => self.values.borrow().get(name).cloned(),
-                Some(env) => env.get_internal(name, distance),
-            }
-        } else {
-            let distance = distance.unwrap();
-            if distance == 0 {
-                self.values.borrow().get(name).cloned()
-            } else {
-                match &self.enclosing {
+        let index = match self.binding(expr_id) {
+            Binding::Local(slot) => {
+                return match self.ancestor(slot.depth) {
+                    Some(scope) => scope.slots.borrow().values.get(slot.index).cloned(),
                     None => {
                         TronError::throw("E3004", 0, vec![]);
                         Some(TronType::Null)
                     }
-                    Some(env) => {
-                        assert!(distance > 0);
-                        env.get_internal(name, Some(distance - 1))
-                    }
-                }
+                };
             }
-        }
+            Binding::Global(index) => index,
+            Binding::Unknown => self.global_index(name, expr_id)?,
+        };
+        self.values.borrow().at(index).cloned()
     }
     pub fn assign(&self, name: &str, value: TronType, expr_id: usize) -> bool {
-        let distance = self.locals.borrow().get(&expr_id).cloned();
-        self.assign_internal(name, value, distance)
-    }
-    fn assign_internal(&self, name: &str, value: TronType, distance: Option<usize>) -> bool {
-        if distance.is_none() {
-            match &self.enclosing {
-                Some(env) => env.assign_internal(name, value, distance),
-                None => match self.values.borrow_mut().insert(name.to_string(), value) {
-                    Some(_) => true,
-                    None => false,
-                },
-            }
-        } else {
-            let distance = distance.unwrap();
-            if distance == 0 {
-                self.values.borrow_mut().insert(name.to_string(), value);
-                true
-            } else {
-                match &self.enclosing {
+        let index = match self.binding(expr_id) {
+            Binding::Local(slot) => {
+                return match self.ancestor(slot.depth) {
+                    Some(scope) => match scope.slots.borrow_mut().values.get_mut(slot.index) {
+                        Some(local) => {
+                            *local = value;
+                            true
+                        }
+                        None => false,
+                    },
                     None => {
                         TronError::throw("E3005", 0, vec![]);
                         false
                     }
-                    Some(env) => env.assign_internal(name, value, Some(distance - 1)),
                 };
+            }
+            Binding::Global(index) => index,
+            Binding::Unknown => match self.global_index(name, expr_id) {
+                Some(index) => index,
+                None => return false,
+            },
+        };
+        match self.values.borrow_mut().at_mut(index) {
+            Some(global) => {
+                *global = value;
                 true
             }
+            None => false,
         }
     }
 }
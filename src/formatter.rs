@@ -0,0 +1,822 @@
+// `tron fmt`: prints a file's statements back in one style, keeping its comments
+//
+// The tree doesn't say where comments and blank lines were, or which statements were written as
+// a `for` loop, so the printer walks the tree and the tokens together: every token it writes is
+// the next one in the source, written as it was spelled there, and the comments before it come
+// out first. Lists are split one item per line when the source started their first item on a
+// new line, and kept on one line otherwise.
+use crate::expressions::{Expression, TronType};
+use crate::parser::Parser;
+use crate::scanner::{Parameter, Scanner, Span, Statement, Token, TokenType};
+use crate::utils::{collect_errors, TronError};
+
+pub const INDENT: &str = "    ";
+
+// `source` in the canonical style, or the errors scanning and parsing it reported
+pub fn format(source: &str) -> Result<String, Vec<TronError>> {
+    Ok(Printer::run(source)?.out)
+}
+
+// formats only the statements `range` touches, giving the part of `source` to replace and its
+// replacement, which are whole lines; `None` when the range touches no statement
+pub fn format_range(source: &str, range: Span) -> Result<Option<(Span, String)>, Vec<TronError>> {
+    let printer = Printer::run(source)?;
+    let records = &printer.records;
+    let touches = |span: Span| {
+        if range.start == range.end {
+            span.start <= range.start && range.start <= span.end
+        } else {
+            span.start < range.end && range.start < span.end
+        }
+    };
+    // the innermost statement holding all of the range
+    let mut parent = records
+        .iter()
+        .rposition(|record| record.source.start <= range.start && range.end <= record.source.end);
+    let mut chosen: Vec<usize> = (0..records.len())
+        .filter(|&index| records[index].parent == parent && touches(records[index].source))
+        .collect();
+    loop {
+        if chosen.is_empty() {
+            let Some(index) = parent else {
+                return Ok(None);
+            };
+            chosen = vec![index];
+            parent = records[index].parent;
+        }
+        // statements sharing a line with the chosen ones are formatted with them
+        let lines = loop {
+            let first = &records[chosen[0]];
+            let last = &records[chosen[chosen.len() - 1]];
+            let lines = Span {
+                start: line_start(source, first.source.start),
+                end: line_end(source, last.source.end),
+            };
+            let before = chosen.len();
+            chosen = (0..records.len())
+                .filter(|&index| {
+                    records[index].parent == parent
+                        && (chosen.contains(&index)
+                            || records[index].source.start < lines.end
+                                && lines.start < records[index].source.end)
+                })
+                .collect();
+            if chosen.len() == before {
+                break lines;
+            }
+        };
+        // and when the lines also hold part of the statement around them, that is formatted
+        let shared = printer.tokens.iter().any(|token| {
+            token.span.start >= lines.start
+                && token.span.start < lines.end
+                && !chosen.iter().any(|&index| {
+                    let source = records[index].source;
+                    source.start <= token.span.start && token.span.start < source.end
+                })
+        });
+        if !shared {
+            let first = &records[chosen[0]];
+            let last = &records[chosen[chosen.len() - 1]];
+            let text = printer.out[first.out.start..last.out.end].to_string();
+            return Ok(Some((lines, text)));
+        }
+        chosen = vec![];
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |newline| offset + newline)
+}
+
+fn newlines(text: &str) -> usize {
+    text.matches('\n').count()
+}
+
+fn failed(message: &str) -> Vec<TronError> {
+    collect_errors(|| TronError::throw("E2008", 0, vec![message.to_string()])).1
+}
+
+// where a statement is in the source and in the output
+struct Record {
+    source: Span,
+    out: Span,
+    // the statement it is in
+    parent: Option<usize>,
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    // the source's tokens, without the `Eof`
+    tokens: Vec<Token>,
+    comments: Vec<Token>,
+    // the next token and comment to write
+    next: usize,
+    next_comment: usize,
+    // where the last token or comment written ends in the source
+    last_end: usize,
+    out: String,
+    indent: usize,
+    // a comment ended the line in the middle of a statement, which carries on on the next line
+    broken: bool,
+    // the current line carries on a statement, so it is indented once more
+    continued: bool,
+    // a `{` was just written, and no blank line follows it
+    opened: bool,
+    // the tree asked for a token the source doesn't have next
+    misaligned: bool,
+    records: Vec<Record>,
+    parent: Option<usize>,
+}
+
+impl<'a> Printer<'a> {
+    fn run(source: &'a str) -> Result<Self, Vec<TronError>> {
+        let ((tokens, comments, statements), errors) = collect_errors(|| {
+            let (tokens, comments) = Scanner::new(source)
+                .scan_with_comments()
+                .unwrap_or_default();
+            let statements = Parser::new(tokens.clone()).parse().unwrap_or_default();
+            (tokens, comments, statements)
+        });
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let mut printer = Printer {
+            source,
+            tokens: tokens
+                .into_iter()
+                .filter(|token| token.token_type != TokenType::Eof)
+                .collect(),
+            comments,
+            next: 0,
+            next_comment: 0,
+            last_end: 0,
+            out: String::new(),
+            indent: 0,
+            broken: false,
+            continued: false,
+            opened: false,
+            misaligned: false,
+            records: vec![],
+            parent: None,
+        };
+        if source.starts_with("#!") {
+            let end = line_end(source, 0);
+            printer.out.push_str(source[..end].trim_end());
+            printer.out.push('\n');
+            printer.last_end = end;
+        }
+        printer.statements(statements.iter().collect());
+        printer.comments_before(source.len());
+        printer.end_line();
+        printer.check()?;
+        Ok(printer)
+    }
+
+    // the output has to be the source's tokens and comments, less trailing commas
+    fn check(&self) -> Result<(), Vec<TronError>> {
+        if self.misaligned
+            || self.next != self.tokens.len()
+            || self.next_comment != self.comments.len()
+        {
+            return Err(failed("the printer lost its place in the source"));
+        }
+        let significant = |source: &str| {
+            let (tokens, comments) = Scanner::new(source)
+                .scan_with_comments()
+                .unwrap_or_default();
+            let mut kept = vec![];
+            for (index, token) in tokens.iter().enumerate() {
+                let closes = tokens.get(index + 1).is_some_and(|next| {
+                    matches!(
+                        next.token_type,
+                        TokenType::RightBracket | TokenType::RightBrace
+                    )
+                });
+                if token.token_type != TokenType::Comma || !closes {
+                    kept.push(token.lexeme.clone());
+                }
+            }
+            kept.extend(
+                comments
+                    .iter()
+                    .map(|comment| comment.lexeme.trim_end().to_string()),
+            );
+            kept
+        };
+        let (formatted, errors) = collect_errors(|| significant(&self.out));
+        if !errors.is_empty() || formatted != collect_errors(|| significant(self.source)).0 {
+            return Err(failed("the formatted code would read differently"));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<TokenType> {
+        self.tokens.get(self.next).map(|token| token.token_type)
+    }
+
+    // where the next token starts, or the end of the source
+    fn next_start(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.source.len(), |token| token.span.start)
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    // ends the current line, along with the statement it carried on
+    fn end_line(&mut self) {
+        if !self.at_line_start() {
+            self.out.truncate(self.out.trim_end_matches(' ').len());
+            self.out.push('\n');
+        }
+        self.broken = false;
+        self.continued = false;
+    }
+
+    fn blank_line(&mut self) {
+        if !self.out.is_empty() && !self.opened && !self.out.ends_with("\n\n") {
+            self.end_line();
+            self.out.push('\n');
+        }
+    }
+
+    fn space(&mut self) {
+        if !self.at_line_start() {
+            self.out.push(' ');
+        }
+    }
+
+    // `hang` indents the line once more when a comment broke it
+    fn write(&mut self, text: &str, hang: bool) {
+        if self.broken {
+            self.broken = false;
+            if !self.at_line_start() {
+                self.out.truncate(self.out.trim_end_matches(' ').len());
+                self.out.push('\n');
+                self.continued = hang;
+            }
+        }
+        if self.at_line_start() {
+            let depth = self.indent + usize::from(self.continued);
+            self.out.push_str(&INDENT.repeat(depth));
+        }
+        self.out.push_str(text);
+        self.opened = false;
+    }
+
+    // trailing commas are left out
+    fn skip_trailing_comma(&mut self) {
+        let closes = self.tokens.get(self.next + 1).is_some_and(|next| {
+            matches!(
+                next.token_type,
+                TokenType::RightBracket | TokenType::RightBrace
+            )
+        });
+        if self.peek() == Some(TokenType::Comma) && closes {
+            self.next += 1;
+        }
+    }
+
+    // writes the next token of the source, which has to be a `token_type`
+    fn token(&mut self, token_type: TokenType) {
+        if token_type != TokenType::Comma {
+            self.skip_trailing_comma();
+        }
+        let Some(token) = self.tokens.get(self.next).cloned() else {
+            self.misaligned = true;
+            return;
+        };
+        if token.token_type != token_type {
+            self.misaligned = true;
+            return;
+        }
+        self.comments_before(token.span.start);
+        self.next += 1;
+        // what closes a line or starts a block lines up with the line it belongs to
+        let hang = !matches!(
+            token_type,
+            TokenType::Else
+                | TokenType::LeftBrace
+                | TokenType::RightBrace
+                | TokenType::RightBracket
+                | TokenType::RightParen
+        );
+        self.write(&token.lexeme, hang);
+        self.last_end = token.span.end;
+        self.trailing_comment();
+    }
+
+    // writes the comments that start before `offset`, and the blank line after them
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment).cloned() {
+            if comment.span.start >= offset {
+                break;
+            }
+            self.next_comment += 1;
+            let gap = &self.source[self.last_end..comment.span.start];
+            if newlines(gap) == 0 && !self.at_line_start() {
+                self.space();
+                self.write(comment.lexeme.trim_end(), true);
+                self.broken = true;
+            } else {
+                if newlines(gap) > 1 && self.at_line_start() && !self.continued {
+                    self.blank_line();
+                }
+                if !self.at_line_start() {
+                    self.broken = true;
+                }
+                self.write(comment.lexeme.trim_end(), true);
+                self.broken = true;
+                if !self.continued {
+                    self.end_line();
+                }
+            }
+            self.last_end = comment.span.end;
+        }
+    }
+
+    // writes a comment on the same line as the token just written
+    fn trailing_comment(&mut self) {
+        let Some(comment) = self.comments.get(self.next_comment).cloned() else {
+            return;
+        };
+        let gap = &self.source[self.last_end..comment.span.start];
+        if comment.span.start < self.next_start() && newlines(gap) == 0 {
+            self.next_comment += 1;
+            self.space();
+            self.write(comment.lexeme.trim_end(), true);
+            self.broken = true;
+            self.last_end = comment.span.end;
+        }
+    }
+
+    // the comments and blank line before the statement starting with the next token
+    fn leading(&mut self) {
+        self.comments_before(self.next_start());
+        self.end_line();
+        if newlines(&self.source[self.last_end..self.next_start()]) > 1 {
+            self.blank_line();
+        }
+    }
+
+    fn statements(&mut self, statements: Vec<&Statement>) {
+        for statement in statements {
+            self.leading();
+            let record = self.records.len();
+            let start = self.next_start();
+            self.records.push(Record {
+                source: Span { start, end: start },
+                out: Span {
+                    start: self.out.len(),
+                    end: self.out.len(),
+                },
+                parent: self.parent,
+            });
+            let parent = self.parent.replace(record);
+            self.statement(statement);
+            self.parent = parent;
+            self.records[record].source.end = self.last_end;
+            self.records[record].out.end = self.out.len();
+            self.end_line();
+        }
+    }
+
+    // the statements of a block after its `{`, then its `}`
+    fn body(&mut self, statements: Vec<&Statement>) {
+        let comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.span.start < self.next_start());
+        if statements.is_empty() && !comments && !self.broken {
+            return self.token(TokenType::RightBrace);
+        }
+        self.indent += 1;
+        self.end_line();
+        self.opened = true;
+        self.statements(statements);
+        self.comments_before(self.next_start());
+        self.indent -= 1;
+        self.end_line();
+        self.token(TokenType::RightBrace);
+    }
+
+    fn block(&mut self, statements: Vec<&Statement>) {
+        self.token(TokenType::LeftBrace);
+        self.body(statements);
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::ExpressionStatement { expression, .. } => {
+                self.expression(expression);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::UseStatement { expression, .. } => {
+                self.token(TokenType::Use);
+                self.space();
+                self.expression(expression);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::VariableStatement {
+                value_type, value, ..
+            } => {
+                self.token(TokenType::Variable);
+                self.space();
+                self.token(TokenType::Identifier);
+                self.token(TokenType::Colon);
+                self.space();
+                self.token(value_type.token_type);
+                self.space();
+                self.token(TokenType::Equal);
+                self.space();
+                self.expression(value);
+                self.token(TokenType::Semicolon);
+            }
+            // `for` loops come out of the parser as a block with the initializer and a `while`
+            Statement::BlockStatement { statements, .. } => match statements.as_slice() {
+                [initializer, lp] if self.peek() == Some(TokenType::For) => {
+                    self.for_loop(Some(initializer), lp)
+                }
+                _ => self.block(statements.iter().map(|statement| &**statement).collect()),
+            },
+            Statement::WhileStatement {
+                conditions, body, ..
+            } => {
+                if self.peek() == Some(TokenType::For) {
+                    return self.for_loop(None, statement);
+                }
+                self.token(TokenType::While);
+                self.space();
+                self.conditions(conditions);
+                self.space();
+                self.statement(body);
+            }
+            Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches,
+                else_branch,
+                ..
+            } => {
+                self.token(TokenType::If);
+                self.space();
+                self.conditions(conditions);
+                self.space();
+                self.statement(then_branch);
+                for (conditions, branch) in elif_branches {
+                    self.space();
+                    self.token(TokenType::Elif);
+                    self.space();
+                    self.conditions(conditions);
+                    self.space();
+                    self.statement(branch);
+                }
+                if let Some(branch) = else_branch {
+                    self.space();
+                    self.token(TokenType::Else);
+                    self.space();
+                    self.statement(branch);
+                }
+            }
+            Statement::FunctionStatement { params, body, .. } => {
+                self.token(TokenType::Function);
+                self.space();
+                self.token(TokenType::Identifier);
+                self.signature(params);
+                self.function_body(body);
+            }
+            Statement::ReturnStatement { value, .. } => {
+                self.token(TokenType::Return);
+                if let Some(value) = value {
+                    self.space();
+                    self.expression(value);
+                }
+                self.token(TokenType::Semicolon);
+            }
+            Statement::BreakStatement { .. } => {
+                self.token(TokenType::Break);
+                self.token(TokenType::Semicolon);
+            }
+            Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                ..
+            } => {
+                self.token(TokenType::Switch);
+                self.space();
+                self.expression(condition);
+                self.space();
+                self.token(TokenType::LeftBrace);
+                self.indent += 1;
+                self.end_line();
+                self.opened = true;
+                for (value, body) in case_branches {
+                    self.leading();
+                    self.token(TokenType::Case);
+                    self.space();
+                    self.expression(value);
+                    self.space();
+                    self.block(body.iter().collect());
+                }
+                if let Some(body) = default_branch {
+                    self.leading();
+                    self.token(TokenType::Default);
+                    self.space();
+                    self.block(body.iter().collect());
+                }
+                self.comments_before(self.next_start());
+                self.indent -= 1;
+                self.end_line();
+                self.token(TokenType::RightBrace);
+            }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.token(TokenType::Try);
+                self.space();
+                self.statement(body);
+                if let Some((_, block)) = catch {
+                    self.space();
+                    self.token(TokenType::Catch);
+                    self.space();
+                    self.token(TokenType::LeftParen);
+                    self.token(TokenType::Identifier);
+                    self.token(TokenType::RightParen);
+                    self.space();
+                    self.statement(block);
+                }
+                if let Some(block) = finally {
+                    self.space();
+                    self.token(TokenType::Finally);
+                    self.space();
+                    self.statement(block);
+                }
+            }
+            Statement::ThrowStatement { value, .. } => {
+                self.token(TokenType::Throw);
+                self.space();
+                self.expression(value);
+                self.token(TokenType::Semicolon);
+            }
+        }
+    }
+
+    // `for initializer; condition; increment body`, which the parser turned into a `while` whose
+    // body is a block of the loop's body and the increment
+    fn for_loop(&mut self, initializer: Option<&Statement>, lp: &Statement) {
+        let Statement::WhileStatement {
+            conditions, body, ..
+        } = lp
+        else {
+            self.misaligned = true;
+            return;
+        };
+        let Statement::BlockStatement { statements, .. } = &**body else {
+            self.misaligned = true;
+            return;
+        };
+        let [body, increment] = statements.as_slice() else {
+            self.misaligned = true;
+            return;
+        };
+        let Statement::ExpressionStatement {
+            expression: increment,
+            ..
+        } = &**increment
+        else {
+            self.misaligned = true;
+            return;
+        };
+        self.token(TokenType::For);
+        self.space();
+        match initializer {
+            Some(initializer) => self.statement(initializer),
+            None => self.token(TokenType::Semicolon),
+        }
+        self.space();
+        // a loop without a condition has `true` made up for it
+        if self.peek() != Some(TokenType::Semicolon) {
+            self.conditions(conditions);
+        }
+        self.token(TokenType::Semicolon);
+        self.space();
+        self.expression(increment);
+        self.space();
+        self.statement(body);
+    }
+
+    fn conditions(&mut self, conditions: &[Expression]) {
+        for (index, condition) in conditions.iter().enumerate() {
+            if index > 0 {
+                self.token(TokenType::Comma);
+                self.space();
+            }
+            self.expression(condition);
+        }
+    }
+
+    // `(params): output_type`
+    fn signature(&mut self, params: &[Parameter]) {
+        self.token(TokenType::LeftParen);
+        self.list(params, TokenType::RightParen, |printer, param| {
+            if param.rest {
+                printer.token(TokenType::Ellipsis);
+            }
+            printer.token(TokenType::Identifier);
+            printer.token(TokenType::Colon);
+            printer.space();
+            printer.token(TokenType::Identifier);
+            if let Some(default) = &param.default {
+                printer.space();
+                printer.token(TokenType::Equal);
+                printer.space();
+                printer.expression(default);
+            }
+        });
+        self.token(TokenType::Colon);
+        self.space();
+        self.token(TokenType::Identifier);
+    }
+
+    // a block, or `= value;` for a function written as one expression
+    fn function_body(&mut self, body: &[Box<Statement>]) {
+        self.space();
+        if let [statement] = body {
+            if let Statement::ReturnStatement {
+                keyword,
+                value: Some(value),
+                ..
+            } = &**statement
+            {
+                if keyword.lexeme.is_empty() && self.peek() == Some(TokenType::Equal) {
+                    self.token(TokenType::Equal);
+                    self.space();
+                    self.expression(value);
+                    return self.token(TokenType::Semicolon);
+                }
+            }
+        }
+        self.block(body.iter().map(|statement| &**statement).collect());
+    }
+
+    // the items of a list after its opening bracket, then its closing one; one per line when the
+    // source put the first item on a new line
+    fn list<T>(&mut self, items: &[T], close: TokenType, mut item: impl FnMut(&mut Self, &T)) {
+        let gap = &self.source[self.last_end..self.next_start()];
+        let split = !items.is_empty() && (self.broken || newlines(gap) > 0);
+        if split {
+            self.indent += 1;
+            self.end_line();
+        }
+        for (index, value) in items.iter().enumerate() {
+            if index > 0 {
+                self.token(TokenType::Comma);
+                if split {
+                    self.end_line();
+                } else {
+                    self.space();
+                }
+            }
+            item(self, value);
+        }
+        if split {
+            self.skip_trailing_comma();
+            self.comments_before(self.next_start());
+            self.indent -= 1;
+            self.end_line();
+        }
+        self.token(close);
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Object { properties, .. } => {
+                self.token(TokenType::LeftBrace);
+                self.list(properties, TokenType::RightBrace, |printer, (_, value)| {
+                    printer.token(TokenType::Identifier);
+                    printer.token(TokenType::Colon);
+                    printer.space();
+                    printer.expression(value);
+                });
+            }
+            Expression::ObjectCall { .. } => {
+                self.token(TokenType::Identifier);
+                self.token(TokenType::Dot);
+                self.token(TokenType::Identifier);
+            }
+            Expression::Array { elements, .. } => {
+                self.token(TokenType::LeftBracket);
+                self.list(elements, TokenType::RightBracket, |printer, element| {
+                    printer.expression(element)
+                });
+            }
+            Expression::Index { array, index, .. } => {
+                self.expression(array);
+                self.token(TokenType::LeftBracket);
+                self.expression(index);
+                self.token(TokenType::RightBracket);
+            }
+            Expression::Assign { value, .. } => {
+                self.token(TokenType::Identifier);
+                self.space();
+                self.token(TokenType::Equal);
+                self.space();
+                self.expression(value);
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            }
+            | Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                self.space();
+                self.token(operator.token_type);
+                self.space();
+                self.expression(right);
+            }
+            Expression::Call {
+                callee,
+                arguments,
+                named,
+                ..
+            } => {
+                self.expression(callee);
+                self.token(TokenType::LeftParen);
+                let arguments: Vec<(bool, &Expression)> = arguments
+                    .iter()
+                    .map(|argument| (false, argument))
+                    .chain(named.iter().map(|(_, argument)| (true, argument)))
+                    .collect();
+                self.list(
+                    &arguments,
+                    TokenType::RightParen,
+                    |printer, (named, argument)| {
+                        if *named {
+                            printer.token(TokenType::Identifier);
+                            printer.token(TokenType::Colon);
+                            printer.space();
+                        }
+                        printer.expression(argument);
+                    },
+                );
+            }
+            Expression::Grouping { expression, .. } => {
+                self.token(TokenType::LeftParen);
+                self.expression(expression);
+                self.token(TokenType::RightParen);
+            }
+            Expression::Literal { value, .. } => {
+                let token_type = match value {
+                    TronType::Number(_) => TokenType::Number,
+                    TronType::StringValue(_) => TokenType::StringLit,
+                    TronType::True => TokenType::True,
+                    TronType::False => TokenType::False,
+                    _ => TokenType::Null,
+                };
+                self.token(token_type);
+            }
+            Expression::Unary {
+                operator, right, ..
+            } => {
+                self.token(operator.token_type);
+                // `- -x` can't become `--x`, which decrements
+                let joins = self.tokens.get(self.next).is_some_and(|next| {
+                    let last = operator.lexeme.chars().last();
+                    matches!(last, Some('-' | '+')) && next.lexeme.chars().next() == last
+                });
+                if joins {
+                    self.space();
+                }
+                self.expression(right);
+            }
+            Expression::Variable { .. } => self.token(TokenType::Identifier),
+            Expression::Function { params, body, .. } => {
+                self.token(TokenType::Function);
+                self.signature(params);
+                self.space();
+                self.block(body.iter().map(|statement| &**statement).collect());
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// exceptions, shared by both engines: the error values `catch` binds and `throw` raises, and the
+// error on its way to the `try` that will catch it
+//
+// While a `try` body runs, the runtime errors tron reports are raised instead, except for the
+// ones `errors::catchable` leaves out. The first error raised is kept here until the innermost
+// `try` takes it, and the engines unwind to that `try` by returning `Err(THROWN)`.
+//
+// Embedded, tron doesn't print errors or end the process: the first error that nothing catches
+// aborts the run, unwinding past every `try` to `Interpreter::run` or `Vm::run`, which return it.
+use crate::expressions::TronType;
+use crate::interpreter::stack::{self, TraceFrame};
+use crate::utils::options::options;
+use crate::utils::{exit, format_frame, TronError};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// what the engines return as their `Err` while an error unwinds
+pub const THROWN: &str = "throw";
+
+// the code of errors made from a message alone
+pub const DEFAULT_CODE: &str = "E4033";
+
+#[derive(Debug)]
+pub struct ErrorValue {
+    pub code: String,
+    pub message: String,
+    // where the error was made, for when nothing catches it
+    pub file: Option<String>,
+    pub line: usize,
+    // innermost call first; empty outside of calls
+    pub stack: Vec<TraceFrame>,
+}
+
+thread_local! {
+    // how many `try` bodies are running
+    static TRYING: Cell<usize> = const { Cell::new(0) };
+    static RAISED: RefCell<Option<Rc<ErrorValue>>> = const { RefCell::new(None) };
+    // the error ending an embedded run
+    static ABORTED: RefCell<Option<TronError>> = const { RefCell::new(None) };
+}
+
+impl ErrorValue {
+    // an error about `line` of the code being run
+    pub fn new(code: &str, message: &str, line: usize) -> Self {
+        let stack = stack::trace(line);
+        let file = file_of(&stack);
+        ErrorValue {
+            code: code.to_string(),
+            message: message.to_string(),
+            file,
+            line,
+            stack,
+        }
+    }
+    // an error made by a native, about the line it was called from
+    pub fn from_native(code: &str, message: &str) -> Self {
+        let mut stack = stack::trace(0);
+        if !stack.is_empty() {
+            stack.remove(0);
+        }
+        let line = stack.first().map_or(0, |frame| frame.line);
+        let file = file_of(&stack);
+        // without the native, a call made from the top level is outside of calls
+        if stack.len() == 1 {
+            stack.clear();
+        }
+        ErrorValue {
+            code: code.to_string(),
+            message: message.to_string(),
+            file,
+            line,
+            stack,
+        }
+    }
+    // the error a runtime error reported inside a `try` raises
+    pub fn reported(error: &TronError) -> Self {
+        ErrorValue {
+            code: error.code.clone(),
+            message: error.message.clone(),
+            file: error.file.clone(),
+            line: error.line,
+            stack: stack::trace(error.line),
+        }
+    }
+    // `error.code`, `error.message`, `error.line` and `error.stack`
+    pub fn field(&self, key: &str) -> Option<TronType> {
+        match key {
+            "code" => Some(TronType::string(self.code.as_str())),
+            "message" => Some(TronType::string(self.message.as_str())),
+            "line" => Some(TronType::Number(self.line as f32)),
+            "stack" => Some(TronType::array(
+                self.stack
+                    .iter()
+                    .map(|frame| TronType::string(format_frame(frame)))
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+    pub fn error(&self) -> TronError {
+        TronError {
+            code: self.code.clone(),
+            line: self.line,
+            message: self.message.clone(),
+            file: self.file.clone(),
+            span: None,
+        }
+    }
+    // reports the error the way tron reports its own
+    pub fn report(&self) {
+        self.error().report_with(&self.stack);
+    }
+}
+
+// the file of the innermost call that has one
+fn file_of(stack: &[TraceFrame]) -> Option<String> {
+    stack
+        .iter()
+        .find_map(|frame| frame.file.clone())
+        .or_else(|| Some(stack::current_file()))
+        .map(|file| file.to_string())
+}
+
+pub fn enter() {
+    TRYING.with(|trying| trying.set(trying.get() + 1));
+}
+
+pub fn leave() {
+    TRYING.with(|trying| trying.set(trying.get().saturating_sub(1)));
+}
+
+// whether a `try` would catch an error raised now
+pub fn trying() -> bool {
+    TRYING.with(|trying| trying.get() > 0)
+}
+
+// an error raised while another is unwinding is dropped
+pub fn raise(error: Rc<ErrorValue>) {
+    RAISED.with(|raised| {
+        raised.borrow_mut().get_or_insert(error);
+    });
+}
+
+// whether the engines are unwinding, to a `try` or out of the run
+pub fn raised() -> bool {
+    aborted() || RAISED.with(|raised| raised.borrow().is_some())
+}
+
+// whether the error unwinding is one a `try` catches
+pub fn catching() -> bool {
+    !aborted() && RAISED.with(|raised| raised.borrow().is_some())
+}
+
+// the error a `try` catches
+pub fn take() -> Option<Rc<ErrorValue>> {
+    if aborted() {
+        return None;
+    }
+    RAISED.with(|raised| raised.borrow_mut().take())
+}
+
+// ends the embedded run with `error`; errors after the first one are dropped
+pub fn abort(error: TronError) {
+    ABORTED.with(|aborted| {
+        aborted.borrow_mut().get_or_insert(error);
+    });
+}
+
+pub fn aborted() -> bool {
+    ABORTED.with(|aborted| aborted.borrow().is_some())
+}
+
+// the error that ended the run, once it has unwound out of it; nothing is left raised after it
+pub fn take_aborted() -> Option<TronError> {
+    let error = ABORTED.with(|aborted| aborted.borrow_mut().take())?;
+    RAISED.with(|raised| raised.borrow_mut().take());
+    Some(error)
+}
+
+// raises `error` for the innermost `try`, or when there is none, reports it and ends the program,
+// or aborts the run when embedded
+pub fn throw<T>(error: Rc<ErrorValue>) -> Result<T, String> {
+    if trying() {
+        raise(error);
+    } else if options().report_errors {
+        error.report();
+        exit(1);
+    } else {
+        abort(error.error());
+    }
+    Err(THROWN.to_string())
+}
+
+// what `throw value` raises: an error as it is, or a string as the message of a new one
+pub fn thrown(value: TronType, line: usize) -> Result<Rc<ErrorValue>, String> {
+    match value {
+        TronType::Error(error) => Ok(error),
+        TronType::StringValue(message) => {
+            Ok(Rc::new(ErrorValue::new(DEFAULT_CODE, &message, line)))
+        }
+        value => {
+            TronError::throw("E4034", line, vec![value.to_type().to_string()]);
+            Err(THROWN.to_string())
+        }
+    }
+}
@@ -1,48 +1,108 @@
 #[derive(Clone)]
+// shared, so copying a value stays cheap
 pub enum CallableImpl {
-    Function(FunctionImpl),
-    StdFunction(StdFunctionImpl),
+    Function(Rc<FunctionImpl>),
+    StdFunction(Rc<StdFunctionImpl>),
+    // compiled by the bytecode engine
+    Closure(Rc<crate::vm::Closure>),
 }
 #[derive(Clone, Debug)]
 pub struct FunctionImpl {
     pub name: String,
-    pub arity: usize,
+    pub arity: Arity,
     pub parent_env: Environment,
-    pub params: Vec<(Token, Token)>,
+    pub params: Vec<Parameter>,
     pub body: Vec<Box<Statement>>,
     pub output_type: Token,
+    // where the function was defined, for stack traces
+    pub file: Rc<str>,
 }
+// natives own their arguments, so one that returns an array it was given changed needn't copy it
+pub type NativeFunction = Rc<dyn Fn(Vec<TronType>) -> TronType>;
 #[derive(Clone)]
 pub struct StdFunctionImpl {
     pub name: String,
-    pub arity: usize,
-    pub function: Rc<dyn Fn(&Vec<TronType>) -> TronType>,
+    pub arity: Arity,
+    pub function: NativeFunction,
 }
-#[derive(Clone)]
-
-pub struct ObjectImpl {
-    pub name: String,
-    pub params: Vec<(Token, Expression)>,
+// how many arguments a callable accepts; `max` is `None` when it takes any number
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
 }
-
-#[derive(Clone)]
-pub struct CallbackImpl {
-    pub id: usize,
-    pub args: Vec<(Token, Option<Token>)>,
-    pub body: Vec<Box<Statement>>,
+impl Arity {
+    pub fn exact(count: usize) -> Self {
+        Arity {
+            min: count,
+            max: Some(count),
+        }
+    }
+    pub fn at_least(min: usize) -> Self {
+        Arity { min, max: None }
+    }
+    pub fn of(params: &[Parameter]) -> Self {
+        let min = params
+            .iter()
+            .filter(|param| param.default.is_none() && !param.rest)
+            .count();
+        if params.iter().any(|param| param.rest) {
+            Arity::at_least(min)
+        } else {
+            Arity {
+                min,
+                max: Some(params.len()),
+            }
+        }
+    }
+    // the check every call goes through, for Tron functions and natives alike; a call that fails
+    // it inside a `try` doesn't happen
+    pub fn check(&self, name: &str, count: usize, line: usize) -> Result<(), String> {
+        let args = |count: usize| {
+            let noun = if count == 1 { "argument" } else { "arguments" };
+            vec![name.to_string(), format!("{} {}", count, noun)]
+        };
+        let (code, expected) = match self.max {
+            Some(max) if max == self.min && count != max => ("E4020", max),
+            _ if count < self.min => ("E4018", self.min),
+            Some(max) if count > max => ("E4023", max),
+            _ => return Ok(()),
+        };
+        TronError::throw(code, line, args(expected));
+        Err(exceptions::THROWN.to_string())
+    }
 }
-
+impl From<usize> for Arity {
+    fn from(count: usize) -> Self {
+        Arity::exact(count)
+    }
+}
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{}..{}", self.min, max),
+            None => write!(f, "{}..", self.min),
+        }
+    }
+}
+// strings, arrays and objects are shared between copies of a value, so reading a variable or
+// passing an argument doesn't copy them; changing one goes through `Rc::make_mut`, which copies
+// only while it is still shared
+// functions are kept inline, as boxing them would cost an allocation for every closure made
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
-
 pub enum TronType {
     Number(f32),
-    StringValue(String),
+    StringValue(Rc<str>),
     True,
     False,
     Null,
-    ArrayValue(Vec<TronType>),
+    ArrayValue(Rc<Vec<TronType>>),
     Callable(CallableImpl),
-    Object(HashMap<String, TronType>),
+    Object(Rc<HashMap<String, TronType>>),
+    // what `catch` binds, see `exceptions`
+    Error(Rc<ErrorValue>),
 }
 
 use std::{
@@ -61,23 +121,17 @@ impl PartialEq for TronType {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Number(x), Number(y)) => x == y,
-            (
-                Callable(CallableImpl::Function(FunctionImpl { name, arity, .. })),
-                Callable(CallableImpl::Function(FunctionImpl {
-                    name: name2,
-                    arity: arity2,
-                    ..
-                })),
-            ) => name == name2 && arity == arity2,
-            (
-                Callable(CallableImpl::StdFunction(StdFunctionImpl { name, arity, .. })),
-                Callable(CallableImpl::StdFunction(StdFunctionImpl {
-                    name: name2,
-                    arity: arity2,
-                    ..
-                })),
-            ) => name == name2 && arity == arity2,
+            (Callable(CallableImpl::Function(x)), Callable(CallableImpl::Function(y))) => {
+                x.name == y.name && x.arity == y.arity
+            }
+            (Callable(CallableImpl::StdFunction(x)), Callable(CallableImpl::StdFunction(y))) => {
+                x.name == y.name && x.arity == y.arity
+            }
+            (Callable(CallableImpl::Closure(x)), Callable(CallableImpl::Closure(y))) => {
+                x.proto.name == y.proto.name && x.proto.arity == y.proto.arity
+            }
             (StringValue(x), StringValue(y)) => x == y,
+            (Error(x), Error(y)) => x.code == y.code && x.message == y.message,
             (True, True) => true,
             (False, False) => true,
             (Null, Null) => true,
@@ -89,7 +143,7 @@ fn unwrap_as_f64(literal: Option<LiteralValue>) -> f64 {
     match literal {
         Some(LiteralValue::NumericValue(x)) => x as f64,
         _ => {
-            TronError::throw("E4007", 0, vec!["uknown".to_string(), "f64".to_string()]);
+            TronError::throw("E4007", 0, vec!["unknown".to_string(), "f64".to_string()]);
             0.0
         }
     }
@@ -98,7 +152,11 @@ fn unwrap_as_string(literal: Option<LiteralValue>) -> String {
     match literal {
         Some(LiteralValue::StringValue(s)) => s.clone(),
         _ => {
-            TronError::throw("E4007", 0, vec!["uknown".to_string(), "string".to_string()]);
+            TronError::throw(
+                "E4007",
+                0,
+                vec!["unknown".to_string(), "string".to_string()],
+            );
             "".to_string()
         }
     }
@@ -120,12 +178,16 @@ impl TronType {
             TronType::True => "true".to_string(),
             TronType::False => "false".to_string(),
             TronType::Null => "null".to_string(),
-            TronType::Callable(CallableImpl::Function(FunctionImpl { name, arity, .. })) => {
-                format!("{name}/{arity}")
+            TronType::Callable(CallableImpl::Function(function)) => {
+                format!("{}/{}", function.name, function.arity)
+            }
+            TronType::Callable(CallableImpl::StdFunction(native)) => {
+                format!("{}/{}", native.name, native.arity)
+            }
+            TronType::Callable(CallableImpl::Closure(closure)) => {
+                format!("{}/{}", closure.proto.name, closure.proto.arity)
             }
-            TronType::Callable(CallableImpl::StdFunction(StdFunctionImpl {
-                name, arity, ..
-            })) => format!("{name}/{arity}"),
+            TronType::Error(error) => format!("{}: {}", error.code, error.message),
         }
     }
     pub fn to_type(&self) -> &str {
@@ -138,25 +200,31 @@ impl TronType {
             TronType::False => "boolean",
             TronType::Null => "null",
             TronType::Callable(_) => "function",
+            TronType::Error(_) => "error",
         }
     }
     pub fn from_token(token: Token) -> Self {
         match token.token_type {
             TokenType::Number => Self::Number(unwrap_as_f64(token.literal) as f32),
-            TokenType::StringLit => Self::StringValue(unwrap_as_string(token.literal)),
+            TokenType::StringLit => Self::string(unwrap_as_string(token.literal)),
             TokenType::False => Self::False,
             TokenType::True => Self::True,
             TokenType::Null => Self::Null,
             _ => {
-                TronError::throw(
-                    "E001",
-                    0,
-                    vec![token.token_type.to_string(), "uknown".to_string()],
-                );
+                TronError::throw("E4008", 0, vec![token.token_type.to_string()]);
                 Self::Null
             }
         }
     }
+    pub fn string(s: impl Into<Rc<str>>) -> Self {
+        StringValue(s.into())
+    }
+    pub fn array(elements: Vec<TronType>) -> Self {
+        ArrayValue(Rc::new(elements))
+    }
+    pub fn object(fields: HashMap<String, TronType>) -> Self {
+        Object(Rc::new(fields))
+    }
     pub fn from_bool(b: bool) -> Self {
         if b {
             True
@@ -168,21 +236,21 @@ impl TronType {
         match self {
             Object(_) => False,
             Number(x) => {
-                if *x == 0.0 as f32 {
+                if *x == 0.0_f32 {
                     True
                 } else {
                     False
                 }
             }
             StringValue(s) => {
-                if s.len() == 0 {
+                if s.is_empty() {
                     True
                 } else {
                     False
                 }
             }
             ArrayValue(x) => {
-                if x.len() == 0 {
+                if x.is_empty() {
                     True
                 } else {
                     False
@@ -192,13 +260,49 @@ impl TronType {
             False => True,
             Null => True,
             Callable(_) => True,
+            Error(_) => False,
+        }
+    }
+    pub fn as_number(&self) -> Option<f32> {
+        match self {
+            Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            StringValue(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            True => Some(true),
+            False => Some(false),
+            _ => None,
+        }
+    }
+    // whether both are the same array, rather than equal ones
+    pub fn same_array(&self, other: &TronType) -> bool {
+        matches!((self, other), (ArrayValue(a), ArrayValue(b)) if Rc::ptr_eq(a, b))
+    }
+    pub fn as_array(&self) -> Option<&Vec<TronType>> {
+        match self {
+            ArrayValue(x) => Some(x.as_ref()),
+            _ => None,
+        }
+    }
+    pub fn as_object(&self) -> Option<&HashMap<String, TronType>> {
+        match self {
+            Object(fields) => Some(fields.as_ref()),
+            _ => None,
         }
     }
     pub fn is_truthy(&self) -> TronType {
         match self {
             Object(_) => True,
             Number(x) => {
-                if *x == 0.0 as f32 {
+                if *x == 0.0_f32 {
                     False
                 } else {
                     True
@@ -206,14 +310,14 @@ impl TronType {
             }
 
             StringValue(s) => {
-                if s.len() == 0 {
+                if s.is_empty() {
                     False
                 } else {
                     True
                 }
             }
             ArrayValue(x) => {
-                if x.len() == 0 {
+                if x.is_empty() {
                     False
                 } else {
                     True
@@ -223,12 +327,19 @@ impl TronType {
             False => False,
             Null => False,
             Callable(_) => False,
+            Error(_) => True,
         }
     }
 }
-use crate::{scanner::Statement, utils::TronError, Interpreter, LiteralValue, Token, TokenType};
+use crate::{
+    library::IN_PLACE,
+    scanner::{Parameter, Statement},
+    utils::TronError,
+    Interpreter, LiteralValue, Token, TokenType,
+};
 
-use super::Environment;
+use super::exceptions::{self, ErrorValue};
+use super::{stack, CallFrame, Environment};
 #[derive(Clone)]
 pub enum Expression {
     Object {
@@ -244,6 +355,13 @@ pub enum Expression {
         id: usize,
         elements: Vec<Box<Expression>>,
     },
+    Index {
+        id: usize,
+        array: Box<Expression>,
+        index: Box<Expression>,
+        // the `]`, for the line of errors
+        bracket: Token,
+    },
     Assign {
         id: usize,
         name: Token,
@@ -258,8 +376,10 @@ pub enum Expression {
     Call {
         id: usize,
         callee: Box<Expression>,
+        #[allow(dead_code)]
         paren: Token,
         arguments: Vec<Expression>,
+        named: Vec<(Token, Expression)>,
     },
     Grouping {
         id: usize,
@@ -287,10 +407,10 @@ pub enum Expression {
     Function {
         id: usize,
         name: Token,
-        params: Vec<(Token, Token)>,
+        params: Vec<Parameter>,
         body: Vec<Box<Statement>>,
         output_type: Token,
-    }
+    },
 }
 
 impl std::fmt::Debug for Expression {
@@ -328,6 +448,7 @@ impl Expression {
             } => *id,
             Expression::Object { id, properties: _ } => *id,
             Expression::Array { id, elements: _ } => *id,
+            Expression::Index { id, .. } => *id,
             Expression::Assign {
                 id,
                 name: _,
@@ -344,6 +465,7 @@ impl Expression {
                 callee: _,
                 paren: _,
                 arguments: _,
+                named: _,
             } => *id,
             Expression::Grouping { id, expression: _ } => *id,
             Expression::Literal { id, value: _ } => *id,
@@ -363,6 +485,30 @@ impl Expression {
     }
 }
 impl Expression {
+    // `name = @push(name, ...)` and the other `IN_PLACE` builtins: the callee, the arguments and the
+    // `(`. Both engines let go of the variable's array just before the call, so the builtin has the
+    // only reference to it and changes it without a copy; the assignment then puts it back.
+    pub fn in_place_call(&self, name: &str) -> Option<(&Expression, &Vec<Expression>, &Token)> {
+        let Expression::Call {
+            callee,
+            paren,
+            arguments,
+            named,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let (
+            Expression::Variable { name: builtin, .. },
+            Some(Expression::Variable { name: first, .. }),
+        ) = (callee.as_ref(), arguments.first())
+        else {
+            return None;
+        };
+        (IN_PLACE.contains(&builtin.lexeme.as_str()) && first.lexeme == name && named.is_empty())
+            .then_some((callee, arguments, paren))
+    }
     pub fn to_string(&self) -> String {
         match self {
             Expression::Function {
@@ -376,7 +522,7 @@ impl Expression {
                 name.lexeme,
                 params
                     .iter()
-                    .map(|(name, typ)| format!("{}: {}", name.lexeme, typ.lexeme).to_string())
+                    .map(|param| format!("{}: {}", param.name.lexeme, param.type_token.lexeme))
                     .collect::<Vec<_>>()
                     .join(", "),
                 output_type.lexeme
@@ -393,7 +539,7 @@ impl Expression {
                 id: _,
                 key,
                 name: _,
-            } => format!("{}", key.lexeme), //
+            } => key.lexeme.to_string(), //
             Expression::Array { id: _, elements } => {
                 let elements_str = elements
                     .iter()
@@ -402,6 +548,9 @@ impl Expression {
                     .join(", ");
                 format!("[{}]", elements_str)
             }
+            Expression::Index { array, index, .. } => {
+                format!("{}[{}]", array.to_string(), index.to_string())
+            }
             Expression::Assign { id: _, name, value } => {
                 format!("({name:?} = {}", value.to_string())
             }
@@ -421,11 +570,21 @@ impl Expression {
                 callee,
                 paren: _,
                 arguments,
-            } => format!("({} {:?})", (*callee).to_string(), arguments),
+                named,
+            } => format!(
+                "({} {:?} {})",
+                (*callee).to_string(),
+                arguments,
+                named
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name.lexeme, value.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             Expression::Grouping { id: _, expression } => {
                 format!("(group {})", (*expression).to_string())
             }
-            Expression::Literal { id: _, value } => format!("{}", value.to_string()),
+            Expression::Literal { id: _, value } => value.to_string(),
             Expression::Logical {
                 id: _,
                 left,
@@ -451,6 +610,14 @@ impl Expression {
     }
 
     pub fn evaluate(&self, environment: Environment) -> Result<TronType, String> {
+        let value = self.evaluate_value(environment)?;
+        // an error raised inside a `try` unwinds to it
+        if exceptions::raised() {
+            return Err(exceptions::THROWN.to_string());
+        }
+        Ok(value)
+    }
+    fn evaluate_value(&self, environment: Environment) -> Result<TronType, String> {
         match self {
             Expression::Function {
                 id: _,
@@ -461,25 +628,16 @@ impl Expression {
             } => {
                 let function_impl = FunctionImpl {
                     name: name.clone().lexeme,
-                    arity: params.len(),
-                    parent_env: environment.clone(),
+                    arity: Arity::of(params),
+                    parent_env: environment,
                     params: params.clone(),
                     body: body.clone(),
                     output_type: output_type.clone(),
+                    file: stack::current_file(),
                 };
-                let callback_env = environment.enclose();
-
-                let int = Interpreter::with_env(callback_env.clone());
-                let callable = int.make_function(&Statement::FunctionStatement {
-                    name: name.clone(),
-                    params: function_impl.clone().params,
-                    body: function_impl.clone().body,
-                    output_type: function_impl.clone().output_type,
-                    line: name.line_number,
-                });
-                let fun = TronType::Callable(CallableImpl::Function(callable));
-                environment.define(function_impl.clone().name.clone(), fun);
-                Ok(TronType::Callable(CallableImpl::Function(function_impl)))
+                Ok(TronType::Callable(CallableImpl::Function(Rc::new(
+                    function_impl,
+                ))))
             }
             Expression::Object { id: _, properties } => {
                 let mut fields = HashMap::new();
@@ -487,59 +645,72 @@ impl Expression {
                     let value = value_expr.evaluate(environment.clone())?;
                     fields.insert(key.lexeme.clone(), value);
                 }
-                Ok(TronType::Object(fields))
+                Ok(TronType::object(fields))
             }
             Expression::ObjectCall { id, key, name } => {
-                let object = environment.get(&name.lexeme, *id);
-                match object {
-                    Some(TronType::Object(fields)) => match fields.get(&key.lexeme) {
-                        Some(value) => Ok(value.clone()),
-                        None => Err(format!("Key '{}' not found in object", key.lexeme)),
-                    },
-                    _ => Err(format!("'{}' is not an object", key.lexeme)),
-                }
+                let object = environment.get(&name.lexeme, *id).unwrap_or(Null);
+                field_op(&object, &key.lexeme, key.line_number)
             }
             Expression::Array { id: _, elements } => {
-                if elements.len() == 2 {
-                    let array = elements[0].evaluate(environment.clone())?;
-                    let index = elements[1].evaluate(environment.clone())?;
-                    if let TronType::Number(index_num) = index {
-                        if let TronType::ArrayValue(arr) = array {
-                            let idx = index_num as usize;
-                            return arr.get(idx).cloned().ok_or_else(|| {
-                                TronError::throw("E4009", 0, vec![]);
-                                "".to_string()
-                            });
-                        }
-                    }
-
-                    TronError::throw("E4010", 0, vec![]);
-                    Ok(TronType::Null)
-                } else {
-                    let mut array_elements = Vec::new();
-                    for element_expr in elements.iter() {
-                        let evaluated = element_expr.evaluate(environment.clone())?;
-                        array_elements.push(evaluated);
-                    }
-                    Ok(TronType::ArrayValue(array_elements))
+                let mut array_elements = Vec::new();
+                for element_expr in elements.iter() {
+                    let evaluated = element_expr.evaluate(environment.clone())?;
+                    array_elements.push(evaluated);
                 }
+                Ok(TronType::array(array_elements))
+            }
+            Expression::Index {
+                id: _,
+                array,
+                index,
+                bracket,
+            } => {
+                let array = array.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                index_op(&array, &index, bracket.line_number)
             }
             Expression::Assign { id: _, name, value } => {
                 if name.lexeme.chars().next().unwrap().is_uppercase() {
                     TronError::throw("E4012", name.line_number, vec![]);
                 }
-                let new_value = (*value).evaluate(environment.clone())?;
+                let new_value = match value.in_place_call(&name.lexeme) {
+                    Some((callee, arguments, paren)) => {
+                        match callee.evaluate(environment.clone())? {
+                            Callable(CallableImpl::StdFunction(native)) => call_native(
+                                &native,
+                                arguments,
+                                environment.clone(),
+                                paren.line_number,
+                                |args| {
+                                    let held = environment.get(&name.lexeme, arguments[0].get_id());
+                                    if held.is_some_and(|held| held.same_array(&args[0])) {
+                                        environment.assign(&name.lexeme, Null, self.get_id());
+                                    }
+                                },
+                            )?,
+                            callable => call(
+                                callable,
+                                arguments,
+                                &[],
+                                environment.clone(),
+                                paren.line_number,
+                            )?,
+                        }
+                    }
+                    None => (*value).evaluate(environment.clone())?,
+                };
                 let assign_success =
                     environment.assign(&name.lexeme, new_value.clone(), self.get_id());
                 let type_annotation = environment.get_value_type(&name.lexeme);
-                match type_annotation {
-                    Some(expected_type) => match (expected_type.as_str(), &new_value) {
+                if let Some(expected_type) = type_annotation {
+                    match (expected_type.as_str(), &new_value) {
                         ("number", TronType::Number(_)) => {}
                         ("string", TronType::StringValue(_)) => {}
                         ("array", TronType::ArrayValue(_)) => {}
                         ("object", TronType::Object(_)) => {}
                         ("bool", TronType::True) | ("bool", TronType::False) => {}
                         ("null", TronType::Null) => {}
+                        ("error", TronType::Error(_)) => {}
                         _ => TronError::throw(
                             "E4003",
                             0,
@@ -550,8 +721,7 @@ impl Expression {
                                 new_value.to_type().to_string(),
                             ],
                         ),
-                    },
-                    None => {}
+                    }
                 }
                 if assign_success {
                     Ok(new_value)
@@ -563,42 +733,21 @@ impl Expression {
             Expression::Call {
                 id: _,
                 callee,
-                paren: _,
+                paren,
                 arguments,
+                named,
             } => {
                 let callable: TronType = (*callee).evaluate(environment.clone())?;
-                match callable {
-                    Callable(CallableImpl::Function(tronfun)) => {
-                        run_tron_function(tronfun, arguments, environment)
-                    }
-                    Callable(CallableImpl::StdFunction(nativefun)) => {
-                        let mut evaluated_arguments = vec![];
-                        for argument in arguments {
-                            evaluated_arguments.push(argument.evaluate(environment.clone())?);
-                        }
-                        Ok((nativefun.function)(&evaluated_arguments))
-                    }
-                    _ => {
-                        TronError::throw("E4013", 0, vec![]);
-                        Ok(TronType::Null)
-                    }
-                }
+                call(callable, arguments, named, environment, paren.line_number)
             }
             Expression::Variable { id: _, name } => {
-                let parts: Vec<&str> = name.lexeme.split('.').collect();
-                if parts.len() == 2 {
-                    let object_name = parts[0];
-                    let key = parts[1];
-                    match environment.get(object_name, self.get_id()) {
-                        Some(TronType::Object(fields)) => match fields.get(key) {
-                            Some(value) => Ok(value.clone()),
-                            None => Err(format!(
-                                "Key '{}' not found in object '{}'",
-                                key, object_name
-                            )),
-                        },
-                        _ => Err(format!("'{}' is not an object", object_name)),
-                    }
+                if let Some((object_name, key)) = name
+                    .lexeme
+                    .split_once('.')
+                    .filter(|(_, key)| !key.contains('.'))
+                {
+                    let object = environment.get(object_name, self.get_id()).unwrap_or(Null);
+                    field_op(&object, key, name.line_number)
                 } else {
                     match environment.get(&name.lexeme, self.get_id()) {
                         Some(value) => Ok(value.clone()),
@@ -664,36 +813,7 @@ impl Expression {
                 right,
             } => {
                 let right = right.evaluate(environment)?;
-                match (&right, operator.token_type) {
-                    // minus
-                    (Number(x), TokenType::Minus) => Ok(Number(-x)),
-                    (True, TokenType::Minus) => Ok(False),
-                    (False, TokenType::Minus) => Ok(True),
-                    (e, TokenType::Minus) => {
-                        TronError::throw("E4015", 0, vec!["minus".to_string(), e.to_string()]);
-                        Ok(TronType::Null)
-                    }
-                    (Number(x), TokenType::Increment) => Ok(Number(x + 1.0)),
-                    (Number(x), TokenType::Decrement) => Ok(Number(x - 1.0)),
-                    (e, TokenType::Increment) => {
-                        TronError::throw("E4015", 0, vec!["increment".to_string(), e.to_string()]);
-
-                        Ok(TronType::Null)
-                    }
-                    (e, TokenType::Decrement) => {
-                        TronError::throw("E4015", 0, vec!["decrement".to_string(), e.to_string()]);
-                        Ok(TronType::Null)
-                    }
-                    (e, TokenType::Percent) => {
-                        TronError::throw("E4015", 0, vec!["percent".to_string(), e.to_string()]);
-                        Ok(TronType::Null)
-                    }
-                    (any, TokenType::Bang) => Ok(any.is_falsy()),
-                    (e, f) => {
-                        TronError::throw("E4015", 0, vec![f.to_string(), e.to_string()]);
-                        Ok(TronType::Null)
-                    }
-                }
+                Ok(unary_op(operator.token_type, &right, operator.line_number))
             }
             Expression::Binary {
                 id: _,
@@ -703,147 +823,353 @@ impl Expression {
             } => {
                 let left = left.evaluate(environment.clone())?;
                 let right = right.evaluate(environment.clone())?;
-                match (&left, operator.token_type, &right) {
-                    (Number(x), TokenType::Plus, Number(y)) => Ok(Number(x + y)),
-                    (StringValue(x), TokenType::Plus, Number(y)) => {
-                        Ok(StringValue(format!("{}{}", x, y.to_string())))
-                    }
-                    (Number(x), TokenType::Plus, StringValue(y)) => {
-                        Ok(StringValue(format!("{}{}", x.to_string(), y)))
-                    }
-                    (StringValue(x), TokenType::Plus, StringValue(y)) => {
-                        Ok(StringValue(format!("{}{}", x.to_string(), y)))
-                    }
-                    (Number(x), TokenType::Minus, Number(y)) => Ok(Number(x - y)),
-                    (Number(x), TokenType::Star, Number(y)) => Ok(Number(x * y)),
-                    (Number(x), TokenType::Slash, Number(y)) => Ok(Number(x / y)),
-                    (Number(x), TokenType::Greater, Number(y)) => Ok(TronType::from_bool(x > y)),
-                    (StringValue(x), TokenType::Greater, StringValue(y)) => {
-                        Ok(TronType::from_bool(x.len() > y.len()))
-                    }
-                    (Number(x), TokenType::GreaterEqual, Number(y)) => {
-                        Ok(TronType::from_bool(x >= y))
-                    }
-                    (StringValue(x), TokenType::GreaterEqual, StringValue(y)) => {
-                        Ok(TronType::from_bool(x.len() >= y.len()))
-                    }
-                    (Number(x), TokenType::Less, Number(y)) => Ok(TronType::from_bool(x < y)),
-                    (StringValue(x), TokenType::Less, StringValue(y)) => {
-                        Ok(TronType::from_bool(x.len() < y.len()))
-                    }
-                    (Number(x), TokenType::LessEqual, Number(y)) => Ok(TronType::from_bool(x <= y)),
-                    (StringValue(x), TokenType::LessEqual, StringValue(y)) => {
-                        Ok(TronType::from_bool(x.len() <= y.len()))
-                    }
-                    (StringValue(_), e, Number(_)) => {
-                        TronError::throw(
-                            "E4015",
-                            0,
-                            vec!["string and number".to_string(), e.to_string()],
-                        );
-                        Ok(TronType::Null)
-                    }
-                    (Number(_), e, StringValue(_)) => {
-                        TronError::throw(
-                            "E4015",
-                            0,
-                            vec!["number and string".to_string(), e.to_string()],
-                        );
-                        Ok(TronType::Null)
-                    }
-                    (x, TokenType::BangEqual, y) => Ok(TronType::from_bool(x != y)),
-                    (x, TokenType::EqualEqual, y) => Ok(TronType::from_bool(x == y)),
-                    (f, e, c) => {
-                        TronError::throw(
-                            "E4015",
-                            0,
-                            vec![
-                                format!("{} and {}", f.to_string(), c.to_string()).to_string(),
-                                e.to_string(),
-                            ],
-                        );
-                        Ok(TronType::Null)
-                    }
-                }
+                Ok(binary_op(
+                    &left,
+                    operator.token_type,
+                    &right,
+                    operator.line_number,
+                ))
             }
         }
     }
 }
-pub fn run_tron_function(
-    tronfun: FunctionImpl,
+// shared by both execution engines, so they agree on every operator
+pub fn unary_op(operator: TokenType, right: &TronType, line: usize) -> TronType {
+    match (right, operator) {
+        // minus
+        (Number(x), TokenType::Minus) => Number(-x),
+        (True, TokenType::Minus) => False,
+        (False, TokenType::Minus) => True,
+        (e, TokenType::Minus) => {
+            TronError::throw("E4015", line, vec!["minus".to_string(), e.to_string()]);
+            TronType::Null
+        }
+        (Number(x), TokenType::Increment) => Number(x + 1.0),
+        (Number(x), TokenType::Decrement) => Number(x - 1.0),
+        (e, TokenType::Increment) => {
+            TronError::throw("E4015", line, vec!["increment".to_string(), e.to_string()]);
+
+            TronType::Null
+        }
+        (e, TokenType::Decrement) => {
+            TronError::throw("E4015", line, vec!["decrement".to_string(), e.to_string()]);
+            TronType::Null
+        }
+        (e, TokenType::Percent) => {
+            TronError::throw("E4015", line, vec!["percent".to_string(), e.to_string()]);
+            TronType::Null
+        }
+        (any, TokenType::Bang) => any.is_falsy(),
+        (e, f) => {
+            TronError::throw("E4015", line, vec![f.to_string(), e.to_string()]);
+            TronType::Null
+        }
+    }
+}
+pub fn binary_op(left: &TronType, operator: TokenType, right: &TronType, line: usize) -> TronType {
+    match (left, operator, right) {
+        (Number(x), TokenType::Plus, Number(y)) => Number(x + y),
+        (StringValue(x), TokenType::Plus, Number(y)) => TronType::string(format!("{}{}", x, y)),
+        (Number(x), TokenType::Plus, StringValue(y)) => TronType::string(format!("{}{}", x, y)),
+        (StringValue(x), TokenType::Plus, StringValue(y)) => {
+            TronType::string(format!("{}{}", x, y))
+        }
+        (Number(x), TokenType::Minus, Number(y)) => Number(x - y),
+        (Number(x), TokenType::Star, Number(y)) => Number(x * y),
+        (Number(x), TokenType::Slash, Number(y)) => Number(x / y),
+        (Number(x), TokenType::Greater, Number(y)) => TronType::from_bool(x > y),
+        (StringValue(x), TokenType::Greater, StringValue(y)) => {
+            TronType::from_bool(x.len() > y.len())
+        }
+        (Number(x), TokenType::GreaterEqual, Number(y)) => TronType::from_bool(x >= y),
+        (StringValue(x), TokenType::GreaterEqual, StringValue(y)) => {
+            TronType::from_bool(x.len() >= y.len())
+        }
+        (Number(x), TokenType::Less, Number(y)) => TronType::from_bool(x < y),
+        (StringValue(x), TokenType::Less, StringValue(y)) => TronType::from_bool(x.len() < y.len()),
+        (Number(x), TokenType::LessEqual, Number(y)) => TronType::from_bool(x <= y),
+        (StringValue(x), TokenType::LessEqual, StringValue(y)) => {
+            TronType::from_bool(x.len() <= y.len())
+        }
+        (StringValue(_), e, Number(_)) => {
+            TronError::throw(
+                "E4015",
+                line,
+                vec!["string and number".to_string(), e.to_string()],
+            );
+            TronType::Null
+        }
+        (Number(_), e, StringValue(_)) => {
+            TronError::throw(
+                "E4015",
+                line,
+                vec!["number and string".to_string(), e.to_string()],
+            );
+            TronType::Null
+        }
+        (x, TokenType::BangEqual, y) => TronType::from_bool(x != y),
+        (x, TokenType::EqualEqual, y) => TronType::from_bool(x == y),
+        (f, e, c) => {
+            TronError::throw(
+                "E4015",
+                line,
+                vec![
+                    format!("{} and {}", f.to_string(), c.to_string()).to_string(),
+                    e.to_string(),
+                ],
+            );
+            TronType::Null
+        }
+    }
+}
+// `value.key`: a field of an object, or `code`, `message`, `line` or `stack` of an error
+pub fn field_op(value: &TronType, key: &str, line: usize) -> Result<TronType, String> {
+    let field = match value {
+        TronType::Object(fields) => fields.get(key).cloned(),
+        TronType::Error(error) => error.field(key),
+        _ => {
+            TronError::throw("E4032", line, vec![key.to_string()]);
+            return Err(String::new());
+        }
+    };
+    field.ok_or_else(|| {
+        TronError::throw("E4031", line, vec![key.to_string()]);
+        String::new()
+    })
+}
+pub fn index_op(array: &TronType, index: &TronType, line: usize) -> Result<TronType, String> {
+    if let (TronType::ArrayValue(arr), TronType::Number(index_num)) = (array, index) {
+        let idx = *index_num as usize;
+        return arr.get(idx).cloned().ok_or_else(|| {
+            TronError::throw("E4009", line, vec![]);
+            "".to_string()
+        });
+    }
+    TronError::throw("E4010", line, vec![]);
+    Ok(TronType::Null)
+}
+// calls any callable value with argument expressions evaluated in `eval_env`
+// `before` sees the arguments once they are evaluated, just before the call
+fn call_native(
+    native: &StdFunctionImpl,
+    arguments: &[Expression],
+    eval_env: Environment,
+    line: usize,
+    before: impl FnOnce(&[TronType]),
+) -> Result<TronType, String> {
+    native.arity.check(&native.name, arguments.len(), line)?;
+    let mut evaluated_arguments = vec![];
+    for argument in arguments {
+        evaluated_arguments.push(argument.evaluate(eval_env.clone())?);
+    }
+    before(&evaluated_arguments);
+    let _frame = CallFrame::native(&native.name, line);
+    let value = (native.function)(evaluated_arguments);
+    if exceptions::raised() {
+        return Err(exceptions::THROWN.to_string());
+    }
+    Ok(value)
+}
+
+pub fn call(
+    callable: TronType,
     arguments: &Vec<Expression>,
+    named: &[(Token, Expression)],
     eval_env: Environment,
+    line: usize,
 ) -> Result<TronType, String> {
-    if arguments.len() != tronfun.arity {
-        return Err(format!(
-            "Callable {} expected {} arguments but got {}",
-            tronfun.name,
-            tronfun.arity,
-            arguments.len()
-        ));
+    match callable {
+        Callable(CallableImpl::Function(tronfun)) => {
+            run_tron_function(tronfun, arguments, named, eval_env, line)
+        }
+        Callable(CallableImpl::StdFunction(nativefun)) => {
+            if let Some((name, _)) = named.first() {
+                TronError::throw(
+                    "E4024",
+                    name.line_number,
+                    vec![nativefun.name.clone(), name.lexeme.clone()],
+                );
+                return Ok(TronType::Null);
+            }
+            call_native(&nativefun, arguments, eval_env, line, |_| {})
+        }
+        _ => {
+            TronError::throw("E4013", line, vec![]);
+            Ok(TronType::Null)
+        }
     }
+}
+pub fn run_tron_function(
+    tronfun: Rc<FunctionImpl>,
+    arguments: &Vec<Expression>,
+    named: &[(Token, Expression)],
+    eval_env: Environment,
+    line: usize,
+) -> Result<TronType, String> {
+    let (arguments, named) = evaluate_arguments(&tronfun, arguments, named, &eval_env, line)?;
+    call_tron_function(tronfun, arguments, named, line)
+}
+// values passed by parameter name, in the order they were written
+pub type NamedArguments = Vec<(Token, TronType)>;
+// `return f(...)` in a Tron function, with the arguments already evaluated; the function it
+// returns from runs it in place of its own call instead of recursing
+#[derive(Debug)]
+pub struct TailCall {
+    pub function: Rc<FunctionImpl>,
+    pub arguments: Vec<TronType>,
+    pub named: NamedArguments,
+    pub line: usize,
+}
+pub fn evaluate_arguments(
+    tronfun: &FunctionImpl,
+    arguments: &Vec<Expression>,
+    named: &[(Token, Expression)],
+    eval_env: &Environment,
+    line: usize,
+) -> Result<(Vec<TronType>, NamedArguments), String> {
+    tronfun
+        .arity
+        .check(&tronfun.name, arguments.len() + named.len(), line)?;
     let mut arg_vals = vec![];
     for arg in arguments {
-        let val = arg.evaluate(eval_env.clone())?;
-        arg_vals.push(val);
+        arg_vals.push(arg.evaluate(eval_env.clone())?);
     }
-    let fun_env = tronfun.parent_env.enclose();
-    for (i, val) in arg_vals.iter().enumerate() {
-        if i < tronfun.params.len() {
-            let (param_name_token, param_type_token) = &tronfun.params[i];
-            let param_name_lexeme = &param_name_token.lexeme;
-
-            let param_type_lexeme = &param_type_token.lexeme;
+    let mut named_vals = vec![];
+    for (name, value) in named {
+        named_vals.push((name.clone(), value.evaluate(eval_env.clone())?));
+    }
+    Ok((arg_vals, named_vals))
+}
+fn call_tron_function(
+    mut tronfun: Rc<FunctionImpl>,
+    mut arguments: Vec<TronType>,
+    mut named: NamedArguments,
+    mut line: usize,
+) -> Result<TronType, String> {
+    let frame = CallFrame::enter(&tronfun.name, tronfun.file.clone(), line);
+    loop {
+        let Some(fun_env) = bind_arguments(&tronfun, arguments, named, line)? else {
+            return Ok(TronType::Null);
+        };
+        if exceptions::raised() {
+            return Err(exceptions::THROWN.to_string());
+        }
+        let mut int = Interpreter::with_env(fun_env);
+        let mut returned = None;
+        for stmt in tronfun.body.iter() {
+            int.interpret(vec![stmt.as_ref()])?;
+            if let Some(value) = int.specials.get("return") {
+                returned = Some(value.clone());
+                break;
+            }
+        }
+        if let Some(tail) = int.tail_call.take() {
+            // with the same output type the callee's check covers this function's as well
+            if tail.function.output_type.lexeme == tronfun.output_type.lexeme {
+                frame.replace(&tail.function.name, tail.function.file.clone());
+                tronfun = tail.function;
+                arguments = tail.arguments;
+                named = tail.named;
+                line = tail.line;
+                continue;
+            }
+            returned = Some(call_tron_function(
+                tail.function,
+                tail.arguments,
+                tail.named,
+                tail.line,
+            )?);
+        }
 
-            match (param_type_lexeme.as_str(), val) {
-                ("number", TronType::Number(_)) => {}
-                ("string", TronType::StringValue(_)) => {}
-                ("array", TronType::ArrayValue(_)) => {}
-                ("object", TronType::Object(_)) => {}
-                ("bool", TronType::True) | ("bool", TronType::False) => {}
-                ("null", TronType::Null) => {}
-                _ => {
-                    TronError::throw(
-                        "E4002",
-                        0,
-                        vec![
-                            tronfun.name.to_string(),
-                            param_name_lexeme.to_string(),
-                            val.to_type().to_string(),
-                            param_type_lexeme.to_string(),
-                        ],
-                    );
+        let output_type_lexeme = &tronfun.output_type.lexeme;
+        return match returned {
+            Some(value) => {
+                if !(*output_type_lexeme == value.to_type()
+                    || *output_type_lexeme == value.to_string())
+                {
+                    TronError::throw("E4017", int.returned_at, vec![]);
                 }
+                Ok(value)
             }
-
-            fun_env.define(param_name_lexeme.clone(), val.clone());
-        } else {
-            TronError::throw("E4014", 0, vec![]);
+            None => {
+                // falling off the end is about the declaration
+                if output_type_lexeme != "null" {
+                    TronError::throw("E4017", tronfun.output_type.line_number, vec![]);
+                }
+                Ok(TronType::Null)
+            }
+        };
+    }
+}
+// the scope a call on `line` runs in, with every parameter defined; `None` when one got no value
+fn bind_arguments(
+    tronfun: &FunctionImpl,
+    arguments: Vec<TronType>,
+    named: NamedArguments,
+    line: usize,
+) -> Result<Option<Environment>, String> {
+    let mut bound: Vec<Option<TronType>> = vec![None; tronfun.params.len()];
+    let mut positional = arguments.into_iter();
+    for (slot, param) in bound.iter_mut().zip(&tronfun.params) {
+        if param.rest {
+            *slot = Some(TronType::array(positional.by_ref().collect()));
+        } else if let Some(val) = positional.next() {
+            *slot = Some(val);
         }
     }
-    let mut int = Interpreter::with_env(fun_env);
-    for stmt in tronfun.body.iter() {
-        let result = int.interpret(vec![stmt.as_ref()]);
-        if let Err(_e) = result {
-            TronError::throw("E4006", 0, vec![]);
-        } else if let Some(value) = int.specials.get("return") {
-            let output_type_lexeme = &tronfun.output_type.lexeme;
-            let value_clone = value.clone();
-            let value_clone_type = value_clone.to_type();
-            let value_clone_string = value_clone.to_string();
-            if !(output_type_lexeme == value_clone_type
-                || output_type_lexeme.clone() == value_clone_string)
-            {
-                TronError::throw("E4017", 0, vec![]);
-            }
-            return Ok(value.clone());
+    for (name, val) in named {
+        let args = vec![tronfun.name.clone(), name.lexeme.clone()];
+        match tronfun
+            .params
+            .iter()
+            .position(|param| param.name.lexeme == name.lexeme && !param.rest)
+        {
+            Some(i) if bound[i].is_none() => bound[i] = Some(val),
+            Some(_) => TronError::throw("E4025", line, args),
+            None => TronError::throw("E4024", line, args),
         }
     }
+    // defaults are evaluated per call, after the parameters before them are defined
+    let fun_env = tronfun.parent_env.enclose();
+    for (param, val) in tronfun.params.iter().zip(bound) {
+        let param_name_lexeme = &param.name.lexeme;
+        let val = match (val, &param.default) {
+            (Some(val), _) => val,
+            (None, Some(default)) => default.evaluate(fun_env.clone())?,
+            (None, None) => {
+                TronError::throw(
+                    "E4026",
+                    line,
+                    vec![tronfun.name.clone(), param_name_lexeme.clone()],
+                );
+                return Ok(None);
+            }
+        };
+        let param_type_lexeme = &param.type_token.lexeme;
 
-    let output_type_lexeme = &tronfun.output_type.lexeme;
-    if output_type_lexeme != "null" {
-        TronError::throw("E4017", 0, vec![]);
-    }
+        match (param_type_lexeme.as_str(), &val) {
+            ("number", TronType::Number(_)) => {}
+            ("string", TronType::StringValue(_)) => {}
+            ("array", TronType::ArrayValue(_)) => {}
+            ("object", TronType::Object(_)) => {}
+            ("bool", TronType::True) | ("bool", TronType::False) => {}
+            ("null", TronType::Null) => {}
+            ("error", TronType::Error(_)) => {}
+            _ => {
+                TronError::throw(
+                    "E4002",
+                    0,
+                    vec![
+                        tronfun.name.to_string(),
+                        param_name_lexeme.to_string(),
+                        param_type_lexeme.to_string(),
+                        val.to_type().to_string(),
+                    ],
+                );
+            }
+        }
 
-    Ok(TronType::Null)
+        fun_env.define(param_name_lexeme.clone(), val);
+    }
+    Ok(Some(fun_env))
 }
@@ -1,17 +1,65 @@
+use crate::debugger;
 use crate::environment::*;
 use crate::expressions::*;
-use crate::library::standard_library;
+use crate::library::native::{declare_native, IntoNative};
+use crate::library::{declare_function, declare_script_args, standard_library};
+use crate::memory;
+use crate::optimizer::optimize;
 use crate::parser::*;
 use crate::resolver::*;
+use crate::sandbox::{self, Sandbox};
 use crate::scanner::*;
+use crate::utils::options::{options, set_options, Options};
 use crate::utils::TronError;
+use expressions::{call, evaluate_arguments, TailCall};
 use std::collections::HashMap;
+use std::rc::Rc;
+pub mod exceptions;
 pub mod expressions;
+pub mod stack;
+
+pub(crate) use stack::CallFrame;
+pub use stack::{limit_stack, TraceFrame};
+
+// every statement counts towards `--max-steps`, including those run inside function calls
+pub(crate) fn count_step(line: usize) {
+    memory::safepoint(line);
+    sandbox::step(line);
+}
+
+// the error that ended a run, for the embedder; the `tron` binary has already reported it
+pub(crate) fn finish<T>(result: Result<T, String>) -> Result<T, TronError> {
+    match (result, exceptions::take_aborted()) {
+        (_, Some(error)) => Err(error),
+        (Ok(value), None) => Ok(value),
+        (Err(message), None) => Err(TronError::new(
+            exceptions::DEFAULT_CODE,
+            0,
+            None,
+            vec![message],
+        )),
+    }
+}
 
 #[derive(Debug)]
 pub struct Interpreter {
     pub specials: HashMap<String, TronType>,
     pub environment: Environment,
+    next_id: usize,
+    // set by `return f(...)`, see `TailCall`
+    tail_call: Option<TailCall>,
+    // the line of the last `return` run, which the function's output type check reports
+    pub(crate) returned_at: usize,
+    // the `try` statements being run, in which a `return` can't leave the call to a tail call
+    tries: usize,
+    // where the program came from, for stack traces
+    file: Rc<str>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
@@ -19,22 +67,148 @@ impl Interpreter {
         let mut interpreter = Self {
             specials: HashMap::new(),
             environment: Environment::new(HashMap::new()),
+            next_id: 0,
+            tail_call: None,
+            returned_at: 0,
+            tries: 0,
+            file: Rc::from(stack::DEFAULT_FILE),
         };
         standard_library(&mut interpreter.environment);
 
         interpreter
     }
-    pub fn resolve(&mut self, locals: HashMap<usize, usize>) {
+    pub fn resolve(&mut self, locals: HashMap<usize, Slot>) {
         self.environment.resolve(locals);
     }
     pub fn with_env(env: Environment) -> Self {
         Self {
             specials: HashMap::new(),
             environment: env,
+            next_id: 0,
+            tail_call: None,
+            returned_at: 0,
+            tries: 0,
+            file: stack::current_file(),
+        }
+    }
+    // the file name stack traces give for code run from now on
+    pub fn set_file(&mut self, file: &str) {
+        self.file = Rc::from(file);
+    }
+    // runs a whole program, stopping at its first error
+    pub fn run(&mut self, source: &str) -> Result<(), TronError> {
+        finish(self.in_file(self.file.clone(), |interpreter| {
+            let stmts = interpreter.load(source)?;
+            interpreter.interpret(stmts.iter().collect())
+        }))
+    }
+    // runs `source` and returns the value of its last statement when that is an expression
+    pub fn eval(&mut self, source: &str) -> Result<TronType, TronError> {
+        finish(self.in_file(self.file.clone(), |interpreter| {
+            interpreter.eval_statements(source)
+        }))
+    }
+    // functions remember the file they were defined in, and errors unwind the calls they left
+    fn in_file<T>(
+        &mut self,
+        file: Rc<str>,
+        run: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let depth = stack::depth();
+        let guarded = stack::guard_stack();
+        let previous = stack::set_file(file);
+        let result = run(self);
+        stack::set_file(previous);
+        if guarded {
+            stack::unguard_stack();
+        }
+        if result.is_err() {
+            stack::truncate(depth);
+        }
+        result
+    }
+    fn eval_statements(&mut self, source: &str) -> Result<TronType, String> {
+        let mut stmts = self.load(source)?;
+        match stmts.pop() {
+            Some(Statement::ExpressionStatement { expression, line }) => {
+                self.interpret(stmts.iter().collect())?;
+                count_step(line);
+                expression.evaluate(self.environment.clone())
+            }
+            Some(last) => {
+                stmts.push(last);
+                self.interpret(stmts.iter().collect())?;
+                Ok(TronType::Null)
+            }
+            None => Ok(TronType::Null),
+        }
+    }
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        arity: impl Into<Arity>,
+        function: impl Fn(&Vec<TronType>) -> TronType + 'static,
+    ) {
+        declare_function(name.to_string(), arity, function, &mut self.environment);
+    }
+    // registers a plain Rust fn, converting and checking its arguments from the signature
+    pub fn register_native<Args>(&mut self, name: &str, function: impl IntoNative<Args> + 'static) {
+        declare_native(name, function, &mut self.environment);
+    }
+    // limits and capabilities for everything run on this thread, counted from this call; they are
+    // kept per thread rather than per interpreter, so other interpreters on the thread get them
+    // too. A `max_memory` without `CountingAllocator` installed could never be hit and is refused.
+    pub fn set_sandbox(&mut self, sandbox: Sandbox) -> Result<(), TronError> {
+        if sandbox.max_memory.is_some() && !memory::counting() {
+            return Err(TronError::new("E0015", 0, None, vec![]));
+        }
+        set_options(Options {
+            sandbox,
+            ..options()
+        });
+        sandbox::start();
+        Ok(())
+    }
+    pub fn set_global(&mut self, name: &str, value: TronType) {
+        self.environment.global().define(name.to_string(), value);
+    }
+    pub fn get_global(&self, name: &str) -> Option<TronType> {
+        self.environment.get_global(name)
+    }
+    // values returned by `@args()`
+    pub fn set_script_args(&mut self, script_args: Vec<String>) {
+        declare_script_args(script_args, &mut self.environment);
+    }
+    // scans, parses, resolves and optimizes `source`, numbering its expressions after everything
+    // loaded before
+    fn load(&mut self, source: &str) -> Result<Vec<Statement>, String> {
+        stack::add_source(&stack::current_file(), source);
+        let tokens = Scanner::new(source).scan_tokens()?;
+        let mut parser = Parser::starting_at(tokens, self.next_id);
+        let stmts = parser.parse()?;
+        self.next_id = parser.next_id();
+        let locals = Resolver::new().resolve(&stmts.iter().collect())?;
+        // embedded, code with errors doesn't run
+        if exceptions::aborted() {
+            return Err(exceptions::THROWN.to_string());
         }
+        self.resolve(locals);
+        // the debugger shows the program as it was written
+        if options().optimize && !debugger::attached() {
+            return Ok(optimize(stmts));
+        }
+        Ok(stmts)
     }
     pub fn interpret(&mut self, stmts: Vec<&Statement>) -> Result<(), String> {
         for stmt in stmts {
+            // a `return` further down already ended the function
+            if self.specials.contains_key("return") {
+                break;
+            }
+            count_step(stmt.get_line());
+            if debugger::attached() {
+                debugger::statement(stmt, &self.environment)?;
+            }
             match stmt {
                 Statement::ExpressionStatement {
                     expression,
@@ -43,32 +217,47 @@ impl Interpreter {
                     expression.evaluate(self.environment.clone())?;
                 }
                 Statement::UseStatement { expression, line } => {
+                    if !sandbox::require("fs", "use", *line) {
+                        return Err(exceptions::THROWN.to_string());
+                    }
                     let value = expression.evaluate(self.environment.clone())?;
                     let path = std::env::current_dir().unwrap();
                     let path_buf =
                         path.join(value.to_string().trim_matches('"').trim_start_matches('/'));
-                    match value.to_string().as_str() {
-                        // "\"#math\"" => include_math_natives(&mut self.environment),
-                        _ => {
-                            if std::path::Path::new(&path_buf).exists() {
-                                let lib_contents = std::fs::read_to_string(&path_buf)
-                                    .map_err(|e| e.to_string())?;
-                                self.execute_lib(&lib_contents)?;
-                            } else {
-                                TronError::throw("E4005", *line, vec![value.to_string()]);
-                            }
-                        }
+                    if std::path::Path::new(&path_buf).exists() {
+                        let lib_contents =
+                            std::fs::read_to_string(&path_buf).map_err(|e| e.to_string())?;
+                        let file = value.to_string().trim_matches('"').to_string();
+                        self.execute_lib(&lib_contents, Rc::from(file))?;
+                    } else {
+                        TronError::throw("E4005", *line, vec![value.to_string()]);
                     }
                 }
                 Statement::VariableStatement {
                     name,
-                    value_type: _,
+                    value_type,
                     value,
-                    line: _,
+                    line,
                 } => {
                     let value = value.evaluate(self.environment.clone())?;
-                    let value_clone = value.clone();
-                    self.environment.define(name.lexeme.clone(), value_clone);
+                    if value_type.lexeme == value.to_type()
+                        || value_type.lexeme == value.to_string()
+                    {
+                        self.environment
+                            .set_value_type(name.lexeme.clone(), value_type.lexeme.clone());
+                    } else {
+                        TronError::throw(
+                            "E4003",
+                            *line,
+                            vec![
+                                "variable".to_string(),
+                                name.lexeme.to_string(),
+                                value_type.lexeme.to_string(),
+                                value.to_type().to_string(),
+                            ],
+                        );
+                    }
+                    self.environment.define(name.lexeme.clone(), value);
                 }
                 Statement::BlockStatement {
                     statements,
@@ -138,7 +327,13 @@ impl Interpreter {
                         }
                     }
                     while all_true {
-                        self.interpret(vec![body.as_ref()])?;
+                        match self.interpret(vec![body.as_ref()]) {
+                            Err(e) if e == "break" => break,
+                            result => result?,
+                        }
+                        if self.specials.contains_key("return") {
+                            break;
+                        }
                         all_true = true;
                         for condition in conditions {
                             let truth_value = condition.evaluate(self.environment.clone())?;
@@ -157,20 +352,50 @@ impl Interpreter {
                     line: _,
                 } => {
                     let callable = self.make_function(stmt);
-                    let fun = TronType::Callable(CallableImpl::Function(callable));
+                    let fun = TronType::Callable(CallableImpl::Function(Rc::new(callable)));
                     self.environment.define(name.lexeme.clone(), fun);
                 }
                 Statement::ReturnStatement {
                     keyword: _,
                     value,
-                    line: _,
+                    line,
                 } => {
-                    let eval_val;
-                    if let Some(value) = value {
-                        eval_val = value.evaluate(self.environment.clone())?;
-                    } else {
-                        eval_val = TronType::Null;
-                    }
+                    self.returned_at = *line;
+                    let eval_val = match value {
+                        Some(Expression::Call {
+                            id: _,
+                            callee,
+                            paren,
+                            arguments,
+                            named,
+                        }) if self.tries == 0 => match callee.evaluate(self.environment.clone())? {
+                            TronType::Callable(CallableImpl::Function(function)) => {
+                                let (arguments, named) = evaluate_arguments(
+                                    &function,
+                                    arguments,
+                                    named,
+                                    &self.environment,
+                                    paren.line_number,
+                                )?;
+                                self.tail_call = Some(TailCall {
+                                    function,
+                                    arguments,
+                                    named,
+                                    line: paren.line_number,
+                                });
+                                TronType::Null
+                            }
+                            callable => call(
+                                callable,
+                                arguments,
+                                named,
+                                self.environment.clone(),
+                                paren.line_number,
+                            )?,
+                        },
+                        Some(value) => value.evaluate(self.environment.clone())?,
+                        None => TronType::Null,
+                    };
                     self.specials.insert("return".to_string(), eval_val);
                 }
                 Statement::BreakStatement { .. } => {
@@ -187,19 +412,85 @@ impl Interpreter {
                     for (case_value, case_body) in case_branches {
                         let case_value = case_value.evaluate(self.environment.clone())?;
                         if condition_value == case_value {
-                            self.interpret(case_body.iter().collect())?;
+                            self.interpret_case(case_body)?;
                             executed = true;
                             break;
                         }
                     }
-                    if !executed && default_branch.is_some() {
-                        self.interpret(default_branch.as_ref().unwrap().iter().collect())?;
+                    if !executed {
+                        if let Some(default_branch) = default_branch {
+                            self.interpret_case(default_branch)?;
+                        }
                     }
                 }
+                Statement::TryStatement {
+                    body,
+                    catch,
+                    finally,
+                    line: _,
+                } => {
+                    self.tries += 1;
+                    let result = self.interpret_try(body, catch, finally);
+                    self.tries -= 1;
+                    result?;
+                }
+                Statement::ThrowStatement {
+                    keyword: _,
+                    value,
+                    line,
+                } => {
+                    let value = value.evaluate(self.environment.clone())?;
+                    return exceptions::throw(exceptions::thrown(value, *line)?);
+                }
             };
+            // a runtime error a statement reported inside a `try`
+            if exceptions::raised() {
+                return Err(exceptions::THROWN.to_string());
+            }
         }
         Ok(())
     }
+    // runs the body, then the catch block if an error was raised in it, and the finally block
+    // last however the others ended
+    fn interpret_try(
+        &mut self,
+        body: &Statement,
+        catch: &Option<(Token, Box<Statement>)>,
+        finally: &Option<Box<Statement>>,
+    ) -> Result<(), String> {
+        exceptions::enter();
+        let mut result = self.interpret(vec![body]);
+        exceptions::leave();
+        // nothing in the program runs once an embedded run is aborted, finally blocks included
+        if exceptions::aborted() {
+            return result;
+        }
+        let mut thrown = result.as_ref().err().and_then(|_| exceptions::take());
+        if let (Some(error), Some((name, block))) = (&thrown, catch) {
+            let old_environment = self.environment.clone();
+            self.environment = self.environment.enclose();
+            self.environment
+                .define(name.lexeme.clone(), TronType::Error(error.clone()));
+            result = self.interpret(vec![block.as_ref()]);
+            self.environment = old_environment;
+            thrown = result.as_ref().err().and_then(|_| exceptions::take());
+        }
+        if let Some(finally) = finally {
+            // the finally block runs to its end even after a `return`, which it can replace
+            let returned = self.specials.remove("return");
+            self.interpret(vec![finally.as_ref()])?;
+            if self.specials.contains_key("return") {
+                return Ok(());
+            }
+            if let Some(value) = returned {
+                self.specials.insert("return".to_string(), value);
+            }
+        }
+        match thrown {
+            Some(error) => exceptions::throw(error),
+            None => result,
+        }
+    }
     fn make_function(&self, fn_stmt: &Statement) -> FunctionImpl {
         if let Statement::FunctionStatement {
             name,
@@ -209,50 +500,61 @@ impl Interpreter {
             line: _,
         } = fn_stmt
         {
-            let arity = params.len();
-            let params: Vec<(Token, Token)> = params
-                .iter()
-                .map(|(name, type_token)| (name.clone(), type_token.clone()))
-                .collect();
+            let arity = Arity::of(params);
+            let params: Vec<Parameter> = params.to_vec();
             let body: Vec<Box<Statement>> = body.iter().map(|b| (*b).clone()).collect();
             let name_clone = name.lexeme.clone();
             let output_type_clone = output_type.clone();
             let parent_env = self.environment.clone();
-            let callable_impl = FunctionImpl {
+
+            FunctionImpl {
                 name: name_clone,
                 arity,
                 parent_env,
                 params,
                 body,
                 output_type: output_type_clone,
-            };
-            callable_impl
+                file: stack::current_file(),
+            }
         } else {
             TronError::throw("E4006", 0, vec![]);
             FunctionImpl {
                 name: "err".to_string(),
-                arity: 0,
+                arity: Arity::exact(0),
                 parent_env: self.environment.clone(),
                 params: vec![],
                 body: vec![],
+                file: stack::current_file(),
                 output_type: Token {
                     token_type: TokenType::Null,
                     lexeme: "".to_string(),
                     literal: None,
                     line_number: 0,
+                    span: Span::default(),
                 },
             }
         }
     }
-    fn execute_lib(&mut self, lib_contents: &str) -> Result<(), String> {
-        let scanner = Scanner::new(lib_contents);
-        let tokens = scanner.scan_tokens().map_err(|e| e.to_string())?;
-        let mut parser = Parser::new(tokens);
-        let stmts = parser.parse().map_err(|e| e.to_string())?;
-        let stmts_refs: Vec<&Statement> = stmts.iter().collect();
-        let resolver = Resolver::new();
-        let locals = resolver.resolve(&stmts.iter().collect(), &mut self.environment)?;
-        self.resolve(locals);
-        self.interpret(stmts_refs)
+    // inside a block a case body gets its own scope, as the resolver gave it one
+    fn interpret_case(&mut self, body: &[Statement]) -> Result<(), String> {
+        if self.environment.is_global() {
+            return self.interpret(body.iter().collect());
+        }
+        let old_environment = self.environment.clone();
+        self.environment = self.environment.enclose();
+        let result = self.interpret(body.iter().collect());
+        self.environment = old_environment;
+        result
+    }
+    // libraries always define globals, even when `use` appears inside a block
+    fn execute_lib(&mut self, lib_contents: &str, file: Rc<str>) -> Result<(), String> {
+        let old_environment = self.environment.clone();
+        self.environment = self.environment.global().clone();
+        let result = self.in_file(file, |interpreter| {
+            let stmts = interpreter.load(lib_contents)?;
+            interpreter.interpret(stmts.iter().collect())
+        });
+        self.environment = old_environment;
+        result
     }
 }
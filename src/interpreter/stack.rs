@@ -0,0 +1,208 @@
+// the call stack both engines keep for diagnostics: runtime errors list the calls they happened
+// in, and it is where `--max-depth` is enforced
+use crate::utils::options::options;
+use crate::utils::TronError;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// the file name of code that didn't come from a file
+pub const DEFAULT_FILE: &str = "<script>";
+
+// a function being run: its name, the file its code is in (`None` for natives), and the line
+// and file it was called from
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub name: Rc<str>,
+    pub file: Option<Rc<str>>,
+    pub line: usize,
+    pub caller_file: Rc<str>,
+}
+
+// a line of a stack trace: a function and the line it was at
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceFrame {
+    pub function: Rc<str>,
+    // `None` for natives
+    pub file: Option<Rc<str>>,
+    // 0 when not known
+    pub line: usize,
+    // consecutive identical frames, as in deep recursion, are folded into one
+    pub times: usize,
+}
+
+thread_local! {
+    // outermost first
+    static CALLS: RefCell<Vec<Call>> = const { RefCell::new(vec![]) };
+    // the file the tree-walking interpreter is running code from
+    static FILE: RefCell<Rc<str>> = RefCell::new(Rc::from(DEFAULT_FILE));
+    // the source of each file that was loaded, to say where spans in it are
+    static SOURCES: RefCell<HashMap<Rc<str>, Rc<str>>> = RefCell::new(HashMap::new());
+    // lowest native stack address a call may start at, 0 when the stack size isn't known
+    static STACK_LIMIT: Cell<usize> = const { Cell::new(0) };
+}
+
+pub fn current_file() -> Rc<str> {
+    FILE.with(|file| file.borrow().clone())
+}
+
+// returns the file that was current before
+pub(crate) fn set_file(file: Rc<str>) -> Rc<str> {
+    FILE.with(|current| current.replace(file))
+}
+
+pub fn add_source(file: &str, source: &str) {
+    SOURCES.with(|sources| {
+        sources
+            .borrow_mut()
+            .insert(Rc::from(file), Rc::from(source))
+    });
+}
+
+pub fn source_of(file: &str) -> Option<Rc<str>> {
+    SOURCES.with(|sources| sources.borrow().get(file).cloned())
+}
+
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+// `available` bytes of native stack are left below the caller; calls report a stack overflow
+// rather than use the last eighth of them, since one call can take any amount of native stack.
+// Embedders running tron on a thread bigger than `EMBEDDED_STACK` call it there to use the rest.
+pub fn limit_stack(available: usize) {
+    let limit = stack_address().saturating_sub(available - available / 8);
+    STACK_LIMIT.with(|stack_limit| stack_limit.set(limit));
+}
+
+// native stack a run assumes is left below where it starts when nothing called `limit_stack` on
+// its thread: half of what a `std::thread` gets, as the host may have used some already
+const EMBEDDED_STACK: usize = 1024 * 1024;
+
+// limits the native stack of a run starting here to `EMBEDDED_STACK` when the thread has no limit,
+// and returns whether it did, so the run can `unguard_stack` when it ends
+pub(crate) fn guard_stack() -> bool {
+    if STACK_LIMIT.with(|stack_limit| stack_limit.get()) != 0 {
+        return false;
+    }
+    limit_stack(EMBEDDED_STACK);
+    true
+}
+
+pub(crate) fn unguard_stack() {
+    STACK_LIMIT.with(|stack_limit| stack_limit.set(0));
+}
+
+// both engines stop at `--max-depth` nested calls, or when the native stack runs low
+pub(crate) fn push(call: Call) {
+    let depth = CALLS.with(|calls| {
+        let mut calls = calls.borrow_mut();
+        calls.push(call);
+        calls.len()
+    });
+    let stack_limit = STACK_LIMIT.with(|stack_limit| stack_limit.get());
+    if depth > options().max_depth || stack_address() < stack_limit {
+        TronError::throw("E4028", 0, vec![depth.to_string()]);
+    }
+}
+
+pub(crate) fn pop() {
+    CALLS.with(|calls| calls.borrow_mut().pop());
+}
+
+pub(crate) fn depth() -> usize {
+    CALLS.with(|calls| calls.borrow().len())
+}
+
+// drops the calls an error unwound past
+pub(crate) fn truncate(depth: usize) {
+    CALLS.with(|calls| calls.borrow_mut().truncate(depth));
+}
+
+// a tail call runs in place of the function it returns from, which leaves the caller at the same
+// line, so only the callee changes
+pub(crate) fn replace(name: Rc<str>, file: Option<Rc<str>>) {
+    CALLS.with(|calls| {
+        if let Some(call) = calls.borrow_mut().last_mut() {
+            call.name = name;
+            call.file = file;
+        }
+    });
+}
+
+// a call of the tree-walking interpreter, from entering the callee until it is dropped
+pub(crate) struct CallFrame {
+    caller_file: Rc<str>,
+}
+
+impl CallFrame {
+    pub(crate) fn enter(name: &str, file: Rc<str>, line: usize) -> Self {
+        let caller_file = set_file(file.clone());
+        push(Call {
+            name: Rc::from(name),
+            file: Some(file),
+            line,
+            caller_file: caller_file.clone(),
+        });
+        CallFrame { caller_file }
+    }
+    pub(crate) fn native(name: &str, line: usize) -> Self {
+        let caller_file = current_file();
+        push(Call {
+            name: Rc::from(name),
+            file: None,
+            line,
+            caller_file: caller_file.clone(),
+        });
+        CallFrame { caller_file }
+    }
+    pub(crate) fn replace(&self, name: &str, file: Rc<str>) {
+        set_file(file.clone());
+        replace(Rc::from(name), Some(file));
+    }
+}
+
+impl Drop for CallFrame {
+    fn drop(&mut self) {
+        pop();
+        set_file(self.caller_file.clone());
+    }
+}
+
+// where every call was when an error happened on `line` of the innermost one: innermost first,
+// ending with the top level of the program; empty outside of calls
+pub fn trace(line: usize) -> Vec<TraceFrame> {
+    CALLS.with(|calls| {
+        let calls = calls.borrow();
+        let Some(outermost) = calls.first() else {
+            return vec![];
+        };
+        let mut frames: Vec<TraceFrame> = vec![];
+        let mut add = |function: &Rc<str>, file: &Option<Rc<str>>, line: usize| {
+            if let Some(last) = frames.last_mut() {
+                if last.function == *function && last.file == *file && last.line == line {
+                    last.times += 1;
+                    return;
+                }
+            }
+            frames.push(TraceFrame {
+                function: function.clone(),
+                file: file.clone(),
+                line,
+                times: 1,
+            });
+        };
+        let mut line = line;
+        for call in calls.iter().rev() {
+            add(&call.name, &call.file, line);
+            line = call.line;
+        }
+        add(
+            &Rc::from("<script>"),
+            &Some(outermost.caller_file.clone()),
+            line,
+        );
+        frames
+    })
+}
@@ -0,0 +1,44 @@
+//! Tron as a library: everything the `tron` binary runs on, usable from other Rust programs.
+//!
+//! ```
+//! use tron_lang::{Interpreter, TronType};
+//!
+//! let mut tron = Interpreter::new();
+//! tron.register_function("@double", 1, |args| match args[0].as_number() {
+//!     Some(x) => TronType::Number(x * 2.0),
+//!     None => TronType::Null,
+//! });
+//! tron.set_global("limit", TronType::Number(21.0));
+//! let value = tron.eval("@double(limit);").unwrap();
+//! assert_eq!(value.as_number(), Some(42.0));
+//! ```
+//!
+//! ### Last Updated: (v3.2.0)
+#![allow(
+    clippy::enum_variant_names,
+    clippy::inherent_to_string,
+    clippy::vec_box
+)]
+pub mod analysis;
+pub mod ast;
+pub mod debugger;
+pub mod environment;
+pub mod formatter;
+pub mod interpreter;
+pub mod library;
+pub mod lint;
+pub mod memory;
+pub mod optimizer;
+pub mod parser;
+pub mod resolver;
+pub mod sandbox;
+pub mod scanner;
+pub mod syntax;
+pub mod utils;
+pub mod vm;
+pub use crate::interpreter::expressions::{
+    Arity, CallableImpl, FunctionImpl, NativeFunction, StdFunctionImpl, TronType,
+};
+pub use crate::interpreter::Interpreter;
+pub use crate::sandbox::{Capabilities, Sandbox};
+use crate::{interpreter::*, scanner::*};
@@ -1,587 +1,285 @@
 use crate::environment::*;
 use crate::expressions::*;
-use crate::panic;
+use crate::interpreter::exceptions::{self, ErrorValue};
+use crate::memory;
+use crate::sandbox;
 use crate::utils::TronError;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io as std_io;
-use std::process::exit;
 use std::process::Command;
 use std::rc::Rc;
+use std::time::Duration;
+pub mod native;
+use native::declare_native;
+
+// builtins that return their first argument changed, which `items = @push(items, ...)` lets them
+// change where it is, see `Expression::in_place_call`
+pub const IN_PLACE: &[&str] = &["@push", "@pop", "@shift"];
+
+// where `@print` and `@ask` write instead of stdout, see `redirect_output`
+type Output = Box<dyn FnMut(&str)>;
+
+thread_local! {
+    static OUTPUT: RefCell<Option<Output>> = const { RefCell::new(None) };
+}
+
+// sends what programs on this thread print to `output`, a line at a time
+pub fn redirect_output(output: impl FnMut(&str) + 'static) {
+    OUTPUT.with(|current| *current.borrow_mut() = Some(Box::new(output)));
+}
+
+fn print_line(line: &str) {
+    OUTPUT.with(|output| match &mut *output.borrow_mut() {
+        Some(output) => output(&format!("{}\n", line)),
+        None => println!("{}", line),
+    });
+}
 
 pub fn declare_function(
     name: String,
-    args: usize,
+    arity: impl Into<Arity>,
     fun: impl Fn(&Vec<TronType>) -> TronType + 'static,
     environment: &mut Environment,
 ) {
     environment.define(
         name.clone(),
-        TronType::Callable(CallableImpl::StdFunction(StdFunctionImpl {
+        TronType::Callable(CallableImpl::StdFunction(Rc::new(StdFunctionImpl {
             name: name.clone(),
-            arity: args,
-            function: Rc::new(fun),
-        })),
+            arity: arity.into(),
+            function: Rc::new(move |args: Vec<TronType>| fun(&args)),
+        }))),
     )
 }
 
-pub fn standard_library(environment: &mut Environment) {
+pub fn declare_script_args(script_args: Vec<String>, environment: &mut Environment) {
     declare_function(
-        "@print".to_string(),
-        1,
-        |args: &Vec<TronType>| {
-            if args.len() > 0 {
-                for arg in args {
-                    println!("{:?}", arg);
-                }
-            } else {
-                println!("");
+        "@args".to_string(),
+        0,
+        move |_args: &Vec<TronType>| -> TronType {
+            if !sandbox::require("env", "@args", 0) {
+                return TronType::Null;
             }
-            TronType::Null
+            TronType::array(
+                script_args
+                    .iter()
+                    .map(|arg| TronType::string(arg.as_str()))
+                    .collect(),
+            )
         },
         environment,
     );
+}
+
+pub fn standard_library(environment: &mut Environment) {
+    declare_script_args(vec![], environment);
     declare_function(
-        "@panic".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() > 0 {
+        "@print".to_string(),
+        Arity::at_least(0),
+        |args: &Vec<TronType>| {
+            if !args.is_empty() {
                 for arg in args {
-                    eprintln!("\x1B[31m{:?}\x1B[0m \n", arg);
+                    print_line(&format!("{:?}", arg));
                 }
             } else {
-                exit(1);
+                print_line("");
             }
             TronType::Null
         },
         environment,
     );
     declare_function(
-        "@shift".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                if let TronType::ArrayValue(arr) = &args[0] {
-                    let mut arr = arr.clone();
-                    if arr.is_empty() {
-                        return TronType::ArrayValue(arr);
-                    }
-                    arr.remove(0);
-                    TronType::ArrayValue(arr)
-                } else {
-                    TronError::throw(
-                        "E4021",
-                        0,
-                        vec![
-                            "@shift".to_string(),
-                            "array".to_string(),
-                            "first".to_string(),
-                        ],
-                    );
-                    exit(1)
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@shift".to_string(), 1.to_string()]);
-                exit(1);
-            }
-        },
-        environment,
-    );
-    declare_function(
-        "@pop".to_string(),
-        1,
+        "@panic".to_string(),
+        Arity::at_least(0),
         |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                if let TronType::ArrayValue(arr) = &args[0] {
-                    let mut arr = arr.clone();
-                    if arr.is_empty() {
-                        return TronType::ArrayValue(arr);
-                    }
-                    arr.pop();
-                    TronType::ArrayValue(arr)
-                } else {
-                    TronError::throw(
-                        "E4021",
-                        0,
-                        vec!["@pop".to_string(), "array".to_string(), "first".to_string()],
-                    );
-                    exit(1)
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@pop".to_string(), 1.to_string()]);
-                exit(1);
-            }
+            let message = match args.is_empty() {
+                true => "explicit panic".to_string(),
+                false => args
+                    .iter()
+                    .map(|arg| format!("{:?}", arg))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            TronError::throw("E4035", 0, vec![message]);
+            TronType::Null
         },
         environment,
     );
-    declare_function(
-        "@join".to_string(),
-        2,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 2 {
-                match (&args[0], &args[1]) {
-                    (TronType::ArrayValue(arr), TronType::StringValue(join_str)) => {
-                        let mut strings = Vec::new();
-                        for val in arr.iter() {
-                            match val {
-                                TronType::Number(num) => strings.push(num.to_string()),
-                                TronType::StringValue(s) => strings.push(s.clone()),
-                                _ => panic("\n join() requires an array of strings or numbers"),
-                            }
-                        }
-                        let joined = strings.join(join_str);
-
-                        TronType::StringValue(joined)
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@join".to_string(),
-                                "array".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@join".to_string(), 2.to_string()]);
-                exit(1);
-            }
+    declare_native(
+        "@error",
+        |message: String, code: Option<String>| {
+            let code = code.as_deref().unwrap_or(exceptions::DEFAULT_CODE);
+            TronType::Error(Rc::new(ErrorValue::from_native(code, &message)))
         },
         environment,
     );
-
-    declare_function(
-        "@push".to_string(),
-        2,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 2 {
-                match &args[0] {
-                    TronType::ArrayValue(arr) => {
-                        let mut arr = arr.clone();
-                        arr.push(args[1].clone());
-                        TronType::ArrayValue(arr)
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@push".to_string(),
-                                "array".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@push".to_string(), 2.to_string()]);
-                exit(1);
+    declare_native(
+        "@shift",
+        |mut arr: Rc<Vec<TronType>>| {
+            if !arr.is_empty() {
+                Rc::make_mut(&mut arr).remove(0);
             }
+            arr
         },
         environment,
     );
-    declare_function(
-        "@length".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::StringValue(n) => TronType::Number(n.len() as f32),
-                    TronType::ArrayValue(n) => TronType::Number(n.len() as f32),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@length".to_string(),
-                                "array or string".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@length".to_string(), 1.to_string()]);
-                exit(1);
-            }
+    declare_native(
+        "@pop",
+        |mut arr: Rc<Vec<TronType>>| {
+            Rc::make_mut(&mut arr).pop();
+            arr
         },
         environment,
     );
-    declare_function(
-        "@ask".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::StringValue(n) => {
-                        println!("{}", n.to_string());
-                        let mut input = String::new();
-                        std_io::stdin().read_line(&mut input).unwrap();
-                        TronType::StringValue(format!("{}", input))
-                    }
+    declare_native(
+        "@join",
+        |arr: Rc<Vec<TronType>>, join_str: String| {
+            let mut strings = Vec::new();
+            for val in arr.iter() {
+                match val {
+                    TronType::Number(num) => strings.push(num.to_string()),
+                    TronType::StringValue(s) => strings.push(s.to_string()),
                     _ => {
                         TronError::throw(
                             "E4021",
                             0,
                             vec![
-                                "@ask".to_string(),
-                                "string".to_string(),
+                                "@join".to_string(),
+                                "array of strings or numbers".to_string(),
                                 "first".to_string(),
                             ],
                         );
-                        exit(1)
+                        return String::new();
                     }
                 }
-            } else {
-                TronError::throw("E4018", 0, vec!["@ask".to_string(), 1.to_string()]);
-                exit(1);
-            }
-        },
-        environment,
-    );
-
-    declare_function(
-        "@typeof".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                TronType::StringValue(args[0].to_type().to_string())
-            } else {
-                TronError::throw("E4018", 0, vec!["@typeof".to_string(), 1.to_string()]);
-                exit(1);
             }
+            strings.join(&join_str)
         },
         environment,
     );
-
-    declare_function(
-        "@if".to_string(),
-        3,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 3 {
-                match &args[0] {
-                    TronType::True => args[1].clone(),
-                    TronType::False => args[2].clone(),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@if".to_string(),
-                                "boolean".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@if".to_string(), 3.to_string()]);
-                exit(1);
-            }
+    declare_native(
+        "@push",
+        |mut arr: Rc<Vec<TronType>>, value: TronType| {
+            Rc::make_mut(&mut arr).push(value);
+            arr
         },
         environment,
     );
-
     declare_function(
-        "@sleep".to_string(),
+        "@length".to_string(),
         1,
         |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(time) => {
-                        std::thread::sleep(std::time::Duration::from_millis(*time as u64));
-                        TronType::Number(*time)
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@sleep".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
+            match &args[0] {
+                TronType::StringValue(n) => TronType::Number(n.len() as f32),
+                TronType::ArrayValue(n) => TronType::Number(n.len() as f32),
+                _ => {
+                    TronError::throw(
+                        "E4021",
+                        0,
+                        vec![
+                            "@length".to_string(),
+                            "array or string".to_string(),
+                            "first".to_string(),
+                        ],
+                    );
+                    TronType::Null
                 }
-            } else {
-                TronError::throw("E4018", 0, vec!["@sleep".to_string(), 1.to_string()]);
-                exit(1);
             }
         },
         environment,
     );
-    declare_function(
-        "@cmd".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::StringValue(command) => {
-                        let output = Command::new("sh").arg("-c").arg(command).output();
-                        match output {
-                            Ok(output) => {
-                                if output.status.success() {
-                                    let stdout = String::from_utf8_lossy(&output.stdout);
-                                    TronType::StringValue(stdout.to_string())
-                                } else {
-                                    let stderr = String::from_utf8_lossy(&output.stderr);
-                                    TronError::throw("E4004", 0, vec![stderr.to_string()]);
-                                    exit(1)
-                                }
-                            }
-                            Err(error) => {
-                                TronError::throw("E4004", 0, vec![error.to_string()]);
-                                exit(1)
-                            }
-                        }
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@cmd".to_string(),
-                                "string".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@cmd".to_string(), 1.to_string()]);
-                exit(1);
+    declare_native(
+        "@ask",
+        |question: String| {
+            if !sandbox::require("ask", "@ask", 0) {
+                return String::new();
             }
+            print_line(&question);
+            let mut input = String::new();
+            std_io::stdin().read_line(&mut input).unwrap();
+            input
         },
         environment,
     );
-
-    declare_function(
-        "@sin".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.to_radians().sin()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@sin".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@sin".to_string(), 1.to_string()]);
-                exit(1);
-            }
-        },
+    declare_native(
+        "@typeof",
+        |value: TronType| value.to_type().to_string(),
         environment,
     );
-
-    declare_function(
-        "@cos".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.to_radians().cos()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@cos".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
+    declare_native(
+        "@if",
+        |condition: bool, then: TronType, otherwise: TronType| {
+            if condition {
+                then
             } else {
-                TronError::throw("E4018", 0, vec!["@cos".to_string(), 1.to_string()]);
-                exit(1);
+                otherwise
             }
         },
         environment,
     );
-
-    declare_function(
-        "@tan".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.to_radians().tan()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@tan".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@tan".to_string(), 1.to_string()]);
-                exit(1);
-            }
+    declare_native(
+        "@sleep",
+        |time: f64| {
+            let duration = Duration::from_millis(time as u64);
+            // a sleep past the timeout ends at the timeout
+            std::thread::sleep(match sandbox::remaining() {
+                Some(remaining) => duration.min(remaining),
+                None => duration,
+            });
+            sandbox::check_time(0);
+            time
         },
         environment,
     );
-
-    declare_function(
-        "@round".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.round()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@round".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@round".to_string(), 1.to_string()]);
-                exit(1);
+    declare_native(
+        "@cmd",
+        |command: String| {
+            if !sandbox::require("cmd", "@cmd", 0) {
+                return String::new();
             }
-        },
-        environment,
-    );
-    declare_function(
-        "@floor".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.floor()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@floor".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
+            let output = Command::new("sh").arg("-c").arg(command).output();
+            match output {
+                Ok(output) => {
+                    if output.status.success() {
+                        String::from_utf8_lossy(&output.stdout).to_string()
+                    } else {
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        TronError::throw("E4004", 0, vec![stderr.to_string()]);
+                        String::new()
                     }
                 }
-            } else {
-                TronError::throw("E4018", 0, vec!["@floor".to_string(), 1.to_string()]);
-                exit(1);
-            }
-        },
-        environment,
-    );
-
-    declare_function(
-        "@ceil".to_string(),
-        1,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 1 {
-                match &args[0] {
-                    TronType::Number(angle) => TronType::Number(angle.ceil()),
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@ceil".to_string(),
-                                "number".to_string(),
-                                "first".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
+                Err(error) => {
+                    TronError::throw("E4004", 0, vec![error.to_string()]);
+                    String::new()
                 }
-            } else {
-                TronError::throw("E4018", 0, vec!["@ceil".to_string(), 1.to_string()]);
-                exit(1);
             }
         },
         environment,
     );
-    declare_function(
-        "@pow".to_string(),
-        2,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 2 {
-                match (&args[0], &args[1]) {
-                    (TronType::Number(base), TronType::Number(exp)) => {
-                        TronType::Number(base.powf(*exp))
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec!["@pow".to_string(), "number".to_string(), "both".to_string()],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@pow".to_string(), 2.to_string()]);
-                exit(1);
-            }
+    declare_native(
+        "@gc_stats",
+        || {
+            let stats = memory::stats();
+            HashMap::from([
+                ("collections".to_string(), stats.collections as f64),
+                ("freed".to_string(), stats.freed as f64),
+                ("tracked".to_string(), stats.tracked as f64),
+                ("allocated".to_string(), stats.allocated as f64),
+            ])
         },
         environment,
     );
-
-    declare_function(
-        "@root".to_string(),
-        2,
-        |args: &Vec<TronType>| -> TronType {
-            if args.len() == 2 {
-                match (&args[0], &args[1]) {
-                    (TronType::Number(number), TronType::Number(n)) => {
-                        TronType::Number(number.powf(1.0 / n))
-                    }
-                    _ => {
-                        TronError::throw(
-                            "E4021",
-                            0,
-                            vec![
-                                "@root".to_string(),
-                                "number".to_string(),
-                                "both".to_string(),
-                            ],
-                        );
-                        exit(1)
-                    }
-                }
-            } else {
-                TronError::throw("E4018", 0, vec!["@root".to_string(), 2.to_string()]);
-                exit(1);
-            }
-        },
+    declare_native("@gc", || memory::collect() as f64, environment);
+    declare_native("@sin", |angle: f64| angle.to_radians().sin(), environment);
+    declare_native("@cos", |angle: f64| angle.to_radians().cos(), environment);
+    declare_native("@tan", |angle: f64| angle.to_radians().tan(), environment);
+    declare_native("@round", |x: f64| x.round(), environment);
+    declare_native("@floor", |x: f64| x.floor(), environment);
+    declare_native("@ceil", |x: f64| x.ceil(), environment);
+    declare_native("@pow", |base: f64, exp: f64| base.powf(exp), environment);
+    declare_native(
+        "@root",
+        |number: f64, n: f64| number.powf(1.0 / n),
         environment,
     );
 }
@@ -0,0 +1,250 @@
+// typed conversion between Rust values and `TronType` for native functions
+use crate::environment::Environment;
+use crate::expressions::{Arity, CallableImpl, StdFunctionImpl, TronType};
+use crate::utils::TronError;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub trait FromTron: Sized {
+    // the type name used in E4021 diagnostics
+    const TYPE: &'static str;
+    fn from_tron(value: &TronType) -> Option<Self>;
+    // an argument the native is given to keep, which types holding an `Rc` take without a copy
+    fn from_owned(value: TronType) -> Option<Self> {
+        Self::from_tron(&value)
+    }
+    // arguments that may be left out entirely (`Option<T>`)
+    fn optional() -> bool {
+        false
+    }
+}
+
+pub trait IntoTron {
+    fn into_tron(self) -> TronType;
+}
+
+impl FromTron for f64 {
+    const TYPE: &'static str = "number";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        value.as_number().map(|x| x as f64)
+    }
+}
+// tron numbers are f32, so a result is rounded to the nearest f32, and one beyond its range
+// becomes an infinity
+impl IntoTron for f64 {
+    fn into_tron(self) -> TronType {
+        TronType::Number(self as f32)
+    }
+}
+
+impl FromTron for String {
+    const TYPE: &'static str = "string";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        value.as_str().map(|s| s.to_string())
+    }
+}
+impl IntoTron for String {
+    fn into_tron(self) -> TronType {
+        TronType::string(self)
+    }
+}
+impl IntoTron for &str {
+    fn into_tron(self) -> TronType {
+        TronType::string(self)
+    }
+}
+
+impl FromTron for bool {
+    const TYPE: &'static str = "boolean";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        value.as_bool()
+    }
+}
+impl IntoTron for bool {
+    fn into_tron(self) -> TronType {
+        TronType::from_bool(self)
+    }
+}
+
+impl FromTron for TronType {
+    const TYPE: &'static str = "any";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        Some(value.clone())
+    }
+    fn from_owned(value: TronType) -> Option<Self> {
+        Some(value)
+    }
+}
+impl IntoTron for TronType {
+    fn into_tron(self) -> TronType {
+        self
+    }
+}
+
+impl IntoTron for () {
+    fn into_tron(self) -> TronType {
+        TronType::Null
+    }
+}
+
+impl<T: FromTron> FromTron for Vec<T> {
+    const TYPE: &'static str = "array";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        value.as_array()?.iter().map(T::from_tron).collect()
+    }
+}
+impl<T: IntoTron> IntoTron for Vec<T> {
+    fn into_tron(self) -> TronType {
+        TronType::array(self.into_iter().map(IntoTron::into_tron).collect())
+    }
+}
+
+// the array itself rather than a converted copy, for natives that only rearrange its elements
+impl FromTron for Rc<Vec<TronType>> {
+    const TYPE: &'static str = "array";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        match value {
+            TronType::ArrayValue(elements) => Some(elements.clone()),
+            _ => None,
+        }
+    }
+    fn from_owned(value: TronType) -> Option<Self> {
+        match value {
+            TronType::ArrayValue(elements) => Some(elements),
+            _ => None,
+        }
+    }
+}
+impl IntoTron for Rc<Vec<TronType>> {
+    fn into_tron(self) -> TronType {
+        TronType::ArrayValue(self)
+    }
+}
+
+impl<T: FromTron> FromTron for HashMap<String, T> {
+    const TYPE: &'static str = "object";
+    fn from_tron(value: &TronType) -> Option<Self> {
+        value
+            .as_object()?
+            .iter()
+            .map(|(key, value)| T::from_tron(value).map(|value| (key.clone(), value)))
+            .collect()
+    }
+}
+impl<T: IntoTron> IntoTron for HashMap<String, T> {
+    fn into_tron(self) -> TronType {
+        TronType::object(
+            self.into_iter()
+                .map(|(key, value)| (key, value.into_tron()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromTron> FromTron for Option<T> {
+    const TYPE: &'static str = T::TYPE;
+    fn from_tron(value: &TronType) -> Option<Self> {
+        match value {
+            TronType::Null => Some(None),
+            value => T::from_tron(value).map(Some),
+        }
+    }
+    fn from_owned(value: TronType) -> Option<Self> {
+        match value {
+            TronType::Null => Some(None),
+            value => T::from_owned(value).map(Some),
+        }
+    }
+    fn optional() -> bool {
+        true
+    }
+}
+impl<T: IntoTron> IntoTron for Option<T> {
+    fn into_tron(self) -> TronType {
+        match self {
+            Some(value) => value.into_tron(),
+            None => TronType::Null,
+        }
+    }
+}
+
+pub fn ordinal(index: usize) -> String {
+    match index {
+        0 => "first".to_string(),
+        1 => "second".to_string(),
+        2 => "third".to_string(),
+        3 => "fourth".to_string(),
+        4 => "fifth".to_string(),
+        n => format!("{}th", n + 1),
+    }
+}
+
+// takes argument `index`, stopping the program with E4021 when it has the wrong type; `None`
+// when a `try` catches the error instead
+pub fn argument<T: FromTron>(name: &str, args: &mut [TronType], index: usize) -> Option<T> {
+    let value = args.get_mut(index).map_or(TronType::Null, |value| {
+        std::mem::replace(value, TronType::Null)
+    });
+    let value = T::from_owned(value);
+    if value.is_none() {
+        TronError::throw(
+            "E4021",
+            0,
+            vec![name.to_string(), T::TYPE.to_string(), ordinal(index)],
+        );
+    }
+    value
+}
+
+// a plain Rust fn or closure usable as a native, e.g. `|x: f64, y: f64| x.powf(y)`
+pub trait IntoNative<Args> {
+    fn arity() -> Arity;
+    fn invoke(&self, name: &str, args: Vec<TronType>) -> TronType;
+}
+
+macro_rules! impl_into_native {
+    ($($arg:ident => $index:tt),*) => {
+        impl<F, R, $($arg),*> IntoNative<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R,
+            R: IntoTron,
+            $($arg: FromTron,)*
+        {
+            fn arity() -> Arity {
+                let optional: &[bool] = &[$($arg::optional()),*];
+                let required = optional.iter().rposition(|optional| !optional).map_or(0, |i| i + 1);
+                Arity { min: required, max: Some(optional.len()) }
+            }
+            #[allow(unused_variables, unused_mut, non_snake_case)]
+            fn invoke(&self, name: &str, mut args: Vec<TronType>) -> TronType {
+                $(let Some($arg) = argument::<$arg>(name, &mut args, $index) else {
+                    return TronType::Null;
+                };)*
+                (self)($($arg),*).into_tron()
+            }
+        }
+    };
+}
+
+impl_into_native!();
+impl_into_native!(A => 0);
+impl_into_native!(A => 0, B => 1);
+impl_into_native!(A => 0, B => 1, C => 2);
+impl_into_native!(A => 0, B => 1, C => 2, D => 3);
+impl_into_native!(A => 0, B => 1, C => 2, D => 3, E => 4);
+
+// declares `function` with its arity and argument types taken from its signature
+pub fn declare_native<Args, F>(name: &str, function: F, environment: &mut Environment)
+where
+    F: IntoNative<Args> + 'static,
+{
+    let native_name = name.to_string();
+    environment.define(
+        name.to_string(),
+        TronType::Callable(CallableImpl::StdFunction(Rc::new(StdFunctionImpl {
+            name: name.to_string(),
+            arity: F::arity(),
+            function: Rc::new(move |args: Vec<TronType>| function.invoke(&native_name, args)),
+        }))),
+    );
+}
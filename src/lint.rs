@@ -0,0 +1,585 @@
+// `tron lint`: finds code that runs but is likely a mistake
+//
+// The rules read the file's syntax tree, which knows where every statement and expression is, and
+// its analysis, which knows what each name refers to. A project turns rules off, or makes them
+// fail the lint, in the `[lint]` table of its `tron.toml`; a `// lint: allow(rule)` comment
+// allows rules on its own line, or on the next one when it stands alone.
+use crate::analysis::{Analysis, SymbolKind};
+use crate::scanner::{Span, Token, TokenType};
+use crate::syntax::{SyntaxKind, SyntaxNode, SyntaxTree};
+use crate::utils::{collect_errors, TronError};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    // fails `tron lint`
+    Error,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Allow => "allow",
+            Level::Warn => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+pub struct Rule {
+    pub name: &'static str,
+    pub about: &'static str,
+    // the level a project gets unless its `tron.toml` sets another
+    pub level: Level,
+}
+
+pub const RULES: [Rule; 8] = [
+    Rule {
+        name: "unused_variable",
+        about: "a local variable is never used",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "unused_parameter",
+        about: "a parameter is never used in its function",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "shadowing",
+        about: "a declaration hides another one with the same name",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "unreachable_code",
+        about: "statements follow a `return` or `break` in the same block",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "constant_condition",
+        about: "an `if` or `while` condition is made only of literals",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "self_assignment",
+        about: "a variable is assigned to itself",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "duplicate_case",
+        about: "a `case` value repeats an earlier one, so its branch never runs",
+        level: Level::Warn,
+    },
+    Rule {
+        name: "wrong_arity",
+        about: "a known function is called with the wrong number of arguments",
+        level: Level::Error,
+    },
+];
+
+// the rule levels of a project
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    levels: HashMap<String, Level>,
+}
+
+impl Config {
+    // reads the `[lint]` table of a `tron.toml`, where each line sets a rule to "allow", "warn"
+    // or "error"; other tables are left for other tools
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut in_lint = false;
+        for (index, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_lint = line == "[lint]";
+                continue;
+            }
+            if !in_lint {
+                continue;
+            }
+            let fail = |message: String| Err(format!("line {}: {}", index + 1, message));
+            let Some((name, value)) = line.split_once('=') else {
+                return fail(format!("expected `rule = \"level\"`, found `{}`", line));
+            };
+            let name = name.trim();
+            if !RULES.iter().any(|rule| rule.name == name) {
+                return fail(format!("unknown lint rule `{}`", name));
+            }
+            let level = match value.trim() {
+                "\"allow\"" => Level::Allow,
+                "\"warn\"" => Level::Warn,
+                "\"error\"" => Level::Error,
+                value => {
+                    return fail(format!(
+                        "expected \"allow\", \"warn\" or \"error\" for `{}`, found {}",
+                        name, value
+                    ))
+                }
+            };
+            config.levels.insert(name.to_string(), level);
+        }
+        Ok(config)
+    }
+
+    pub fn level(&self, rule: &str) -> Level {
+        self.levels.get(rule).copied().unwrap_or_else(|| {
+            RULES
+                .iter()
+                .find(|known| known.name == rule)
+                .map_or(Level::Warn, |known| known.level)
+        })
+    }
+}
+
+// `line` without a `#` comment, which can't be inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+// replacing `span` of the source with `text`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fix {
+    pub span: Span,
+    pub text: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub level: Level,
+    pub message: String,
+    pub line: usize,
+    pub span: Span,
+    // offered when it can't change what the program does
+    pub fix: Option<Fix>,
+}
+
+// what the rules `config` doesn't allow find in `source`, in source order, or the errors scanning
+// and parsing it reported; `load` finds used files as for `Analysis::new`
+pub fn lint(
+    source: &str,
+    config: &Config,
+    load: impl FnMut(&str) -> Option<(String, String)>,
+) -> Result<Vec<Finding>, Vec<TronError>> {
+    let tree = SyntaxTree::parse(source);
+    if !tree.errors.is_empty() {
+        return Err(tree.errors);
+    }
+    let analysis = Analysis::new(source, load);
+    let mut linter = Linter {
+        source,
+        root: &tree.root,
+        analysis: &analysis,
+        findings: vec![],
+    };
+    linter.symbols();
+    linter.node(&tree.root, None);
+    let allowed = allowed(&tree.root);
+    let mut findings = linter.findings;
+    for finding in &mut findings {
+        finding.level = config.level(finding.rule);
+    }
+    findings.retain(|finding| {
+        finding.level != Level::Allow
+            && !allowed
+                .get(&finding.line)
+                .is_some_and(|rules| rules.iter().any(|rule| rule == finding.rule))
+    });
+    findings.sort_by_key(|finding| finding.span.start);
+    Ok(findings)
+}
+
+// `source` with the fixes of `findings` applied, leaving out any that overlap one before it
+pub fn apply_fixes(source: &str, findings: &[Finding]) -> String {
+    let mut fixes: Vec<&Fix> = findings
+        .iter()
+        .filter_map(|finding| finding.fix.as_ref())
+        .collect();
+    fixes.sort_by_key(|fix| fix.span.start);
+    let mut out = String::new();
+    let mut last = 0;
+    for fix in fixes {
+        if fix.span.start < last {
+            continue;
+        }
+        out.push_str(&source[last..fix.span.start]);
+        out.push_str(&fix.text);
+        last = fix.span.end;
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+// the rules `// lint: allow(...)` comments allow, by line
+fn allowed(root: &SyntaxNode) -> HashMap<usize, Vec<String>> {
+    let tokens = root.tokens();
+    let mut allowed: HashMap<usize, Vec<String>> = HashMap::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if token.token_type != TokenType::Comment {
+            continue;
+        }
+        let text = token.lexeme.trim_start_matches('/').trim();
+        let Some(rules) = text
+            .strip_prefix("lint: allow(")
+            .and_then(|rest| rest.split_once(')'))
+            .map(|(rules, _)| rules)
+        else {
+            continue;
+        };
+        let alone = tokens[..index]
+            .iter()
+            .rev()
+            .find(|token| token.token_type != TokenType::Whitespace)
+            .is_none_or(|token| token.token_type == TokenType::Newline);
+        let line = match alone {
+            true => tokens[index..]
+                .iter()
+                .find(|token| !token.token_type.is_trivia())
+                .map_or(token.line_number, |token| token.line_number),
+            false => token.line_number,
+        };
+        allowed
+            .entry(line)
+            .or_default()
+            .extend(rules.split(',').map(|rule| rule.trim().to_string()));
+    }
+    allowed
+}
+
+struct Linter<'a> {
+    source: &'a str,
+    root: &'a SyntaxNode,
+    analysis: &'a Analysis,
+    findings: Vec<Finding>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: &'static str, span: Span, message: String, fix: Option<Fix>) {
+        self.findings.push(Finding {
+            rule,
+            level: Level::Warn,
+            message,
+            line: self.source[..span.start].matches('\n').count() + 1,
+            span,
+            fix,
+        });
+    }
+
+    // the rules about declarations: unused variables and parameters, and shadowing
+    fn symbols(&mut self) {
+        let symbols = &self.analysis.symbols;
+        for (index, symbol) in symbols.iter().enumerate() {
+            if symbol.library.is_some() || symbol.name.starts_with('_') {
+                continue;
+            }
+            let used = self.analysis.references_to(index).next().is_some();
+            match symbol.kind {
+                // globals can be used by the files that use this one
+                SymbolKind::Variable if symbol.scope.is_some() && !used => self.report(
+                    "unused_variable",
+                    symbol.span,
+                    format!("variable `{}` is never used", symbol.name),
+                    None,
+                ),
+                SymbolKind::Parameter if !used => self.report(
+                    "unused_parameter",
+                    symbol.span,
+                    format!("parameter `{}` is never used", symbol.name),
+                    None,
+                ),
+                _ => {}
+            }
+            if symbol.scope.is_none() {
+                continue;
+            }
+            let at = symbol.span.start;
+            let shadowed = symbols.iter().find(|outer| {
+                outer.name == symbol.name
+                    && outer.library.is_none()
+                    && outer.kind != SymbolKind::Builtin
+                    && outer.span.start < at
+                    && self
+                        .scope(outer.scope, outer.span.start)
+                        .is_none_or(|scope| scope.start <= at && at <= scope.end)
+            });
+            if let Some(outer) = shadowed {
+                let kind = match outer.kind {
+                    SymbolKind::Parameter => "parameter",
+                    SymbolKind::Function => "function",
+                    _ => "variable",
+                };
+                let line = self.source[..outer.span.start].matches('\n').count() + 1;
+                self.report(
+                    "shadowing",
+                    symbol.span,
+                    format!(
+                        "`{}` shadows the {} declared on line {}",
+                        symbol.name, kind, line
+                    ),
+                    None,
+                );
+            }
+        }
+    }
+
+    // where a symbol declared at `at` can be used: the analysis reaches to the end of the
+    // enclosing braces, but a `for` loop's variable ends with the loop
+    fn scope(&self, scope: Option<Span>, at: usize) -> Option<Span> {
+        let mut node = self.root;
+        loop {
+            let Some(child) = node
+                .nodes()
+                .find(|child| child.span.start <= at && at < child.span.end)
+            else {
+                return scope;
+            };
+            if child.kind == SyntaxKind::ForStatement
+                && child
+                    .nodes()
+                    .next()
+                    .is_some_and(|init| init.span.start <= at && at < init.span.end)
+            {
+                return Some(child.span);
+            }
+            node = child;
+        }
+    }
+
+    fn node(&mut self, node: &SyntaxNode, parent: Option<&SyntaxNode>) {
+        match node.kind {
+            SyntaxKind::File
+            | SyntaxKind::BlockStatement
+            | SyntaxKind::CaseBranch
+            | SyntaxKind::DefaultBranch => self.unreachable(node),
+            SyntaxKind::IfStatement | SyntaxKind::WhileStatement => self.constant_condition(node),
+            SyntaxKind::Assign => self.self_assignment(node, parent),
+            SyntaxKind::SwitchStatement => self.duplicate_cases(node),
+            SyntaxKind::Call => self.arity(node),
+            _ => {}
+        }
+        for child in node.nodes() {
+            self.node(child, Some(node));
+        }
+    }
+
+    fn unreachable(&mut self, block: &SyntaxNode) {
+        let statements: Vec<&SyntaxNode> = block
+            .nodes()
+            .filter(|node| node.kind.is_statement())
+            .collect();
+        let Some(exit) = statements.iter().position(|statement| {
+            matches!(
+                statement.kind,
+                SyntaxKind::ReturnStatement
+                    | SyntaxKind::BreakStatement
+                    | SyntaxKind::ThrowStatement
+            )
+        }) else {
+            return;
+        };
+        let (Some(first), Some(last)) = (statements.get(exit + 1), statements.last()) else {
+            return;
+        };
+        let keyword =
+            first_token(statements[exit]).map_or(String::new(), |token| token.lexeme.clone());
+        let span = Span {
+            start: first.span.start,
+            end: last.span.end,
+        };
+        let fix = self.removal(span);
+        self.report(
+            "unreachable_code",
+            span,
+            format!("unreachable code after `{}`", keyword),
+            fix,
+        );
+    }
+
+    fn constant_condition(&mut self, statement: &SyntaxNode) {
+        let conditions: Vec<&SyntaxNode> = statement
+            .nodes()
+            .take_while(|node| !node.kind.is_statement())
+            .collect();
+        // `while true` is how a loop that ends with `break` is written
+        if statement.kind == SyntaxKind::WhileStatement
+            && conditions.len() == 1
+            && first_token(conditions[0]).is_some_and(|token| token.token_type == TokenType::True)
+            && conditions[0].kind == SyntaxKind::Literal
+        {
+            return;
+        }
+        for condition in conditions {
+            if constant(condition) {
+                self.report(
+                    "constant_condition",
+                    condition.span,
+                    format!("condition `{}` is constant", condition.text()),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn self_assignment(&mut self, assign: &SyntaxNode, parent: Option<&SyntaxNode>) {
+        let nodes: Vec<&SyntaxNode> = assign.nodes().collect();
+        let [target, value] = nodes[..] else {
+            return;
+        };
+        if target.kind != SyntaxKind::Variable
+            || value.kind != SyntaxKind::Variable
+            || target.text() != value.text()
+        {
+            return;
+        }
+        // the statement does nothing else, so it can go
+        let fix = parent
+            .filter(|parent| {
+                parent.kind == SyntaxKind::ExpressionStatement && parent.nodes().count() == 1
+            })
+            .and_then(|parent| self.removal(parent.span));
+        self.report(
+            "self_assignment",
+            assign.span,
+            format!("`{}` is assigned to itself", target.text()),
+            fix,
+        );
+    }
+
+    fn duplicate_cases(&mut self, switch: &SyntaxNode) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for branch in switch
+            .nodes()
+            .filter(|node| node.kind == SyntaxKind::CaseBranch)
+        {
+            let Some(value) = branch.nodes().next() else {
+                continue;
+            };
+            let Some(token) = first_token(value).filter(|_| value.kind == SyntaxKind::Literal)
+            else {
+                continue;
+            };
+            let key = match token.token_type {
+                TokenType::Number => match token.lexeme.parse::<f64>() {
+                    Ok(number) => number.to_string(),
+                    Err(_) => token.lexeme.clone(),
+                },
+                _ => token.lexeme.clone(),
+            };
+            let line = token.line_number;
+            match seen.get(&key) {
+                Some(first) => {
+                    let message = format!(
+                        "`case {}` repeats the one on line {}, so its branch never runs",
+                        token.lexeme, first
+                    );
+                    self.report("duplicate_case", value.span, message, None);
+                }
+                None => {
+                    seen.insert(key, line);
+                }
+            }
+        }
+    }
+
+    fn arity(&mut self, call: &SyntaxNode) {
+        let mut nodes = call.nodes();
+        let (Some(callee), Some(arguments)) = (nodes.next(), nodes.next()) else {
+            return;
+        };
+        if callee.kind != SyntaxKind::Variable || arguments.kind != SyntaxKind::ArgumentList {
+            return;
+        }
+        let Some(name) = first_token(callee) else {
+            return;
+        };
+        let Some(arity) = self
+            .analysis
+            .references
+            .iter()
+            .find(|(span, _)| *span == name.span)
+            .and_then(|(_, symbol)| self.analysis.symbols[*symbol].arity)
+        else {
+            return;
+        };
+        let count = arguments.nodes().count();
+        let (_, errors) = collect_errors(|| arity.check(&name.lexeme, count, name.line_number));
+        if let Some(error) = errors.into_iter().next() {
+            self.report("wrong_arity", call.span, error.message, None);
+        }
+    }
+
+    // deleting `span`: whole lines when nothing else is on them, or the span and the spaces
+    // before it; `None` when that would delete a comment
+    fn removal(&self, span: Span) -> Option<Fix> {
+        let source = self.source;
+        let line_start = source[..span.start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_end = source[span.end..]
+            .find('\n')
+            .map_or(source.len(), |index| span.end + index + 1);
+        let alone = source[line_start..span.start].trim().is_empty()
+            && source[span.end..line_end].trim().is_empty();
+        let span = match alone {
+            true => Span {
+                start: line_start,
+                end: line_end,
+            },
+            false if source[line_start..span.start].trim().is_empty() => Span {
+                start: span.start,
+                end: span.end + spaces(&source[span.end..]),
+            },
+            false => Span {
+                start: span.start - spaces_before(&source[..span.start]),
+                end: span.end,
+            },
+        };
+        let comment = self.root.tokens().iter().any(|token| {
+            token.token_type == TokenType::Comment
+                && span.start <= token.span.start
+                && token.span.end <= span.end
+        });
+        (!comment).then(|| Fix {
+            span,
+            text: String::new(),
+        })
+    }
+}
+
+fn spaces(text: &str) -> usize {
+    text.len() - text.trim_start_matches([' ', '\t']).len()
+}
+
+fn spaces_before(text: &str) -> usize {
+    text.len() - text.trim_end_matches([' ', '\t']).len()
+}
+
+fn first_token(node: &SyntaxNode) -> Option<&Token> {
+    node.tokens()
+        .into_iter()
+        .find(|token| !token.token_type.is_trivia())
+}
+
+// whether `node` is an expression of literals only
+fn constant(node: &SyntaxNode) -> bool {
+    match node.kind {
+        SyntaxKind::Literal => true,
+        SyntaxKind::Grouping | SyntaxKind::Unary | SyntaxKind::Binary | SyntaxKind::Logical => {
+            node.nodes().all(constant)
+        }
+        _ => false,
+    }
+}
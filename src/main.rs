@@ -1,61 +1,150 @@
 mod commands;
-mod environment;
-mod interpreter;
-mod library;
-mod parser;
-mod resolver;
-mod scanner;
-mod utils;
+use crate::commands::args::{find_command, parse_args};
+use crate::commands::bench::cli_bench;
+use crate::commands::dap::cli_dap;
+use crate::commands::debug::cli_debug;
+use crate::commands::explain::cli_explain;
+use crate::commands::fmt::cli_fmt;
 use crate::commands::help::cli_help;
-use crate::commands::run::cli_run;
+use crate::commands::inspect::{cli_ast, cli_resolve, cli_tokens};
+use crate::commands::lint::cli_lint;
+use crate::commands::lsp::cli_lsp;
+use crate::commands::run::{cli_eval, cli_run};
+use crate::commands::uninstall::cli_uninstall;
 use crate::commands::update::cli_update;
 use crate::commands::version::cli_version;
-use crate::{interpreter::*, parser::*, resolver::*, scanner::*};
-use std::{env, fs, process::exit};
-use utils::TronError;
+use std::env;
+use std::io::IsTerminal;
+use tron_lang::memory::{parse_size, CountingAllocator};
+use tron_lang::sandbox::{parse_duration, Capabilities};
+use tron_lang::utils::options::{set_options, Engine, ErrorFormat, Options};
+use tron_lang::utils::{exit, TronError};
 
-pub fn panic(message: &str) {
-    eprintln!("\x1B[31m{}\x1B[0m \n", message);
-    exit(1);
+// counts heap bytes for `--max-memory` and `@gc_stats()`
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn usage_error(message: String) -> ! {
+    TronError::throw("E0008", 0, vec![message]);
+    exit(64);
 }
 
-pub fn run_file(path: &str) -> Result<(), String> {
-    let current_dir = std::env::current_dir().unwrap();
-    match fs::read_to_string(&current_dir.join(path).to_str().unwrap().to_string()) {
-        Err(_msg) => {
-            TronError::throw("E0001", 0, vec![]);
-            Ok(())
-        }
-        Ok(contents) => run(&contents),
-    }
+fn main() {
+    cli();
+    exit(0);
 }
 
-fn run(contents: &str) -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
-    let scanner = Scanner::new(contents);
-    let tokens = scanner.scan_tokens()?;
-    let mut parser = Parser::new(tokens);
-    let stmts = parser.parse()?;
-    let resolver = Resolver::new();
-    let locals = resolver.resolve(&stmts.iter().collect(), &mut interpreter.environment)?;
-    interpreter.resolve(locals);
-    interpreter.interpret(stmts.iter().collect())?;
-    Ok(())
+// colors are for people reading a terminal, and not for those who set `NO_COLOR`
+fn use_color(no_color: bool) -> bool {
+    !no_color
+        && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stderr().is_terminal()
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn cli() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let path = std::env::current_dir().unwrap();
-    if args.len() == 1 {
-        TronError::throw("E0002", 0, vec![]);
-        exit(64);
+    // for errors in the arguments themselves
+    set_options(Options {
+        color: use_color(args.iter().any(|arg| arg == "--no-color")),
+        report_errors: true,
+        ..Options::default()
+    });
+    let matches = parse_args(&args).unwrap_or_else(|message| usage_error(message));
+
+    let mut options = Options {
+        color: use_color(matches.flag("no-color")),
+        quiet: matches.flag("quiet"),
+        optimize: !matches.flag("no-optimize"),
+        report_errors: true,
+        ..Options::default()
+    };
+    if let Some(format) = matches.value("error-format") {
+        options.error_format = ErrorFormat::parse(format)
+            .unwrap_or_else(|| usage_error(format!("unknown error format {}", format)));
+    }
+    if let Some(steps) = matches.value("max-steps") {
+        options.sandbox.max_steps = Some(steps.parse().unwrap_or_else(|_| {
+            usage_error(format!("--max-steps expects a number, got {}", steps))
+        }));
+    }
+    if let Some(depth) = matches.value("max-depth") {
+        options.max_depth = depth.parse().unwrap_or_else(|_| {
+            usage_error(format!("--max-depth expects a number, got {}", depth))
+        });
+    }
+    if let Some(size) = matches.value("max-memory") {
+        options.sandbox.max_memory = Some(parse_size(size).unwrap_or_else(|| {
+            usage_error(format!(
+                "--max-memory expects a size like 64m, got {}",
+                size
+            ))
+        }));
+    }
+    if let Some(timeout) = matches.value("timeout") {
+        options.sandbox.timeout = Some(parse_duration(timeout).unwrap_or_else(|| {
+            usage_error(format!(
+                "--timeout expects a duration like 2s, got {}",
+                timeout
+            ))
+        }));
+    }
+    if matches.flag("sandbox") {
+        options.sandbox.capabilities = Capabilities::NONE;
+    }
+    if let Some(allowed) = matches.value("allow") {
+        let allowed = Capabilities::parse(allowed).unwrap_or_else(|message| usage_error(message));
+        let capabilities = &mut options.sandbox.capabilities;
+        capabilities.cmd |= allowed.cmd;
+        capabilities.fs |= allowed.fs;
+        capabilities.env |= allowed.env;
+        capabilities.ask |= allowed.ask;
+    }
+    if let Some(engine) = matches.value("engine") {
+        options.engine = Engine::parse(engine)
+            .unwrap_or_else(|| usage_error(format!("unknown engine {}", engine)));
+    }
+    set_options(options);
+
+    if let Some(code) = matches.value("eval").map(|code| code.to_string()) {
+        return cli_eval(&code, "<eval>", matches.script_args);
+    }
+    let command = match matches.command {
+        Some(command) => command,
+        None if matches.flag("help") => return cli_help(None),
+        None => {
+            TronError::throw("E0002", 0, vec![]);
+            exit(64);
+        }
+    };
+    if matches.flag("help") {
+        return cli_help(Some(command));
     }
-    let command = args[1].as_str();
-    match command {
+    match command.name {
         "version" => cli_version(),
-        "update" => cli_update(),
-        "help" => cli_help(),
-        "run" => cli_run(args[2].as_str(), path),
+        "update" => cli_update(matches.value("from"), matches.flag("rollback")),
+        "uninstall" => cli_uninstall(),
+        "help" => match matches.positionals.first() {
+            Some(name) => match find_command(name) {
+                Some(command) => cli_help(Some(command)),
+                None => usage_error(format!("unknown command {}", name)),
+            },
+            None => cli_help(None),
+        },
+        "run" => cli_run(&matches.positionals[0], path, matches.script_args),
+        "debug" => cli_debug(&matches.positionals[0], path, matches.script_args),
+        "dap" => cli_dap(),
+        "lsp" => cli_lsp(),
+        "fmt" => cli_fmt(&matches.positionals, matches.flag("check")),
+        "lint" => cli_lint(&matches.positionals, matches.flag("fix")),
+        "explain" => cli_explain(&matches.positionals[0]),
+        "tokens" => cli_tokens(&matches.positionals[0]),
+        "ast" => cli_ast(&matches.positionals[0], matches.flag("json")),
+        "resolve" => cli_resolve(&matches.positionals[0]),
+        "bench" => cli_bench(
+            matches.positionals.first().map(|target| target.as_str()),
+            matches.value("runs"),
+        ),
         _ => TronError::throw("E0002", 0, vec![]),
     }
 }
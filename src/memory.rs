@@ -0,0 +1,381 @@
+// cycle collection for the mutable cells values live in, and the heap limit
+//
+// Values are reference counted, which frees everything except cycles. Every cycle goes through a
+// mutable cell: the slots of a scope or the globals (a function holds the environment it was
+// defined in), or a closed vm upvalue (a closure that captured itself). Those cells are tracked
+// here, and a collection finds the ones nothing outside the heap can reach:
+//
+// 1. walk everything reachable from the tracked cells, counting for each node how many of its
+//    references come from other nodes
+// 2. a node with more references than that is held from outside: by a running scope, the vm
+//    stack, the embedder. It and everything it reaches is alive
+// 3. the cells left over are only reachable from each other, so emptying them breaks the cycles
+//    and reference counting frees the rest
+use crate::environment::{Environment, Globals, Slots};
+use crate::expressions::{CallableImpl, FunctionImpl, TronType};
+use crate::utils::options::options;
+use crate::utils::TronError;
+use crate::vm::{Closure, Upvalue};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// tracked cells created before a collection runs at the next statement
+const MIN_THRESHOLD: usize = 10_000;
+
+// a cell whose contents can form a cycle
+pub(crate) trait Traced {
+    // calls `visit` with every value held directly; false when the cell is being written to
+    fn trace(&self, visit: &mut dyn FnMut(&TronType)) -> bool;
+    // drops the held values
+    fn clear(&self);
+}
+
+impl Traced for RefCell<Slots> {
+    fn trace(&self, visit: &mut dyn FnMut(&TronType)) -> bool {
+        match self.try_borrow() {
+            Ok(slots) => {
+                slots.values.iter().for_each(visit);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+    fn clear(&self) {
+        // dropped once the borrow has ended
+        let _values = self
+            .try_borrow_mut()
+            .map(|mut slots| std::mem::take(&mut slots.values));
+    }
+}
+
+impl Traced for RefCell<Globals> {
+    fn trace(&self, visit: &mut dyn FnMut(&TronType)) -> bool {
+        match self.try_borrow() {
+            Ok(globals) => {
+                globals.iter().for_each(|(_, value)| visit(value));
+                true
+            }
+            Err(_) => false,
+        }
+    }
+    fn clear(&self) {
+        let _globals = self
+            .try_borrow_mut()
+            .map(|mut globals| std::mem::take(&mut *globals));
+    }
+}
+
+impl Traced for RefCell<Upvalue> {
+    fn trace(&self, visit: &mut dyn FnMut(&TronType)) -> bool {
+        match self.try_borrow() {
+            Ok(upvalue) => {
+                if let Upvalue::Closed(value) = &*upvalue {
+                    visit(value);
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+    fn clear(&self) {
+        let _value = self.try_borrow_mut().map(|mut upvalue| {
+            std::mem::replace(&mut *upvalue, Upvalue::Closed(Box::new(TronType::Null)))
+        });
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub collections: usize,
+    // cells emptied because only a cycle held them
+    pub freed: usize,
+    // cells alive after the last collection
+    pub tracked: usize,
+    // bytes on the heap, when `CountingAllocator` is the global allocator
+    pub allocated: usize,
+}
+
+struct Heap {
+    cells: Vec<Weak<dyn Traced>>,
+    created: usize,
+    threshold: usize,
+    stats: Stats,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            cells: vec![],
+            created: 0,
+            threshold: MIN_THRESHOLD,
+            stats: Stats {
+                collections: 0,
+                freed: 0,
+                tracked: 0,
+                allocated: 0,
+            },
+        })
+    };
+}
+
+pub(crate) fn track<T: Traced + 'static>(cell: &Rc<T>) {
+    let weak: Weak<dyn Traced> = Rc::downgrade(cell) as Weak<dyn Traced>;
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.cells.push(weak);
+        heap.created += 1;
+    });
+}
+
+pub fn stats() -> Stats {
+    let mut stats = HEAP.with(|heap| heap.borrow().stats);
+    stats.allocated = allocated();
+    stats
+}
+
+// called between statements, where no environment is borrowed: collects once enough cells were
+// created since the last collection, and enforces `--max-memory`
+pub(crate) fn safepoint(line: usize) {
+    let due = HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.created >= heap.threshold
+    });
+    if due {
+        collect();
+    }
+    if let Some(max_memory) = options().sandbox.max_memory {
+        if allocated() > max_memory {
+            collect();
+            if allocated() > max_memory {
+                TronError::throw("E4027", line, vec![format_size(max_memory)]);
+            }
+        }
+    }
+}
+
+// frees the cycles nothing outside the heap refers to, returning how many cells were emptied
+pub fn collect() -> usize {
+    let cells = HEAP.with(|heap| std::mem::take(&mut heap.borrow_mut().cells));
+    let mut graph = Graph::default();
+    for cell in &cells {
+        if let Some(cell) = cell.upgrade() {
+            // less the reference `upgrade` just made
+            let count = Rc::strong_count(&cell) - 1;
+            graph.insert(address(&cell), count, Node::Cell(cell));
+        }
+    }
+    let garbage = graph.garbage();
+    let freed = garbage.len();
+    drop(graph);
+    for cell in &garbage {
+        cell.clear();
+    }
+    drop(garbage);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        // keep anything tracked while the garbage was dropped
+        let created = std::mem::take(&mut heap.cells);
+        heap.cells = cells;
+        heap.cells.extend(created);
+        heap.cells.retain(|cell| cell.strong_count() > 0);
+        heap.created = 0;
+        heap.threshold = MIN_THRESHOLD.max(heap.cells.len());
+        heap.stats.collections += 1;
+        heap.stats.freed += freed;
+        heap.stats.tracked = heap.cells.len();
+    });
+    freed
+}
+
+#[derive(Clone)]
+enum Node {
+    Cell(Rc<dyn Traced>),
+    Array(Rc<Vec<TronType>>),
+    Object(Rc<HashMap<String, TronType>>),
+    Scope(Rc<Environment>),
+    Function(Rc<FunctionImpl>),
+    Closure(Rc<Closure>),
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    // references to each node when it was found, and how many of them come from other nodes
+    counts: Vec<usize>,
+    internal: Vec<usize>,
+    // cells that were being written to, so their references are unknown
+    busy: Vec<bool>,
+    children: Vec<Vec<usize>>,
+    indexes: HashMap<usize, usize>,
+}
+
+impl Graph {
+    fn insert(&mut self, address: usize, count: usize, node: Node) -> usize {
+        if let Some(&index) = self.indexes.get(&address) {
+            return index;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        self.counts.push(count);
+        self.internal.push(0);
+        self.busy.push(false);
+        self.children.push(vec![]);
+        self.indexes.insert(address, index);
+        index
+    }
+    // the count is read before the node takes its own reference
+    fn link<T: ?Sized>(&mut self, from: usize, rc: &Rc<T>, node: impl FnOnce() -> Node) {
+        let to = match self.indexes.get(&address(rc)) {
+            Some(&to) => to,
+            None => self.insert(address(rc), Rc::strong_count(rc), node()),
+        };
+        self.internal[to] += 1;
+        self.children[from].push(to);
+    }
+    fn value(&mut self, from: usize, value: &TronType) {
+        match value {
+            TronType::ArrayValue(items) => self.link(from, items, || Node::Array(items.clone())),
+            TronType::Object(fields) => self.link(from, fields, || Node::Object(fields.clone())),
+            TronType::Callable(CallableImpl::Function(function)) => {
+                self.link(from, function, || Node::Function(function.clone()))
+            }
+            TronType::Callable(CallableImpl::Closure(closure)) => {
+                self.link(from, closure, || Node::Closure(closure.clone()))
+            }
+            _ => {}
+        }
+    }
+    fn environment(&mut self, from: usize, environment: &Environment) {
+        let values = &environment.values;
+        self.link(from, values, || Node::Cell(values.clone()));
+        let slots = &environment.slots;
+        self.link(from, slots, || Node::Cell(slots.clone()));
+        if let Some(enclosing) = &environment.enclosing {
+            self.link(from, enclosing, || Node::Scope(enclosing.clone()));
+        }
+    }
+    fn expand(&mut self, index: usize) {
+        match self.nodes[index].clone() {
+            Node::Cell(cell) => {
+                if !cell.trace(&mut |value| self.value(index, value)) {
+                    self.busy[index] = true;
+                }
+            }
+            Node::Array(items) => items.iter().for_each(|item| self.value(index, item)),
+            Node::Object(fields) => fields.values().for_each(|field| self.value(index, field)),
+            Node::Scope(environment) => self.environment(index, &environment),
+            Node::Function(function) => self.environment(index, &function.parent_env),
+            Node::Closure(closure) => {
+                for upvalue in &closure.upvalues {
+                    self.link(index, upvalue, || Node::Cell(upvalue.clone()));
+                }
+            }
+        }
+    }
+    // the cells only reachable through other nodes
+    fn garbage(&mut self) -> Vec<Rc<dyn Traced>> {
+        let mut next = 0;
+        while next < self.nodes.len() {
+            self.expand(next);
+            next += 1;
+        }
+        let mut alive = vec![false; self.nodes.len()];
+        let mut pending: Vec<usize> = (0..self.nodes.len())
+            .filter(|&index| self.busy[index] || self.counts[index] > self.internal[index])
+            .collect();
+        while let Some(index) = pending.pop() {
+            if !alive[index] {
+                alive[index] = true;
+                pending.extend(&self.children[index]);
+            }
+        }
+        self.nodes
+            .iter()
+            .zip(alive)
+            .filter_map(|(node, alive)| match node {
+                Node::Cell(cell) if !alive => Some(cell.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn address<T: ?Sized>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const u8 as usize
+}
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+// the system allocator, counting the bytes in use so `--max-memory` can be enforced; the `tron`
+// binary installs it, embedders opt in with `#[global_allocator]`
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+// bytes in use, or 0 when `CountingAllocator` isn't the global allocator
+pub fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+// whether `CountingAllocator` is the global allocator, which has counted something by the time
+// there is an interpreter to ask
+pub fn counting() -> bool {
+    allocated() > 0
+}
+
+// `4096`, `512k`, `64m` or `1g`
+pub fn parse_size(text: &str) -> Option<usize> {
+    let lower = text.to_ascii_lowercase();
+    let (digits, unit) = match lower.strip_suffix(['k', 'm', 'g']) {
+        Some(digits) => (digits, &lower[digits.len()..]),
+        None => (lower.as_str(), ""),
+    };
+    let size: usize = digits.parse().ok()?;
+    let multiplier = match unit {
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => 1,
+    };
+    size.checked_mul(multiplier)
+}
+
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        bytes if bytes >= 1 << 30 && bytes % (1 << 30) == 0 => format!("{}GB", bytes >> 30),
+        bytes if bytes >= 1 << 20 && bytes % (1 << 20) == 0 => format!("{}MB", bytes >> 20),
+        bytes if bytes >= 1 << 10 && bytes % (1 << 10) == 0 => format!("{}KB", bytes >> 10),
+        bytes => format!("{} bytes", bytes),
+    }
+}
@@ -0,0 +1,931 @@
+// work done once before a program runs instead of every time a statement does: operators on
+// literals are folded into one literal, `if`, `switch` and `while` branches that can never run
+// are dropped, and calls of short `fn f(...): type = ...;` functions with literal arguments are
+// replaced by their result
+//
+// The pass runs on the resolved statements, so it keeps the id of every expression the resolver
+// has a local for, and it never opens or closes a scope: a branch that always runs stays the
+// block it was. Anything whose value would be an error is left for the program to report.
+use crate::expressions::{binary_op, unary_op, Expression, TronType};
+use crate::scanner::{Parameter, Statement, TokenType};
+use crate::utils::collect_errors;
+use std::collections::HashMap;
+
+pub fn optimize(statements: Vec<Statement>) -> Vec<Statement> {
+    let mut scan = Scan::default();
+    for statement in &statements {
+        scan.statement(statement);
+    }
+    let mut inline = HashMap::new();
+    // a library could define the function again
+    if !scan.uses {
+        for statement in &statements {
+            if let Statement::FunctionStatement {
+                name,
+                params,
+                body,
+                output_type,
+                ..
+            } = statement
+            {
+                let function = Inline::of(name.span.start, params, body, &output_type.lexeme);
+                if let (Some(function), Some(1)) = (function, scan.bindings.get(&name.lexeme)) {
+                    inline.insert(name.lexeme.clone(), function);
+                }
+            }
+        }
+    }
+    let mut optimizer = Optimizer {
+        inline,
+        loops: 0,
+        in_function: false,
+    };
+    statements
+        .into_iter()
+        .filter_map(|statement| optimizer.statement(statement))
+        .collect()
+}
+
+// every name the program binds, with how many times, and whether it uses a library
+#[derive(Default)]
+struct Scan {
+    bindings: HashMap<String, usize>,
+    uses: bool,
+}
+
+impl Scan {
+    fn bind(&mut self, name: &str) {
+        *self.bindings.entry(name.to_string()).or_default() += 1;
+    }
+
+    fn params(&mut self, params: &[Parameter]) {
+        for param in params {
+            self.bind(&param.name.lexeme);
+            if let Some(default) = &param.default {
+                self.expression(default);
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::ExpressionStatement { expression, .. } => self.expression(expression),
+            Statement::UseStatement { expression, .. } => {
+                self.uses = true;
+                self.expression(expression);
+            }
+            Statement::VariableStatement { name, value, .. } => {
+                self.bind(&name.lexeme);
+                self.expression(value);
+            }
+            Statement::BlockStatement { statements, .. } => statements
+                .iter()
+                .for_each(|statement| self.statement(statement)),
+            Statement::WhileStatement {
+                conditions, body, ..
+            } => {
+                conditions.iter().for_each(|c| self.expression(c));
+                self.statement(body);
+            }
+            Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches,
+                else_branch,
+                ..
+            } => {
+                conditions.iter().for_each(|c| self.expression(c));
+                self.statement(then_branch);
+                for (conditions, body) in elif_branches {
+                    conditions.iter().for_each(|c| self.expression(c));
+                    self.statement(body);
+                }
+                if let Some(body) = else_branch {
+                    self.statement(body);
+                }
+            }
+            Statement::FunctionStatement {
+                name, params, body, ..
+            } => {
+                self.bind(&name.lexeme);
+                self.params(params);
+                body.iter().for_each(|statement| self.statement(statement));
+            }
+            Statement::ReturnStatement { value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            Statement::BreakStatement { .. } => {}
+            Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                ..
+            } => {
+                self.expression(condition);
+                for (value, body) in case_branches {
+                    self.expression(value);
+                    body.iter().for_each(|statement| self.statement(statement));
+                }
+                for statement in default_branch.iter().flatten() {
+                    self.statement(statement);
+                }
+            }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                ..
+            } => {
+                self.statement(body);
+                if let Some((name, block)) = catch {
+                    self.bind(&name.lexeme);
+                    self.statement(block);
+                }
+                if let Some(block) = finally {
+                    self.statement(block);
+                }
+            }
+            Statement::ThrowStatement { value, .. } => self.expression(value),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Assign { name, .. } => self.bind(&name.lexeme),
+            Expression::Function {
+                name, params, body, ..
+            } => {
+                self.bind(&name.lexeme);
+                self.params(params);
+                body.iter().for_each(|statement| self.statement(statement));
+            }
+            _ => {}
+        }
+        for operand in operands(expression) {
+            self.expression(operand);
+        }
+    }
+}
+
+// the expressions right inside `expression`, leaving out the body of a function
+fn operands(expression: &Expression) -> Vec<&Expression> {
+    match expression {
+        Expression::Object { properties, .. } => properties.iter().map(|(_, v)| v).collect(),
+        Expression::ObjectCall { .. } | Expression::Literal { .. } => vec![],
+        Expression::Variable { .. } => vec![],
+        Expression::Array { elements, .. } => elements.iter().map(|e| e.as_ref()).collect(),
+        Expression::Index { array, index, .. } => vec![array, index],
+        Expression::Assign { value, .. } => vec![value],
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            vec![left, right]
+        }
+        Expression::Call {
+            callee,
+            arguments,
+            named,
+            ..
+        } => std::iter::once(callee.as_ref())
+            .chain(arguments)
+            .chain(named.iter().map(|(_, value)| value))
+            .collect(),
+        Expression::Grouping { expression, .. } => vec![expression],
+        Expression::Unary { right, .. } => vec![right],
+        Expression::Function { params, .. } => {
+            params.iter().filter_map(|p| p.default.as_ref()).collect()
+        }
+    }
+}
+
+// a `fn f(...): type = expression;` whose expression only combines its parameters, so a call
+// with literal arguments can be worked out before the program runs
+struct Inline {
+    // where the function is declared; calls written before it run before it exists
+    declared: usize,
+    params: Vec<(String, String)>,
+    value: Expression,
+    output_type: String,
+}
+
+impl Inline {
+    fn of(
+        declared: usize,
+        params: &[Parameter],
+        body: &[Box<Statement>],
+        output_type: &str,
+    ) -> Option<Inline> {
+        // the parser makes the `return` of an `=` body up, without a lexeme
+        let [statement] = body else {
+            return None;
+        };
+        let Statement::ReturnStatement {
+            keyword,
+            value: Some(value),
+            ..
+        } = statement.as_ref()
+        else {
+            return None;
+        };
+        let simple = |param: &Parameter| {
+            param.default.is_none()
+                && !param.rest
+                && matches!(
+                    param.type_token.lexeme.as_str(),
+                    "number" | "string" | "bool" | "null"
+                )
+        };
+        if !keyword.lexeme.is_empty()
+            || !params.iter().all(simple)
+            || !matches!(output_type, "number" | "string")
+        {
+            return None;
+        }
+        let params: Vec<(String, String)> = params
+            .iter()
+            .map(|param| (param.name.lexeme.clone(), param.type_token.lexeme.clone()))
+            .collect();
+        if !only_uses(value, &params) {
+            return None;
+        }
+        Some(Inline {
+            declared,
+            params,
+            value: value.clone(),
+            output_type: output_type.to_string(),
+        })
+    }
+
+    // the body with `arguments` in place of the parameters, when each has the parameter's type
+    fn with(&self, arguments: &[Expression]) -> Option<Expression> {
+        if arguments.len() != self.params.len() {
+            return None;
+        }
+        let mut values = HashMap::new();
+        for ((name, type_name), argument) in self.params.iter().zip(arguments) {
+            let Expression::Literal { value, .. } = argument else {
+                return None;
+            };
+            let conforms = matches!(
+                (type_name.as_str(), value),
+                ("number", TronType::Number(_))
+                    | ("string", TronType::StringValue(_))
+                    | ("bool", TronType::True | TronType::False)
+                    | ("null", TronType::Null)
+            );
+            if !conforms {
+                return None;
+            }
+            values.insert(name.as_str(), value.clone());
+        }
+        Some(substitute(&self.value, &values))
+    }
+}
+
+// whether `expression` is made of literals, operators and the parameters
+fn only_uses(expression: &Expression, params: &[(String, String)]) -> bool {
+    match expression {
+        Expression::Literal { .. } => true,
+        Expression::Variable { name, .. } => params.iter().any(|(p, _)| *p == name.lexeme),
+        Expression::Grouping { .. }
+        | Expression::Unary { .. }
+        | Expression::Binary { .. }
+        | Expression::Logical { .. } => operands(expression)
+            .into_iter()
+            .all(|operand| only_uses(operand, params)),
+        _ => false,
+    }
+}
+
+fn substitute(expression: &Expression, values: &HashMap<&str, TronType>) -> Expression {
+    let operand = |operand: &Expression| Box::new(substitute(operand, values));
+    match expression {
+        Expression::Variable { id, name } => Expression::Literal {
+            id: *id,
+            value: values[name.lexeme.as_str()].clone(),
+        },
+        Expression::Grouping { id, expression } => Expression::Grouping {
+            id: *id,
+            expression: operand(expression),
+        },
+        Expression::Unary {
+            id,
+            operator,
+            right,
+        } => Expression::Unary {
+            id: *id,
+            operator: operator.clone(),
+            right: operand(right),
+        },
+        Expression::Binary {
+            id,
+            left,
+            operator,
+            right,
+        } => Expression::Binary {
+            id: *id,
+            left: operand(left),
+            operator: operator.clone(),
+            right: operand(right),
+        },
+        Expression::Logical {
+            id,
+            left,
+            operator,
+            right,
+        } => Expression::Logical {
+            id: *id,
+            left: operand(left),
+            operator: operator.clone(),
+            right: operand(right),
+        },
+        expression => expression.clone(),
+    }
+}
+
+fn literal(expression: &Expression) -> Option<&TronType> {
+    match expression {
+        Expression::Literal { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+// `operation` on literals, unless it reports an error
+fn fold(id: usize, operation: impl FnOnce() -> TronType) -> Option<Expression> {
+    match collect_errors(operation) {
+        (value, errors) if errors.is_empty() => Some(Expression::Literal { id, value }),
+        _ => None,
+    }
+}
+
+// whether conditions all hold, when that is known without running anything; the ones after a
+// false one are never evaluated
+fn decided(conditions: &[Expression]) -> Option<bool> {
+    for condition in conditions {
+        if literal(condition)?.is_truthy() != TronType::True {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+// whether `statement` has a `break` or `return` that is an error where it stands, which dropping
+// it would hide
+fn misplaced(statement: &Statement, in_loop: bool, in_function: bool) -> bool {
+    match statement {
+        Statement::BreakStatement { .. } => !in_loop,
+        Statement::ReturnStatement { value, .. } => !in_function || value.iter().any(misplaced_in),
+        Statement::BlockStatement { statements, .. } => statements
+            .iter()
+            .any(|s| misplaced(s, in_loop, in_function)),
+        Statement::WhileStatement {
+            conditions, body, ..
+        } => conditions.iter().any(misplaced_in) || misplaced(body, true, in_function),
+        Statement::IfStatement {
+            conditions,
+            then_branch,
+            elif_branches,
+            else_branch,
+            ..
+        } => {
+            conditions.iter().any(misplaced_in)
+                || misplaced(then_branch, in_loop, in_function)
+                || elif_branches.iter().any(|(conditions, body)| {
+                    conditions.iter().any(misplaced_in) || misplaced(body, in_loop, in_function)
+                })
+                || else_branch
+                    .iter()
+                    .any(|body| misplaced(body, in_loop, in_function))
+        }
+        Statement::SwitchStatement {
+            condition,
+            case_branches,
+            default_branch,
+            ..
+        } => {
+            misplaced_in(condition)
+                || case_branches.iter().any(|(value, body)| {
+                    misplaced_in(value) || body.iter().any(|s| misplaced(s, in_loop, in_function))
+                })
+                || default_branch
+                    .iter()
+                    .flatten()
+                    .any(|s| misplaced(s, in_loop, in_function))
+        }
+        Statement::FunctionStatement { params, body, .. } => {
+            params
+                .iter()
+                .filter_map(|p| p.default.as_ref())
+                .any(misplaced_in)
+                || body.iter().any(|s| misplaced(s, false, true))
+        }
+        Statement::TryStatement {
+            body,
+            catch,
+            finally,
+            ..
+        } => {
+            misplaced(body, in_loop, in_function)
+                || catch
+                    .iter()
+                    .any(|(_, block)| misplaced(block, in_loop, in_function))
+                || finally
+                    .iter()
+                    .any(|block| misplaced(block, in_loop, in_function))
+        }
+        Statement::ExpressionStatement { expression, .. }
+        | Statement::UseStatement { expression, .. }
+        | Statement::ThrowStatement {
+            value: expression, ..
+        }
+        | Statement::VariableStatement {
+            value: expression, ..
+        } => misplaced_in(expression),
+    }
+}
+
+// `misplaced` for the bodies of the functions in `expression`
+fn misplaced_in(expression: &Expression) -> bool {
+    if let Expression::Function { body, .. } = expression {
+        if body.iter().any(|s| misplaced(s, false, true)) {
+            return true;
+        }
+    }
+    operands(expression).into_iter().any(misplaced_in)
+}
+
+struct Optimizer {
+    inline: HashMap<String, Inline>,
+    // loops around the statement being optimized, inside its function
+    loops: usize,
+    in_function: bool,
+}
+
+impl Optimizer {
+    // `None` when the statement can never do anything
+    fn statement(&mut self, statement: Statement) -> Option<Statement> {
+        match statement {
+            Statement::ExpressionStatement { expression, line } => {
+                Some(Statement::ExpressionStatement {
+                    expression: self.expression(expression),
+                    line,
+                })
+            }
+            Statement::UseStatement { expression, line } => Some(Statement::UseStatement {
+                expression: self.expression(expression),
+                line,
+            }),
+            Statement::VariableStatement {
+                name,
+                value_type,
+                value,
+                line,
+            } => Some(Statement::VariableStatement {
+                name,
+                value_type,
+                value: self.expression(value),
+                line,
+            }),
+            Statement::BlockStatement { statements, line } => Some(Statement::BlockStatement {
+                statements: self.statements(statements),
+                line,
+            }),
+            Statement::WhileStatement {
+                conditions,
+                body,
+                line,
+            } => {
+                let conditions = self.expressions(conditions);
+                self.loops += 1;
+                let body = self.branch(body);
+                self.loops -= 1;
+                if decided(&conditions) == Some(false) && !misplaced(&body, true, self.in_function)
+                {
+                    return None;
+                }
+                Some(Statement::WhileStatement {
+                    conditions,
+                    body,
+                    line,
+                })
+            }
+            Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches,
+                else_branch,
+                line,
+            } => {
+                let branches: Vec<(Vec<Expression>, Box<Statement>)> =
+                    std::iter::once((conditions, then_branch))
+                        .chain(elif_branches)
+                        .map(|(conditions, body)| (self.expressions(conditions), self.branch(body)))
+                        .collect();
+                let else_branch = else_branch.map(|body| self.branch(body));
+                self.choose(branches, else_branch, line)
+            }
+            Statement::FunctionStatement {
+                name,
+                params,
+                body,
+                output_type,
+                line,
+            } => {
+                let (params, body) = self.function(params, body);
+                Some(Statement::FunctionStatement {
+                    name,
+                    params,
+                    body,
+                    output_type,
+                    line,
+                })
+            }
+            Statement::ReturnStatement {
+                keyword,
+                value,
+                line,
+            } => Some(Statement::ReturnStatement {
+                keyword,
+                value: value.map(|value| self.expression(value)),
+                line,
+            }),
+            Statement::BreakStatement { .. } => Some(statement),
+            Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                line,
+            } => {
+                let condition = self.expression(condition);
+                let case_branches: Vec<(Expression, Vec<Statement>)> = case_branches
+                    .into_iter()
+                    .map(|(value, body)| (self.expression(value), self.case(body)))
+                    .collect();
+                let default_branch = default_branch.map(|body| self.case(body));
+                self.switch(condition, case_branches, default_branch, line)
+            }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                line,
+            } => Some(Statement::TryStatement {
+                body: self.branch(body),
+                catch: catch.map(|(name, block)| (name, self.branch(block))),
+                finally: finally.map(|block| self.branch(block)),
+                line,
+            }),
+            Statement::ThrowStatement {
+                keyword,
+                value,
+                line,
+            } => Some(Statement::ThrowStatement {
+                keyword,
+                value: self.expression(value),
+                line,
+            }),
+        }
+    }
+
+    fn statements(&mut self, statements: Vec<Box<Statement>>) -> Vec<Box<Statement>> {
+        statements
+            .into_iter()
+            .filter_map(|statement| self.statement(*statement).map(Box::new))
+            .collect()
+    }
+
+    fn case(&mut self, body: Vec<Statement>) -> Vec<Statement> {
+        body.into_iter()
+            .filter_map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    // the body of an `if`, `while` or `try`, which has to stay a statement
+    fn branch(&mut self, body: Box<Statement>) -> Box<Statement> {
+        let line = body.get_line();
+        Box::new(self.statement(*body).unwrap_or(Statement::BlockStatement {
+            statements: vec![],
+            line,
+        }))
+    }
+
+    fn function(
+        &mut self,
+        params: Vec<Parameter>,
+        body: Vec<Box<Statement>>,
+    ) -> (Vec<Parameter>, Vec<Box<Statement>>) {
+        let outer = (self.loops, self.in_function);
+        (self.loops, self.in_function) = (0, true);
+        let params = params
+            .into_iter()
+            .map(|param| Parameter {
+                default: param.default.map(|default| self.expression(default)),
+                ..param
+            })
+            .collect();
+        let body = self.statements(body);
+        (self.loops, self.in_function) = outer;
+        (params, body)
+    }
+
+    // an `if` without the branches that can't run; the first that always does becomes the else
+    fn choose(
+        &mut self,
+        branches: Vec<(Vec<Expression>, Box<Statement>)>,
+        else_branch: Option<Box<Statement>>,
+        line: usize,
+    ) -> Option<Statement> {
+        let decisions: Vec<Option<bool>> = branches.iter().map(|(c, _)| decided(c)).collect();
+        let taken = decisions
+            .iter()
+            .position(|decision| *decision == Some(true));
+        let dead =
+            |index: usize| decisions[index] == Some(false) || taken.is_some_and(|t| index > t);
+        let hidden = branches
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| dead(*index))
+            .map(|(_, (_, body))| body)
+            .chain(else_branch.iter().filter(|_| taken.is_some()))
+            .any(|body| misplaced(body, self.loops > 0, self.in_function));
+        if hidden || (taken.is_none() && decisions.iter().all(Option::is_none)) {
+            let mut branches = branches.into_iter();
+            let (conditions, then_branch) = branches.next()?;
+            return Some(Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches: branches.collect(),
+                else_branch,
+                line,
+            });
+        }
+        let mut live = vec![];
+        let mut otherwise = else_branch;
+        for (index, (conditions, body)) in branches.into_iter().enumerate() {
+            if Some(index) == taken {
+                otherwise = Some(body);
+                break;
+            }
+            if !dead(index) {
+                live.push((conditions, body));
+            }
+        }
+        if live.is_empty() {
+            return otherwise.map(|body| *body);
+        }
+        let (conditions, then_branch) = live.remove(0);
+        Some(Statement::IfStatement {
+            conditions,
+            then_branch,
+            elif_branches: live,
+            else_branch: otherwise,
+            line,
+        })
+    }
+
+    // a `switch` on a literal keeps only the case it picks, as a `default`, so its body runs in
+    // the scope it would have
+    fn switch(
+        &mut self,
+        condition: Expression,
+        case_branches: Vec<(Expression, Vec<Statement>)>,
+        default_branch: Option<Vec<Statement>>,
+        line: usize,
+    ) -> Option<Statement> {
+        let unchanged = |condition, case_branches, default_branch| {
+            Some(Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                line,
+            })
+        };
+        let Some(value) = literal(&condition) else {
+            return unchanged(condition, case_branches, default_branch);
+        };
+        // cases are compared in order until one matches
+        let mut picked = None;
+        for (index, (case_value, _)) in case_branches.iter().enumerate() {
+            match literal(case_value) {
+                Some(case_value) if case_value == value => {
+                    picked = Some(index);
+                    break;
+                }
+                Some(_) => {}
+                None => return unchanged(condition, case_branches, default_branch),
+            }
+        }
+        let hidden = case_branches
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != picked)
+            .flat_map(|(_, (_, body))| body)
+            .chain(default_branch.iter().flatten().filter(|_| picked.is_some()))
+            .any(|statement| misplaced(statement, self.loops > 0, self.in_function));
+        if hidden {
+            return unchanged(condition, case_branches, default_branch);
+        }
+        let body = match picked {
+            Some(index) => case_branches.into_iter().nth(index).map(|(_, body)| body),
+            None => default_branch,
+        }?;
+        unchanged(condition, vec![], Some(body))
+    }
+
+    fn expressions(&mut self, expressions: Vec<Expression>) -> Vec<Expression> {
+        expressions
+            .into_iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    fn boxed(&mut self, expression: Box<Expression>) -> Box<Expression> {
+        Box::new(self.expression(*expression))
+    }
+
+    fn expression(&mut self, expression: Expression) -> Expression {
+        match expression {
+            Expression::Object { id, properties } => Expression::Object {
+                id,
+                properties: properties
+                    .into_iter()
+                    .map(|(key, value)| (key, self.expression(value)))
+                    .collect(),
+            },
+            Expression::Array { id, elements } => Expression::Array {
+                id,
+                elements: elements
+                    .into_iter()
+                    .map(|element| self.boxed(element))
+                    .collect(),
+            },
+            Expression::Index {
+                id,
+                array,
+                index,
+                bracket,
+            } => Expression::Index {
+                id,
+                array: self.boxed(array),
+                index: self.boxed(index),
+                bracket,
+            },
+            Expression::Assign { id, name, value } => Expression::Assign {
+                id,
+                name,
+                value: self.boxed(value),
+            },
+            Expression::Call {
+                id,
+                callee,
+                paren,
+                arguments,
+                named,
+            } => {
+                let callee = self.boxed(callee);
+                let arguments = self.expressions(arguments);
+                let named: Vec<_> = named
+                    .into_iter()
+                    .map(|(name, value)| (name, self.expression(value)))
+                    .collect();
+                if named.is_empty() {
+                    if let Some(value) = self.inlined(id, &callee, &arguments) {
+                        return value;
+                    }
+                }
+                Expression::Call {
+                    id,
+                    callee,
+                    paren,
+                    arguments,
+                    named,
+                }
+            }
+            Expression::Grouping { id, expression } => {
+                let expression = self.expression(*expression);
+                match literal(&expression) {
+                    Some(value) => Expression::Literal {
+                        id,
+                        value: value.clone(),
+                    },
+                    None => Expression::Grouping {
+                        id,
+                        expression: Box::new(expression),
+                    },
+                }
+            }
+            Expression::Unary {
+                id,
+                operator,
+                right,
+            } => {
+                let right = self.boxed(right);
+                literal(&right)
+                    .and_then(|value| {
+                        fold(id, || {
+                            unary_op(operator.token_type, value, operator.line_number)
+                        })
+                    })
+                    .unwrap_or(Expression::Unary {
+                        id,
+                        operator,
+                        right,
+                    })
+            }
+            Expression::Binary {
+                id,
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.boxed(left);
+                let right = self.boxed(right);
+                match (literal(&left), literal(&right)) {
+                    (Some(x), Some(y)) => fold(id, || {
+                        binary_op(x, operator.token_type, y, operator.line_number)
+                    }),
+                    _ => None,
+                }
+                .unwrap_or(Expression::Binary {
+                    id,
+                    left,
+                    operator,
+                    right,
+                })
+            }
+            Expression::Logical {
+                id,
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.boxed(left);
+                let right = self.boxed(right);
+                // what the interpreter does with a left operand it knows
+                let truthy = literal(&left).map(|value| value.is_truthy() == TronType::True);
+                match (operator.token_type, truthy) {
+                    (TokenType::Or, Some(true)) => *left,
+                    (TokenType::Or, Some(false)) | (TokenType::And, Some(true)) => *right,
+                    (TokenType::And, Some(false)) => Expression::Literal {
+                        id,
+                        value: TronType::False,
+                    },
+                    (TokenType::Xor | TokenType::Nor, Some(truthy)) => Expression::Literal {
+                        id,
+                        value: TronType::from_bool(!truthy),
+                    },
+                    _ => Expression::Logical {
+                        id,
+                        left,
+                        operator,
+                        right,
+                    },
+                }
+            }
+            Expression::Function {
+                id,
+                name,
+                params,
+                body,
+                output_type,
+            } => {
+                let (params, body) = self.function(params, body);
+                Expression::Function {
+                    id,
+                    name,
+                    params,
+                    body,
+                    output_type,
+                }
+            }
+            expression @ (Expression::ObjectCall { .. }
+            | Expression::Literal { .. }
+            | Expression::Variable { .. }) => expression,
+        }
+    }
+
+    // the result of a call of an `Inline` function, at the top level of the program: functions
+    // can be called after another program run by the same interpreter defined theirs again
+    fn inlined(
+        &mut self,
+        id: usize,
+        callee: &Expression,
+        arguments: &[Expression],
+    ) -> Option<Expression> {
+        let Expression::Variable { name, .. } = callee else {
+            return None;
+        };
+        let function = self.inline.get(&name.lexeme)?;
+        if self.in_function || name.span.start < function.declared {
+            return None;
+        }
+        let output_type = function.output_type.clone();
+        let body = function.with(arguments)?;
+        match self.expression(body) {
+            Expression::Literal { value, .. } if value.to_type() == output_type => {
+                Some(Expression::Literal { id, value })
+            }
+            _ => None,
+        }
+    }
+}
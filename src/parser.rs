@@ -1,6 +1,11 @@
 use crate::expressions::{Expression, Expression::*, TronType};
-use crate::scanner::{Statement, Token, TokenType, TokenType::*};
+use crate::scanner::{Parameter, Span, Statement, Token, TokenType, TokenType::*};
+use crate::syntax::{Event, SyntaxKind};
 use crate::utils::TronError;
+
+// parameters a function takes and arguments a call passes, E2004 past it
+const MAX_ARGUMENTS: usize = 32;
+
 /// The `Parser` struct in Rust is responsible for parsing.
 /// It maintains a list of tokens and provides methods to parse statements and expressions.
 ///
@@ -9,6 +14,7 @@ use crate::utils::TronError;
 /// - `tokens`: A vector of tokens that the parser will process.
 /// - `current`: The index of the current token being parsed.
 /// - `next_id`: A counter for generating unique IDs for expressions and statements.
+/// - `events`: The nodes and tokens parsed so far, when the parser was made with `recording()`.
 ///
 /// # Usage
 ///
@@ -22,11 +28,12 @@ use crate::utils::TronError;
 /// let statements = parser.parse()?;
 /// ```
 ///
-/// ### Last Updated: (v3.0.0)
+/// ### Last Updated: (v3.2.0)
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     next_id: usize,
+    events: Option<Vec<Event>>,
 }
 impl Parser {
     /// The `new()` function is a constructor for the `Parser` struct.
@@ -58,8 +65,84 @@ impl Parser {
             tokens,
             current: 0,
             next_id: 0,
+            events: None,
+        }
+    }
+    /// The `starting_at()` function creates a `Parser` whose expression ids start at `next_id`.
+    ///
+    /// Expression ids key the resolver's `locals` map, which an `Interpreter` keeps between runs. Source loaded
+    /// into the same interpreter later (a `use`d library, another `eval` call) must not reuse ids handed out before.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut parser = Parser::starting_at(tokens, previous.next_id());
+    /// ```
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn starting_at(tokens: Vec<Token>, next_id: usize) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            next_id,
+            events: None,
+        }
+    }
+    /// The `recording()` function creates a `Parser` that also records where every node starts and ends.
+    ///
+    /// Parsing works exactly as with `new()`, but each node the parser builds and each token it consumes is
+    /// logged as an `Event`, which `SyntaxTree` turns into a concrete syntax tree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut parser = Parser::recording(tokens);
+    /// let statements = parser.parse()?;
+    /// let events = parser.take_events();
+    /// ```
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn recording(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            next_id: 0,
+            events: Some(vec![]),
+        }
+    }
+    /// The `take_events()` method hands over the events a `recording()` parser logged, leaving it empty.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+    /// The `checkpoint()` method marks where a node may start, for `wrap()` to use once the parser knows what
+    /// the node is.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn checkpoint(&self) -> usize {
+        self.events.as_ref().map_or(0, Vec::len)
+    }
+    /// The `wrap()` method turns everything recorded since `checkpoint` into a node of the given kind.
+    ///
+    /// Nodes are wrapped after they are parsed, so a binary expression can take in the left operand it started
+    /// with. Nothing is recorded when the parser isn't recording or no token was consumed.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(events) = &mut self.events {
+            if checkpoint < events.len() {
+                events.insert(checkpoint, Event::Start(kind));
+                events.push(Event::Finish);
+            }
         }
     }
+    /// The `next_id()` method returns the id the next parsed expression would receive.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
     /// The `get_id()` method is used to generate a unique ID for expressions and statements.
     ///
     /// It increments the `next_id` counter and returns the current value, ensuring that each expression or statement has a unique ID.
@@ -113,7 +196,12 @@ impl Parser {
             match stmt {
                 Ok(s) => stmts.push(s),
                 Err(msg) => {
-                    TronError::throw("E2001", self.current, vec![msg]);
+                    TronError::throw_at(
+                        "E2001",
+                        self.peek().line_number,
+                        Some(self.peek().span),
+                        vec![msg],
+                    );
                 }
             }
         }
@@ -141,13 +229,16 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn declaration(&mut self) -> Result<Statement, String> {
-        if self.match_token(Variable) {
-            self.var_declaration()
+        let checkpoint = self.checkpoint();
+        let (kind, result) = if self.match_token(Variable) {
+            (SyntaxKind::VariableStatement, self.var_declaration())
         } else if self.match_token(Function) {
-            self.function()
+            (SyntaxKind::FunctionStatement, self.function())
         } else {
-            self.statement()
-        }
+            return self.statement();
+        };
+        self.wrap(checkpoint, kind);
+        result
     }
     /// The `function()` method is responsible for parsing function declarations.
     ///
@@ -172,30 +263,11 @@ impl Parser {
     /// ### Last Updated: (v3.1.0)
     fn function(&mut self) -> Result<Statement, String> {
         let line_number = self.peek().line_number;
-        let name = self.consume(Identifier, &format!("expected function name"), line_number)?;
-        self.consume(
-            LeftParen,
-            &format!("expected '(' after function name"),
-            line_number,
-        )?;
-        let mut params: Vec<(Token, Token)> = vec![];
-        if !self.check(RightParen) {
-            loop {
-                if params.len() >= 32 {
-                    TronError::throw("E2004", self.current, vec![]);
-                }
-                let param_name =
-                    self.consume(Identifier, "expected parameter name", line_number)?;
-                self.consume(Colon, "expected `:` after parameter name", line_number)?;
-                let param_type = self.consume(Identifier, "expected type after ':'", line_number);
-
-                params.push((param_name, param_type?));
-                if !self.match_token(Comma) {
-                    break;
-                }
-            }
-        }
-        self.consume(RightParen, "expected ')' after parameters.", line_number)?;
+        let name = self.consume(Identifier, "expected function name", line_number)?;
+        let checkpoint = self.checkpoint();
+        self.consume(LeftParen, "expected '(' after function name", line_number)?;
+        let params = self.parameters(line_number)?;
+        self.wrap(checkpoint, SyntaxKind::ParameterList);
         self.consume(Colon, "expected `:` before function body", line_number)?;
         let output_type = self.consume(Identifier, "expected type after `:`", line_number)?;
 
@@ -213,8 +285,12 @@ impl Parser {
                     keyword: Token {
                         token_type: TokenType::Return,
                         lexeme: "".to_string(),
-                        line_number: line_number,
+                        line_number,
                         literal: None,
+                        span: Span {
+                            start: self.previous(1).span.start,
+                            end: self.previous(1).span.start,
+                        },
                     },
                     value: Some(body_expr),
                     line: line_number,
@@ -223,14 +299,22 @@ impl Parser {
                 line: line_number,
             });
         }
+        let checkpoint = self.checkpoint();
         self.consume(LeftBrace, "Expected '{' before function body.", line_number)?;
-        let body = match self.block_statement()? {
+        let block = self.block_statement()?;
+        self.wrap(checkpoint, SyntaxKind::BlockStatement);
+        let body = match block {
             Statement::BlockStatement {
                 statements,
                 line: _,
             } => statements,
             _ => {
-                TronError::throw("E2002", self.current, vec![]);
+                TronError::throw_at(
+                    "E2002",
+                    self.peek().line_number,
+                    Some(self.peek().span),
+                    vec![],
+                );
                 vec![]
             }
         };
@@ -242,6 +326,84 @@ impl Parser {
             line: line_number,
         })
     }
+    /// The `parameters()` method parses a parameter list up to and including the closing `)`.
+    ///
+    /// Each parameter is `name: type`, optionally followed by `= default`, or `...name: array` for a rest parameter.
+    /// Parameters without a default can't follow ones with a default, and the rest parameter has to come last.
+    ///
+    /// # Return Value
+    ///
+    /// A `Result` containing the parsed `Parameter`s, or an error message if parsing fails.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn parameters(&mut self, line_number: usize) -> Result<Vec<Parameter>, String> {
+        let mut params: Vec<Parameter> = vec![];
+        if !self.check(RightParen) {
+            let mut count = 0;
+            loop {
+                if count == MAX_ARGUMENTS {
+                    TronError::throw_at(
+                        "E2004",
+                        self.peek().line_number,
+                        Some(self.peek().span),
+                        vec![],
+                    );
+                }
+                if params.last().is_some_and(|param| param.rest) {
+                    TronError::throw(
+                        "E2003",
+                        line_number,
+                        vec!["rest parameter has to be the last parameter".to_string()],
+                    );
+                }
+                let checkpoint = self.checkpoint();
+                let rest = self.match_token(Ellipsis);
+                let name = self.consume(Identifier, "expected parameter name", line_number)?;
+                self.consume(Colon, "expected `:` after parameter name", line_number)?;
+                let type_token =
+                    self.consume(Identifier, "expected type after ':'", line_number)?;
+                if rest && type_token.lexeme != "array" {
+                    TronError::throw(
+                        "E2003",
+                        line_number,
+                        vec![format!("rest parameter {} has to be an array", name.lexeme)],
+                    );
+                }
+                let default = if !rest && self.match_token(Equal) {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                if default.is_none() && !rest && params.iter().any(|param| param.default.is_some())
+                {
+                    TronError::throw(
+                        "E2003",
+                        line_number,
+                        vec![format!(
+                            "parameter {} without a default follows one with a default",
+                            name.lexeme
+                        )],
+                    );
+                }
+                self.wrap(checkpoint, SyntaxKind::Parameter);
+                // the ones past the limit are parsed but left out
+                if count < MAX_ARGUMENTS {
+                    params.push(Parameter {
+                        name,
+                        type_token,
+                        default,
+                        rest,
+                    });
+                }
+                count += 1;
+                if !self.match_token(Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(RightParen, "expected ')' after parameters.", line_number)?;
+        Ok(params)
+    }
     /// The `var_declaration()` method is responsible for parsing variable declarations.
     ///
     /// It handles the parsing of variable names and their initial values. It ensures that the variable declaration is syntactically correct and constructs a `VariableStatement` object.
@@ -270,7 +432,7 @@ impl Parser {
         let value_type = if self.match_tokens(&[Identifier, StringLit, Number]) {
             self.previous(1)
         } else {
-            return Err(format!("Expected type after ':'"));
+            return Err("Expected type after ':'".to_string());
         };
 
         self.consume(Equal, "Expected '=' after variable name", line_number)?;
@@ -309,25 +471,32 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn statement(&mut self) -> Result<Statement, String> {
-        if self.match_token(LeftBrace) {
-            self.block_statement()
+        let checkpoint = self.checkpoint();
+        let (kind, result) = if self.match_token(LeftBrace) {
+            (SyntaxKind::BlockStatement, self.block_statement())
         } else if self.match_token(Use) {
-            self.use_statement()
+            (SyntaxKind::UseStatement, self.use_statement())
         } else if self.match_token(If) {
-            self.if_statement()
+            (SyntaxKind::IfStatement, self.if_statement())
         } else if self.match_token(While) {
-            self.while_statement()
+            (SyntaxKind::WhileStatement, self.while_statement())
         } else if self.match_token(For) {
-            self.for_statement()
+            (SyntaxKind::ForStatement, self.for_statement())
         } else if self.match_token(Return) {
-            self.return_statement()
+            (SyntaxKind::ReturnStatement, self.return_statement())
         } else if self.match_token(Break) {
-            self.break_statement()
+            (SyntaxKind::BreakStatement, self.break_statement())
         } else if self.match_token(Switch) {
-            self.switch_statement()
+            (SyntaxKind::SwitchStatement, self.switch_statement())
+        } else if self.match_token(Try) {
+            (SyntaxKind::TryStatement, self.try_statement())
+        } else if self.match_token(Throw) {
+            (SyntaxKind::ThrowStatement, self.throw_statement())
         } else {
-            self.expression_statement()
-        }
+            (SyntaxKind::ExpressionStatement, self.expression_statement())
+        };
+        self.wrap(checkpoint, kind);
+        result
     }
     /// The `switch_statement()` method is responsible for parsing switch statements.
     ///
@@ -355,18 +524,28 @@ impl Parser {
         let condition = self.expression()?;
         self.consume(LeftBrace, "Expected '{' after match value.", line_number)?;
         let mut case_branches: Vec<(Expression, Vec<Statement>)> = Vec::new();
-        while self.match_token(Case) {
+        loop {
+            let checkpoint = self.checkpoint();
+            if !self.match_token(Case) {
+                break;
+            }
             let case_value = self.expression()?;
             self.consume(LeftBrace, "Expected Start after case value.", line_number)?;
             let mut case_body = Vec::new();
-            while !self.check(RightBrace) && !self.check(Case) && !self.check(Default) {
+            while !self.check(RightBrace)
+                && !self.check(Case)
+                && !self.check(Default)
+                && !self.is_at_end()
+            {
                 let stmt = self.declaration()?;
                 case_body.push(stmt);
             }
             self.consume(RightBrace, "Expected End after case body.", line_number)?;
+            self.wrap(checkpoint, SyntaxKind::CaseBranch);
             case_branches.push((case_value, case_body));
         }
         let mut default_branch = None;
+        let checkpoint = self.checkpoint();
         if self.match_token(Default) {
             self.consume(
                 LeftBrace,
@@ -374,11 +553,12 @@ impl Parser {
                 line_number,
             )?;
             let mut default_body = Vec::new();
-            while !self.check(RightBrace) {
+            while !self.check(RightBrace) && !self.is_at_end() {
                 let stmt = self.declaration()?;
                 default_body.push(stmt);
             }
             self.consume(RightBrace, "Expected End after default body.", line_number)?;
+            self.wrap(checkpoint, SyntaxKind::DefaultBranch);
             default_branch = Some(default_body);
         }
         self.consume(
@@ -393,6 +573,75 @@ impl Parser {
             line: line_number,
         })
     }
+    /// The `try_statement()` method is responsible for parsing try statements.
+    ///
+    /// It parses the body block, then an optional `catch (name)` clause and an optional `finally` clause, at least one of
+    /// which has to be there, and constructs a `TryStatement` object.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn try_statement(&mut self) -> Result<Statement, String> {
+        let line_number = self.peek().line_number;
+        self.block_start("Expected '{' after try.", line_number);
+        let body = Box::new(self.statement()?);
+        let mut catch = None;
+        let checkpoint = self.checkpoint();
+        if self.match_token(Catch) {
+            self.consume(LeftParen, "Expected '(' after catch.", line_number)?;
+            let name = self.consume(Identifier, "Expected error name.", line_number)?;
+            self.consume(RightParen, "Expected ')' after error name.", line_number)?;
+            self.block_start("Expected '{' after catch clause.", line_number);
+            catch = Some((name, Box::new(self.statement()?)));
+            self.wrap(checkpoint, SyntaxKind::CatchClause);
+        }
+        let mut finally = None;
+        let checkpoint = self.checkpoint();
+        if self.match_token(Finally) {
+            self.block_start("Expected '{' after finally.", line_number);
+            finally = Some(Box::new(self.statement()?));
+            self.wrap(checkpoint, SyntaxKind::FinallyClause);
+        }
+        if catch.is_none() && finally.is_none() {
+            let token = self.peek();
+            TronError::throw_at(
+                "E2003",
+                line_number,
+                Some(token.span),
+                vec!["Expected catch or finally after try block.".to_string()],
+            );
+        }
+        Ok(Statement::TryStatement {
+            body,
+            catch,
+            finally,
+            line: line_number,
+        })
+    }
+    /// The `block_start()` method reports an error unless the next token opens a block, which the clauses of a try
+    /// statement must be.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn block_start(&mut self, msg: &str, line: usize) {
+        if !self.check(LeftBrace) {
+            let token = self.peek();
+            TronError::throw_at("E2003", line, Some(token.span), vec![msg.to_string()]);
+        }
+    }
+    /// The `throw_statement()` method is responsible for parsing throw statements.
+    ///
+    /// It parses the value after the throw keyword and constructs a `ThrowStatement` object.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn throw_statement(&mut self) -> Result<Statement, String> {
+        let line_number = self.peek().line_number;
+        let keyword = self.previous(1);
+        let value = self.expression()?;
+        self.consume(Semicolon, "Expected ';' after thrown value.", line_number)?;
+        Ok(Statement::ThrowStatement {
+            keyword,
+            value,
+            line: line_number,
+        })
+    }
     /// The `return_statement()` method is responsible for parsing return statements.
     ///
     /// It handles the parsing of the return keyword and the optional expression that follows it. It ensures that the return statement is syntactically correct and constructs a `ReturnStatement` object.
@@ -417,12 +666,11 @@ impl Parser {
     fn return_statement(&mut self) -> Result<Statement, String> {
         let line_number = self.peek().line_number;
         let keyword = self.previous(1);
-        let value;
-        if !self.check(Semicolon) {
-            value = Some(self.expression()?);
+        let value = if !self.check(Semicolon) {
+            Some(self.expression()?)
         } else {
-            value = None;
-        }
+            None
+        };
         self.consume(Semicolon, "Expected ';' after return value;", line_number)?;
         Ok(Statement::ReturnStatement {
             keyword,
@@ -487,31 +735,30 @@ impl Parser {
     /// ### Last Updated: (v3.0.0)
     fn for_statement(&mut self) -> Result<Statement, String> {
         let line_number = self.peek().line_number;
+        let checkpoint = self.checkpoint();
         let initializer;
         if self.match_token(Semicolon) {
             initializer = None;
         } else if self.match_token(Variable) {
             let var_decl = self.var_declaration()?;
+            self.wrap(checkpoint, SyntaxKind::VariableStatement);
             initializer = Some(var_decl);
         } else {
             let expr = self.expression_statement()?;
+            self.wrap(checkpoint, SyntaxKind::ExpressionStatement);
             initializer = Some(expr);
         }
-        let condition;
-        if !self.check(Semicolon) {
-            let expr = self.expression()?;
-            condition = Some(expr);
+        let condition = if !self.check(Semicolon) {
+            Some(self.expression()?)
         } else {
-            condition = None;
-        }
+            None
+        };
         self.consume(Semicolon, "Expected ';' after loop condition.", line_number)?;
-        let increment;
-        if !self.check(RightParen) {
-            let expr = self.expression()?;
-            increment = Some(expr);
+        let increment = if !self.check(RightParen) {
+            Some(self.expression()?)
         } else {
-            increment = None;
-        }
+            None
+        };
         let mut body = self.statement()?;
         if let Some(incr) = increment {
             body = Statement::BlockStatement {
@@ -525,16 +772,13 @@ impl Parser {
                 line: line_number,
             };
         }
-        let cond;
-        match condition {
-            None => {
-                cond = Expression::Literal {
-                    id: self.get_id(),
-                    value: TronType::True,
-                }
-            }
-            Some(c) => cond = c,
-        }
+        let cond = match condition {
+            None => Expression::Literal {
+                id: self.get_id(),
+                value: TronType::True,
+            },
+            Some(c) => c,
+        };
         body = Statement::WhileStatement {
             conditions: vec![cond],
             body: Box::new(body),
@@ -760,9 +1004,11 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn expression(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let expr = self.or()?;
         if self.match_token(Equal) {
             let value = self.expression()?;
+            self.wrap(checkpoint, SyntaxKind::Assign);
             match expr {
                 Expression::Variable { id: _, name } => Ok(Assign {
                     id: self.get_id(),
@@ -770,7 +1016,12 @@ impl Parser {
                     value: Box::from(value),
                 }),
                 _ => {
-                    TronError::throw("E2007", self.current, vec![]);
+                    TronError::throw_at(
+                        "E2007",
+                        self.peek().line_number,
+                        Some(self.peek().span),
+                        vec![],
+                    );
                     Ok(Expression::Literal {
                         id: self.get_id() * 19,
                         value: TronType::Null,
@@ -795,6 +1046,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn or(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.nor()?;
         while self.match_token(Or) {
             let operator = self.previous(1);
@@ -805,6 +1057,7 @@ impl Parser {
                 operator,
                 right: Box::new(right),
             };
+            self.wrap(checkpoint, SyntaxKind::Logical);
         }
         Ok(expr)
     }
@@ -822,6 +1075,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn nor(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.xor()?;
         while self.match_token(Nor) {
             let operator = self.previous(1);
@@ -832,6 +1086,7 @@ impl Parser {
                 operator,
                 right: Box::new(right),
             };
+            self.wrap(checkpoint, SyntaxKind::Logical);
         }
         Ok(expr)
     }
@@ -849,6 +1104,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn xor(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.and()?;
         while self.match_token(Xor) {
             let operator = self.previous(1);
@@ -859,6 +1115,7 @@ impl Parser {
                 operator,
                 right: Box::new(right),
             };
+            self.wrap(checkpoint, SyntaxKind::Logical);
         }
         Ok(expr)
     }
@@ -876,6 +1133,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn and(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.equality()?;
         while self.match_token(And) {
             let operator = self.previous(1);
@@ -886,6 +1144,7 @@ impl Parser {
                 operator,
                 right: Box::new(right),
             };
+            self.wrap(checkpoint, SyntaxKind::Logical);
         }
         Ok(expr)
     }
@@ -903,6 +1162,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn equality(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.comparasion()?;
         while self.match_tokens(&[BangEqual, EqualEqual]) {
             let operator = self.previous(1);
@@ -913,6 +1173,7 @@ impl Parser {
                 operator,
                 right: Box::from(rhs),
             };
+            self.wrap(checkpoint, SyntaxKind::Binary);
         }
         Ok(expr)
     }
@@ -929,6 +1190,7 @@ impl Parser {
     /// The `comparasion()` method is called internally by the `Parser` to process comparasion expressions within the code.
     ///
     fn comparasion(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.term()?;
         while self.match_tokens(&[Greater, GreaterEqual, Less, LessEqual]) {
             let op = self.previous(1);
@@ -939,6 +1201,7 @@ impl Parser {
                 operator: op,
                 right: Box::from(rhs),
             };
+            self.wrap(checkpoint, SyntaxKind::Binary);
         }
         Ok(expr)
     }
@@ -956,6 +1219,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn term(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.factor()?;
         while self.match_tokens(&[Minus, Plus]) {
             let op = self.previous(1);
@@ -966,6 +1230,7 @@ impl Parser {
                 operator: op,
                 right: Box::from(rhs),
             };
+            self.wrap(checkpoint, SyntaxKind::Binary);
         }
         Ok(expr)
     }
@@ -983,6 +1248,7 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn factor(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.unary()?;
         while self.match_tokens(&[Slash, Star, Power]) {
             let op = self.previous(1);
@@ -993,6 +1259,7 @@ impl Parser {
                 operator: op,
                 right: Box::from(rhs),
             };
+            self.wrap(checkpoint, SyntaxKind::Binary);
         }
         Ok(expr)
     }
@@ -1010,9 +1277,11 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn unary(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         if self.match_tokens(&[Bang, Minus, Increment, Decrement, Percent]) {
             let op = self.previous(1);
             let rhs = self.unary()?;
+            self.wrap(checkpoint, SyntaxKind::Unary);
             Ok(Unary {
                 id: self.get_id(),
                 operator: op,
@@ -1036,10 +1305,14 @@ impl Parser {
     ///
     /// ### Last Updated: (v3.0.0)
     fn call(&mut self) -> Result<Expression, String> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.primary()?;
         loop {
+            let arguments = self.checkpoint();
             if self.match_token(LeftParen) {
                 expr = self.finish_call(expr)?;
+                self.wrap(arguments, SyntaxKind::ArgumentList);
+                self.wrap(checkpoint, SyntaxKind::Call);
             } else {
                 break;
             }
@@ -1068,10 +1341,13 @@ impl Parser {
             lexeme: self.previous(2).lexeme,
             line_number,
             literal: None,
+            span: self.previous(2).span,
         };
+        let checkpoint = self.checkpoint();
         match token.token_type {
             Function => {
                 result = self.parse_function_expr(var_name)?;
+                self.wrap(checkpoint, SyntaxKind::FunctionExpression);
             }
             Identifier => {
                 self.advance();
@@ -1080,20 +1356,30 @@ impl Parser {
                     name: self.previous(1),
                 };
 
+                if self.check(LeftBracket) {
+                    self.wrap(checkpoint, SyntaxKind::Variable);
+                }
                 if self.match_token(LeftBracket) {
                     let index = self.expression()?;
-                    self.consume(RightBracket, "Expected ']' after index", line_number)?;
-                    expr = Expression::Array {
+                    let bracket =
+                        self.consume(RightBracket, "Expected ']' after index", line_number)?;
+                    self.wrap(checkpoint, SyntaxKind::Index);
+                    expr = Expression::Index {
                         id: self.get_id(),
-                        elements: vec![Box::new(expr), Box::new(index)],
+                        array: Box::new(expr),
+                        index: Box::new(index),
+                        bracket,
                     };
                 } else if self.match_token(Dot) {
                     let key = self.consume(Identifier, "Expected key after '.'", line_number)?;
+                    self.wrap(checkpoint, SyntaxKind::ObjectCall);
                     expr = Expression::ObjectCall {
                         id: self.get_id(),
                         name: token,
                         key,
                     }
+                } else {
+                    self.wrap(checkpoint, SyntaxKind::Variable);
                 }
                 result = expr;
             }
@@ -1101,6 +1387,7 @@ impl Parser {
                 self.advance();
                 let expr = self.expression()?;
                 self.consume(RightParen, "Expected ')' after expression", line_number)?;
+                self.wrap(checkpoint, SyntaxKind::Grouping);
                 result = Expression::Grouping {
                     id: self.get_id(),
                     expression: Box::new(expr),
@@ -1108,6 +1395,7 @@ impl Parser {
             }
             False | True | Null | Number | StringLit => {
                 self.advance();
+                self.wrap(checkpoint, SyntaxKind::Literal);
                 result = Expression::Literal {
                     id: self.get_id(),
                     value: TronType::from_token(token),
@@ -1119,7 +1407,17 @@ impl Parser {
             TokenType::LeftBrace => return self.parse_object(),
 
             _ => {
-                TronError::throw("E2003", self.current, vec![token.token_type.to_string()]);
+                TronError::throw_at(
+                    "E2003",
+                    line_number,
+                    Some(token.span),
+                    vec![token.token_type.to_string()],
+                );
+                // skip the token, or parsing would stop at it forever
+                if !self.is_at_end() {
+                    self.advance();
+                    self.wrap(checkpoint, SyntaxKind::Error);
+                }
                 result = Expression::Literal {
                     id: self.get_id() * 23,
                     value: TronType::Null,
@@ -1144,13 +1442,39 @@ impl Parser {
     fn finish_call(&mut self, callee: Expression) -> Result<Expression, String> {
         let line_number = self.peek().line_number;
         let mut arguments = vec![];
+        let mut named = vec![];
         if !self.check(RightParen) {
+            // the arguments past the limit are parsed but left out, as parameters are
+            let mut count = 0;
             loop {
-                let arg = self.expression()?;
-                arguments.push(arg);
-                if arguments.len() >= 32 {
-                    TronError::throw("E2004", line_number, vec![]);
-                } else if !self.match_token(Comma) {
+                if count == MAX_ARGUMENTS {
+                    TronError::throw_at("E2004", line_number, Some(self.peek().span), vec![]);
+                }
+                // `name: value` passes an argument by parameter name
+                if self.check(Identifier) && self.check_next(Colon) {
+                    let checkpoint = self.checkpoint();
+                    let name = self.advance();
+                    self.advance();
+                    let value = self.expression()?;
+                    self.wrap(checkpoint, SyntaxKind::NamedArgument);
+                    if count < MAX_ARGUMENTS {
+                        named.push((name, value));
+                    }
+                } else {
+                    if !named.is_empty() {
+                        TronError::throw(
+                            "E2003",
+                            line_number,
+                            vec!["positional arguments can't follow named arguments".to_string()],
+                        );
+                    }
+                    let value = self.expression()?;
+                    if count < MAX_ARGUMENTS {
+                        arguments.push(value);
+                    }
+                }
+                count += 1;
+                if !self.match_token(Comma) {
                     break;
                 }
             }
@@ -1161,6 +1485,7 @@ impl Parser {
             callee: Box::new(callee),
             paren,
             arguments,
+            named,
         })
     }
     /// The `parse_array()` method is responsible for parsing array literals.
@@ -1178,6 +1503,7 @@ impl Parser {
     /// ### Last Updated: (v3.0.0)
     fn parse_array(&mut self) -> Result<Expression, String> {
         let line_number = self.peek().line_number;
+        let checkpoint = self.checkpoint();
         let mut elements = Vec::new();
         let array_id = self.get_id();
         self.advance();
@@ -1194,6 +1520,7 @@ impl Parser {
             "Expect ']' after array elements.",
             line_number,
         )?;
+        self.wrap(checkpoint, SyntaxKind::Array);
 
         Ok(Expression::Array {
             id: array_id,
@@ -1215,10 +1542,12 @@ impl Parser {
     /// ### Last Updated: (v3.0.0)
     fn parse_object(&mut self) -> Result<Expression, String> {
         let line_number = self.peek().line_number;
+        let checkpoint = self.checkpoint();
         let mut properties: Vec<(Token, Expression)> = Vec::new();
         let object_id = self.get_id();
         self.advance();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let property = self.checkpoint();
             let key = self.consume(
                 Identifier,
                 "Expected identifier for object key",
@@ -1226,6 +1555,7 @@ impl Parser {
             )?;
             self.consume(Colon, "Expected ':' after object key", line_number)?;
             let value = self.expression()?;
+            self.wrap(property, SyntaxKind::Property);
             properties.push((key, value));
             if !self.match_token(Comma) {
                 break;
@@ -1236,6 +1566,7 @@ impl Parser {
             "Expected '}' after object fields",
             line_number,
         )?;
+        self.wrap(checkpoint, SyntaxKind::Object);
         Ok(Expression::Object {
             id: object_id,
             properties,
@@ -1245,44 +1576,32 @@ impl Parser {
     fn parse_function_expr(&mut self, var_name: Token) -> Result<Expression, String> {
         let line_number = self.peek().line_number;
         self.consume(Function, "", line_number)?;
-        self.consume(
-            LeftParen,
-            &format!("expected '(' after function"),
-            line_number,
-        )?;
-
-        let mut params = Vec::new();
-        if !self.check(RightParen) {
-            loop {
-                if params.len() >= 32 {
-                    TronError::throw("E2004", self.current, vec![]);
-                }
-                let param_name =
-                    self.consume(Identifier, "expected parameter name", line_number)?;
-                self.consume(Colon, "expected `:` after parameter name", line_number)?;
-                let param_type = self.consume(Identifier, "expected type after ':'", line_number);
-
-                params.push((param_name, param_type?));
-                if !self.match_token(Comma) {
-                    break;
-                }
-            }
-        }
-        self.consume(RightParen, "expected ')' after parameters.", line_number)?;
+        let checkpoint = self.checkpoint();
+        self.consume(LeftParen, "expected '(' after function", line_number)?;
+        let params = self.parameters(line_number)?;
+        self.wrap(checkpoint, SyntaxKind::ParameterList);
 
         self.consume(Colon, "expected `:` before function body", line_number)?;
 
         let output_type = self.consume(Identifier, "expected type after `:`", line_number)?;
 
+        let checkpoint = self.checkpoint();
         self.consume(LeftBrace, "Expected '{' before function body.", line_number)?;
+        let block = self.block_statement()?;
+        self.wrap(checkpoint, SyntaxKind::BlockStatement);
 
-        let body = match self.block_statement()? {
+        let body = match block {
             Statement::BlockStatement {
                 statements,
                 line: _,
             } => statements,
             _ => {
-                TronError::throw("E2002", self.current, vec![]);
+                TronError::throw_at(
+                    "E2002",
+                    self.peek().line_number,
+                    Some(self.peek().span),
+                    vec![],
+                );
                 vec![]
             }
         };
@@ -1320,7 +1639,7 @@ impl Parser {
             let token = self.previous(1);
             return Ok(token);
         }
-        TronError::throw("E2003", line, vec![msg.to_string()]);
+        TronError::throw_at("E2003", line, Some(token.span), vec![msg.to_string()]);
         Ok(token)
     }
     /// The `check()` method is used to check if the current token is of a specific type without consuming it.
@@ -1341,6 +1660,14 @@ impl Parser {
     fn check(&mut self, typ: TokenType) -> bool {
         self.peek().token_type == typ
     }
+    /// The `check_next()` method is like `check()`, but looks at the token after the current one.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn check_next(&mut self, typ: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.token_type == typ)
+    }
     /// The `match_token()` method is used to check if the current token matches a specific type and consumes it if it does.
     ///
     /// # Parameters
@@ -1398,6 +1725,9 @@ impl Parser {
     /// ### Last Updated: (v3.0.0)
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
+            if let Some(events) = &mut self.events {
+                events.push(Event::Token(self.current));
+            }
             self.current += 1;
         }
         self.previous(1)
@@ -1427,7 +1757,6 @@ impl Parser {
     /// The `previous()` method is called internally by the `Parser` to inspect the last token that was consumed.
     ///
     /// ### Last Updated: (v3.0.0)
-
     fn previous(&mut self, steps_back: usize) -> Token {
         if self.current < steps_back {
             Token {
@@ -1435,6 +1764,7 @@ impl Parser {
                 lexeme: String::new(),
                 line_number: 0,
                 literal: None,
+                span: Span::default(),
             }
         } else {
             self.tokens[self.current - steps_back].clone()
@@ -1451,7 +1781,6 @@ impl Parser {
     /// The `is_at_end()` method is called internally by the `Parser` to determine if there are more tokens to process.
     ///
     /// ### Last Updated: (v3.0.0)
-
     fn is_at_end(&mut self) -> bool {
         self.peek().token_type == Eof
     }
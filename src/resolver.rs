@@ -1,27 +1,41 @@
-use crate::environment::Environment;
+use crate::environment::Slot;
 use crate::expressions::Expression;
-use crate::scanner::{Statement, Token};
+use crate::scanner::{Parameter, Statement, Token};
 use crate::utils::TronError;
 use std::collections::HashMap;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 enum FunctionType {
+    #[default]
     None,
     Function,
 }
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 enum LoopType {
+    #[default]
     None,
+    Loop,
+}
+/// A scope being resolved: the slot of every name declared in it, and whether its initializer has been resolved.
+///
+/// Slots are handed out in declaration order, which is also the order the interpreter defines locals in at run time,
+/// so a redeclared name gets a new slot just like it gets a new value.
+///
+/// ### Last Updated: (v3.2.0)
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    names: HashMap<String, (usize, bool)>,
+    slots: usize,
 }
 /// The `Resolver` struct in Rust is responsible for resolving symbols.
 /// It maintains a stack of scopes, tracks the current function and loop context, and manages local variables.
 ///
 /// # Fields
 ///
-/// - `scopes`: A stack of scopes, where each scope is a `HashMap` mapping variable names to a boolean indicating if the variable is initialized.
+/// - `scopes`: A stack of scopes, where each scope maps variable names to their slot and a boolean indicating if the variable is initialized.
 /// - `current_function`: The type of the current function being resolved.
 /// - `current_loop`: The type of the current loop being resolved.
-/// - `locals`: A map of local variable IDs to their scope depth.
+/// - `locals`: A map of local variable IDs to their scope depth and slot index.
 ///
 /// # Usage
 ///
@@ -31,18 +45,17 @@ enum LoopType {
 ///
 /// ```
 /// let mut Resolver::new();
-/// let mut environment = Environment::new();
 /// let statements = vec![/* ... */];
-/// let result = resolver.resolve(&statements, &mut environment);
+/// let result = resolver.resolve(&statements);
 /// ```
 ///
-/// ### Last Updated: (v3.0.0)
-#[derive(Debug, Clone)]
+/// ### Last Updated: (v3.2.0)
+#[derive(Debug, Clone, Default)]
 pub struct Resolver {
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<Scope>,
     current_function: FunctionType,
     current_loop: LoopType,
-    locals: HashMap<usize, usize>,
+    locals: HashMap<usize, Slot>,
 }
 impl Resolver {
     /// The `new()` function is a constructor for the `Resolver` struct.
@@ -72,14 +85,13 @@ impl Resolver {
             locals: HashMap::new(),
         }
     }
-    /// Resolves a single statement within the given environment.
+    /// Resolves a single statement.
     ///
     /// This method is responsible for handling various types of statements, including blocks, variable declarations, function declarations, expressions, and control flow statements like if, while, and switch. It recursively resolves nested statements and expressions, ensuring that all symbols are correctly resolved within the current scope.
     ///
     /// # Parameters
     ///
     /// - `stmt`: A reference to the statement to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the statement is being resolved.
     ///
     /// # Returns
     ///
@@ -89,7 +101,6 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let statement = Statement::VariableStatement
     /// ```
     ///
@@ -100,31 +111,27 @@ impl Resolver {
     /// - Panics if a variable is declared with a mismatched type.
     ///
     /// ### Last Updated: (v3.0.0)
-    fn resolve_internal(
-        &mut self,
-        stmt: &Statement,
-        environment: &mut Environment,
-    ) -> Result<(), String> {
+    fn resolve_internal(&mut self, stmt: &Statement) -> Result<(), String> {
         match stmt {
             Statement::BlockStatement {
                 statements: _,
                 line,
-            } => self.resolve_block(stmt, environment, *line)?,
+            } => self.resolve_block(stmt, *line)?,
             Statement::VariableStatement {
                 name: _,
                 value_type: _,
                 value: _,
                 line,
-            } => self.resolve_var(stmt, environment, *line)?,
+            } => self.resolve_var(stmt, *line)?,
             Statement::FunctionStatement {
                 name: _,
                 params: _,
                 body: _,
                 output_type: _,
                 line: _,
-            } => self.resolve_function(stmt, FunctionType::Function, environment)?,
+            } => self.resolve_function(stmt, FunctionType::Function)?,
             Statement::ExpressionStatement { expression, line } => {
-                self.resolve_expr(expression, *line, environment)?
+                self.resolve_expr(expression, *line)?
             }
             Statement::IfStatement {
                 conditions: _,
@@ -132,19 +139,17 @@ impl Resolver {
                 elif_branches: _,
                 else_branch: _,
                 line,
-            } => self.resolve_if_stmt(stmt, environment, *line)?,
-            Statement::UseStatement { expression, line } => {
-                self.resolve_expr(expression, *line, environment)?
-            }
+            } => self.resolve_if_stmt(stmt, *line)?,
+            Statement::UseStatement { expression, line } => self.resolve_expr(expression, *line)?,
             Statement::ReturnStatement {
-                keyword: _,
+                keyword,
                 value,
                 line,
             } => {
                 if self.current_function == FunctionType::None {
-                    TronError::throw("E3006", *line, vec![]);
+                    TronError::throw_at("E3006", *line, Some(keyword.span), vec![]);
                 } else if let Some(value) = value {
-                    self.resolve_expr(value, *line, environment)?;
+                    self.resolve_expr(value, *line)?;
                 }
             }
             Statement::WhileStatement {
@@ -153,13 +158,16 @@ impl Resolver {
                 line,
             } => {
                 for condition in conditions {
-                    self.resolve_expr(condition, *line, environment)?;
+                    self.resolve_expr(condition, *line)?;
                 }
-                self.resolve_internal(body.as_ref(), environment)?;
+                let enclosing_loop = self.current_loop;
+                self.current_loop = LoopType::Loop;
+                self.resolve_internal(body.as_ref())?;
+                self.current_loop = enclosing_loop;
             }
-            Statement::BreakStatement { keyword: _, line } => {
+            Statement::BreakStatement { keyword, line } => {
                 if self.current_loop == LoopType::None {
-                    TronError::throw("E3007", *line, vec![]);
+                    TronError::throw_at("E3007", *line, Some(keyword.span), vec![]);
                 }
             }
             Statement::SwitchStatement {
@@ -168,29 +176,45 @@ impl Resolver {
                 default_branch,
                 line,
             } => {
-                self.resolve_expr(condition, *line, environment)?;
-                for case_branch in case_branches {
-                    for branch in case_branch.1.clone() {
-                        self.resolve_internal(&branch, environment)?;
-                    }
+                self.resolve_expr(condition, *line)?;
+                for (case_value, case_body) in case_branches {
+                    self.resolve_expr(case_value, *line)?;
+                    self.resolve_case(case_body)?;
                 }
                 if let Some(default_branch) = default_branch {
-                    for branch in default_branch {
-                        self.resolve_internal(&branch, environment)?;
-                    }
+                    self.resolve_case(default_branch)?;
                 }
             }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                line: _,
+            } => {
+                self.resolve_internal(body.as_ref())?;
+                if let Some((name, block)) = catch {
+                    // the error gets a scope of its own, even at the top level
+                    self.begin_scope();
+                    self.declare(name)?;
+                    self.define(name);
+                    self.resolve_internal(block.as_ref())?;
+                    self.end_scope();
+                }
+                if let Some(finally) = finally {
+                    self.resolve_internal(finally.as_ref())?;
+                }
+            }
+            Statement::ThrowStatement { value, line, .. } => self.resolve_expr(value, *line)?,
         }
         Ok(())
     }
-    /// Resolves a collection of statements within the given environment.
+    /// Resolves a collection of statements.
     ///
     /// This method iterates over a collection of statements and resolves each one using the `resolve_internal` method. It's designed to handle multiple statements in sequence, ensuring that all symbols within the statements are correctly resolved within the current scope.
     ///
     /// # Parameters
     ///
     /// - `stmts`: A reference to a vector of statements to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the statements are being resolved.
     ///
     /// # Returns
     ///
@@ -200,61 +224,49 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Evnrionment::new();
     /// let statements = vec![/* ... */];
-    /// resolver.resolve_many(&statements, &mut environment)?;
+    /// resolver.resolve_many(&statements)?;
     /// ```
     ///
     /// ### Last Updated: (v3.0.0)
-    fn resolve_many(
-        &mut self,
-        stmts: &Vec<&Statement>,
-        environment: &mut Environment,
-    ) -> Result<(), String> {
+    fn resolve_many(&mut self, stmts: &Vec<&Statement>) -> Result<(), String> {
         for stmt in stmts {
-            self.resolve_internal(stmt, environment)?;
+            self.resolve_internal(stmt)?;
         }
         Ok(())
     }
-    /// Resolves a collection of statements within the given environment and returns a map of local variable IDs to their scope depth.
+    /// Resolves a collection of statements and returns a map of local variable IDs to their scope depth and slot.
     ///
     /// This method is a wrapper around `resolve_many` that also returns the `locals` map of the `Resolver` instance. It's designed to resolve multiple statements in sequence and then provide information about the local variables that were resolved.
     ///
     /// # Parameters
     ///
     /// - `stmts`: A reference to a vector of statements to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the statements are being resolved.
     ///
     /// # Returns
     ///
-    /// - `Result<HashMap<usize, usize>, String>`: Returns a `HashMap` mapping local variable IDs to their scope depth if all statements are successfully resolved, or an error message if resolution fails for any statement.
+    /// - `Result<HashMap<usize, Slot>, String>`: Returns a `HashMap` mapping local variable IDs to their `Slot` if all statements are successfully resolved, or an error message if resolution fails for any statement.
     ///
     /// # Examples
     ///
     /// ```
     /// let mut resolver = Resolve::new();
-    /// let mut environment = Environment::new();
     /// let statements = vec![/* ... */];
-    /// let result = resolver.resolve(&statements, &mut environment)?;
+    /// let result = resolver.resolve(&statements)?;
     /// ```
     ///
-    /// ### Last Updated: (v3.0.0)
-    pub fn resolve(
-        mut self,
-        stmts: &Vec<&Statement>,
-        environment: &mut Environment,
-    ) -> Result<HashMap<usize, usize>, String> {
-        self.resolve_many(stmts, environment)?;
+    /// ### Last Updated: (v3.2.0)
+    pub fn resolve(mut self, stmts: &Vec<&Statement>) -> Result<HashMap<usize, Slot>, String> {
+        self.resolve_many(stmts)?;
         Ok(self.locals)
     }
-    /// Resolves a block statement within the given environment.
+    /// Resolves a block statement.
     ///
     /// This method is responsible for handling block statements, which are essentially a collection of statements enclosed within curly braces `{}`. It begins a new scope for the block, resolves all statements within the block, and then ends the scope. This ensures that any variables declared within the block are properly scoped and do not leak into the surrounding environment.
     ///
     /// # Parameters
     ///
     /// - `stmt`: A reference to the block statement to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the block statement is being resolved.
     ///
     /// # Returns
     ///
@@ -264,11 +276,10 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let block_statement = Statement::BlockStatement {
     ///  statements: vec![/* ... */]
     /// };
-    /// resolver.resolve_block(&block_statement, &mut environment)?;
+    /// resolver.resolve_block(&block_statement)?;
     /// ```
     ///
     ///
@@ -277,36 +288,27 @@ impl Resolver {
     /// - Panics if the provided statement is not a block statement.
     ///
     /// ### Last Updated: (v3.0.0)
-    fn resolve_block(
-        &mut self,
-        stmt: &Statement,
-        environment: &mut Environment,
-        line: usize,
-    ) -> Result<(), String> {
+    fn resolve_block(&mut self, stmt: &Statement, line: usize) -> Result<(), String> {
         match stmt {
             Statement::BlockStatement {
                 statements,
                 line: _,
             } => {
                 self.begin_scope();
-                self.resolve_many(
-                    &statements.iter().map(|b| b.as_ref()).collect(),
-                    environment,
-                )?;
+                self.resolve_many(&statements.iter().map(|b| b.as_ref()).collect())?;
                 self.end_scope();
             }
             _ => TronError::throw("E3001", line, vec!["block".to_string()]),
         }
         Ok(())
     }
-    /// Resolves a variable declaration statement within the given environment.
+    /// Resolves a variable declaration statement.
     ///
-    /// This method is responsible for handling variable declarations. It declares the variable in the current scope, checks for type mismatches if a type is specified, and then defines the variable. This ensures that variables are correctly declared and initialized within the current scope.
+    /// This method is responsible for handling variable declarations. It declares the variable in the current scope, resolves its initializer, and then defines the variable. The declared type is checked by the interpreter when the initializer has been evaluated.
     ///
     /// # Parameters
     ///
     /// - `stmt`: A reference to the variable declaration statement to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the variable declaration is being resolved.
     ///
     /// # Returns
     ///
@@ -316,66 +318,39 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let variable_statement = Statement::VariableStatement {/* ... */};
-    /// resolver.resolve_var(&variable_statement, &mut environment)?;
+    /// resolver.resolve_var(&variable_statement)?;
     /// ```
     ///
     /// # Panics
     ///
     /// - Panics if the provided statement is not a variable declaration statement.
-    /// - Panics if a variable is declared with a mismatched type.
     ///
-    /// ### Last Updated: (v3.0.0)
-    fn resolve_var(
-        &mut self,
-        stmt: &Statement,
-        environment: &mut Environment,
-        line: usize,
-    ) -> Result<(), String> {
+    /// ### Last Updated: (v3.2.0)
+    fn resolve_var(&mut self, stmt: &Statement, line: usize) -> Result<(), String> {
         if let Statement::VariableStatement {
             name,
-            value_type,
+            value_type: _,
             value,
             line,
         } = stmt
         {
             self.declare(name)?;
-            let new_value = (*value).evaluate(environment.clone())?;
-            let value_clone = new_value.clone();
-
-            if value_type.lexeme == value_clone.to_type()
-                || value_type.lexeme == value_clone.to_string()
-            {
-                environment.set_value_type(name.lexeme.clone(), value_type.lexeme.clone());
-            } else {
-                TronError::throw(
-                    "E4003",
-                    *line,
-                    vec![
-                        "variable".to_string(),
-                        name.lexeme.to_string(),
-                        value_type.lexeme.to_string(),
-                        value_clone.to_type().to_string(),
-                    ],
-                );
-            }
-            self.resolve_expr(value, *line, environment)?;
+            self.resolve_expr(value, *line)?;
             self.define(name);
         } else {
             TronError::throw("E3001", line, vec!["variable".to_string()]);
         }
         Ok(())
     }
-    /// Resolves a function declaration statement within the given environment.
+    /// Resolves a function declaration statement.
     ///
-    /// This method is responsible for handling function declarations. It declares the function in the current scope, checks for type mismatches if a type is specified, and then defines the function. This ensures that functions are correctly declared and initialized within the current scope.
+    /// This method is responsible for handling function declarations. It declares and defines the function in the current scope before resolving its body, so the function can call itself, and then resolves its parameters and body in a new scope.
     ///
     /// # Parameters
     ///
     /// - `stmt`: A reference to the function declaration statement to be resolved.
     /// - `resolving_function`: The type of the function being resolved.
-    /// - `environment`: A mutable reference to the environment in which the function declaration is being resolved.
     ///
     /// # Returns
     ///
@@ -385,53 +360,68 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let function_statement = Statement::FunctionStatement {/* ... */};
-    /// resolver.resolve_function(&function_statement, FunctionType::Function, &mut environment)?;
+    /// resolver.resolve_function(&function_statement, FunctionType::Function)?;
     /// ```
     ///
     /// # Panics
     ///
     /// - Panics if the provided statement is not a function declaration statement.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn resolve_function(
         &mut self,
         stmt: &Statement,
         resolving_function: FunctionType,
-        environment: &mut Environment,
     ) -> Result<(), String> {
         if let Statement::FunctionStatement {
-            name: _,
+            name,
             params,
             body,
             output_type: _,
-            line: _,
+            line,
         } = stmt
         {
+            self.declare(name)?;
+            self.define(name);
             let enclosing_function = self.current_function;
+            let enclosing_loop = self.current_loop;
             self.current_function = resolving_function;
+            self.current_loop = LoopType::None;
             self.begin_scope();
-            for (param_name, _param_type) in params {
-                self.declare(param_name)?;
-                self.define(param_name);
-            }
-            self.resolve_many(&body.iter().map(|b| b.as_ref()).collect(), environment)?;
+            self.resolve_params(params, *line)?;
+            self.resolve_many(&body.iter().map(|b| b.as_ref()).collect())?;
             self.end_scope();
             self.current_function = enclosing_function;
+            self.current_loop = enclosing_loop;
             Ok(())
         } else {
             panic!("resolve_function called with non-function statement");
         }
     }
-    /// Resolves an if statement within the given environment.
+    /// Declares the parameters of a function in the current scope.
+    ///
+    /// Default values are resolved inside the function scope before their own parameter is declared, so a default
+    /// can refer to the parameters before it, just like it is evaluated at call time.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn resolve_params(&mut self, params: &[Parameter], line: usize) -> Result<(), String> {
+        for param in params {
+            if let Some(default) = &param.default {
+                self.resolve_expr(default, line)?;
+            }
+            self.declare(&param.name)?;
+            self.define(&param.name);
+        }
+        Ok(())
+    }
+    /// Resolves an if statement.
     ///
     /// This method is responsible for handling if statements, which are conditional branches in the code. It resolves the conditions and the branches of the if statement, ensuring that all symbols within the branches are correctly resolved within the current scope.
     ///
     /// # Parameters
     ///
     /// - `stmt`: A reference to the if statement to be resolved.
-    /// - `environment`: A mutable reference to the environment in which the if statement is being resolved.
     ///
     /// # Returns
     ///
@@ -441,8 +431,7 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolve::new();
-    /// let mut environment = Statement:IfStatement {/* ... */};
-    /// resolver.resolve_if_stmt(&if_statement, &mut environment)?;
+    /// resolver.resolve_if_stmt(&if_statement)?;
     /// ```
     ///
     /// # Panics
@@ -450,12 +439,7 @@ impl Resolver {
     /// - Panics if the provided statement is not an if statement.
     ///
     /// ### Last Updated: (v3.0.0)
-    fn resolve_if_stmt(
-        &mut self,
-        stmt: &Statement,
-        environment: &mut Environment,
-        line: usize,
-    ) -> Result<(), String> {
+    fn resolve_if_stmt(&mut self, stmt: &Statement, line: usize) -> Result<(), String> {
         if let Statement::IfStatement {
             conditions,
             then_branch: then,
@@ -465,17 +449,17 @@ impl Resolver {
         } = stmt
         {
             for condition in conditions {
-                self.resolve_expr(condition, *line, environment)?;
+                self.resolve_expr(condition, *line)?;
             }
-            self.resolve_internal(then.as_ref(), environment)?;
+            self.resolve_internal(then.as_ref())?;
             for (elif_predicates, elif_stmt) in elif_branches {
                 for elif_predicate in elif_predicates {
-                    self.resolve_expr(elif_predicate, *line, environment)?;
+                    self.resolve_expr(elif_predicate, *line)?;
                 }
-                self.resolve_internal(elif_stmt.as_ref(), environment)?;
+                self.resolve_internal(elif_stmt.as_ref())?;
             }
             if let Some(els) = els {
-                self.resolve_internal(els.as_ref(), environment)?;
+                self.resolve_internal(els.as_ref())?;
             }
             Ok(())
         } else {
@@ -483,9 +467,26 @@ impl Resolver {
             Ok(())
         }
     }
+    /// Resolves the body of a `case` or `default` branch.
+    ///
+    /// Inside a function or block each branch gets its own scope, which the interpreter mirrors, so a variable declared
+    /// in one branch never takes a slot that another branch expects. At the top level the branches declare globals.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    fn resolve_case(&mut self, body: &[Statement]) -> Result<(), String> {
+        let scoped = !self.scopes.is_empty();
+        if scoped {
+            self.begin_scope();
+        }
+        self.resolve_many(&body.iter().collect())?;
+        if scoped {
+            self.end_scope();
+        }
+        Ok(())
+    }
     /// Begins a new scope for variable resolution.
     ///
-    /// This method is called to start a new scope, which is typically used when entering a block of code. It pushes a new `HashMap` onto the `scopes` stack to track variables declared within this scope.
+    /// This method is called to start a new scope, which is typically used when entering a block of code. It pushes a new `Scope` onto the `scopes` stack to track variables declared within this scope.
     ///
     /// # Examples
     ///
@@ -496,11 +497,11 @@ impl Resolver {
     ///
     /// ### Last Updated: (v3.0.0)
     fn begin_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(Scope::default());
     }
     /// Ends the current scope for variable resolution.
     ///
-    /// This method is called to end the current scope, which is typically used when exiting a block of code. It pops the last `HashMap` from the `scopes` stack, effectively closing the scope and making any variables declared within it inaccessible.
+    /// This method is called to end the current scope, which is typically used when exiting a block of code. It pops the last `Scope` from the `scopes` stack, effectively closing the scope and making any variables declared within it inaccessible.
     ///
     /// # Examples
     ///
@@ -517,7 +518,7 @@ impl Resolver {
     }
     /// Declares a variable in the current scope.
     ///
-    /// This method is used to declare a variable in the current scope. It checks if the variable is already declared in the current scope and panics if it is. Otherwise, it adds the variable to the current scope's `HashMap`.
+    /// This method is used to declare a variable in the current scope. It checks if the variable is already declared in the current scope and reports it if it is. Either way, the variable gets the next slot of the current scope.
     ///
     /// # Parameters
     ///
@@ -539,15 +540,18 @@ impl Resolver {
     ///
     /// - Panics if a variable with the same name is already declared in the current scope.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn declare(&mut self, name: &Token) -> Result<(), String> {
-        let size = self.scopes.len();
-        if self.scopes.is_empty() {
+        let Some(scope) = self.scopes.last_mut() else {
             return Ok(());
-        } else if self.scopes[size - 1].contains_key(&name.lexeme.clone()) {
-            TronError::throw("E3004", name.line_number, vec![]);
+        };
+        if scope.names.contains_key(&name.lexeme) {
+            TronError::throw_at("E3004", name.line_number, Some(name.span), vec![]);
         }
-        self.scopes[size - 1].insert(name.lexeme.clone(), false);
+        scope
+            .names
+            .insert(name.lexeme.clone(), (scope.slots, false));
+        scope.slots += 1;
         Ok(())
     }
     /// Defines a variable in the current scope.
@@ -567,15 +571,15 @@ impl Resolver {
     /// resolver.define(&variable_name);
     /// ```
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn define(&mut self, name: &Token) {
-        if self.scopes.is_empty() {
-            return;
+        if let Some(scope) = self.scopes.last_mut() {
+            if let Some((_, defined)) = scope.names.get_mut(&name.lexeme) {
+                *defined = true;
+            }
         }
-        let size = self.scopes.len();
-        self.scopes[size - 1].insert(name.lexeme.clone(), true);
     }
-    /// Resolves an expression within the given environment.
+    /// Resolves an expression.
     ///
     /// This method is responsible for handling various types of expressions, including literals, variables, binary operations, unary operations, and function calls. It recursively resolves nested expressions, ensuring that all symbols are correctly resolved within the current scope.
     ///
@@ -591,18 +595,12 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let expression = Expressiion::Variable {id : 0, name: Token::new(TokenTYpe::Identifier, "testVar", None, 1)};
     /// resolver.resolve_expr(&expression)?;
     /// ```
     ///
     /// ### Last Updated: (v3.0.0)
-    fn resolve_expr(
-        &mut self,
-        expr: &Expression,
-        line: usize,
-        environment: &mut Environment,
-    ) -> Result<(), String> {
+    fn resolve_expr(&mut self, expr: &Expression, line: usize) -> Result<(), String> {
         match expr {
             Expression::Function {
                 id: _,
@@ -612,28 +610,24 @@ impl Resolver {
                 output_type: _,
             } => {
                 let enclosing_function = self.current_function;
+                let enclosing_loop = self.current_loop;
                 self.current_function = FunctionType::Function;
+                self.current_loop = LoopType::None;
                 self.begin_scope();
-                for (param_name, _param_type) in params {
-                    self.declare(param_name)?;
-                    self.define(param_name);
-                }
-                self.resolve_many(&body.iter().map(|b| b.as_ref()).collect(), environment)?;
+                self.resolve_params(params, line)?;
+                self.resolve_many(&body.iter().map(|b| b.as_ref()).collect())?;
                 self.end_scope();
                 self.current_function = enclosing_function;
+                self.current_loop = enclosing_loop;
                 Ok(())
             }
             Expression::Object { id: _, properties } => {
                 for (_, value_expr) in properties {
-                    self.resolve_expr(value_expr, line, environment)?;
+                    self.resolve_expr(value_expr, line)?;
                 }
                 Ok(())
             }
-            Expression::ObjectCall {
-                id: _,
-                key: _,
-                name: _,
-            } => Ok(()), //
+            Expression::ObjectCall { id, key: _, name } => self.resolve_local(name, *id),
             Expression::Variable { id: _, name: _ } => {
                 self.resolve_expr_var(expr, expr.get_id(), line)
             }
@@ -641,37 +635,43 @@ impl Resolver {
                 id: _,
                 name: _,
                 value: _,
-            } => self.resolve_expr_assign(expr, expr.get_id(), line, environment),
+            } => self.resolve_expr_assign(expr, expr.get_id(), line),
             Expression::Array { id: _, elements } => {
                 for element in elements {
-                    self.resolve_expr(element, line, environment)?;
+                    self.resolve_expr(element, line)?;
                 }
                 Ok(())
             }
+            Expression::Index { array, index, .. } => {
+                self.resolve_expr(array, line)?;
+                self.resolve_expr(index, line)
+            }
             Expression::Binary {
                 id: _,
                 left,
                 operator: _,
                 right,
             } => {
-                self.resolve_expr(left, line, environment)?;
-                self.resolve_expr(right, line, environment)
+                self.resolve_expr(left, line)?;
+                self.resolve_expr(right, line)
             }
             Expression::Call {
                 id: _,
                 callee,
                 paren: _,
                 arguments,
+                named,
             } => {
-                self.resolve_expr(callee.as_ref(), line, environment)?;
+                self.resolve_expr(callee.as_ref(), line)?;
                 for arg in arguments {
-                    self.resolve_expr(arg, line, environment)?;
+                    self.resolve_expr(arg, line)?;
+                }
+                for (_, value) in named {
+                    self.resolve_expr(value, line)?;
                 }
                 Ok(())
             }
-            Expression::Grouping { id: _, expression } => {
-                self.resolve_expr(expression, line, environment)
-            }
+            Expression::Grouping { id: _, expression } => self.resolve_expr(expression, line),
             Expression::Literal { id: _, value: _ } => Ok(()),
             Expression::Logical {
                 id: _,
@@ -679,17 +679,17 @@ impl Resolver {
                 operator: _,
                 right,
             } => {
-                self.resolve_expr(left, line, environment)?;
-                self.resolve_expr(right, line, environment)
+                self.resolve_expr(left, line)?;
+                self.resolve_expr(right, line)
             }
             Expression::Unary {
                 id: _,
                 operator: _,
                 right,
-            } => self.resolve_expr(right, line, environment),
+            } => self.resolve_expr(right, line),
         }
     }
-    /// Resolves a variable expression within the given environment.
+    /// Resolves a variable expression.
     ///
     /// This method is used to resolve a variable expression, ensuring that the variable is declared and initialized in the current scope. It also handles the resolution of function calls where the callee is a variable.
     ///
@@ -706,7 +706,6 @@ impl Resolver {
     ///
     /// ```
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let variable_expression = Expressiion::Variable {id : 0, name: Token::new(TokenTYpe::Identifier, "testVar", None, 1)};
     /// resolver.resolve_expr_var(&variable_expression, variable_expression.get_id())?;
     /// ```
@@ -724,9 +723,9 @@ impl Resolver {
     ) -> Result<(), String> {
         match expr {
             Expression::Variable { id: _, name } => {
-                if !self.scopes.is_empty() {
-                    if let Some(false) = self.scopes[self.scopes.len() - 1].get(&name.lexeme) {
-                        TronError::throw("E3003", name.line_number, vec![]);
+                if let Some(scope) = self.scopes.last() {
+                    if let Some((_, false)) = scope.names.get(variable_name(name)) {
+                        TronError::throw_at("E3003", name.line_number, Some(name.span), vec![]);
                     }
                 }
                 self.resolve_local(name, resolve_id)
@@ -736,8 +735,9 @@ impl Resolver {
                 callee,
                 paren: _,
                 arguments: _,
+                named: _,
             } => match callee.as_ref() {
-                Expression::Variable { id: _, name } => self.resolve_local(&name, resolve_id),
+                Expression::Variable { id: _, name } => self.resolve_local(name, resolve_id),
                 _ => {
                     TronError::throw("E3001", line, vec!["variable".to_string()]);
                     Ok(())
@@ -749,9 +749,9 @@ impl Resolver {
             }
         }
     }
-    /// Resolves a local variable within the given environment.
+    /// Resolves a local variable.
     ///
-    /// This method is used to resolve a local variable, ensuring that the variable is declared and initialized in the current scope. It also handles the resolution of function calls where the callee is a variable.
+    /// This method is used to resolve a local variable, recording how many scopes out it was declared and its slot in that scope. Names that are not declared in any scope are globals and are looked up by name at run time. For `object.key` only the object is resolved.
     ///
     /// # Parameters
     ///
@@ -770,22 +770,24 @@ impl Resolver {
     /// resolver.resolve_local(&variable_name, variable_name.get_id())?
     /// ```
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn resolve_local(&mut self, name: &Token, resolve_id: usize) -> Result<(), String> {
-        let size = self.scopes.len();
-        if size == 0 {
-            return Ok(());
-        }
-        for i in (0..=(size - 1)).rev() {
-            let scope = &self.scopes[i];
-            if scope.contains_key(&name.lexeme) {
-                self.locals.insert(resolve_id, size - 1 - i);
+        let name = variable_name(name);
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some((index, _)) = scope.names.get(name) {
+                self.locals.insert(
+                    resolve_id,
+                    Slot {
+                        depth,
+                        index: *index,
+                    },
+                );
                 return Ok(());
             }
         }
         Ok(())
     }
-    /// Resolves an assignment expression within the given environment.
+    /// Resolves an assignment expression.
     ///
     /// This method is used to resolve an assignment expression, ensuring that the variable being assigned to is declared and initialized in the current scope. It also handles the resolution of function calls where the callee is a variable.
     ///
@@ -803,7 +805,6 @@ impl Resolver {
     /// ```
     ///
     /// let mut resolver = Resolver::new();
-    /// let mut environment = Environment::new();
     /// let assignment_expression = Expression::Assign { /* ... */ };
     /// resolver.resolve_expr_assign(&assignment_expression, assignment_expression.get_id())?;
     /// ```
@@ -818,10 +819,9 @@ impl Resolver {
         expr: &Expression,
         resolve_id: usize,
         line: usize,
-        environment: &mut Environment,
     ) -> Result<(), String> {
         if let Expression::Assign { id: _, name, value } = expr {
-            self.resolve_expr(value.as_ref(), line, environment)?;
+            self.resolve_expr(value.as_ref(), line)?;
             self.resolve_local(name, resolve_id)?;
         } else {
             TronError::throw("E3001", line, vec!["assign".to_string()]);
@@ -829,3 +829,9 @@ impl Resolver {
         Ok(())
     }
 }
+/// The name a variable expression refers to: `object.key` reads the variable `object`.
+///
+/// ### Last Updated: (v3.2.0)
+fn variable_name(name: &Token) -> &str {
+    name.lexeme.split('.').next().unwrap_or(&name.lexeme)
+}
@@ -0,0 +1,179 @@
+// limits and capabilities for running code that isn't trusted, set with `--sandbox`, `--timeout`,
+// `--max-steps` and `--max-memory` on the command line or `Interpreter::set_sandbox` when embedding
+use crate::utils::options::options;
+use crate::utils::TronError;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+// what a program may reach outside the interpreter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    // `@cmd`
+    pub cmd: bool,
+    // `use` of library files
+    pub fs: bool,
+    // `@args`
+    pub env: bool,
+    // `@ask`
+    pub ask: bool,
+}
+
+impl Capabilities {
+    pub const ALL: Self = Self {
+        cmd: true,
+        fs: true,
+        env: true,
+        ask: true,
+    };
+    pub const NONE: Self = Self {
+        cmd: false,
+        fs: false,
+        env: false,
+        ask: false,
+    };
+
+    // a comma separated list such as `fs,env`
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut capabilities = Self::NONE;
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            match name {
+                "cmd" => capabilities.cmd = true,
+                "fs" => capabilities.fs = true,
+                "env" => capabilities.env = true,
+                "ask" => capabilities.ask = true,
+                _ => return Err(format!("unknown capability {}", name)),
+            }
+        }
+        Ok(capabilities)
+    }
+
+    pub fn allows(&self, capability: &str) -> bool {
+        match capability {
+            "cmd" => self.cmd,
+            "fs" => self.fs,
+            "env" => self.env,
+            "ask" => self.ask,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sandbox {
+    // statements executed, including those in function calls
+    pub max_steps: Option<usize>,
+    // wall-clock time, counted from the first statement after `start`
+    pub timeout: Option<Duration>,
+    // bytes the heap of the whole process may grow to, see `memory::CountingAllocator`
+    pub max_memory: Option<usize>,
+    pub capabilities: Capabilities,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            timeout: None,
+            max_memory: None,
+            capabilities: Capabilities::ALL,
+        }
+    }
+}
+
+impl Sandbox {
+    // no capabilities; limits are left unset for the caller to choose
+    pub fn strict() -> Self {
+        Self {
+            capabilities: Capabilities::NONE,
+            ..Self::default()
+        }
+    }
+}
+
+thread_local! {
+    static STEPS: Cell<usize> = const { Cell::new(0) };
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+// restarts the step count and the clock
+pub fn start() {
+    STEPS.with(|steps| steps.set(0));
+    DEADLINE.with(|deadline| deadline.set(None));
+}
+
+// counts a statement towards `max_steps` and checks the clock
+pub(crate) fn step(line: usize) {
+    let sandbox = options().sandbox;
+    if let Some(max_steps) = sandbox.max_steps {
+        let steps = STEPS.with(|steps| {
+            steps.set(steps.get() + 1);
+            steps.get()
+        });
+        if steps > max_steps {
+            TronError::throw("E4022", line, vec![max_steps.to_string()]);
+        }
+    }
+    if sandbox.timeout.is_some() {
+        check_time(line);
+    }
+}
+
+// time left before `timeout`, `None` when there is no timeout
+pub(crate) fn remaining() -> Option<Duration> {
+    let timeout = options().sandbox.timeout?;
+    // the clock starts at the first check after `start`
+    let deadline = DEADLINE.with(|deadline| {
+        let value = deadline.get().unwrap_or_else(|| Instant::now() + timeout);
+        deadline.set(Some(value));
+        value
+    });
+    Some(deadline.saturating_duration_since(Instant::now()))
+}
+
+pub(crate) fn check_time(line: usize) {
+    if let Some(timeout) = options().sandbox.timeout {
+        if remaining() == Some(Duration::ZERO) {
+            TronError::throw("E4029", line, vec![format_duration(timeout)]);
+        }
+    }
+}
+
+// stops the program when the sandbox doesn't grant `capability`, which `function` needs; false
+// then, and embedded, where stopping returns, the caller must not go on to use it
+pub(crate) fn require(capability: &str, function: &str, line: usize) -> bool {
+    let allowed = options().sandbox.capabilities.allows(capability);
+    if !allowed {
+        TronError::throw(
+            "E4030",
+            line,
+            vec![function.to_string(), capability.to_string()],
+        );
+    }
+    allowed
+}
+
+// `250ms`, `2s`, `1m`, or a number of seconds
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let (digits, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let amount: u64 = digits.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(amount)),
+        "s" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_secs(amount.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    match duration.as_millis() {
+        millis if millis % 1000 == 0 => format!("{}s", millis / 1000),
+        millis => format!("{}ms", millis),
+    }
+}
@@ -57,6 +57,10 @@ pub fn get_keywords_hashmap() -> HashMap<&'static str, TokenType> {
         ("switch", Switch),
         ("case", Case),
         ("default", Default),
+        ("try", Try),
+        ("catch", Catch),
+        ("finally", Finally),
+        ("throw", Throw),
     ])
 }
 /// Enum list of tokens (`TokenType`) used in the interpreter.
@@ -98,6 +102,8 @@ pub enum TokenType {
     Comma,
     /// - `Dot`: Represents the dot symbol (`.`).
     Dot,
+    /// - `Ellipsis`: Represents the rest parameter marker (`...`).
+    Ellipsis,
     /// - `Minus`: Represents the minus symbol (`-`).
     Minus,
     /// - `Plus`: Represents the plus symbol (`+`).
@@ -180,12 +186,38 @@ pub enum TokenType {
     Case,
     /// - `Default`: Represents the `default` keyword.
     Default,
+    /// - `Try`: Represents the `try` keyword.
+    Try,
+    /// - `Catch`: Represents the `catch` keyword.
+    Catch,
+    /// - `Finally`: Represents the `finally` keyword.
+    Finally,
+    /// - `Throw`: Represents the `throw` keyword.
+    Throw,
     /// - `Question`: Represents the questioan mark (`?`).
     Question,
     /// - `Line`: Represents the line Symbold (`|`)
     Line,
+    /// - `Comment`: Represents a `//` comment, or the `#!` line, which only `scan_with_comments()` and
+    ///   `scan_lossless()` return.
+    Comment,
+    /// - `Whitespace`: Represents spaces, tabs and carriage returns, which only `scan_lossless()` returns.
+    Whitespace,
+    /// - `Newline`: Represents a line break (`\n` or `\r\n`), which only `scan_lossless()` returns.
+    Newline,
+    /// - `Unknown`: Represents text the scanner couldn't read as a token, which only `scan_lossless()` returns.
+    Unknown,
 }
 use TokenType::*;
+impl TokenType {
+    /// The `is_trivia()` method tells whether a token only matters to people reading the code: comments, whitespace
+    /// and line breaks.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Comment | Whitespace | Newline)
+    }
+}
 impl std::fmt::Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -234,6 +266,30 @@ pub enum LiteralValue {
 }
 use LiteralValue::*;
 
+/// The `Parameter` struct represents a single parameter of a function declaration or function expression.
+///
+/// # Fields
+///
+/// - `name`: The `Token` holding the parameter name.
+/// - `type_token`: The `Token` holding the declared type of the parameter.
+/// - `default`: The expression evaluated when a call leaves the parameter out, if any.
+/// - `rest`: Whether the parameter collects all remaining positional arguments into an array (`...items: array`).
+///
+/// # Example
+///
+/// ```
+/// fn greet(name: string, greeting: string = "hi", ...rest: array): string = greeting + " " + name;
+/// ```
+///
+/// ### Last Updated: (v3.2.0)
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: Token,
+    pub type_token: Token,
+    pub default: Option<Expression>,
+    pub rest: bool,
+}
+
 #[derive(Debug, Clone)]
 pub enum Statement {
     /// The `ExpressionStatement` variant in the `Statement` enum represents a statement in the code that
@@ -442,7 +498,7 @@ pub enum Statement {
     /// # Fields
     ///
     /// - `name`: This field holds the `Token` that represents the name of the function being declared.
-    /// - `params`: This field is a vector of tuples, where each tuple contains a `Token` representing the name of a parameter and `Token` representing the type of the parameter.
+    /// - `params`: This field is a vector of `Parameter`s, each holding the name and type of a parameter, its default value and whether it collects the remaining arguments.
     /// - `body`: This field holds a vector of boxed `Statement` enums, which represent the sequence of statements that make up the body of the function.
     /// - `output_type`: `Token` that represents the return type of the function.
     /// - `line`: This field represents the line number in the source code where the statement was found.
@@ -465,7 +521,7 @@ pub enum Statement {
     /// ### Last Updated: (v3.1.0)
     FunctionStatement {
         name: Token,
-        params: Vec<(Token, Token)>,
+        params: Vec<Parameter>,
         body: Vec<Box<Statement>>,
         output_type: Token,
         line: usize,
@@ -498,6 +554,7 @@ pub enum Statement {
     ///
     /// ### Last Updated: (v3.0.0)
     ReturnStatement {
+        #[allow(dead_code)]
         keyword: Token,
         value: Option<Expression>,
         line: usize,
@@ -532,7 +589,11 @@ pub enum Statement {
     /// - `line`: This field represents the line number in the source code where the statement was found.
     ///
     /// ### Last Updated: (v3.0.0)
-    BreakStatement { keyword: Token, line: usize },
+    BreakStatement {
+        #[allow(dead_code)]
+        keyword: Token,
+        line: usize,
+    },
     /// The `SwitchStatement` variant in the `Statement` enum represents a switch statement.
     ///
     /// A switch statement is used to perform different actions based on different conditions. It evaluates an expression and executes the corresponding case block.
@@ -574,6 +635,82 @@ pub enum Statement {
         default_branch: Option<Vec<Statement>>,
         line: usize,
     },
+    /// The `TryStatement` variant in the `Statement` enum represents a try statement.
+    ///
+    /// A try statement runs its body and, if an error is thrown inside it, hands the error to the catch block. The finally block
+    /// runs however the statement is left: normally, through an error, or by `break` or `return`.
+    ///
+    /// # Fields
+    ///
+    /// - `body`: This field holds the `Statement` (a block) that is run first.
+    /// - `catch`: This field holds the name the error is bound to and the block that handles it, if there is a catch clause.
+    /// - `finally`: This field holds the block that always runs last, if there is a finally clause.
+    /// - `line`: This field represents the line number in the source code where the statement was found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// try {
+    ///  @cmd("missing-program");
+    /// } catch (e) {
+    ///  @print(e.code);
+    /// } finally {
+    ///  @print("done");
+    /// }
+    /// ```
+    ///
+    /// A try statement needs at least one of its catch and finally clauses.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    TryStatement {
+        body: Box<Statement>,
+        catch: Option<(Token, Box<Statement>)>,
+        finally: Option<Box<Statement>>,
+        line: usize,
+    },
+    /// The `ThrowStatement` variant in the `Statement` enum represents a throw statement.
+    ///
+    /// A throw statement raises an error, which unwinds to the nearest enclosing try statement. The value is either an error
+    /// made with `@error` or a string, which becomes the message of a new error.
+    ///
+    /// # Fields
+    ///
+    /// - `keyword`: This field holds the `Token` that represents the `throw` keyword.
+    /// - `value`: This field holds the `Expression` whose value is thrown.
+    /// - `line`: This field represents the line number in the source code where the statement was found.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    ThrowStatement {
+        keyword: Token,
+        value: Expression,
+        line: usize,
+    },
+}
+impl Statement {
+    /// The `get_line()` method returns the source line a statement starts on.
+    ///
+    /// # Usage
+    ///
+    /// Used by the interpreter to attach a line to runtime diagnostics that are raised per statement,
+    /// such as the `--max-steps` limit.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn get_line(&self) -> usize {
+        match self {
+            Statement::ExpressionStatement { line, .. }
+            | Statement::UseStatement { line, .. }
+            | Statement::VariableStatement { line, .. }
+            | Statement::BlockStatement { line, .. }
+            | Statement::WhileStatement { line, .. }
+            | Statement::IfStatement { line, .. }
+            | Statement::FunctionStatement { line, .. }
+            | Statement::ReturnStatement { line, .. }
+            | Statement::BreakStatement { line, .. }
+            | Statement::SwitchStatement { line, .. }
+            | Statement::TryStatement { line, .. }
+            | Statement::ThrowStatement { line, .. } => *line,
+        }
+    }
 }
 
 /// The `Token` struct in Rust represents a token in the Tron.
@@ -586,6 +723,7 @@ pub enum Statement {
 /// - `lexeme`: This field holds the `String` that represents the actual text of the token in the source code.
 /// - `literal`: This field is an optional `LiteralValue` enum variant that represents the literal value associated with the token, if any.
 /// - `line_number`: This field holds the `usize` that represents the line number in the source code where the token was found.
+/// - `span`: This field holds the byte range of the token in the source code.
 ///
 /// # Example
 ///
@@ -594,7 +732,8 @@ pub enum Statement {
 ///  token_type: TokenType::Identifier,
 ///  lexeme: "x".to_string(),
 ///  literal: None,
-///  line_number: 1
+///  line_number: 1,
+///  span: Span { start: 4, end: 5 },
 /// }
 /// ```
 ///
@@ -606,13 +745,26 @@ pub enum Statement {
 /// Each token is categorized by its type, which can be a keyword, operator, identifier, literal, or other types of tokens.
 /// The `literal` field is used to store the actual value of literals, such as numbers or strings, while the `lexeme` field stores the text of the token.
 ///
-/// ### Last Updated: (v3.0.0)
+/// ### Last Updated: (v3.2.0)
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: Option<LiteralValue>,
     pub line_number: usize,
+    pub span: Span,
+}
+/// The `Span` struct is the byte range `start..end` a token covers in the source code.
+///
+/// Both offsets are byte positions and always fall on character boundaries, so `&source[span.start..span.end]` is the
+/// token's text even when the source contains non-ASCII characters. Tokens that the parser makes up, and the `Eof`
+/// token, have an empty span at the position they stand for.
+///
+/// ### Last Updated: (v3.2.0)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
 }
 impl Token {
     pub fn to_string(&self) -> String {
@@ -629,8 +781,8 @@ impl Token {
 ///
 /// - `source`: This field holds the source code as a `String`.
 /// - `tokens`: This field is a vector of `Token` enums that will hold the tokens generated by the scanner.
-/// - `start`: This field holds the starting position of the current token being scanned.
-/// - `current`: This field holds the current position in the source code.
+/// - `start`: This field holds the byte offset where the current token being scanned starts.
+/// - `current`: This field holds the byte offset of the next character to be scanned.
 /// - `line`: This field holds the current line number in the source code.
 /// - `keywords`: This field is a `HashMap` that maps keywords to their corresponding `TokenType` values.
 ///
@@ -649,7 +801,10 @@ impl Token {
 ///
 /// In this example, a `Scanner` is created with the source code `"let x = 5;"`, and then the `scan_tokens` method is called to tokenize the code.
 ///
-/// ### Last Updated: (v3.0.0)
+/// The scanner walks the source with a byte cursor and decodes one character at a time, so scanning is linear in the
+/// size of the source and never slices through a multi-byte character.
+///
+/// ### Last Updated: (v3.2.0)
 #[derive(Debug, Clone)]
 pub struct Scanner {
     source: String,
@@ -658,6 +813,7 @@ pub struct Scanner {
     current: usize,
     line: usize,
     keywords: HashMap<&'static str, TokenType>,
+    comments: Vec<Token>,
 }
 impl Scanner {
     /// The `new()` function is a constructor for the `Scanner` struct.
@@ -699,6 +855,7 @@ impl Scanner {
             current: 0,
             line: 1,
             keywords: get_keywords_hashmap(),
+            comments: vec![],
         }
     }
     /// The `scan_tokens()` method of the `Scanner` struct is responsible for tokenizing the source code of the Tron language.
@@ -727,13 +884,47 @@ impl Scanner {
     /// ```
     ///
     /// In this example, the `scan_tokens()` method is called on a `Scanner` instance to tokenize the source code `"let x = 5;"`.
-    pub fn scan_tokens(mut self) -> Result<Vec<Token>, String> {
+    ///
+    /// A `#!` interpreter line at the very start of the source (`#!/usr/bin/env tron`) is skipped, so
+    /// Tron scripts can be made executable.
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn scan_tokens(self) -> Result<Vec<Token>, String> {
+        self.scan_with_comments().map(|(tokens, _)| tokens)
+    }
+    /// The `scan_with_comments()` method tokenizes the source like `scan_tokens()`, and also returns its `//` comments.
+    ///
+    /// Comments never reach the parser. They come back as a separate list of `Comment` tokens, in source order, for
+    /// tools that have to keep them, such as the formatter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let (tokens, comments) = Scanner::new("let x: number = 5; // five").scan_with_comments()?;
+    /// assert_eq!(comments[0].lexeme, "// five");
+    /// ```
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn scan_with_comments(mut self) -> Result<(Vec<Token>, Vec<Token>), String> {
+        if self.source.starts_with("#!") {
+            while !self.is_at_end() && self.peek() != '\n' {
+                self.advance();
+            }
+        }
         while !self.is_at_end() {
             self.start = self.current;
             match self.scan_token() {
                 Ok(_) => (),
                 Err(msg) => {
-                    TronError::throw("E1004", self.line, vec![msg]);
+                    TronError::throw_at(
+                        "E1004",
+                        self.line,
+                        Some(Span {
+                            start: self.start,
+                            end: self.current,
+                        }),
+                        vec![msg],
+                    );
                 }
             }
         }
@@ -742,8 +933,81 @@ impl Scanner {
             lexeme: "".to_string(),
             literal: None,
             line_number: self.line,
+            span: Span {
+                start: self.current,
+                end: self.current,
+            },
         });
-        Ok(self.tokens)
+        Ok((self.tokens, self.comments))
+    }
+    /// The `scan_lossless()` method of the `Scanner` struct tokenizes the source code without dropping anything.
+    ///
+    /// Alongside the tokens the parser reads, it returns the trivia between them: `Whitespace`, `Newline` and
+    /// `Comment` tokens (the `#!` line included), and `Unknown` tokens for any text the scanner rejected. Joining the
+    /// lexemes of every token, in order, gives back the source byte for byte, which is what the concrete syntax tree
+    /// in `syntax.rs` is built on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let tokens = Scanner::new("let x: number = 5; // five").scan_lossless()?;
+    /// let text: String = tokens.iter().map(|token| token.lexeme.as_str()).collect();
+    /// assert_eq!(text, "let x: number = 5; // five");
+    /// ```
+    ///
+    /// ### Last Updated: (v3.2.0)
+    pub fn scan_lossless(self) -> Result<Vec<Token>, String> {
+        let source = self.source.clone();
+        let (tokens, comments) = self.scan_with_comments()?;
+        let mut pieces = tokens;
+        pieces.extend(comments);
+        pieces.sort_by_key(|token| token.span.start);
+        let mut lossless = vec![];
+        let mut line = 1;
+        let mut offset = 0;
+        for piece in pieces {
+            let gap = &source[offset..piece.span.start];
+            let mut rest = gap;
+            while !rest.is_empty() {
+                let start = offset + gap.len() - rest.len();
+                let (token_type, length) = if start == 0 && rest.starts_with("#!") {
+                    (Comment, rest.find('\n').unwrap_or(rest.len()))
+                } else if rest.starts_with('\n') {
+                    (Newline, 1)
+                } else if rest.starts_with("\r\n") {
+                    (Newline, 2)
+                } else if rest.starts_with([' ', '\t', '\r']) {
+                    let bytes = rest.as_bytes();
+                    let mut length = 0;
+                    while length < bytes.len()
+                        && (matches!(bytes[length], b' ' | b'\t')
+                            || bytes[length] == b'\r' && bytes.get(length + 1) != Some(&b'\n'))
+                    {
+                        length += 1;
+                    }
+                    (Whitespace, length.max(1))
+                } else {
+                    let length = rest.find(|c: char| c.is_whitespace()).unwrap_or(rest.len());
+                    (Unknown, length)
+                };
+                lossless.push(Token {
+                    token_type,
+                    lexeme: rest[..length].to_string(),
+                    literal: None,
+                    line_number: line,
+                    span: Span {
+                        start,
+                        end: start + length,
+                    },
+                });
+                line += usize::from(token_type == Newline);
+                rest = &rest[length..];
+            }
+            line = line.max(piece.line_number);
+            offset = piece.span.end;
+            lossless.push(piece);
+        }
+        Ok(lossless)
     }
     /// The `scan_token()` method of the `Scanner` struct is responsible for scanning a single token from the source code.
     ///
@@ -772,7 +1036,15 @@ impl Scanner {
             ',' => self.add_token(Comma),
             '%' => self.add_token(Percent),
             ':' => self.add_token(Colon),
-            '.' => self.add_token(Dot),
+            '.' => {
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    self.advance();
+                    self.advance();
+                    self.add_token(Ellipsis);
+                } else {
+                    self.add_token(Dot);
+                }
+            }
             '&' => self.add_token(And),
             '|' => {
                 let token = if self.char_match('|') { Or } else { Line };
@@ -839,6 +1111,16 @@ impl Scanner {
                         }
                         self.advance();
                     }
+                    self.comments.push(Token {
+                        token_type: Comment,
+                        lexeme: self.source[self.start..self.current].to_string(),
+                        literal: None,
+                        line_number: self.line,
+                        span: Span {
+                            start: self.start,
+                            end: self.current,
+                        },
+                    });
                 } else {
                     self.add_token(Slash);
                 }
@@ -847,15 +1129,22 @@ impl Scanner {
             '\n' => {
                 self.line += 1;
             }
-            '"' => self.string()?,
-            '\'' => self.string()?,
+            '"' | '\'' => self.string(c)?,
             c => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.number()?;
                 } else if c.is_alphabetic() || c == '@' || c == '_' || c == '$' {
                     self.identifier();
                 } else {
-                    TronError::throw("E1002", self.current, vec![c.to_string()]);
+                    TronError::throw_at(
+                        "E1002",
+                        self.line,
+                        Some(Span {
+                            start: self.start,
+                            end: self.current,
+                        }),
+                        vec![c.to_string()],
+                    );
                 }
             }
         }
@@ -899,12 +1188,12 @@ impl Scanner {
     ///
     /// ### Last Updated: (v3.0.0)
     fn number(&mut self) -> Result<(), String> {
-        while self.peek().is_digit(10) {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             self.advance();
-            while self.peek().is_digit(10) {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
@@ -938,12 +1227,11 @@ impl Scanner {
     /// - The method is designed to be safe and will not panic if called at the end of the source code. Instead, it will return a null character (`'\0'`).
     /// - This method is a crucial part of the scanner's functionality, as it allows the scanner to make decisions based on the upcoming characters in the source code.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-        self.source.chars().nth(self.current + 1).unwrap()
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
     /// The `string` method of the `Scanner` struct is responsible for scanning a string literal from the source code.
     ///
@@ -961,21 +1249,31 @@ impl Scanner {
     ///
     /// # Notes
     ///
-    /// - The `string` method handles the scanning of string literals enclosed in double quotes (`"`).
-    /// - It ensures that the string literal is properly terminated by a double quote. If the end of the source code is reached before a closing double quote is found, the method will panic with an "unterminated string" error.
+    /// - The `string` method handles the scanning of string literals enclosed in double (`"`) or single (`'`) quotes.
+    /// - It ensures that the string literal is properly terminated by the same quote it was opened with. If the end of the source code is reached before a closing double quote is found, the method will panic with an "unterminated string" error.
     /// - The method increments the line number if a newline character (`\n`) is encountered within the string literal.
     /// - The scanned string literal is added to the list of tokens with its associated literal value.
     ///
-    /// ### Last Updated: (v3.0.0)
-    fn string(&mut self) -> Result<(), String> {
-        while self.peek() != '"' && !self.is_at_end() {
+    /// ### Last Updated: (v3.2.0)
+    fn string(&mut self, quote: char) -> Result<(), String> {
+        while self.peek() != quote && !self.is_at_end() {
             if self.peek() == '\n' {
                 self.line += 1;
             }
             self.advance();
         }
         if self.is_at_end() {
-            TronError::throw("E1001", self.current, vec![]);
+            TronError::throw_at(
+                "E1001",
+                self.line,
+                Some(Span {
+                    start: self.start,
+                    end: self.current,
+                }),
+                vec![],
+            );
+            // only reached while errors are collected, as E1001 ends the program
+            return Ok(());
         }
         self.advance();
         let value = &self.source[self.start + 1..self.current - 1];
@@ -1024,12 +1322,9 @@ impl Scanner {
     /// - The method is designed to be safe and will not panic if called at the end of the source code. Instead, it will return a null character (`'\0'`).
     /// - This method is a crucial part of the scanner's functionality, as it allows the scanner to make decisions based on the upcoming characters in the source code.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        self.source.chars().nth(self.current).unwrap()
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
     /// The `char_match` method of the `Scanner` struct is used to check if the next character in the source code matches a specified character.
     ///
@@ -1051,12 +1346,12 @@ impl Scanner {
     /// - The method is designed to be safe and will not panic if called at the end of the source code. Instead, it will return `false`.
     /// - This method is a crucial part of the scanner's functionality, as it allows the scanner to make decisions based on the upcoming characters in the source code.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn char_match(&mut self, ch: char) -> bool {
-        if self.is_at_end() || self.source.chars().nth(self.current).unwrap() != ch {
+        if self.is_at_end() || self.peek() != ch {
             return false;
         }
-        self.current += 1;
+        self.current += ch.len_utf8();
         true
     }
     /// The `advance` method of the `Scanner` struct is used to move the current position in the source code to the next character.
@@ -1075,12 +1370,12 @@ impl Scanner {
     /// # Notes
     ///
     /// - The `advance` method is a crucial part of the scanner's functionality, as it allows the scanner to progress through the source code character by character.
-    /// - It is used to update the current position in the source code, which is essential for the scanning process.
+    /// - The position moves by the UTF-8 length of the character, so it always stays on a character boundary.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn advance(&mut self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap();
-        self.current += 1;
+        let c = self.peek();
+        self.current += c.len_utf8();
         c
     }
     /// The `add_token` method of the `Scanner` struct is used to add a token to the list of tokens that have been scanned from the source code.
@@ -1124,7 +1419,7 @@ impl Scanner {
     /// - The `add_token_lit` method is a crucial part of the scanner's functionality, as it allows the scanner to build a list of tokens with associated literal values that can be used by the parser to construct the abstract syntax tree (AST).
     /// - It is used to ensure that the scanner's output is a sequence of tokens that accurately represents the source code, including any literal values that are part of the tokens.
     ///
-    /// ### Last Updated: (v3.0.0)
+    /// ### Last Updated: (v3.2.0)
     fn add_token_lit(&mut self, token_type: TokenType, literal: Option<LiteralValue>) {
         let text = self.source[self.start..self.current].to_string();
         self.tokens.push(Token {
@@ -1132,6 +1427,10 @@ impl Scanner {
             lexeme: text,
             literal,
             line_number: self.line,
+            span: Span {
+                start: self.start,
+                end: self.current,
+            },
         });
     }
 }
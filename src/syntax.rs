@@ -0,0 +1,288 @@
+// A concrete syntax tree: every byte of the source, comments and whitespace included, in a tree of
+// the nodes the parser built
+//
+// The parser records where each node starts and ends while it parses the significant tokens; the
+// tree builder then lays those nodes over the lossless token stream. Trivia in front of a node
+// belongs to its parent, so a node's span starts at its first token. Joining the lexemes of every
+// token in the tree gives back the source unchanged, and parsing its significant tokens gives
+// back the statements the interpreter runs.
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Span, Statement, Token, TokenType};
+use crate::utils::{collect_errors, TronError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    File,
+    VariableStatement,
+    FunctionStatement,
+    ParameterList,
+    Parameter,
+    BlockStatement,
+    UseStatement,
+    IfStatement,
+    WhileStatement,
+    ForStatement,
+    ReturnStatement,
+    BreakStatement,
+    SwitchStatement,
+    CaseBranch,
+    DefaultBranch,
+    TryStatement,
+    CatchClause,
+    FinallyClause,
+    ThrowStatement,
+    ExpressionStatement,
+    Assign,
+    Logical,
+    Binary,
+    Unary,
+    Call,
+    ArgumentList,
+    NamedArgument,
+    Grouping,
+    Literal,
+    Variable,
+    Index,
+    ObjectCall,
+    Array,
+    Object,
+    Property,
+    FunctionExpression,
+    // a token the parser skipped because no expression can start with it
+    Error,
+}
+
+impl SyntaxKind {
+    pub fn is_statement(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::VariableStatement
+                | SyntaxKind::FunctionStatement
+                | SyntaxKind::BlockStatement
+                | SyntaxKind::UseStatement
+                | SyntaxKind::IfStatement
+                | SyntaxKind::WhileStatement
+                | SyntaxKind::ForStatement
+                | SyntaxKind::ReturnStatement
+                | SyntaxKind::BreakStatement
+                | SyntaxKind::SwitchStatement
+                | SyntaxKind::TryStatement
+                | SyntaxKind::ThrowStatement
+                | SyntaxKind::ExpressionStatement
+        )
+    }
+}
+
+// what a recording parser logs; `Token` holds the index of a significant token
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Start(SyntaxKind),
+    Token(usize),
+    Finish,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+impl SyntaxElement {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span,
+            SyntaxElement::Token(token) => token.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub span: Span,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    // the source the node covers, as written
+    pub fn text(&self) -> String {
+        self.tokens()
+            .iter()
+            .map(|token| token.lexeme.as_str())
+            .collect()
+    }
+
+    // every token under the node, trivia included, in source order
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = vec![];
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // the innermost node holding `offset`
+    pub fn node_at(&self, offset: usize) -> &SyntaxNode {
+        match self
+            .nodes()
+            .find(|node| node.span.start <= offset && offset < node.span.end)
+        {
+            Some(node) => node.node_at(offset),
+            None => self,
+        }
+    }
+
+    // one line per node and token, indented by depth, for tests and debugging
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        self.dump_into(&mut out, 0);
+        out
+    }
+
+    fn dump_into(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!(
+            "{}{:?}@{}..{}\n",
+            "  ".repeat(depth),
+            self.kind,
+            self.span.start,
+            self.span.end
+        ));
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.dump_into(out, depth + 1),
+                SyntaxElement::Token(token) => out.push_str(&format!(
+                    "{}{:?}@{}..{} {:?}\n",
+                    "  ".repeat(depth + 1),
+                    token.token_type,
+                    token.span.start,
+                    token.span.end,
+                    token.lexeme
+                )),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+    // what scanning and parsing reported; the tree still holds all of the source
+    pub errors: Vec<TronError>,
+}
+
+impl SyntaxTree {
+    pub fn parse(source: &str) -> SyntaxTree {
+        let ((tokens, events), errors) = collect_errors(|| {
+            let tokens = Scanner::new(source).scan_lossless().unwrap_or_default();
+            let mut parser = Parser::recording(significant(&tokens).cloned().collect());
+            let _ = parser.parse();
+            (tokens, parser.take_events())
+        });
+        let mut builder = Builder {
+            tokens: &tokens,
+            positions: (0..tokens.len())
+                .filter(|&index| is_significant(&tokens[index]))
+                .collect(),
+            next: 0,
+            stack: vec![(SyntaxKind::File, vec![])],
+        };
+        for event in events {
+            builder.event(event);
+        }
+        builder.flush(tokens.len());
+        let (_, children) = builder.stack.swap_remove(0);
+        SyntaxTree {
+            root: SyntaxNode {
+                kind: SyntaxKind::File,
+                span: Span {
+                    start: 0,
+                    end: source.len(),
+                },
+                children,
+            },
+            errors,
+        }
+    }
+
+    // the statements the source parses to, read off the tree
+    pub fn statements(&self) -> Vec<Statement> {
+        let tokens = self.root.tokens();
+        let tokens = significant(tokens).cloned().collect();
+        collect_errors(|| Parser::new(tokens).parse().unwrap_or_default()).0
+    }
+}
+
+fn is_significant(token: &Token) -> bool {
+    !token.token_type.is_trivia() && token.token_type != TokenType::Unknown
+}
+
+fn significant<'a>(tokens: impl IntoIterator<Item = &'a Token>) -> impl Iterator<Item = &'a Token> {
+    tokens.into_iter().filter(|token| is_significant(token))
+}
+
+struct Builder<'a> {
+    tokens: &'a [Token],
+    // where each significant token is in `tokens`
+    positions: Vec<usize>,
+    next: usize,
+    stack: Vec<(SyntaxKind, Vec<SyntaxElement>)>,
+}
+
+impl Builder<'_> {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(kind) => {
+                // trivia before the node's first token stays outside it
+                let first = self.tokens[self.next..]
+                    .iter()
+                    .position(is_significant)
+                    .map_or(self.tokens.len(), |offset| self.next + offset);
+                self.flush(first);
+                self.stack.push((kind, vec![]));
+            }
+            Event::Token(index) => {
+                let position = self.positions[index];
+                self.flush(position + 1);
+            }
+            Event::Finish => {
+                let (kind, children) = self.stack.pop().expect("a started node");
+                let span = match (children.first(), children.last()) {
+                    (Some(first), Some(last)) => Span {
+                        start: first.span().start,
+                        end: last.span().end,
+                    },
+                    _ => Span::default(),
+                };
+                self.push(SyntaxElement::Node(SyntaxNode {
+                    kind,
+                    span,
+                    children,
+                }));
+            }
+        }
+    }
+
+    // adds the tokens before `end` to the open node
+    fn flush(&mut self, end: usize) {
+        while self.next < end {
+            let token = self.tokens[self.next].clone();
+            self.push(SyntaxElement::Token(token));
+            self.next += 1;
+        }
+    }
+
+    fn push(&mut self, element: SyntaxElement) {
+        let (_, children) = self.stack.last_mut().expect("the file node");
+        children.push(element);
+    }
+}
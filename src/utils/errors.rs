@@ -0,0 +1,661 @@
+// every error tron reports: the template of its message, which `TronError::throw` fills in, and
+// the longer explanation `tron explain` prints; `errors.md` is generated from it
+pub struct ErrorCode {
+    pub code: &'static str,
+    // `{name}`s are filled with the arguments of `throw`, in order
+    pub template: &'static str,
+    // the error ends the program
+    pub fatal: bool,
+    pub explanation: &'static str,
+    // a program that reports the error, and the same program fixed, or for errors about the
+    // command line a `$ tron` command run next to a `main.tron`; `None` for bugs in tron and for
+    // errors that need an installation or an embedding program to show
+    pub wrong: Option<&'static str>,
+    pub fixed: Option<&'static str>,
+}
+
+// whether an example is a command line rather than a program
+pub fn is_command(example: &str) -> bool {
+    example.starts_with("$ ")
+}
+
+impl ErrorCode {
+    pub fn message(&self, args: &[String]) -> Option<String> {
+        let mut message = String::new();
+        let mut rest = self.template;
+        let mut args = args.iter();
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}')?;
+            message.push_str(&rest[..start]);
+            message.push_str(args.next()?);
+            rest = &rest[end + 1..];
+        }
+        if args.next().is_some() {
+            return None;
+        }
+        message.push_str(rest);
+        Some(message)
+    }
+}
+
+// `code` in any case
+pub fn find(code: &str) -> Option<&'static ErrorCode> {
+    ERRORS
+        .iter()
+        .find(|error| error.code.eq_ignore_ascii_case(code))
+}
+
+// the message of `code` for `args`
+pub fn message(code: &str, args: &[String]) -> String {
+    find(code)
+        .and_then(|error| error.message(args))
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+const GROUPS: &[(&str, &str)] = &[
+    ("E0", "E0000: System Errors"),
+    ("E1", "E1000: Scanner Errors"),
+    ("E2", "E2000: Parser Errors"),
+    ("E3", "E3000: Resolver Errors"),
+    ("E4", "E4000: Interpreter Errors"),
+];
+
+// the contents of `errors.md`
+pub fn markdown() -> String {
+    let mut out = String::from(
+        "<!-- generated from src/utils/errors.rs by `TRON_BLESS=1 cargo test --test explain` -->\n\n",
+    );
+    out.push_str("```diff\n");
+    for (prefix, title) in GROUPS {
+        out.push_str(title);
+        out.push('\n');
+        for error in ERRORS.iter().filter(|error| error.code.starts_with(prefix)) {
+            out.push_str(&format!(
+                "- {}: {}{}\n",
+                error.code,
+                error.template.replace('\n', "\\n"),
+                if error.fatal { " --- throw" } else { "" }
+            ));
+        }
+    }
+    out.push_str("```\n");
+    for error in ERRORS {
+        out.push_str(&format!(
+            "\n## {}\n\n`{}`\n\n{}\n",
+            error.code,
+            error.template.replace('\n', "\\n"),
+            error.explanation
+        ));
+        if let (Some(wrong), Some(fixed)) = (error.wrong, error.fixed) {
+            let language = if is_command(wrong) { "sh" } else { "tron" };
+            out.push_str(&format!(
+                "\nWrong:\n\n```{0}\n{1}\n```\n\nFixed:\n\n```{0}\n{2}\n```\n",
+                language, wrong, fixed
+            ));
+        }
+    }
+    out
+}
+
+pub const ERRORS: &[ErrorCode] = &[
+    ErrorCode {
+        code: "E0001",
+        template: "failed to run file",
+        fatal: false,
+        explanation: "The file given to `tron run` couldn't be read: it doesn't exist, is a \
+            directory, or isn't readable, or stdin couldn't be read for `tron run -`.",
+        wrong: Some("$ tron run mian.tron"),
+        fixed: Some("$ tron run main.tron"),
+    },
+    ErrorCode {
+        code: "E0002",
+        template: "failed to run command",
+        fatal: false,
+        explanation: "The command line named neither a command nor a file to run. `tron help` \
+            lists the commands; an unknown one is E0008.",
+        wrong: Some("$ tron --quiet"),
+        fixed: Some("$ tron --quiet run main.tron"),
+    },
+    ErrorCode {
+        code: "E0004",
+        template: "failed to update: {message}",
+        fatal: false,
+        explanation: "`tron update` couldn't download, unpack or install a release. The \
+            message says which step failed; the installed version is left as it was.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0005",
+        template: "checksum mismatch: {file}",
+        fatal: false,
+        explanation: "A file of the release `tron update` was given doesn't match its entry in \
+            the release's SHA256SUMS, so nothing was installed. The download may be incomplete \
+            or the archive may have been changed.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0006",
+        template: "no previous version to roll back to",
+        fatal: false,
+        explanation: "`tron update --rollback` restores the version the last update replaced, \
+            and no update has replaced one yet.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0007",
+        template: "failed to uninstall: {message}",
+        fatal: false,
+        explanation: "`tron uninstall` couldn't remove the installed files; the message says \
+            which one and why.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0008",
+        template: "invalid arguments: {message}",
+        fatal: false,
+        explanation: "The command line couldn't be parsed: an unknown flag, a flag missing its \
+            value, a value of the wrong form or an unknown command. `tron help <command>` lists \
+            what a command accepts.",
+        wrong: Some("$ tron run --max-depth=deep main.tron"),
+        fixed: Some("$ tron run --max-depth=100 main.tron"),
+    },
+    ErrorCode {
+        code: "E0009",
+        template: "not enough memory for a stack of {depth} nested calls",
+        fatal: false,
+        explanation: "Programs run on a thread with enough stack for `--max-depth` nested \
+            calls, up to 256 MiB, and the system couldn't provide one that big. Lower \
+            `--max-depth`.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0010",
+        template: "failed to format {file}",
+        fatal: false,
+        explanation: "`tron fmt` couldn't read, parse or write the file. Errors in the file \
+            are reported before this one; the formatter leaves files that don't parse alone.",
+        wrong: Some("$ tron fmt mian.tron"),
+        fixed: Some("$ tron fmt main.tron"),
+    },
+    ErrorCode {
+        code: "E0011",
+        template: "failed to lint {file}",
+        fatal: false,
+        explanation: "`tron lint` couldn't read, parse or write the file. Errors in the file \
+            are reported before this one; the lint rules only run on files that parse.",
+        wrong: Some("$ tron lint mian.tron"),
+        fixed: Some("$ tron lint main.tron"),
+    },
+    ErrorCode {
+        code: "E0012",
+        template: "invalid tron.toml: {message}",
+        fatal: false,
+        explanation: "The `[lint]` table of the closest `tron.toml` names a rule that doesn't \
+            exist, or sets one to something other than \"allow\", \"warn\" or \"error\".",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E0013",
+        template: "unknown error code {code}",
+        fatal: false,
+        explanation: "`tron explain` was given a code that isn't one of tron's. Codes are an E \
+            and four digits, like E4003.",
+        wrong: Some("$ tron explain E403"),
+        fixed: Some("$ tron explain E4003"),
+    },
+    ErrorCode {
+        code: "E0014",
+        template: "failed to read {file}",
+        fatal: false,
+        explanation: "`tron tokens`, `tron ast` or `tron resolve` couldn't read the file: it \
+            doesn't exist, is a directory, or isn't readable.",
+        wrong: Some("$ tron tokens mian.tron"),
+        fixed: Some("$ tron tokens main.tron"),
+    },
+    ErrorCode {
+        code: "E0015",
+        template: "max_memory needs memory::CountingAllocator as the global allocator",
+        fatal: false,
+        explanation: "`Interpreter::set_sandbox` was given a `max_memory`, but the program \
+            embedding tron doesn't count its allocations, so the limit could never be reached. \
+            Install `#[global_allocator] static ALLOCATOR: CountingAllocator = CountingAllocator;` \
+            or leave `max_memory` unset. The count covers the whole process, not one interpreter.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E1001",
+        template: "unterminated string",
+        fatal: true,
+        explanation: "A string was opened with a quote that is never closed, so the rest of \
+            the file would be part of it. Strings may span lines, so the error is reported at \
+            the end of the file.",
+        wrong: Some("let name: string = \"tron;"),
+        fixed: Some("let name: string = \"tron\";"),
+    },
+    ErrorCode {
+        code: "E1002",
+        template: "unrecognized character: {character}",
+        fatal: false,
+        explanation: "The character can't start any token, outside of a string or a comment.",
+        wrong: Some("let total: number = 1 # 2;"),
+        fixed: Some("let total: number = 1 + 2;"),
+    },
+    ErrorCode {
+        code: "E1004",
+        template: "failed to scan tokens: \n {message}",
+        fatal: false,
+        explanation: "A token couldn't be read, like a number too large to represent. The \
+            message says which.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E2001",
+        template: "failed to parse statements: \n {message}",
+        fatal: false,
+        explanation: "A statement couldn't be parsed far enough to recover from. The parser \
+            skips to the next statement and carries on, so later errors may follow from this \
+            one.",
+        wrong: Some("let = 1;"),
+        fixed: Some("let one: number = 1;"),
+    },
+    ErrorCode {
+        code: "E2002",
+        template: "failed to parse block statement",
+        fatal: false,
+        explanation: "A function body parsed to something other than a block of statements.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E2003",
+        template: "unexpected token: {message}",
+        fatal: false,
+        explanation: "The parser found a token where the grammar doesn't allow it, or didn't \
+            find one the grammar requires, like the `;` after a statement. The message names \
+            the token or what was expected.",
+        wrong: Some("let total: number = 1 +;"),
+        fixed: Some("let total: number = 1 + 2;"),
+    },
+    ErrorCode {
+        code: "E2004",
+        template: "function can't have more than 32 arguments",
+        fatal: false,
+        explanation: "Functions take at most 32 parameters, and calls pass at most 32 \
+            arguments. Pass an array or an object instead.",
+        wrong: Some("@print(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33);"),
+        fixed: Some("@print([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33]);"),
+    },
+    ErrorCode {
+        code: "E2007",
+        template: "invalid assignment target",
+        fatal: false,
+        explanation: "Only variables can be assigned to. Array elements and object keys are \
+            changed through functions like `@push`, or by building a new value.",
+        wrong: Some("let total: number = 1;\ntotal + 1 = 2;"),
+        fixed: Some("let total: number = 1;\ntotal = 2;"),
+    },
+    ErrorCode {
+        code: "E2008",
+        template: "failed to format: {message}",
+        fatal: false,
+        explanation: "The formatter would have changed what the file means, so it left the \
+            file alone. This is a bug in the formatter; the message says what would have \
+            changed.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E3001",
+        template: "failed to resolve {statement} statement: incorrect type",
+        fatal: false,
+        explanation: "The resolver was given a statement of a different kind than it expected. \
+            This is a bug in tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E3003",
+        template: "failed to read local variable",
+        fatal: false,
+        explanation: "A local variable was read in its own declaration, before it has a value.",
+        wrong: Some("fn next(): number {\n    let count: number = count + 1;\n    return count;\n}"),
+        fixed: Some("fn next(): number {\n    let count: number = 0 + 1;\n    return count;\n}"),
+    },
+    ErrorCode {
+        code: "E3004",
+        template: "failed to resolve a variable in a too deep level",
+        fatal: false,
+        explanation: "A name was declared twice in the same block or parameter list. Give the \
+            second one another name, or assign to the first.",
+        wrong: Some("fn area(): number {\n    let side: number = 2;\n    let side: number = 3;\n    return side * side;\n}"),
+        fixed: Some("fn area(): number {\n    let side: number = 2;\n    side = 3;\n    return side * side;\n}"),
+    },
+    ErrorCode {
+        code: "E3005",
+        template: "failed to define a variable in a too deep level",
+        fatal: false,
+        explanation: "A variable was defined in a scope that doesn't exist. This is a bug in \
+            tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E3006",
+        template: "return isn't allowed outside of a function",
+        fatal: false,
+        explanation: "`return` ends a function call, so it can only be used in a function \
+            body. A program ends after its last statement.",
+        wrong: Some("return 1;"),
+        fixed: Some("fn one(): number {\n    return 1;\n}"),
+    },
+    ErrorCode {
+        code: "E3007",
+        template: "break isn't allowed outside of a loop",
+        fatal: false,
+        explanation: "`break` ends the innermost `while` or `for` loop, so it can only be used \
+            in the body of one.",
+        wrong: Some("let count: number = 0;\nif count == 0 {\n    break;\n}"),
+        fixed: Some("let count: number = 0;\nwhile true {\n    if count == 3 {\n        break;\n    }\n    count = count + 1;\n}"),
+    },
+    ErrorCode {
+        code: "E4002",
+        template: "{function}({parameter}) is expecting {expected} type, but got {type}",
+        fatal: false,
+        explanation: "A function was called with an argument whose type isn't the type of its \
+            parameter.",
+        wrong: Some("fn double(n: number): number {\n    return n * 2;\n}\ndouble(\"2\");"),
+        fixed: Some("fn double(n: number): number {\n    return n * 2;\n}\ndouble(2);"),
+    },
+    ErrorCode {
+        code: "E4003",
+        template: "{statement} {name} is expecting {expected} type, but got {type}",
+        fatal: false,
+        explanation: "A variable was declared or assigned a value whose type isn't the type it \
+            was declared with. Variables keep their type for as long as they exist.",
+        wrong: Some("let count: number = \"3\";"),
+        fixed: Some("let count: number = 3;"),
+    },
+    ErrorCode {
+        code: "E4004",
+        template: "failed to execute command: \n {message}",
+        fatal: true,
+        explanation: "The shell command `@cmd` ran couldn't be started or exited with a \
+            failure. The message is what it wrote to stderr.",
+        wrong: Some("@cmd(\"exit 3\");"),
+        fixed: Some("@cmd(\"exit 0\");"),
+    },
+    ErrorCode {
+        code: "E4005",
+        template: "failed to find library: {library}",
+        fatal: false,
+        explanation: "The file a `use` statement names doesn't exist. Paths are relative to \
+            the directory tron is run from.",
+        wrong: Some("use \"mian.tron\";"),
+        fixed: Some("use \"main.tron\";"),
+    },
+    ErrorCode {
+        code: "E4006",
+        template: "failed to make function",
+        fatal: false,
+        explanation: "A function value was made from something other than a function \
+            declaration. This is a bug in tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E4007",
+        template: "failed to unwrap {value} as {type}",
+        fatal: false,
+        explanation: "A literal didn't hold the kind of value it was read as. This is a bug in \
+            tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E4008",
+        template: "failed to create type from {type}",
+        fatal: false,
+        explanation: "A value couldn't be made from a token. This is a bug in tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E4009",
+        template: "array index is out of bounds",
+        fatal: false,
+        explanation: "An array was indexed past its end. Indexes start at 0, so the last \
+            element of an array is at `@length(array) - 1`.",
+        wrong: Some("let primes: array = [2, 3, 5];\n@print(primes[3]);"),
+        fixed: Some("let primes: array = [2, 3, 5];\n@print(primes[2]);"),
+    },
+    ErrorCode {
+        code: "E4010",
+        template: "failed to perform operation on array",
+        fatal: false,
+        explanation: "Something other than an array was indexed, or an array was indexed with \
+            something other than a number.",
+        wrong: Some("let count: number = 3;\n@print(count[0]);"),
+        fixed: Some("let counts: array = [3];\n@print(counts[0]);"),
+    },
+    ErrorCode {
+        code: "E4011",
+        template: "variable {name} has not been declared",
+        fatal: false,
+        explanation: "A name was used that isn't declared in the scope it is used in, nor in \
+            any around it. Check its spelling, and that it is declared before it is used.",
+        wrong: Some("@print(total);"),
+        fixed: Some("let total: number = 0;\n@print(total);"),
+    },
+    ErrorCode {
+        code: "E4012",
+        template: "immutable variables can't be re-declared",
+        fatal: false,
+        explanation: "Variables whose names start with an uppercase letter are constants, and \
+            can't be assigned after their declaration.",
+        wrong: Some("let Limit: number = 1;\nLimit = 2;"),
+        fixed: Some("let limit: number = 1;\nlimit = 2;"),
+    },
+    ErrorCode {
+        code: "E4013",
+        template: "failed to call",
+        fatal: false,
+        explanation: "Something other than a function was called.",
+        wrong: Some("let count: number = 1;\ncount();"),
+        fixed: Some("fn count(): number {\n    return 1;\n}\ncount();"),
+    },
+    ErrorCode {
+        code: "E4015",
+        template: "{operator} is not implemented for {target}",
+        fatal: false,
+        explanation: "The operator can't be used on values of these types. `+` adds numbers \
+            and joins strings and arrays; the other arithmetic operators only take numbers.",
+        wrong: Some("@print(10 - \"1\");"),
+        fixed: Some("@print(10 - 1);"),
+    },
+    ErrorCode {
+        code: "E4016",
+        template: "invalid operator {operator}",
+        fatal: false,
+        explanation: "An expression was evaluated with an operator it doesn't have. This is a \
+            bug in tron.",
+        wrong: None,
+        fixed: None,
+    },
+    ErrorCode {
+        code: "E4017",
+        template: "invalid function output type",
+        fatal: false,
+        explanation: "A function returned a value whose type isn't the output type it was \
+            declared with, or returned nothing when it was declared to return a value.",
+        wrong: Some("fn name(): string {\n    return 1;\n}\nname();"),
+        fixed: Some("fn name(): string {\n    return \"tron\";\n}\nname();"),
+    },
+    ErrorCode {
+        code: "E4018",
+        template: "{function} requires at least {arguments}",
+        fatal: true,
+        explanation: "A function was called with fewer arguments than it has parameters \
+            without a default value.",
+        wrong: Some("fn greet(name: string, greeting: string = \"hi \"): string {\n    return greeting + name;\n}\ngreet();"),
+        fixed: Some("fn greet(name: string, greeting: string = \"hi \"): string {\n    return greeting + name;\n}\ngreet(\"tron\");"),
+    },
+    ErrorCode {
+        code: "E4020",
+        template: "{function} requires exactly {arguments}",
+        fatal: true,
+        explanation: "A function without default values or a rest parameter was called with \
+            a different number of arguments than it has parameters.",
+        wrong: Some("fn add(a: number, b: number): number {\n    return a + b;\n}\nadd(1);"),
+        fixed: Some("fn add(a: number, b: number): number {\n    return a + b;\n}\nadd(1, 2);"),
+    },
+    ErrorCode {
+        code: "E4021",
+        template: "{function} expects {type} type as {argument} argument",
+        fatal: true,
+        explanation: "A builtin was called with an argument of a type it doesn't take.",
+        wrong: Some("@print(@length(5));"),
+        fixed: Some("@print(@length(\"5\"));"),
+    },
+    ErrorCode {
+        code: "E4022",
+        template: "exceeded the maximum of {steps} steps",
+        fatal: true,
+        explanation: "The program executed more statements than `--max-steps` allows. Raise \
+            the limit, or look for a loop that doesn't end.",
+        wrong: Some("$ tron run --max-steps=10 main.tron"),
+        fixed: Some("$ tron run --max-steps=1000 main.tron"),
+    },
+    ErrorCode {
+        code: "E4023",
+        template: "{function} accepts at most {arguments}",
+        fatal: true,
+        explanation: "A function with default values was called with more arguments than it \
+            has parameters.",
+        wrong: Some("fn greet(name: string, greeting: string = \"hi \"): string {\n    return greeting + name;\n}\ngreet(\"tron\", \"hello \", \"!\");"),
+        fixed: Some("fn greet(name: string, greeting: string = \"hi \"): string {\n    return greeting + name;\n}\ngreet(\"tron\", \"hello \");"),
+    },
+    ErrorCode {
+        code: "E4024",
+        template: "{function} has no parameter named {name}",
+        fatal: true,
+        explanation: "A call passed an argument by a name that isn't one of the function's \
+            parameters.",
+        wrong: Some("fn half(n: number): number {\n    return n / 2;\n}\nhalf(m: 4);"),
+        fixed: Some("fn half(n: number): number {\n    return n / 2;\n}\nhalf(n: 4);"),
+    },
+    ErrorCode {
+        code: "E4025",
+        template: "{function} got more than one value for {parameter}",
+        fatal: true,
+        explanation: "A call passed a parameter a value both by position and by name, or by \
+            name twice.",
+        wrong: Some("fn add(a: number, b: number): number {\n    return a + b;\n}\nadd(1, a: 2);"),
+        fixed: Some("fn add(a: number, b: number): number {\n    return a + b;\n}\nadd(1, b: 2);"),
+    },
+    ErrorCode {
+        code: "E4026",
+        template: "{function} is missing a value for {parameter}",
+        fatal: true,
+        explanation: "A call passed arguments by name, but none for a parameter without a \
+            default value.",
+        wrong: Some("fn add(a: number, b: number = 1): number {\n    return a + b;\n}\nadd(b: 2);"),
+        fixed: Some("fn add(a: number, b: number = 1): number {\n    return a + b;\n}\nadd(a: 1, b: 2);"),
+    },
+    ErrorCode {
+        code: "E4027",
+        template: "exceeded the memory limit of {size}",
+        fatal: true,
+        explanation: "The program allocated more memory than `--max-memory` allows.",
+        wrong: Some("$ tron run --max-memory=1k main.tron"),
+        fixed: Some("$ tron run --max-memory=64m main.tron"),
+    },
+    ErrorCode {
+        code: "E4028",
+        template: "stack overflow after {depth} nested calls",
+        fatal: true,
+        explanation: "Calls nested deeper than `--max-depth` allows, which is usually a \
+            recursive function that doesn't stop. Calls in a `return` don't nest, so a \
+            function that returns the call to itself can recurse without limit.",
+        wrong: Some("fn sum(n: number): number {\n    return sum(n - 1) + n;\n}\nsum(3);"),
+        fixed: Some("fn sum(n: number): number {\n    if n == 0 {\n        return 0;\n    }\n    return sum(n - 1) + n;\n}\nsum(3);"),
+    },
+    ErrorCode {
+        code: "E4029",
+        template: "exceeded the time limit of {duration}",
+        fatal: true,
+        explanation: "The program ran for longer than `--timeout` allows.",
+        wrong: Some("$ tron run --timeout=0s main.tron"),
+        fixed: Some("$ tron run --timeout=1m main.tron"),
+    },
+    ErrorCode {
+        code: "E4030",
+        template: "{function} needs the {capability} capability, which the sandbox doesn't allow",
+        fatal: true,
+        explanation: "The program called a builtin that needs a capability, like running \
+            commands or reading the environment, that the sandbox doesn't grant. `--allow` \
+            grants it.",
+        wrong: Some("$ tron run --sandbox main.tron"),
+        fixed: Some("$ tron run --sandbox --allow=env main.tron"),
+    },
+    ErrorCode {
+        code: "E4031",
+        template: "object has no key {key}",
+        fatal: false,
+        explanation: "An object was read at a key it doesn't have.",
+        wrong: Some("let point: object = { x: 1 };\n@print(point.y);"),
+        fixed: Some("let point: object = { x: 1, y: 2 };\n@print(point.y);"),
+    },
+    ErrorCode {
+        code: "E4032",
+        template: "can't read key {key} of a value that isn't an object",
+        fatal: false,
+        explanation: "A key was read from a value that isn't an object or an error. `@length` \
+            gives the length of strings and arrays.",
+        wrong: Some("let name: string = \"tron\";\n@print(name.length);"),
+        fixed: Some("let name: string = \"tron\";\n@print(@length(name));"),
+    },
+    ErrorCode {
+        code: "E4033",
+        template: "{message}",
+        fatal: true,
+        explanation: "A string was thrown, or an error made by `@error` without a code, and no \
+            `try` caught it. `catch` gets the error, whose `code`, `message`, `line` and `stack` \
+            say what happened and where.",
+        wrong: Some("throw \"the list is empty\";"),
+        fixed: Some("try {\n    throw \"the list is empty\";\n} catch (e) {\n    @print(e.message);\n}"),
+    },
+    ErrorCode {
+        code: "E4034",
+        template: "can only throw an error or a string, not {type}",
+        fatal: true,
+        explanation: "`throw` was given a value that is neither an error nor a string. `@error` \
+            makes an error with a message and, optionally, a code.",
+        wrong: Some("throw 404;"),
+        fixed: Some("try {\n    throw @error(\"not found\", \"404\");\n} catch (e) {\n    @print(e.code);\n}"),
+    },
+    ErrorCode {
+        code: "E4035",
+        template: "panic: {message}",
+        fatal: true,
+        explanation: "The program called `@panic`, which ends it with the values it was given. \
+            Unlike an error that is thrown, a panic can't be caught.",
+        wrong: Some("let items: array = [];\nif @length(items) == 0 {\n    @panic(\"no items\");\n}"),
+        fixed: Some("let items: array = [1];\nif @length(items) == 0 {\n    @panic(\"no items\");\n}"),
+    },
+];
+
+// runtime errors a `try` can't catch: the limits of the sandbox, and `@panic`
+const UNCATCHABLE: &[&str] = &["E4022", "E4027", "E4028", "E4029", "E4030", "E4035"];
+
+// whether a `try` catches `code` when it is reported inside one
+pub fn catchable(code: &str) -> bool {
+    code.starts_with("E4") && !UNCATCHABLE.contains(&code)
+}
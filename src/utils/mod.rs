@@ -1,132 +1,207 @@
-use std::process::exit;
+use std::cell::RefCell;
+use std::rc::Rc;
+pub mod errors;
+pub mod json;
+pub mod options;
+pub mod protocol;
+pub mod report;
+pub mod sha256;
+use crate::interpreter::exceptions::{self, ErrorValue};
+use crate::interpreter::stack::{self, TraceFrame};
+use crate::scanner::Span;
+use options::{options, ErrorFormat};
+use report::Report;
 
+#[derive(Clone, Debug)]
 pub struct TronError {
     pub code: String,
     pub line: usize,
     pub message: String,
+    // the file the error is in, `None` for errors about tron itself
+    pub file: Option<String>,
+    // the source the error is about, when it is known more precisely than a line
+    pub span: Option<Span>,
+}
+
+thread_local! {
+    // where `collect_errors` keeps the errors thrown while it runs
+    static COLLECTED: RefCell<Option<Vec<TronError>>> = const { RefCell::new(None) };
+}
+
+// runs `f` with the errors it throws returned instead of reported; while collected, errors that
+// end the program don't, so `f` has to carry on after them
+pub fn collect_errors<T>(f: impl FnOnce() -> T) -> (T, Vec<TronError>) {
+    let outer = COLLECTED.with(|collected| collected.replace(Some(vec![])));
+    let value = f();
+    let errors = COLLECTED.with(|collected| collected.replace(outer));
+    (value, errors.unwrap_or_default())
+}
+
+// ends tron, first printing the SARIF log of `--error-format=sarif`
+pub fn exit(code: i32) -> ! {
+    if options().error_format == ErrorFormat::Sarif {
+        report::flush_sarif();
+    }
+    std::process::exit(code)
 }
 
 impl TronError {
     pub fn throw(error_code: &str, line: usize, args: Vec<String>) {
-        let message: String = match args.len() {
-            0 => match error_code {
-                "E0001" => "failed to run file".to_string(),
-                "E0002" => "failed to run command".to_string(),
-                "E0003" => "unsupported platform".to_string(),
-                "E1001" => "unterminated string".to_string(),
-                "E2002" => "failed to parse block statement".to_string(),
-                "E2004" => "function can't have more than 32 arguments".to_string(),
-                "E2005" => "invalid assigment target".to_string(),
-                "E3003" => "failed to read local variable".to_string(),
-                "E3004" => "failed to resolve a variable in a too deep level".to_string(),
-                "E3005" => "failed to define a variable in a too deep level".to_string(),
-                "E3006" => "return isn't allowed outside of a function".to_string(),
-                "E3007" => "break isn't allowed outside of a loop".to_string(),
-                "E4006" => "failed to make function".to_string(),
-                "E4009" => "array index is out of bounds".to_string(),
-                "E4010" => "failed to perform operation on array".to_string(),
-                "E4012" => "immutable variables can't be re-declared".to_string(),
-                "E4013" => "failed to call".to_string(),
-                "E4014" => "function call argument count doesn't match parameter count".to_string(),
-                "E4017" => "invalid function output type".to_string(),
-                _ => "uknwon error".to_string(),
-            },
-            1 => {
-                let e1002 = format!("unrecognized character: {}", args[0]);
-                let e1003 = format!("unsupported character: {}", args[0]);
-                let e1004 = format!("failted to scan tokens: \n {}", args[0]);
-                let e2001 = format!("failed to parse statements: \n {}", args[0]);
-                let e2003 = format!("unexpected token:  {}", args[0]);
-                let e3001 = format!("failed to resolve {} statement: incorrect type", args[0]);
-                let e3002 = format!("variable {} already exists", args[0]);
-                let e4004 = format!("failed to execute command: \n {}", args[0]);
-                let e4005 = format!("failed to find library: {}", args[0]);
-                let e4008 = format!("failed to create type from {}", args[0]);
-                let e4011 = format!("variable {} has not been declared", args[0]);
-                let e4016 = format!("invalid operator {}", args[0]);
-
-                match error_code {
-                    "E1002" => e1002,
-                    "E1003" => e1003,
-                    "E1004" => e1004,
-                    "E2001" => e2001,
-                    "E2003" => e2003,
-                    "E3001" => e3001,
-                    "E3002" => e3002,
-                    "E4004" => e4004,
-                    "E4005" => e4005,
-                    "E4008" => e4008,
-                    "E4011" => e4011,
-                    "E4016" => e4016,
-                    _ => "uknwon error".to_string(),
-                }
-            }
-            2 => {
-                let e4007 = format!("failed to unwrap {} as {}", args[0], args[1]);
-                let e4015 = format!("{} is not implemented for {}", args[0], args[1]);
-                let e4018 = format!("{} requires at least {} arguments", args[0], args[1]);
-                let e4019 = format!("{} requires more than {} arguments", args[0], args[1]);
-                let e4020 = format!("{} exactly exactly {} arguments", args[0], args[1]);
-                match error_code {
-                    "E4007" => e4007,
-                    "E4015" => e4015,
-                    "E4018" => e4018,
-                    "E4019" => e4019,
-                    "E4020" => e4020,
-                    _ => "uknwon error".to_string(),
-                }
+        TronError::throw_at(error_code, line, None, args)
+    }
+
+    // the error `error_code` with its message filled in from `args`
+    pub fn new(error_code: &str, line: usize, span: Option<Span>, args: Vec<String>) -> Self {
+        let message = errors::message(error_code, &args);
+
+        // scanning, parsing, resolving and running are all about the file being run, and a
+        // runtime error inside a call about the file of the innermost call that has one
+        let file = match error_code.get(..2) {
+            Some("E0") | None => None,
+            Some("E4") => stack::trace(line)
+                .into_iter()
+                .find_map(|frame| frame.file)
+                .or_else(|| Some(stack::current_file()))
+                .map(|file| file.to_string()),
+            _ => Some(stack::current_file().to_string()),
+        };
+        TronError {
+            code: error_code.to_string(),
+            line,
+            message,
+            file,
+            span,
+        }
+    }
+
+    // `throw` for an error about the source `span` covers
+    pub fn throw_at(error_code: &str, line: usize, span: Option<Span>, args: Vec<String>) {
+        let error = TronError::new(error_code, line, span, args);
+        let collected = COLLECTED.with(|collected| match collected.borrow_mut().as_mut() {
+            Some(errors) => {
+                errors.push(error.clone());
+                true
             }
-            3 => {
-                let e4001 = format!(
-                    "{}() is expecting {} arguments, but got{}",
-                    args[0], args[1], args[2]
-                );
-                let e4002 = format!("{}({}: {})", args[0], args[1], args[2]);
-                let e4018 = format!(
-                    "{} expects {} type as {} argument",
-                    args[0], args[1], args[2]
-                );
-
-                match error_code {
-                    "E4001" => e4001,
-                    "E4002" => e4002,
-                    "E4018" => e4018,
-                    _ => "uknwon error".to_string(),
+            None => false,
+        });
+        if collected {
+            return;
+        }
+        // inside a `try` a runtime error is raised for it to catch
+        if exceptions::trying() && errors::catchable(error_code) {
+            exceptions::raise(Rc::new(ErrorValue::reported(&error)));
+            return;
+        }
+        if !options().report_errors {
+            exceptions::abort(error);
+            return;
+        }
+        error.report();
+
+        if errors::find(error_code).is_some_and(|error| error.fatal) {
+            exit(1);
+        }
+    }
+
+    // prints the error in the chosen `--error-format`
+    pub fn report(&self) {
+        // runtime errors inside calls say which calls they happened in
+        let trace = if self.code.starts_with("E4") {
+            stack::trace(self.line)
+        } else {
+            vec![]
+        };
+        self.report_with(&trace);
+    }
+
+    // `[code] message (line n)`, the first line of the human format without color
+    pub fn summary(&self) -> String {
+        match self.line {
+            0 => format!("[{}] {}", self.code, self.message),
+            line => format!("[{}] {} (line {})", self.code, self.message, line),
+        }
+    }
+
+    // `report` with the calls the error happened in given
+    pub fn report_with(&self, trace: &[TraceFrame]) {
+        let (error_code, message, line) = (&self.code, &self.message, self.line);
+        let options = options();
+        match options.error_format {
+            ErrorFormat::Json => eprintln!("{}", self.diagnostic(trace).json()),
+            ErrorFormat::Sarif => report::add_sarif(&self.diagnostic(trace)),
+            ErrorFormat::Human if !options.color => {
+                eprintln!("{}", self.summary());
+                for line in trace_lines(trace) {
+                    eprintln!("  {}", line);
                 }
             }
-            _ => {
-                let e4003 = format!(
-                    "{} {} is expecting {} type, but got {}",
-                    args[0], args[1], args[2], args[3]
-                );
-
-                if error_code == "E4003" {
-                    e4003
+            ErrorFormat::Human => {
+                if line == 0 {
+                    eprintln!(
+                        "[\x1B[91;1m{}\x1B[0m] \x1B[91;1m{} \x1B[0m",
+                        error_code, message
+                    );
                 } else {
-                    "uknwon error".to_string()
+                    eprintln!(
+                        "[\x1B[91;1m{}\x1B[0m] \x1B[91;1m{} \x1B[0m(\x1B[96mline {}\x1B[0m)",
+                        error_code, message, line
+                    );
+                }
+                for line in trace_lines(trace) {
+                    eprintln!("  \x1B[2m{}\x1B[0m", line);
                 }
             }
-        };
-
-        if line == 0 {
-            eprintln!(
-                "[\x1B[91;1m{}\x1B[0m] \x1B[91;1m{} \x1B[0m",
-                error_code, message
-            );
-        } else {
-            eprintln!(
-                "[\x1B[91;1m{}\x1B[0m] \x1B[91;1m{} \x1B[0m(\x1B[96mline {}\x1B[0m)",
-                error_code, message, line
-            );
         }
+    }
 
-        match error_code {
-            "E1001" => exit(1),
-            "E4018" => exit(1),
-            "E4019" => exit(1),
-            "E4020" => exit(1),
-            "E4021" => exit(1),
-            _ => {}
+    fn diagnostic<'a>(&'a self, trace: &'a [TraceFrame]) -> Report<'a> {
+        let span = match (&self.file, self.span) {
+            (Some(file), Some(span)) => {
+                stack::source_of(file).map(|source| report::positions(&source, span))
+            }
+            _ => None,
+        };
+        Report {
+            code: &self.code,
+            severity: "error",
+            message: &self.message,
+            file: self.file.as_deref(),
+            line: self.line,
+            span,
+            trace,
         }
     }
 }
+
+// frames the human format shows at each end of a long trace
+const TRACE_ENDS: usize = 10;
+
+// `at name (file:line)` for each frame, leaving out the middle of a deep recursion
+fn trace_lines(trace: &[TraceFrame]) -> Vec<String> {
+    let lines = trace
+        .iter()
+        .map(|frame| format!("at {}", format_frame(frame)));
+    if trace.len() <= 2 * TRACE_ENDS + 1 {
+        return lines.collect();
+    }
+    let mut lines: Vec<String> = lines.collect();
+    let hidden = lines.len() - 2 * TRACE_ENDS;
+    lines.splice(
+        TRACE_ENDS..lines.len() - TRACE_ENDS,
+        [format!("... {} more", hidden)],
+    );
+    lines
+}
+
+// `name (file:line)`, `name (native)` or `name (file)` when the line isn't known
+pub fn format_frame(frame: &TraceFrame) -> String {
+    let location = match (&frame.file, frame.line) {
+        (None, _) => "native".to_string(),
+        (Some(file), 0) => file.to_string(),
+        (Some(file), line) => format!("{}:{}", file, line),
+    };
+    match frame.times {
+        1 => format!("{} ({})", frame.function, location),
+        times => format!("{} ({}), {} times", frame.function, location, times),
+    }
+}
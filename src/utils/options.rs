@@ -0,0 +1,93 @@
+// process-wide settings chosen on the command line
+use crate::sandbox::Sandbox;
+use std::cell::Cell;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+    // one SARIF 2.1.0 log of every diagnostic, printed when tron exits
+    Sarif,
+}
+
+impl ErrorFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            "sarif" => Some(ErrorFormat::Sarif),
+            _ => None,
+        }
+    }
+}
+
+// which implementation executes `tron run`: the tree-walking interpreter or the bytecode vm
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Engine {
+    Tree,
+    Vm,
+}
+
+impl Engine {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(Engine::Tree),
+            "vm" => Some(Engine::Vm),
+            _ => None,
+        }
+    }
+}
+
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    pub color: bool,
+    pub quiet: bool,
+    pub error_format: ErrorFormat,
+    // nested calls before a stack overflow
+    pub max_depth: usize,
+    // limits and capabilities for the program
+    pub sandbox: Sandbox,
+    pub engine: Engine,
+    // run `optimizer::optimize` over programs before they run
+    pub optimize: bool,
+    // print errors as they happen, carry on after the ones that aren't fatal and exit on the
+    // others, the way the `tron` binary runs programs; off, `Interpreter::run` and `eval` and
+    // `Vm::run` stop at the first error and return it
+    pub report_errors: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            color: true,
+            quiet: false,
+            error_format: ErrorFormat::Human,
+            max_depth: DEFAULT_MAX_DEPTH,
+            sandbox: Sandbox::default(),
+            engine: Engine::Tree,
+            optimize: true,
+            report_errors: false,
+        }
+    }
+}
+
+thread_local! {
+    static OPTIONS: Cell<Options> = Cell::new(Options::default());
+}
+
+pub fn options() -> Options {
+    OPTIONS.with(|options| options.get())
+}
+
+pub fn set_options(new_options: Options) {
+    OPTIONS.with(|options| options.set(new_options));
+}
+
+// informational output that `--quiet` silences; program output and errors are unaffected
+pub fn inform(message: &str) {
+    if !options().quiet {
+        println!("{}", message);
+    }
+}
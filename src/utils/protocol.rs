@@ -0,0 +1,36 @@
+// the base protocol of the debug adapter and language server protocols: every message is a
+// `Content-Length` header, a blank line and a json body
+use super::json::Json;
+use std::io::{self, BufRead, Write};
+
+// `None` once the input ends
+pub fn read_message(input: &mut impl BufRead) -> Option<Result<Json, String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Some(Err("message without a Content-Length header".to_string()));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).ok()?;
+    Some(Json::parse(&String::from_utf8_lossy(&body)))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
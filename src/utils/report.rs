@@ -0,0 +1,222 @@
+// diagnostics in the machine-readable `--error-format`s: one json object per line as they happen,
+// or a single SARIF log of all of them, which `exit` prints when tron is done
+use crate::interpreter::stack::TraceFrame;
+use crate::scanner::Span;
+use crate::utils::json::Json;
+use std::sync::Mutex;
+
+// the results of the SARIF log, from every thread
+static SARIF: Mutex<Vec<Json>> = Mutex::new(vec![]);
+
+// a line and a column, both counted from 1; columns count characters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    // where `offset` is in `source`; for more than one offset, make the `LineStarts` once
+    pub fn of(source: &str, offset: usize) -> Position {
+        LineStarts::new(source).position(source, offset)
+    }
+}
+
+// where each line of a source starts, so finding the line of an offset doesn't scan the source
+pub struct LineStarts(Vec<usize>);
+
+impl LineStarts {
+    pub fn new(source: &str) -> LineStarts {
+        let after_newlines = source.match_indices('\n').map(|(index, _)| index + 1);
+        LineStarts(std::iter::once(0).chain(after_newlines).collect())
+    }
+
+    // the line `offset` is on, counted from 0, and the offset that line starts at
+    pub fn line(&self, offset: usize) -> (usize, usize) {
+        let line = self.0.partition_point(|&start| start <= offset) - 1;
+        (line, self.0[line])
+    }
+
+    // where the line counted from 0 starts, `None` past the last one
+    pub fn start(&self, line: usize) -> Option<usize> {
+        self.0.get(line).copied()
+    }
+
+    // where `offset` is in `source`, the source these are the lines of
+    pub fn position(&self, source: &str, offset: usize) -> Position {
+        let offset = offset.min(source.len());
+        let (line, start) = self.line(offset);
+        Position {
+            line: line + 1,
+            column: source[start..offset].chars().count() + 1,
+        }
+    }
+}
+
+// what the formats say about a diagnostic
+pub struct Report<'a> {
+    pub code: &'a str,
+    // "error" or "warning"
+    pub severity: &'a str,
+    pub message: &'a str,
+    pub file: Option<&'a str>,
+    // 0 when not known
+    pub line: usize,
+    // from the start of the text it is about to just after its end
+    pub span: Option<(Position, Position)>,
+    pub trace: &'a [TraceFrame],
+}
+
+impl Report<'_> {
+    pub fn json(&self) -> Json {
+        let position = |position: Position| {
+            Json::object([
+                ("line", position.line.into()),
+                ("column", position.column.into()),
+            ])
+        };
+        let mut fields = vec![
+            ("code".to_string(), self.code.into()),
+            ("severity".to_string(), self.severity.into()),
+            ("message".to_string(), self.message.into()),
+            ("file".to_string(), self.file.map_or(Json::Null, Json::from)),
+            ("line".to_string(), self.line.into()),
+            (
+                "span".to_string(),
+                self.span.map_or(Json::Null, |(start, end)| {
+                    Json::object([("start", position(start)), ("end", position(end))])
+                }),
+            ),
+        ];
+        if !self.trace.is_empty() {
+            let frames = self.trace.iter().map(frame_json).collect::<Vec<Json>>();
+            fields.push(("trace".to_string(), frames.into()));
+        }
+        Json::Object(fields)
+    }
+
+    // a result of a SARIF log
+    pub fn sarif(&self) -> Json {
+        let mut fields = vec![
+            ("ruleId".to_string(), self.code.into()),
+            ("level".to_string(), self.severity.into()),
+            (
+                "message".to_string(),
+                Json::object([("text", self.message.into())]),
+            ),
+        ];
+        if let Some(file) = self.file {
+            let region = match self.span {
+                Some((start, end)) => Some(Json::object([
+                    ("startLine", start.line.into()),
+                    ("startColumn", start.column.into()),
+                    ("endLine", end.line.into()),
+                    ("endColumn", end.column.into()),
+                ])),
+                None => line_region(self.line),
+            };
+            fields.push(("locations".to_string(), vec![location(file, region)].into()));
+        }
+        if !self.trace.is_empty() {
+            let frames: Vec<Json> = self
+                .trace
+                .iter()
+                .map(|frame| {
+                    let mut location = match &frame.file {
+                        Some(file) => location(file, line_region(frame.line)),
+                        None => Json::Object(vec![]),
+                    };
+                    if let Json::Object(fields) = &mut location {
+                        fields.push((
+                            "logicalLocations".to_string(),
+                            vec![Json::object([("name", frame.function.as_ref().into())])].into(),
+                        ));
+                    }
+                    Json::object([("location", location)])
+                })
+                .collect();
+            fields.push((
+                "stacks".to_string(),
+                vec![Json::object([("frames", frames.into())])].into(),
+            ));
+        }
+        Json::Object(fields)
+    }
+}
+
+// SARIF lines start at 1, so an error without a line has no region
+fn line_region(line: usize) -> Option<Json> {
+    (line > 0).then(|| Json::object([("startLine", line.into())]))
+}
+
+fn location(file: &str, region: Option<Json>) -> Json {
+    let mut physical = vec![(
+        "artifactLocation".to_string(),
+        Json::object([("uri", file.into())]),
+    )];
+    if let Some(region) = region {
+        physical.push(("region".to_string(), region));
+    }
+    Json::object([("physicalLocation", Json::Object(physical))])
+}
+
+fn frame_json(frame: &TraceFrame) -> Json {
+    Json::object([
+        ("function", frame.function.as_ref().into()),
+        (
+            "file",
+            frame.file.as_deref().map_or(Json::Null, |file| file.into()),
+        ),
+        ("line", frame.line.into()),
+        ("times", frame.times.into()),
+    ])
+}
+
+// where `span` of `source` starts and ends
+pub fn positions(source: &str, span: Span) -> (Position, Position) {
+    let lines = LineStarts::new(source);
+    (
+        lines.position(source, span.start),
+        lines.position(source, span.end),
+    )
+}
+
+// keeps a result for the SARIF log
+pub fn add_sarif(report: &Report) {
+    if let Ok(mut results) = SARIF.lock() {
+        results.push(report.sarif());
+    }
+}
+
+// prints the SARIF log to stderr, when there is one
+pub fn flush_sarif() {
+    let Ok(mut results) = SARIF.lock() else {
+        return;
+    };
+    let results = std::mem::take(&mut *results);
+    let log = Json::object([
+        (
+            "$schema",
+            "https://json.schemastore.org/sarif-2.1.0.json".into(),
+        ),
+        ("version", "2.1.0".into()),
+        (
+            "runs",
+            vec![Json::object([
+                (
+                    "tool",
+                    Json::object([(
+                        "driver",
+                        Json::object([
+                            ("name", "tron".into()),
+                            ("version", env!("CARGO_PKG_VERSION").into()),
+                        ]),
+                    )]),
+                ),
+                ("results", results.into()),
+            ])]
+            .into(),
+        ),
+    ]);
+    eprintln!("{}", log);
+}
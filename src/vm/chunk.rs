@@ -0,0 +1,140 @@
+// bytecode produced by the compiler and executed by the `Vm`
+use crate::expressions::{Arity, TronType};
+use crate::scanner::TokenType;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    Null,
+    True,
+    False,
+    Pop,
+    Dup,
+    // statement boundary, counted towards `--max-steps`
+    Step,
+    GetLocal(u32),
+    SetLocal(u32),
+    GetUpvalue(u32),
+    SetUpvalue(u32),
+    // operands are indexes in `Vm::globals`, see `Globals::index`
+    GetGlobal(u32),
+    SetGlobal(u32),
+    DefineGlobal(u32),
+    // `let name: type = value;` check of the value on top of the stack, `(name, type)` indexing
+    // `Chunk::names`
+    CheckDeclaration(u32, u32),
+    // the same for a global, `(global, type)`, which keeps the type for `SetGlobal` to check
+    DeclareGlobal(u32, u32),
+    // before assigning to a local or upvalue declared with a type, `(name, type)` as above
+    CheckType(u32, u32),
+    // before assigning to an uppercase name, which is a constant
+    AssignConstant,
+    Unary(TokenType),
+    Binary(TokenType),
+    Index,
+    // operand indexes `Chunk::names`
+    GetField(u32),
+    Array(u32),
+    // operand indexes `Chunk::key_lists`
+    Object(u32),
+    // jump operands are absolute positions in `Chunk::code`
+    Jump(u32),
+    JumpIfFalse(u32),
+    // `a and b`: leaves `false` and jumps when `a` is falsy, otherwise pops it
+    And(u32),
+    // `a or b`: keeps `a` and jumps when it is truthy, otherwise pops it
+    Or(u32),
+    // `name = @push(name, ...)`: lets go of the local or global's array if it is the first of the
+    // call's arguments, `(arguments, slot or global)`, see `Expression::in_place_call`
+    ReleaseLocal(u8, u32),
+    ReleaseGlobal(u8, u32),
+    // calls have at most 32 arguments, see `MAX_ARGUMENTS` in the parser
+    Call(u8),
+    // positional count and an index into `Chunk::key_lists` for the names that follow them
+    CallNamed(u8, u32),
+    // `return f(...)`: a call followed by `Return` that may reuse the returning frame instead
+    TailCall(u8),
+    TailCallNamed(u8, u32),
+    // operand indexes `Chunk::functions`
+    Closure(u32),
+    CloseUpvalue,
+    // skips the default value code of a parameter that got an argument, `(parameter, jump)`
+    DefaultArgument(u8, u32),
+    CheckParameters,
+    Return,
+    // falling off the end of a function body
+    ReturnNull,
+    Use,
+    // an error raised before the matching `EndTry` continues at the operand, see `Vm::catch`
+    Try(u32),
+    EndTry,
+    // throws the value on top of the stack
+    Throw,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UpvalueRef {
+    pub is_local: bool,
+    pub index: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParameterInfo {
+    pub name: String,
+    pub type_name: String,
+    pub has_default: bool,
+    pub rest: bool,
+}
+
+#[derive(Default, Debug)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub lines: Vec<usize>,
+    pub constants: Vec<TronType>,
+    pub names: Vec<String>,
+    pub key_lists: Vec<Vec<String>>,
+    pub functions: Vec<Rc<FunctionProto>>,
+    name_indexes: HashMap<String, u32>,
+}
+
+impl Chunk {
+    pub fn emit(&mut self, op: Op, line: usize) -> usize {
+        self.code.push(op);
+        self.lines.push(line);
+        self.code.len() - 1
+    }
+    pub fn constant(&mut self, value: TronType) -> u32 {
+        self.constants.push(value);
+        (self.constants.len() - 1) as u32
+    }
+    pub fn name(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.name_indexes.get(name) {
+            return *index;
+        }
+        let index = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.name_indexes.insert(name.to_string(), index);
+        index
+    }
+    pub fn key_list(&mut self, keys: Vec<String>) -> u32 {
+        self.key_lists.push(keys);
+        (self.key_lists.len() - 1) as u32
+    }
+}
+
+// a compiled function body; closures pair it with their captured variables
+#[derive(Debug)]
+pub struct FunctionProto {
+    pub name: Rc<str>,
+    // where the function was defined, for stack traces
+    pub file: Rc<str>,
+    pub arity: Arity,
+    pub params: Vec<ParameterInfo>,
+    pub output_type: String,
+    pub upvalues: Vec<UpvalueRef>,
+    pub chunk: Chunk,
+    // top-level code of a program or a `use`d library
+    pub script: bool,
+}
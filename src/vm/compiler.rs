@@ -0,0 +1,755 @@
+// compiles parsed statements into bytecode for the `Vm`
+//
+// Variables are resolved here instead of in the `Resolver`: locals live in stack slots, variables
+// captured by an inner function become upvalues and everything else is a global, by the index
+// `Globals` gives its name.
+use super::chunk::{Chunk, FunctionProto, Op, ParameterInfo, UpvalueRef};
+use super::globals::Globals;
+use crate::expressions::{Arity, Expression, TronType};
+use crate::interpreter::stack::DEFAULT_FILE;
+use crate::scanner::{Parameter, Statement, Token, TokenType};
+use crate::utils::{options::options, TronError};
+use std::rc::Rc;
+
+struct Local {
+    name: String,
+    depth: usize,
+    captured: bool,
+    // the type a `let` declared, which assignments are checked against
+    type_name: Option<String>,
+}
+
+struct Loop {
+    // locals declared before the loop, which `break` keeps
+    locals: usize,
+    breaks: Vec<usize>,
+}
+
+// a `try` statement being compiled, which a `break` or `return` leaving it has to close
+#[derive(Clone)]
+struct Try {
+    finally: Option<Rc<Statement>>,
+    // loops entered before the try
+    loops: usize,
+}
+
+struct FunctionState {
+    proto: FunctionProto,
+    locals: Vec<Local>,
+    scope_depth: usize,
+    loops: Vec<Loop>,
+    // only those whose handler is in place, with the innermost last
+    tries: Vec<Try>,
+}
+
+impl FunctionState {
+    fn new(proto: FunctionProto, scope_depth: usize) -> Self {
+        Self {
+            proto,
+            locals: vec![],
+            scope_depth,
+            loops: vec![],
+            tries: vec![],
+        }
+    }
+}
+
+pub struct Compiler<'a> {
+    states: Vec<FunctionState>,
+    globals: &'a mut Globals,
+    line: usize,
+    // `Step` ops are only worth their cost when `--max-steps` is set
+    count_steps: bool,
+    // the file being compiled, which every function in it records for stack traces
+    file: Rc<str>,
+}
+
+impl<'a> Compiler<'a> {
+    pub fn new(globals: &'a mut Globals) -> Self {
+        Self {
+            states: vec![],
+            globals,
+            line: 0,
+            count_steps: {
+                let sandbox = options().sandbox;
+                sandbox.max_steps.is_some() || sandbox.timeout.is_some()
+            },
+            file: Rc::from(DEFAULT_FILE),
+        }
+    }
+
+    // compiles a whole program (or `use`d library) from `file` into a function without parameters
+    pub fn compile(mut self, stmts: &[Statement], file: &str) -> Rc<FunctionProto> {
+        self.file = Rc::from(file);
+        let script = FunctionProto {
+            name: Rc::from("script"),
+            file: self.file.clone(),
+            arity: Arity::exact(0),
+            params: vec![],
+            output_type: "null".to_string(),
+            upvalues: vec![],
+            chunk: Chunk::default(),
+            script: true,
+        };
+        self.states.push(FunctionState::new(script, 0));
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+        self.emit(Op::ReturnNull);
+        Rc::new(self.states.pop().unwrap().proto)
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().unwrap()
+    }
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().proto.chunk
+    }
+    fn emit(&mut self, op: Op) -> usize {
+        let line = self.line;
+        self.chunk().emit(op, line)
+    }
+    // `emit` for an op whose errors are about a token on `line` rather than the statement, as
+    // the interpreter reports them
+    fn emit_at(&mut self, op: Op, line: usize) -> usize {
+        self.chunk().emit(op, line)
+    }
+    fn here(&mut self) -> u32 {
+        self.chunk().code.len() as u32
+    }
+    // points the jump at `index` to the next instruction
+    fn patch(&mut self, index: usize) {
+        let target = self.here();
+        let chunk = self.chunk();
+        chunk.code[index] = match chunk.code[index] {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::And(_) => Op::And(target),
+            Op::Or(_) => Op::Or(target),
+            Op::DefaultArgument(parameter, _) => Op::DefaultArgument(parameter, target),
+            Op::Try(_) => Op::Try(target),
+            op => op,
+        };
+    }
+    fn name(&mut self, name: &str) -> u32 {
+        self.chunk().name(name)
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+    fn end_scope(&mut self) {
+        self.state().scope_depth -= 1;
+        let depth = self.state().scope_depth;
+        while let Some(local) = self.state().locals.last() {
+            if local.depth <= depth {
+                break;
+            }
+            let op = if local.captured {
+                Op::CloseUpvalue
+            } else {
+                Op::Pop
+            };
+            self.state().locals.pop();
+            self.emit(op);
+        }
+    }
+    fn add_local(&mut self, name: &str) {
+        self.declare_local(name, None);
+    }
+    fn declare_local(&mut self, name: &str, type_name: Option<&str>) {
+        let depth = self.state().scope_depth;
+        self.state().locals.push(Local {
+            name: name.to_string(),
+            depth,
+            captured: false,
+            type_name: type_name.map(str::to_string),
+        });
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        self.line = stmt.get_line();
+        if self.count_steps {
+            self.emit(Op::Step);
+        }
+        match stmt {
+            Statement::ExpressionStatement { expression, .. } => {
+                self.expression(expression);
+                self.emit(Op::Pop);
+            }
+            Statement::UseStatement { expression, .. } => {
+                self.expression(expression);
+                self.emit(Op::Use);
+                self.emit(Op::Pop);
+            }
+            Statement::VariableStatement {
+                name,
+                value_type,
+                value,
+                ..
+            } => {
+                self.expression(value);
+                let type_index = self.name(&value_type.lexeme);
+                if self.state().scope_depth == 0 {
+                    let global = self.globals.index(&name.lexeme);
+                    self.emit(Op::DeclareGlobal(global, type_index));
+                    self.emit(Op::DefineGlobal(global));
+                } else {
+                    let name_index = self.name(&name.lexeme);
+                    self.emit(Op::CheckDeclaration(name_index, type_index));
+                    self.declare_local(&name.lexeme, Some(&value_type.lexeme));
+                }
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.begin_scope();
+                for stmt in statements {
+                    self.statement(stmt);
+                }
+                self.end_scope();
+            }
+            Statement::IfStatement {
+                conditions,
+                then_branch,
+                elif_branches,
+                else_branch,
+                ..
+            } => {
+                let mut ends = vec![];
+                let mut branches: Vec<(&Vec<Expression>, &Statement)> =
+                    vec![(conditions, then_branch.as_ref())];
+                for (predicates, branch) in elif_branches {
+                    branches.push((predicates, branch.as_ref()));
+                }
+                for (predicates, branch) in branches {
+                    let skips = self.conditions(predicates);
+                    self.statement(branch);
+                    ends.push(self.emit(Op::Jump(0)));
+                    for skip in skips {
+                        self.patch(skip);
+                    }
+                }
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+                for end in ends {
+                    self.patch(end);
+                }
+            }
+            Statement::WhileStatement {
+                conditions, body, ..
+            } => {
+                let start = self.here();
+                let exits = self.conditions(conditions);
+                let locals = self.state().locals.len();
+                self.state().loops.push(Loop {
+                    locals,
+                    breaks: vec![],
+                });
+                self.statement(body);
+                self.emit(Op::Jump(start));
+                let finished = self.state().loops.pop().unwrap();
+                for exit in exits.into_iter().chain(finished.breaks) {
+                    self.patch(exit);
+                }
+            }
+            Statement::FunctionStatement {
+                name,
+                params,
+                body,
+                output_type,
+                line,
+            } => {
+                if self.state().scope_depth == 0 {
+                    self.function(&name.lexeme, params, body, output_type, *line);
+                    let global = self.globals.index(&name.lexeme);
+                    self.emit(Op::DefineGlobal(global));
+                } else {
+                    // declared first so the function can call itself
+                    self.add_local(&name.lexeme);
+                    self.function(&name.lexeme, params, body, output_type, *line);
+                }
+            }
+            Statement::ReturnStatement { value, line, .. } => {
+                if self.state().proto.script {
+                    TronError::throw("E3006", *line, vec![]);
+                }
+                // a call in a `try` has to return to it, for the try to catch its errors
+                let tail = self.state().tries.is_empty();
+                match value {
+                    Some(Expression::Call {
+                        callee,
+                        paren,
+                        arguments,
+                        named,
+                        ..
+                    }) => self.call(callee, arguments, named, paren.line_number, tail),
+                    Some(value) => self.expression(value),
+                    None => {
+                        self.emit(Op::Null);
+                    }
+                }
+                if !tail {
+                    // the value waits in a local of its own while the finally blocks run
+                    self.begin_scope();
+                    self.add_local("");
+                    self.leave_tries(0);
+                    self.state().locals.pop();
+                    self.state().scope_depth -= 1;
+                }
+                self.emit(Op::Return);
+            }
+            Statement::BreakStatement { line, .. } => {
+                let Some(locals) = self.state().loops.last().map(|target| target.locals) else {
+                    TronError::throw("E3007", *line, vec![]);
+                    return;
+                };
+                let loops = self.state().loops.len();
+                if let Some(first) = self.state().tries.iter().position(|t| t.loops == loops) {
+                    self.leave_tries(first);
+                }
+                let captured: Vec<bool> = self.state().locals[locals..]
+                    .iter()
+                    .rev()
+                    .map(|local| local.captured)
+                    .collect();
+                for captured in captured {
+                    self.emit(if captured { Op::CloseUpvalue } else { Op::Pop });
+                }
+                let jump = self.emit(Op::Jump(0));
+                self.state().loops.last_mut().unwrap().breaks.push(jump);
+            }
+            Statement::SwitchStatement {
+                condition,
+                case_branches,
+                default_branch,
+                ..
+            } => {
+                self.expression(condition);
+                let mut ends = vec![];
+                for (value, body) in case_branches {
+                    self.emit(Op::Dup);
+                    self.expression(value);
+                    self.emit(Op::Binary(TokenType::EqualEqual));
+                    let next = self.emit(Op::JumpIfFalse(0));
+                    self.emit(Op::Pop);
+                    self.branch(body);
+                    ends.push(self.emit(Op::Jump(0)));
+                    self.patch(next);
+                }
+                self.emit(Op::Pop);
+                if let Some(body) = default_branch {
+                    self.branch(body);
+                }
+                for end in ends {
+                    self.patch(end);
+                }
+            }
+            Statement::TryStatement {
+                body,
+                catch,
+                finally,
+                ..
+            } => self.try_statement(body, catch, finally),
+            Statement::ThrowStatement { value, .. } => {
+                self.expression(value);
+                self.emit(Op::Throw);
+            }
+        }
+    }
+
+    // the handlers of `Op::Try` start with the error on top of the stack, right after the locals
+    fn try_statement(
+        &mut self,
+        body: &Statement,
+        catch: &Option<(Token, Box<Statement>)>,
+        finally: &Option<Box<Statement>>,
+    ) {
+        let finally = finally.as_deref().cloned().map(Rc::new);
+        let handler = self.begin_try(&finally);
+        self.statement(body);
+        self.end_try();
+        self.finally(&finally);
+        let mut ends = vec![self.emit(Op::Jump(0))];
+        self.patch(handler);
+        self.begin_scope();
+        match catch {
+            Some((name, block)) => {
+                self.add_local(&name.lexeme);
+                // an error in the catch block runs the finally block too
+                let rethrow = finally.is_some().then(|| self.begin_try(&finally));
+                self.statement(block);
+                if let Some(rethrow) = rethrow {
+                    self.end_try();
+                    self.end_scope();
+                    self.finally(&finally);
+                    ends.push(self.emit(Op::Jump(0)));
+                    self.patch(rethrow);
+                    // the catch error, which the finally block can't see, and the new one
+                    self.begin_scope();
+                    self.add_local("");
+                    self.rethrow(&finally);
+                }
+            }
+            None => self.rethrow(&finally),
+        }
+        self.end_scope();
+        for end in ends {
+            self.patch(end);
+        }
+    }
+
+    fn begin_try(&mut self, finally: &Option<Rc<Statement>>) -> usize {
+        let handler = self.emit(Op::Try(0));
+        let loops = self.state().loops.len();
+        self.state().tries.push(Try {
+            finally: finally.clone(),
+            loops,
+        });
+        handler
+    }
+
+    fn end_try(&mut self) {
+        self.emit(Op::EndTry);
+        self.state().tries.pop();
+    }
+
+    fn finally(&mut self, finally: &Option<Rc<Statement>>) {
+        if let Some(finally) = finally {
+            self.statement(finally);
+        }
+    }
+
+    // runs the finally block with the error on top of the stack, then throws it again
+    fn rethrow(&mut self, finally: &Option<Rc<Statement>>) {
+        self.add_local("");
+        let slot = self.state().locals.len() - 1;
+        self.finally(finally);
+        self.emit(Op::GetLocal(slot as u32));
+        self.emit(Op::Throw);
+    }
+
+    // closes the tries from the innermost out to `first`, for a `break` or `return` leaving them
+    fn leave_tries(&mut self, first: usize) {
+        let line = self.line;
+        let tries = self.state().tries.clone();
+        for index in (first..tries.len()).rev() {
+            self.emit(Op::EndTry);
+            // a `break` or `return` in the finally block only leaves the tries around it
+            self.state().tries.truncate(index);
+            self.finally(&tries[index].finally);
+        }
+        self.state().tries = tries;
+        self.line = line;
+    }
+
+    // `if a, b` and `while a, b` need every condition to hold; returns the jumps taken when one fails
+    fn conditions(&mut self, conditions: &[Expression]) -> Vec<usize> {
+        let mut jumps = vec![];
+        for condition in conditions {
+            self.expression(condition);
+            jumps.push(self.emit(Op::JumpIfFalse(0)));
+        }
+        jumps
+    }
+
+    // switch cases share the surrounding scope at the top level, like in the tree-walking interpreter
+    fn branch(&mut self, body: &[Statement]) {
+        let scoped = self.state().scope_depth > 0;
+        if scoped {
+            self.begin_scope();
+        }
+        for stmt in body {
+            self.statement(stmt);
+        }
+        if scoped {
+            self.end_scope();
+        }
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        params: &[Parameter],
+        body: &[Box<Statement>],
+        output_type: &Token,
+        line: usize,
+    ) {
+        let proto = FunctionProto {
+            name: Rc::from(name),
+            file: self.file.clone(),
+            arity: Arity::of(params),
+            params: params
+                .iter()
+                .map(|param| ParameterInfo {
+                    name: param.name.lexeme.clone(),
+                    type_name: param.type_token.lexeme.clone(),
+                    has_default: param.default.is_some(),
+                    rest: param.rest,
+                })
+                .collect(),
+            output_type: output_type.lexeme.clone(),
+            upvalues: vec![],
+            chunk: Chunk::default(),
+            script: false,
+        };
+        let enclosing_line = self.line;
+        self.states.push(FunctionState::new(proto, 1));
+        for param in params {
+            self.add_local(&param.name.lexeme);
+        }
+        for (index, param) in params.iter().enumerate() {
+            if let Some(default) = &param.default {
+                let skip = self.emit(Op::DefaultArgument(index as u8, 0));
+                self.expression(default);
+                self.emit(Op::SetLocal(index as u32));
+                self.emit(Op::Pop);
+                self.patch(skip);
+            }
+        }
+        self.emit(Op::CheckParameters);
+        for stmt in body {
+            self.statement(stmt);
+        }
+        self.line = line;
+        self.emit(Op::ReturnNull);
+        let state = self.states.pop().unwrap();
+        self.line = enclosing_line;
+        let index = {
+            let chunk = self.chunk();
+            chunk.functions.push(Rc::new(state.proto));
+            (chunk.functions.len() - 1) as u32
+        };
+        self.emit(Op::Closure(index));
+    }
+
+    fn call(
+        &mut self,
+        callee: &Expression,
+        arguments: &[Expression],
+        named: &[(Token, Expression)],
+        line: usize,
+        tail: bool,
+    ) {
+        self.expression(callee);
+        for argument in arguments {
+            self.expression(argument);
+        }
+        if named.is_empty() {
+            let call = match tail {
+                true => Op::TailCall(arguments.len() as u8),
+                false => Op::Call(arguments.len() as u8),
+            };
+            self.emit_at(call, line);
+        } else {
+            for (_, value) in named {
+                self.expression(value);
+            }
+            let names = named.iter().map(|(name, _)| name.lexeme.clone()).collect();
+            let names = self.chunk().key_list(names);
+            let call = match tail {
+                true => Op::TailCallNamed(arguments.len() as u8, names),
+                false => Op::CallNamed(arguments.len() as u8, names),
+            };
+            self.emit_at(call, line);
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Literal { value, .. } => {
+                match value {
+                    TronType::Null => self.emit(Op::Null),
+                    TronType::True => self.emit(Op::True),
+                    TronType::False => self.emit(Op::False),
+                    value => {
+                        let index = self.chunk().constant(value.clone());
+                        self.emit(Op::Constant(index))
+                    }
+                };
+            }
+            Expression::Grouping { expression, .. } => self.expression(expression),
+            Expression::Variable { name, .. } => self.get_variable(&name.lexeme),
+            Expression::Assign { name, value, .. } => {
+                let top = self.states.len() - 1;
+                match value.in_place_call(&name.lexeme) {
+                    Some((callee, arguments, paren)) => {
+                        self.expression(callee);
+                        for argument in arguments {
+                            self.expression(argument);
+                        }
+                        let count = arguments.len() as u8;
+                        if let Some(slot) = self.resolve_local(top, &name.lexeme) {
+                            self.emit(Op::ReleaseLocal(count, slot));
+                        } else if self.resolve_upvalue(top, &name.lexeme).is_none() {
+                            let global = self.globals.index(&name.lexeme);
+                            self.emit(Op::ReleaseGlobal(count, global));
+                        }
+                        self.emit_at(Op::Call(count), paren.line_number);
+                    }
+                    None => self.expression(value),
+                }
+                // uppercase names are constants
+                if name.lexeme.chars().next().is_some_and(char::is_uppercase) {
+                    self.emit(Op::AssignConstant);
+                }
+                if let Some(slot) = self.resolve_local(top, &name.lexeme) {
+                    self.check_type(&name.lexeme);
+                    self.emit(Op::SetLocal(slot));
+                } else if let Some(index) = self.resolve_upvalue(top, &name.lexeme) {
+                    self.check_type(&name.lexeme);
+                    self.emit(Op::SetUpvalue(index));
+                } else {
+                    let global = self.globals.index(&name.lexeme);
+                    self.emit(Op::SetGlobal(global));
+                }
+            }
+            Expression::Unary {
+                operator, right, ..
+            } => {
+                self.expression(right);
+                self.emit_at(Op::Unary(operator.token_type), operator.line_number);
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                self.expression(right);
+                self.emit_at(Op::Binary(operator.token_type), operator.line_number);
+            }
+            Expression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                match operator.token_type {
+                    TokenType::And | TokenType::Or => {
+                        let jump = if operator.token_type == TokenType::And {
+                            self.emit(Op::And(0))
+                        } else {
+                            self.emit(Op::Or(0))
+                        };
+                        self.expression(right);
+                        self.patch(jump);
+                    }
+                    // `xor` and `nor` only look at their left operand
+                    _ => {
+                        self.emit(Op::Unary(TokenType::Bang));
+                    }
+                }
+            }
+            Expression::Call {
+                callee,
+                paren,
+                arguments,
+                named,
+                ..
+            } => self.call(callee, arguments, named, paren.line_number, false),
+            Expression::Array { elements, .. } => {
+                for element in elements {
+                    self.expression(element);
+                }
+                self.emit(Op::Array(elements.len() as u32));
+            }
+            Expression::Index {
+                array,
+                index,
+                bracket,
+                ..
+            } => {
+                self.expression(array);
+                self.expression(index);
+                self.emit_at(Op::Index, bracket.line_number);
+            }
+            Expression::Object { properties, .. } => {
+                for (_, value) in properties {
+                    self.expression(value);
+                }
+                let keys = properties
+                    .iter()
+                    .map(|(key, _)| key.lexeme.clone())
+                    .collect();
+                let keys = self.chunk().key_list(keys);
+                self.emit(Op::Object(keys));
+            }
+            Expression::ObjectCall { name, key, .. } => {
+                self.get_variable(&name.lexeme);
+                let key = self.name(&key.lexeme);
+                self.emit(Op::GetField(key));
+            }
+            Expression::Function {
+                name,
+                params,
+                body,
+                output_type,
+                ..
+            } => {
+                let line = self.line;
+                self.function(&name.lexeme, params, body, output_type, line);
+            }
+        }
+    }
+
+    fn get_variable(&mut self, name: &str) {
+        let top = self.states.len() - 1;
+        if let Some(slot) = self.resolve_local(top, name) {
+            self.emit(Op::GetLocal(slot));
+        } else if let Some(index) = self.resolve_upvalue(top, name) {
+            self.emit(Op::GetUpvalue(index));
+        } else {
+            let global = self.globals.index(name);
+            self.emit(Op::GetGlobal(global));
+        }
+    }
+    // checks the value assigned to the local or upvalue `name` against the type its `let` declared
+    fn check_type(&mut self, name: &str) {
+        let type_name = self
+            .states
+            .iter()
+            .rev()
+            .find_map(|state| state.locals.iter().rev().find(|local| local.name == name))
+            .and_then(|local| local.type_name.clone());
+        if let Some(type_name) = type_name {
+            let name_index = self.name(name);
+            let type_index = self.name(&type_name);
+            self.emit(Op::CheckType(name_index, type_index));
+        }
+    }
+    fn resolve_local(&self, state: usize, name: &str) -> Option<u32> {
+        self.states[state]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u32)
+    }
+    fn resolve_upvalue(&mut self, state: usize, name: &str) -> Option<u32> {
+        if state == 0 {
+            return None;
+        }
+        let upvalue = if let Some(slot) = self.resolve_local(state - 1, name) {
+            self.states[state - 1].locals[slot as usize].captured = true;
+            UpvalueRef {
+                is_local: true,
+                index: slot,
+            }
+        } else {
+            UpvalueRef {
+                is_local: false,
+                index: self.resolve_upvalue(state - 1, name)?,
+            }
+        };
+        let upvalues = &mut self.states[state].proto.upvalues;
+        if let Some(index) = upvalues
+            .iter()
+            .position(|known| known.is_local == upvalue.is_local && known.index == upvalue.index)
+        {
+            return Some(index as u32);
+        }
+        upvalues.push(upvalue);
+        Some((upvalues.len() - 1) as u32)
+    }
+}
@@ -0,0 +1,54 @@
+// the global variables of a `Vm`, which the compiler resolves to indexes so running code never
+// looks them up by name
+use crate::expressions::TronType;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct Globals {
+    indexes: HashMap<String, u32>,
+    names: Vec<String>,
+    // `None` until the variable is defined
+    values: Vec<Option<TronType>>,
+    // declared types, as the interpreter's `Environment::value_types`
+    types: Vec<Option<String>>,
+}
+
+impl Globals {
+    // the index of `name`, which stays the same for every program the `Vm` runs
+    pub fn index(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.indexes.get(name) {
+            return *index;
+        }
+        let index = self.names.len() as u32;
+        self.indexes.insert(name.to_string(), index);
+        self.names.push(name.to_string());
+        self.values.push(None);
+        self.types.push(None);
+        index
+    }
+    pub fn name(&self, index: u32) -> &str {
+        &self.names[index as usize]
+    }
+    pub fn get(&self, index: u32) -> Option<&TronType> {
+        self.values[index as usize].as_ref()
+    }
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut TronType> {
+        self.values[index as usize].as_mut()
+    }
+    pub fn define(&mut self, index: u32, value: TronType) {
+        self.values[index as usize] = Some(value);
+    }
+    pub fn declared_type(&self, index: u32) -> Option<&String> {
+        self.types[index as usize].as_ref()
+    }
+    pub fn declare_type(&mut self, index: u32, type_name: String) {
+        self.types[index as usize] = Some(type_name);
+    }
+    pub fn get_by_name(&self, name: &str) -> Option<&TronType> {
+        self.get(*self.indexes.get(name)?)
+    }
+    pub fn set_by_name(&mut self, name: &str, value: TronType) {
+        let index = self.index(name);
+        self.define(index, value);
+    }
+}
@@ -0,0 +1,764 @@
+// a stack based virtual machine for Tron, selected with `--engine=vm`
+//
+// The compiler turns the parsed program into `FunctionProto`s whose chunks hold the bytecode,
+// constants and names. Each call gets a `Frame` whose locals are slots on the shared value stack,
+// starting right after the callee. Natives are the same `StdFunctionImpl`s the interpreter uses.
+pub mod chunk;
+pub mod compiler;
+pub mod globals;
+
+use crate::environment::Environment;
+use crate::expressions::{binary_op, field_op, index_op, unary_op, CallableImpl, TronType};
+use crate::interpreter::exceptions;
+use crate::interpreter::stack::{self, Call, DEFAULT_FILE};
+use crate::interpreter::{count_step, finish};
+use crate::library::{declare_script_args, standard_library};
+use crate::memory::{self, track};
+use crate::optimizer::optimize;
+use crate::parser::Parser;
+use crate::sandbox;
+use crate::scanner::{Scanner, TokenType};
+use crate::utils::options::options;
+use crate::utils::TronError;
+use chunk::{FunctionProto, Op};
+use compiler::Compiler;
+use globals::Globals;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+// a variable captured by a closure: still on the stack while its scope is alive, moved out after
+#[derive(Debug)]
+pub enum Upvalue {
+    Open(usize),
+    Closed(Box<TronType>),
+}
+
+#[derive(Debug)]
+pub struct Closure {
+    pub proto: Rc<FunctionProto>,
+    pub upvalues: Vec<Rc<RefCell<Upvalue>>>,
+}
+
+// where an error raised inside a `try` continues: the code of its handler, with the frames,
+// stack and calls as they were when the try began
+struct Handler {
+    frames: usize,
+    stack: usize,
+    calls: usize,
+    target: usize,
+}
+
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    base: usize,
+    // parameters that got no argument, so their default value code runs
+    missing: u64,
+}
+
+pub struct Vm {
+    stack: Vec<TronType>,
+    frames: Vec<Frame>,
+    globals: Globals,
+    open_upvalues: Vec<Rc<RefCell<Upvalue>>>,
+    // of the `try` statements being run, innermost last
+    handlers: Vec<Handler>,
+    // where the program came from, for stack traces
+    file: Rc<str>,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        let mut environment = Environment::new(HashMap::new());
+        standard_library(&mut environment);
+        let mut globals = Globals::default();
+        for (name, value) in environment.values.borrow().iter() {
+            globals.set_by_name(name, value.clone());
+        }
+        Self {
+            stack: Vec::with_capacity(256),
+            frames: vec![],
+            globals,
+            open_upvalues: vec![],
+            handlers: vec![],
+            file: Rc::from(DEFAULT_FILE),
+        }
+    }
+    // values returned by `@args()`
+    pub fn set_script_args(&mut self, script_args: Vec<String>) {
+        let mut environment = Environment::new(HashMap::new());
+        declare_script_args(script_args, &mut environment);
+        if let Some(args) = environment.get_global("@args") {
+            self.globals.set_by_name("@args", args);
+        }
+    }
+    pub fn set_global(&mut self, name: &str, value: TronType) {
+        self.globals.set_by_name(name, value);
+    }
+    pub fn get_global(&self, name: &str) -> Option<TronType> {
+        self.globals.get_by_name(name).cloned()
+    }
+    // the file name stack traces give for code run from now on
+    pub fn set_file(&mut self, file: &str) {
+        self.file = Rc::from(file);
+    }
+    // runs a whole program
+    pub fn run(&mut self, source: &str) -> Result<(), TronError> {
+        let script = finish(
+            compile(source, &self.file, &mut self.globals).and_then(|script| {
+                // embedded, code with errors doesn't run
+                match exceptions::aborted() {
+                    true => Err(exceptions::THROWN.to_string()),
+                    false => Ok(script),
+                }
+            }),
+        )?;
+        let closure = Rc::new(Closure {
+            proto: script,
+            upvalues: vec![],
+        });
+        let depth = self.frames.len();
+        let calls = stack::depth();
+        let handlers = self.handlers.len();
+        let guarded = stack::guard_stack();
+        let previous = stack::set_file(self.file.clone());
+        self.stack
+            .push(TronType::Callable(CallableImpl::Closure(closure.clone())));
+        let result = self
+            .call_closure(closure, 0, None, 0)
+            .and_then(|_| self.execute(depth));
+        stack::set_file(previous);
+        if guarded {
+            stack::unguard_stack();
+        }
+        if result.is_err() {
+            self.frames.truncate(depth);
+            stack::truncate(calls);
+            for _ in self.handlers.drain(handlers..) {
+                exceptions::leave();
+            }
+        }
+        self.stack.clear();
+        self.open_upvalues.clear();
+        finish(result.map(|_| ()))
+    }
+
+    fn execute(&mut self, depth: usize) -> Result<TronType, String> {
+        loop {
+            match self.run_frames(depth) {
+                Err(_)
+                    if exceptions::catching()
+                        && self
+                            .handlers
+                            .last()
+                            .is_some_and(|handler| handler.frames > depth) =>
+                {
+                    self.catch()
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // unwinds to the innermost handler, with the error that was raised on top of the stack
+    fn catch(&mut self) {
+        let handler = self.handlers.pop().unwrap();
+        exceptions::leave();
+        let error = exceptions::take().unwrap();
+        self.frames.truncate(handler.frames);
+        self.close_upvalues(handler.stack);
+        self.stack.truncate(handler.stack);
+        stack::truncate(handler.calls);
+        self.frames.last_mut().unwrap().ip = handler.target;
+        self.stack.push(TronType::Error(error));
+    }
+
+    fn run_frames(&mut self, depth: usize) -> Result<TronType, String> {
+        let mut frame = self.frames.last().unwrap();
+        let mut closure = frame.closure.clone();
+        let mut ip = frame.ip;
+        let mut base = frame.base;
+        // embedded, any error unwinds out of the run
+        let unwinding = !options().report_errors;
+        self.unwind(unwinding)?;
+        loop {
+            let chunk = &closure.proto.chunk;
+            let op = chunk.code[ip];
+            let line = chunk.lines[ip];
+            ip += 1;
+            match op {
+                Op::Constant(index) => self.stack.push(chunk.constants[index as usize].clone()),
+                Op::Null => self.stack.push(TronType::Null),
+                Op::True => self.stack.push(TronType::True),
+                Op::False => self.stack.push(TronType::False),
+                Op::Pop => {
+                    self.stack.pop();
+                }
+                Op::Dup => self.stack.push(self.peek(0).clone()),
+                Op::Step => {
+                    count_step(line);
+                    self.unwind(unwinding)?;
+                }
+                Op::GetLocal(slot) => self.stack.push(self.stack[base + slot as usize].clone()),
+                Op::SetLocal(slot) => self.stack[base + slot as usize] = self.peek(0).clone(),
+                Op::ReleaseLocal(count, slot) => {
+                    let first = self.stack.len() - count as usize;
+                    if self.stack[base + slot as usize].same_array(&self.stack[first]) {
+                        self.stack[base + slot as usize] = TronType::Null;
+                    }
+                }
+                Op::ReleaseGlobal(count, global) => {
+                    let first = &self.stack[self.stack.len() - count as usize];
+                    if let Some(global) = self.globals.get_mut(global) {
+                        if global.same_array(first) {
+                            *global = TronType::Null;
+                        }
+                    }
+                }
+                Op::GetUpvalue(index) => {
+                    let value = match &*closure.upvalues[index as usize].borrow() {
+                        Upvalue::Open(slot) => self.stack[*slot].clone(),
+                        Upvalue::Closed(value) => (**value).clone(),
+                    };
+                    self.stack.push(value);
+                }
+                Op::SetUpvalue(index) => {
+                    let value = self.peek(0).clone();
+                    let mut upvalue = closure.upvalues[index as usize].borrow_mut();
+                    match &mut *upvalue {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => **closed = value,
+                    }
+                }
+                Op::GetGlobal(global) => match self.globals.get(global) {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => {
+                        let name = self.globals.name(global).to_string();
+                        TronError::throw("E4011", line, vec![name]);
+                        self.stack.push(TronType::Null);
+                        self.unwind(unwinding)?;
+                    }
+                },
+                Op::SetGlobal(global) => {
+                    if let Some(type_name) = self.globals.declared_type(global) {
+                        self.check_type(self.globals.name(global), type_name);
+                    }
+                    let value = self.peek(0).clone();
+                    match self.globals.get_mut(global) {
+                        Some(global) => *global = value,
+                        None => {
+                            let name = self.globals.name(global).to_string();
+                            TronError::throw("E4011", line, vec![name]);
+                            *self.peek_mut() = TronType::Null;
+                        }
+                    }
+                    self.unwind(unwinding)?;
+                }
+                Op::DefineGlobal(global) => {
+                    let value = self.stack.pop().unwrap();
+                    self.globals.define(global, value);
+                }
+                Op::CheckDeclaration(name, type_name) => {
+                    let name = &chunk.names[name as usize];
+                    let type_name = &chunk.names[type_name as usize];
+                    self.check_declaration(name, type_name, line);
+                    self.unwind(unwinding)?;
+                }
+                Op::DeclareGlobal(global, type_name) => {
+                    let type_name = &chunk.names[type_name as usize];
+                    if self.check_declaration(self.globals.name(global), type_name, line) {
+                        self.globals.declare_type(global, type_name.clone());
+                    }
+                    self.unwind(unwinding)?;
+                }
+                Op::CheckType(name, type_name) => {
+                    let name = &chunk.names[name as usize];
+                    self.check_type(name, &chunk.names[type_name as usize]);
+                    self.unwind(unwinding)?;
+                }
+                Op::AssignConstant => {
+                    TronError::throw("E4012", 0, vec![]);
+                    self.unwind(unwinding)?;
+                }
+                Op::Unary(operator) => {
+                    let value = self.peek_mut();
+                    *value = unary_op(operator, value, line);
+                    self.unwind(unwinding)?;
+                }
+                Op::Binary(operator) => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.peek_mut();
+                    *left = match (&*left, operator, &right) {
+                        (TronType::Number(x), TokenType::Plus, TronType::Number(y)) => {
+                            TronType::Number(x + y)
+                        }
+                        (TronType::Number(x), TokenType::Minus, TronType::Number(y)) => {
+                            TronType::Number(x - y)
+                        }
+                        (TronType::Number(x), TokenType::Less, TronType::Number(y)) => {
+                            TronType::from_bool(x < y)
+                        }
+                        (left, operator, right) => {
+                            let result = binary_op(left, operator, right, line);
+                            *self.peek_mut() = result;
+                            self.unwind(unwinding)?;
+                            continue;
+                        }
+                    };
+                }
+                Op::Index => {
+                    let index = self.stack.pop().unwrap();
+                    let array = self.peek_mut();
+                    *array = index_op(array, &index, line)?;
+                    self.unwind(unwinding)?;
+                }
+                Op::GetField(key) => {
+                    let key = &chunk.names[key as usize];
+                    let value = self.peek_mut();
+                    *value = field_op(value, key, line)?;
+                    self.unwind(unwinding)?;
+                }
+                Op::Array(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count as usize);
+                    self.stack.push(TronType::array(elements));
+                }
+                Op::Object(keys) => {
+                    let keys = &chunk.key_lists[keys as usize];
+                    let values = self.stack.split_off(self.stack.len() - keys.len());
+                    let fields = keys.iter().cloned().zip(values).collect();
+                    self.stack.push(TronType::object(fields));
+                }
+                Op::Jump(target) => {
+                    // loops jump backwards, and together with calls that bounds the work between
+                    // two collections
+                    if (target as usize) < ip {
+                        memory::safepoint(line);
+                        self.unwind(unwinding)?;
+                    }
+                    ip = target as usize;
+                }
+                Op::JumpIfFalse(target) => {
+                    if self.stack.pop().unwrap().is_truthy() != TronType::True {
+                        ip = target as usize;
+                    }
+                }
+                Op::And(target) => {
+                    if self.peek(0).is_truthy() == TronType::False {
+                        *self.peek_mut() = TronType::False;
+                        ip = target as usize;
+                    } else {
+                        self.stack.pop();
+                    }
+                }
+                Op::Or(target) => {
+                    if self.peek(0).is_truthy() == TronType::True {
+                        ip = target as usize;
+                    } else {
+                        self.stack.pop();
+                    }
+                }
+                Op::Call(count)
+                | Op::CallNamed(count, _)
+                | Op::TailCall(count)
+                | Op::TailCallNamed(count, _) => {
+                    let names = match op {
+                        Op::CallNamed(_, names) | Op::TailCallNamed(_, names) => {
+                            Some(&chunk.key_lists[names as usize])
+                        }
+                        _ => None,
+                    };
+                    let count = count as usize + names.map_or(0, |names| names.len());
+                    let callee = self.peek(count).clone();
+                    match callee {
+                        TronType::Callable(CallableImpl::Closure(callee)) => {
+                            // before the call is entered, as the interpreter checks it
+                            callee.proto.arity.check(&callee.proto.name, count, line)?;
+                            memory::safepoint(line);
+                            // with the same output type the callee's check at `Return` covers
+                            // this function's as well, so its frame can go
+                            let tail = matches!(op, Op::TailCall(_) | Op::TailCallNamed(..))
+                                && callee.proto.output_type == closure.proto.output_type;
+                            if tail {
+                                self.close_upvalues(base);
+                                let callee_slot = self.stack.len() - count - 1;
+                                self.stack.drain(base - 1..callee_slot);
+                                self.frames.pop();
+                                stack::replace(
+                                    callee.proto.name.clone(),
+                                    Some(callee.proto.file.clone()),
+                                );
+                            } else {
+                                self.frames.last_mut().unwrap().ip = ip;
+                                stack::push(Call {
+                                    name: callee.proto.name.clone(),
+                                    file: Some(callee.proto.file.clone()),
+                                    line,
+                                    caller_file: closure.proto.file.clone(),
+                                });
+                            }
+                            let names = names.cloned();
+                            self.call_closure(callee, count, names.as_deref(), line)?;
+                            frame = self.frames.last().unwrap();
+                            closure = frame.closure.clone();
+                            ip = 0;
+                            base = frame.base;
+                            self.unwind(unwinding)?;
+                        }
+                        TronType::Callable(CallableImpl::StdFunction(native)) => {
+                            if let Some(name) = names.and_then(|names| names.first()) {
+                                TronError::throw(
+                                    "E4024",
+                                    line,
+                                    vec![native.name.clone(), name.clone()],
+                                );
+                                return Err(exceptions::THROWN.to_string());
+                            }
+                            native.arity.check(&native.name, count, line)?;
+                            let args = self.stack.split_off(self.stack.len() - count);
+                            stack::push(Call {
+                                name: Rc::from(native.name.as_str()),
+                                file: None,
+                                line,
+                                caller_file: closure.proto.file.clone(),
+                            });
+                            *self.peek_mut() = (native.function)(args);
+                            stack::pop();
+                            self.unwind(unwinding)?;
+                        }
+                        _ => {
+                            TronError::throw("E4013", line, vec![]);
+                            self.stack.truncate(self.stack.len() - count);
+                            *self.peek_mut() = TronType::Null;
+                            self.unwind(unwinding)?;
+                        }
+                    }
+                }
+                Op::Closure(index) => {
+                    let proto = chunk.functions[index as usize].clone();
+                    let upvalues = proto
+                        .upvalues
+                        .iter()
+                        .map(|upvalue| {
+                            if upvalue.is_local {
+                                self.capture_upvalue(base + upvalue.index as usize)
+                            } else {
+                                closure.upvalues[upvalue.index as usize].clone()
+                            }
+                        })
+                        .collect();
+                    let closure = Closure { proto, upvalues };
+                    self.stack
+                        .push(TronType::Callable(CallableImpl::Closure(Rc::new(closure))));
+                }
+                Op::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.stack.pop();
+                }
+                Op::DefaultArgument(parameter, target) => {
+                    if self.frames.last().unwrap().missing & (1 << parameter) == 0 {
+                        ip = target as usize;
+                    }
+                }
+                Op::CheckParameters => {
+                    let proto = &closure.proto;
+                    for (index, param) in proto.params.iter().enumerate() {
+                        let value = &self.stack[base + index];
+                        if !conforms(&param.type_name, value) {
+                            TronError::throw(
+                                "E4002",
+                                0,
+                                vec![
+                                    proto.name.to_string(),
+                                    param.name.clone(),
+                                    param.type_name.clone(),
+                                    value.to_type().to_string(),
+                                ],
+                            );
+                        }
+                    }
+                    self.unwind(unwinding)?;
+                }
+                Op::Return | Op::ReturnNull => {
+                    let proto = &closure.proto;
+                    let result = match op {
+                        Op::Return => self.stack.pop().unwrap(),
+                        _ => TronType::Null,
+                    };
+                    if !proto.script {
+                        let output_type = &proto.output_type;
+                        let matches = match op {
+                            Op::Return => {
+                                *output_type == result.to_type()
+                                    || *output_type == result.to_string()
+                            }
+                            _ => output_type == "null",
+                        };
+                        if !matches {
+                            TronError::throw("E4017", line, vec![]);
+                            self.unwind(unwinding)?;
+                        }
+                        stack::pop();
+                    }
+                    self.close_upvalues(base);
+                    self.stack.truncate(base - 1);
+                    self.frames.pop();
+                    if self.frames.len() == depth {
+                        return Ok(result);
+                    }
+                    self.stack.push(result);
+                    frame = self.frames.last().unwrap();
+                    closure = frame.closure.clone();
+                    ip = frame.ip;
+                    base = frame.base;
+                }
+                Op::Use => {
+                    let path = self.stack.pop().unwrap();
+                    if !sandbox::require("fs", "use", line) {
+                        self.stack.push(TronType::Null);
+                        self.unwind(unwinding)?;
+                        continue;
+                    }
+                    let path_buf = std::env::current_dir()
+                        .unwrap()
+                        .join(path.to_string().trim_matches('"').trim_start_matches('/'));
+                    if !path_buf.exists() {
+                        TronError::throw("E4005", line, vec![path.to_string()]);
+                        self.stack.push(TronType::Null);
+                        self.unwind(unwinding)?;
+                        continue;
+                    }
+                    let source = std::fs::read_to_string(&path_buf).map_err(|e| e.to_string())?;
+                    let library = Rc::new(Closure {
+                        proto: compile(
+                            &source,
+                            path.to_string().trim_matches('"'),
+                            &mut self.globals,
+                        )?,
+                        upvalues: vec![],
+                    });
+                    self.stack
+                        .push(TronType::Callable(CallableImpl::Closure(library.clone())));
+                    self.frames.last_mut().unwrap().ip = ip;
+                    self.call_closure(library, 0, None, line)?;
+                    frame = self.frames.last().unwrap();
+                    closure = frame.closure.clone();
+                    ip = 0;
+                    base = frame.base;
+                    self.unwind(unwinding)?;
+                }
+                Op::Try(target) => {
+                    self.handlers.push(Handler {
+                        frames: self.frames.len(),
+                        stack: self.stack.len(),
+                        calls: stack::depth(),
+                        target: target as usize,
+                    });
+                    exceptions::enter();
+                }
+                Op::EndTry => {
+                    self.handlers.pop();
+                    exceptions::leave();
+                }
+                Op::Throw => {
+                    let value = self.stack.pop().unwrap();
+                    return exceptions::throw(exceptions::thrown(value, line)?);
+                }
+            }
+        }
+    }
+
+    fn peek(&self, distance: usize) -> &TronType {
+        &self.stack[self.stack.len() - 1 - distance]
+    }
+    fn peek_mut(&mut self) -> &mut TronType {
+        self.stack.last_mut().unwrap()
+    }
+
+    // a runtime error reported inside a `try`, or one that aborts the run, unwinds from the op that
+    // raised it; only ops that can raise one check
+    fn unwind(&self, unwinding: bool) -> Result<(), String> {
+        match (unwinding || !self.handlers.is_empty()) && exceptions::raised() {
+            true => Err(exceptions::THROWN.to_string()),
+            false => Ok(()),
+        }
+    }
+
+    // whether the value on top of the stack is of the type `let name: type_name` declared
+    fn check_declaration(&self, name: &str, type_name: &str, line: usize) -> bool {
+        let value = self.peek(0);
+        if type_name == value.to_type() || *type_name == value.to_string() {
+            return true;
+        }
+        TronError::throw(
+            "E4003",
+            line,
+            vec![
+                "variable".to_string(),
+                name.to_string(),
+                type_name.to_string(),
+                value.to_type().to_string(),
+            ],
+        );
+        false
+    }
+
+    // the check of assigning in the interpreter: variables declared with a type keep it
+    fn check_type(&self, name: &str, expected_type: &str) {
+        let value = self.peek(0);
+        if !conforms(expected_type, value) {
+            TronError::throw(
+                "E4003",
+                0,
+                vec![
+                    "variable".to_string(),
+                    name.to_string(),
+                    expected_type.to_string(),
+                    value.to_type().to_string(),
+                ],
+            );
+        }
+    }
+
+    // binds the `count` values on top of the stack to the parameters of `closure`, called on
+    // `line`, and enters it; the arity has been checked
+    fn call_closure(
+        &mut self,
+        closure: Rc<Closure>,
+        count: usize,
+        names: Option<&[String]>,
+        line: usize,
+    ) -> Result<(), String> {
+        let proto = &closure.proto;
+        let params = &proto.params;
+        let base = self.stack.len() - count;
+        let named = names.map_or(0, |names| names.len());
+        let mut missing = 0;
+        let simple = named == 0 && !params.last().is_some_and(|param| param.rest);
+        if simple && count == params.len() {
+            // every parameter got a positional argument, which is already where it belongs
+        } else if simple {
+            for (index, param) in params.iter().enumerate().skip(count) {
+                // only after the parse error for a required parameter following a default one
+                if !param.has_default {
+                    TronError::throw(
+                        "E4026",
+                        line,
+                        vec![proto.name.to_string(), param.name.clone()],
+                    );
+                    return Err(String::new());
+                }
+                missing |= 1 << index;
+                self.stack.push(TronType::Null);
+            }
+        } else {
+            let mut values = self.stack.split_off(base);
+            let named_values = values.split_off(count - named);
+            let mut bound: Vec<Option<TronType>> = vec![None; params.len()];
+            let mut positional = values.into_iter();
+            for (slot, param) in bound.iter_mut().zip(params) {
+                if param.rest {
+                    *slot = Some(TronType::array(positional.by_ref().collect()));
+                } else if let Some(value) = positional.next() {
+                    *slot = Some(value);
+                }
+            }
+            for (name, value) in names.unwrap_or(&[]).iter().zip(named_values) {
+                let args = vec![proto.name.to_string(), name.clone()];
+                match params
+                    .iter()
+                    .position(|param| param.name == *name && !param.rest)
+                {
+                    Some(index) if bound[index].is_none() => bound[index] = Some(value),
+                    Some(_) => TronError::throw("E4025", line, args),
+                    None => TronError::throw("E4024", line, args),
+                }
+            }
+            for (index, (value, param)) in bound.into_iter().zip(params).enumerate() {
+                match value {
+                    Some(value) => self.stack.push(value),
+                    None if param.has_default => {
+                        missing |= 1 << index;
+                        self.stack.push(TronType::Null);
+                    }
+                    None => {
+                        TronError::throw(
+                            "E4026",
+                            line,
+                            vec![proto.name.to_string(), param.name.clone()],
+                        );
+                        return Err(String::new());
+                    }
+                }
+            }
+        }
+        self.frames.push(Frame {
+            closure,
+            ip: 0,
+            base,
+            missing,
+        });
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> Rc<RefCell<Upvalue>> {
+        for upvalue in &self.open_upvalues {
+            if matches!(&*upvalue.borrow(), Upvalue::Open(open) if *open == slot) {
+                return upvalue.clone();
+            }
+        }
+        let upvalue = Rc::new(RefCell::new(Upvalue::Open(slot)));
+        // once closed it can hold the closure that captured it
+        track(&upvalue);
+        self.open_upvalues.push(upvalue.clone());
+        upvalue
+    }
+    // moves the values of captured variables at or above `from` off the stack
+    fn close_upvalues(&mut self, from: usize) {
+        let stack = &self.stack;
+        self.open_upvalues.retain(|upvalue| {
+            let slot = match &*upvalue.borrow() {
+                Upvalue::Open(slot) => *slot,
+                Upvalue::Closed(_) => return false,
+            };
+            if slot < from {
+                return true;
+            }
+            *upvalue.borrow_mut() = Upvalue::Closed(Box::new(stack[slot].clone()));
+            false
+        });
+    }
+}
+
+// errors while compiling are about `file`
+fn compile(source: &str, file: &str, globals: &mut Globals) -> Result<Rc<FunctionProto>, String> {
+    stack::add_source(file, source);
+    let previous = stack::set_file(Rc::from(file));
+    let stmts = Scanner::new(source)
+        .scan_tokens()
+        .and_then(|tokens| Parser::new(tokens).parse());
+    stack::set_file(previous);
+    let stmts = match stmts? {
+        stmts if options().optimize => optimize(stmts),
+        stmts => stmts,
+    };
+    Ok(Compiler::new(globals).compile(&stmts, file))
+}
+
+// the parameter and assignment type check of the interpreter
+fn conforms(type_name: &str, value: &TronType) -> bool {
+    matches!(
+        (type_name, value),
+        ("number", TronType::Number(_))
+            | ("string", TronType::StringValue(_))
+            | ("array", TronType::ArrayValue(_))
+            | ("object", TronType::Object(_))
+            | ("bool", TronType::True | TronType::False)
+            | ("null", TronType::Null)
+            | ("error", TronType::Error(_))
+    )
+}
@@ -0,0 +1,190 @@
+// the command line: flags global and per command, their values, `--help` and what reaches the
+// script after `--`, and running code from stdin and scripts with a `#!` line
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// exit code, stdout and stderr
+fn tron(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("--no-color")
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn assert_invalid(args: &[&str], message: &str) {
+    let (code, _, stderr) = tron(args);
+    assert_eq!(code, 64, "{:?}: {}", args, stderr);
+    assert_eq!(
+        stderr,
+        format!("[E0008] invalid arguments: {}\n", message),
+        "{:?}",
+        args
+    );
+}
+
+#[test]
+fn unknown_flags_and_missing_values() {
+    assert_invalid(&["--bogus", "version"], "unknown flag --bogus");
+    assert_invalid(&["-x", "version"], "unknown flag -x");
+    // flags of one command aren't flags of another
+    assert_invalid(&["fmt", "--fix"], "unknown flag --fix");
+    assert_invalid(&["-e", "1;", "--max-steps"], "--max-steps requires <n>");
+    assert_invalid(&["--quiet=yes", "version"], "--quiet doesn't take a value");
+    assert_invalid(&["explain"], "explain requires <code>");
+    assert_invalid(&["version", "now"], "unexpected argument now for version");
+    assert_invalid(&["nothing.tron"], "unknown command nothing.tron");
+}
+
+#[test]
+fn values_inline_or_separate() {
+    let program = "let i: number = 0; while true { i = i + 1; }";
+    for args in [
+        vec!["--max-steps=3", "-e", program],
+        vec!["--max-steps", "3", "-e", program],
+    ] {
+        let (code, _, stderr) = tron(&args);
+        assert_eq!(code, 1, "{:?}", args);
+        assert!(
+            stderr.starts_with("[E4022] exceeded the maximum of 3 steps"),
+            "{:?}: {}",
+            args,
+            stderr
+        );
+    }
+    // flags can come after the command
+    let (code, stdout, _) = tron(&["-e", "@print(1);", "--quiet"]);
+    assert_eq!((code, stdout.as_str()), (0, "1\n"));
+}
+
+#[test]
+fn arguments_after_dashes_reach_the_script() {
+    let (code, stdout, _) = tron(&[
+        "-e",
+        "let args: array = @args(); @print(@length(args), args[1]);",
+        "--",
+        "one",
+        "--two",
+        "-e",
+    ]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "3\n\"--two\"\n");
+}
+
+#[test]
+fn help_for_every_command() {
+    for command in [
+        "run",
+        "debug",
+        "dap",
+        "lsp",
+        "fmt",
+        "lint",
+        "tokens",
+        "ast",
+        "resolve",
+        "explain",
+        "bench",
+        "version",
+        "update",
+        "uninstall",
+        "help",
+    ] {
+        for args in [vec![command, "--help"], vec!["help", command]] {
+            let (code, stdout, _) = tron(&args);
+            assert_eq!(code, 0, "{:?}", args);
+            assert!(
+                stdout.contains(&format!("tron {}", command)),
+                "{:?}: {}",
+                args,
+                stdout
+            );
+            assert!(stdout.contains("--help"), "{:?}: {}", args, stdout);
+        }
+    }
+    // flags of the command are listed with it
+    let (_, stdout, _) = tron(&["fmt", "-h"]);
+    assert!(stdout.contains("--check"), "{}", stdout);
+}
+
+// runs `args` with `input` on stdin
+fn tron_with_stdin(args: &[&str], input: &str) -> (i32, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .arg("--no-color")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+    )
+}
+
+#[test]
+fn programs_from_stdin() {
+    assert_eq!(
+        tron_with_stdin(&["run", "-"], "let n: number = 2;\n@print(n * 21);\n"),
+        (0, "42\n".to_string())
+    );
+    assert_eq!(
+        tron_with_stdin(&["run", "-", "--", "x"], "@print(@length(@args()));"),
+        (0, "1\n".to_string())
+    );
+}
+
+#[test]
+fn scripts_with_a_shebang() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("cli");
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("greet.tron");
+    fs::write(
+        &script,
+        "#!/usr/bin/env tron\nlet args: array = @args();\n@print(\"hi \" + args[0], @length(args));\n",
+    )
+    .unwrap();
+    let script = script.to_str().unwrap();
+    // how `#!/usr/bin/env tron` runs it: the file, then its arguments, flags included
+    let (code, stdout, stderr) = tron(&[script, "tron", "--quiet"]);
+    assert_eq!(code, 0, "{}", stderr);
+    assert_eq!(stdout, "\"hi tron\"\n2\n");
+    // the `#!` line is skipped wherever the file is run from, and errors keep its line numbers
+    let (code, stdout, _) = tron(&["run", script, "--", "you"]);
+    assert_eq!((code, stdout.as_str()), (0, "\"hi you\"\n1\n"));
+    fs::write(
+        dir.join("broken.tron"),
+        "#!/usr/bin/env tron\n@print(missing);\n",
+    )
+    .unwrap();
+    let (_, _, stderr) = tron(&["run", dir.join("broken.tron").to_str().unwrap()]);
+    assert!(stderr.contains("(line 2)"), "{}", stderr);
+}
+
+#[test]
+fn a_large_max_depth_still_gets_a_thread() {
+    let program = "fn down(n: number): number {\n    if n < 1 {\n        return 0;\n    }\n    \
+                   return down(n - 1) + 1;\n}\n@print(down(200000));\n";
+    // the vm's calls don't use the native stack
+    let (code, stdout, stderr) = tron(&["--engine=vm", "--max-depth=100000000", "-e", program]);
+    assert_eq!((code, stdout.as_str()), (0, "200000\n"), "{}", stderr);
+    // the interpreter's thread is capped, and recursion past it is a stack overflow
+    let (code, _, stderr) = tron(&["--max-depth=100000000", "-e", program]);
+    assert_eq!(code, 1);
+    assert!(stderr.starts_with("[E4028] stack overflow"), "{}", stderr);
+}
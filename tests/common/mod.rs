@@ -0,0 +1,85 @@
+// what the tests comparing output with the `.out` file next to their input share; each test binary
+// uses only some of it
+#![allow(dead_code)]
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ChildStdout;
+use tron_lang::utils::json::Json;
+use tron_lang::utils::protocol::read_message;
+
+// the files in `tests/<dir>` with the given extension, sorted
+pub fn inputs(dir: &str, extension: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(dir);
+    let mut inputs: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    inputs.sort();
+    inputs
+}
+
+// collects the outputs that differ from their `.out` file, and rewrites them under `TRON_BLESS=1`
+pub struct Expected {
+    bless: bool,
+    failures: Vec<String>,
+}
+
+impl Expected {
+    pub fn new() -> Self {
+        Expected {
+            bless: std::env::var_os("TRON_BLESS").is_some(),
+            failures: vec![],
+        }
+    }
+
+    // compares `actual` with the file at `path`, rewriting it first when blessing
+    pub fn check(&mut self, name: impl std::fmt::Display, path: &Path, actual: &str) {
+        if self.bless {
+            fs::write(path, actual).unwrap();
+        }
+        self.compare(name, path, actual);
+    }
+
+    // compares without ever rewriting, for outputs that must match one already checked
+    pub fn compare(&mut self, name: impl std::fmt::Display, path: &Path, actual: &str) {
+        let expected = fs::read_to_string(path).unwrap_or_default();
+        if actual != expected {
+            self.failures.push(format!(
+                "{}\n--- expected\n{}--- actual\n{}",
+                name, expected, actual
+            ));
+        }
+    }
+
+    pub fn assert(self) {
+        assert!(self.failures.is_empty(), "\n{}", self.failures.join("\n"));
+    }
+}
+
+// reads messages from `tron dap` or `tron lsp` into `transcript` until one satisfies `done`
+pub fn read_until(
+    output: &mut BufReader<ChildStdout>,
+    transcript: &mut Vec<String>,
+    done: impl Fn(&Json) -> bool,
+) {
+    loop {
+        let message = read_message(output).expect("tron ended early").unwrap();
+        transcript.push(message.to_string());
+        if done(&message) {
+            return;
+        }
+    }
+}
+
+// the transcript of a session, one message a line, with paths under the repository as `<root>`
+pub fn transcript(messages: &[String]) -> String {
+    let root = env!("CARGO_MANIFEST_DIR");
+    messages
+        .iter()
+        .map(|message| format!("{}\n", message.replace(root, "<root>")))
+        .collect()
+}
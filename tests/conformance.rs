@@ -0,0 +1,106 @@
+// runs every program in tests/conformance with both engines, optimized and as written, and compares
+// what they print and how they exit with the expected `.out` file next to it; `TRON_BLESS=1`
+// rewrites the `.out` files
+mod common;
+
+use common::{inputs, Expected};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const ENGINES: &[&str] = &["tree", "vm"];
+
+// stdout followed by the exit code, which is what the `.out` files hold
+fn run(engine: &str, optimize: bool, program: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--no-color", "--engine", engine])
+        .args((!optimize).then_some("--no-optimize"))
+        .arg("run")
+        .arg(program)
+        .output()
+        .unwrap();
+    format!(
+        "{}-- exit {}\n",
+        String::from_utf8_lossy(&output.stdout),
+        output.status.code().unwrap_or(-1)
+    )
+}
+
+#[test]
+fn engines_agree_with_expected_output() {
+    let mut expected = Expected::new();
+    for program in inputs("conformance", "tron") {
+        let expected_path = program.with_extension("out");
+        for engine in ENGINES {
+            for optimize in [true, false] {
+                let actual = run(engine, optimize, &program);
+                let name = format!(
+                    "{} with --engine={}{}",
+                    program.display(),
+                    engine,
+                    if optimize { "" } else { " --no-optimize" }
+                );
+                // the first run is what's blessed, the others have to agree with it
+                if *engine == ENGINES[0] && optimize {
+                    expected.check(name, &expected_path, &actual);
+                } else {
+                    expected.compare(name, &expected_path, &actual);
+                }
+            }
+        }
+    }
+    expected.assert();
+}
+
+// generated rather than kept in tests/conformance, where every other test would read it too
+#[test]
+fn array_literals_longer_than_16_bits_count() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("conformance");
+    fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("large_array.tron");
+    let elements = vec!["1"; 70_000].join(", ");
+    fs::write(
+        &program,
+        format!(
+            "let ones: array = [{}];\n@print(@length(ones), ones[69999]);\n",
+            elements
+        ),
+    )
+    .unwrap();
+    for engine in ENGINES {
+        for optimize in [true, false] {
+            assert_eq!(
+                run(engine, optimize, &program),
+                "70000\n1\n-- exit 0\n",
+                "--engine={}",
+                engine
+            );
+        }
+    }
+}
+
+// `items = @push(items, x)` changes the array where it is; copying it on every push made such
+// loops take quadratic time, minutes for this one
+#[test]
+fn pushing_in_a_loop_doesnt_copy_the_array() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("conformance");
+    fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("pushes.tron");
+    fs::write(
+        &program,
+        "let items: array = [];\nlet i: number = 0;\nwhile i < 50000 {\n    items = @push(items, i);\n    i = i + 1;\n}\n@print(@length(items));\n",
+    )
+    .unwrap();
+    for engine in ENGINES {
+        let start = Instant::now();
+        assert_eq!(run(engine, true, &program), "50000\n-- exit 0\n");
+        assert!(
+            start.elapsed() < Duration::from_secs(20),
+            "--engine={} took {:?}",
+            engine,
+            start.elapsed()
+        );
+    }
+}
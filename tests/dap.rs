@@ -0,0 +1,57 @@
+// drives `tron dap` with the client messages recorded in each `tests/dap/*.in` file and compares
+// everything the adapter sends with the `.out` file next to it; `TRON_BLESS=1` rewrites them
+//
+// After each request the harness waits for its response, and a `{"wait":"<event>"}` line waits
+// for that event, so the transcript doesn't depend on timing. Paths under the repository are
+// written as `<root>`.
+mod common;
+
+use common::{inputs, read_until, transcript, Expected};
+use std::fs;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tron_lang::utils::json::Json;
+use tron_lang::utils::protocol::write_message;
+
+fn run(session: &Path) -> String {
+    let mut adapter = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("dap")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = adapter.stdin.take().unwrap();
+    let mut output = BufReader::new(adapter.stdout.take().unwrap());
+    let mut messages = vec![];
+    for line in fs::read_to_string(session).unwrap().lines() {
+        let message = Json::parse(line).unwrap();
+        if let Some(event) = message.get("wait").cloned() {
+            read_until(&mut output, &mut messages, |message| {
+                message.get("event") == Some(&event)
+            });
+            continue;
+        }
+        write_message(&mut input, &message).unwrap();
+        let seq = message.get("seq").cloned();
+        read_until(&mut output, &mut messages, |message| {
+            message.get("type").and_then(Json::as_str) == Some("response")
+                && message.get("request_seq") == seq.as_ref()
+        });
+    }
+    input.flush().unwrap();
+    drop(input);
+    adapter.wait().unwrap();
+    transcript(&messages)
+}
+
+#[test]
+fn recorded_sessions() {
+    let mut expected = Expected::new();
+    for session in inputs("dap", "in") {
+        let actual = run(&session);
+        expected.check(session.display(), &session.with_extension("out"), &actual);
+    }
+    expected.assert();
+}
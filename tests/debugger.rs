@@ -0,0 +1,81 @@
+// `tron debug` driven through stdin, checking what it prints
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const PROGRAM: &str = "tests/debugger/add.tron";
+
+// stdout and the exit code of a session that reads `commands`
+fn debug(commands: &str) -> (String, i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--no-color", "debug", PROGRAM])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(commands.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        output.status.code().unwrap_or(-1),
+    )
+}
+
+#[test]
+fn breakpoints_pause_with_locals_and_the_stack() {
+    let (stdout, status) =
+        debug("break 2\ncontinue\nlocals\nstack\nprint a * 10\nglobals\ncontinue\n");
+    assert_eq!(status, 0);
+    assert_eq!(
+        stdout,
+        "paused before the first statement at tests/debugger/add.tron:1
+>    1 | fn add(a: number, b: number): number {
+(tron) breakpoint at tests/debugger/add.tron:2
+(tron) paused at a breakpoint at tests/debugger/add.tron:2
+>    2 |     let sum: number = a + b;
+(tron) scope 0:
+  a = 1
+  b = 2
+(tron) #0 add (tests/debugger/add.tron:2)
+#1 <script> (tests/debugger/add.tron:6)
+(tron) 10
+(tron)   add = add/2
+  x = 1
+(tron) 3
+the program finished
+"
+    );
+}
+
+#[test]
+fn steps_go_into_over_and_out_of_calls() {
+    let (stdout, _) = debug("next\nnext\nstep\nnext\nout\nquit\n");
+    let paused: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.split(" at tests/debugger/add.tron:").nth(1))
+        .collect();
+    assert_eq!(paused, ["1", "5", "6", "2", "3", "7"]);
+}
+
+#[test]
+fn quitting_ends_the_program() {
+    let (stdout, status) = debug("quit\n");
+    assert_eq!(status, 0);
+    assert!(!stdout.contains("the program finished"));
+}
+
+#[test]
+fn builtins_can_be_called_while_paused() {
+    let (stdout, _) = debug("break 2\ncontinue\nprint @length(\"four\") + b\nglobals\nquit\n");
+    let printed: Vec<&str> = stdout.lines().skip(5).collect();
+    // `globals` still lists only the program's own
+    assert_eq!(
+        printed,
+        ["(tron) 6", "(tron)   add = add/2", "  x = 1", "(tron) "]
+    );
+}
@@ -0,0 +1,196 @@
+// tron as a library: errors in the code it runs come back from `run`, `eval` and `Vm::run` as
+// `Err`, and never print or end the host process
+use std::collections::HashMap;
+use std::thread;
+use tron_lang::interpreter::limit_stack;
+use tron_lang::vm::Vm;
+use tron_lang::{Interpreter, Sandbox, TronType};
+
+fn eval_error(code: &str) -> String {
+    let error = Interpreter::new().eval(code).unwrap_err();
+    let vm_error = Vm::new().run(code).unwrap_err();
+    assert_eq!(error.code, vm_error.code, "{}", code);
+    error.code
+}
+
+#[test]
+fn errors_are_returned() {
+    assert_eq!(eval_error("@print(missing);"), "E4011");
+    assert_eq!(eval_error(r#"let n: number = "one";"#), "E4003");
+    assert_eq!(eval_error(r#"1 - "one";"#), "E4015");
+    assert_eq!(eval_error("1 +;"), "E2003");
+    // the ones the binary exits on
+    assert_eq!(eval_error("@length(1, 2);"), "E4020");
+    assert_eq!(eval_error(r#"@panic("stop");"#), "E4035");
+}
+
+#[test]
+fn code_after_an_error_doesnt_run() {
+    let mut tron = Interpreter::new();
+    tron.set_global("count", TronType::Number(0.0));
+    let error = tron
+        .run("count = 1; @print(missing); count = 2;")
+        .unwrap_err();
+    assert_eq!((error.code.as_str(), error.line), ("E4011", 1));
+    assert_eq!(tron.get_global("count"), Some(TronType::Number(1.0)));
+    // and the interpreter is still usable
+    assert_eq!(tron.eval("count + 1;").unwrap().as_number(), Some(2.0));
+}
+
+#[test]
+fn try_still_catches() {
+    let code = r#"
+        let caught: string = "";
+        try { let n: number = "one"; } catch (e) { caught = e.code; }
+        caught;
+    "#;
+    let value = Interpreter::new().eval(code).unwrap();
+    assert_eq!(value.as_str(), Some("E4003"));
+}
+
+// a `std::thread` gets 2MiB of native stack, far less than the `tron` binary gives its program
+// thread, and deep recursion ends with E4028 rather than overflowing it
+#[test]
+fn deep_recursion_on_small_threads() {
+    let code = "fn down(n: number): number {
+    if n == 0 { return 0; }
+    let below: number = down(n - 1);
+    return below + 1;
+}
+down(5000);";
+    let tree = thread::spawn(move || Interpreter::new().run(code).map_err(|e| e.code));
+    assert_eq!(tree.join().unwrap(), Err("E4028".to_string()));
+    // the vm keeps its calls off the native stack
+    let vm = thread::spawn(move || Vm::new().run(code).map_err(|e| e.code));
+    assert_eq!(vm.join().unwrap(), Ok(()));
+
+    // given a bigger thread, `limit_stack` lets the tree interpreter use it
+    let tree = thread::Builder::new()
+        .stack_size(1024 * 1024 * 1024)
+        .spawn(move || {
+            limit_stack(1024 * 1024 * 1024);
+            Interpreter::new().run(code).map_err(|e| e.code)
+        })
+        .unwrap();
+    assert_eq!(tree.join().unwrap(), Ok(()));
+}
+
+#[test]
+fn max_memory_needs_the_counting_allocator() {
+    // this test binary doesn't install it
+    let mut tron = Interpreter::new();
+    let error = tron
+        .set_sandbox(Sandbox {
+            max_memory: Some(1 << 20),
+            ..Sandbox::strict()
+        })
+        .unwrap_err();
+    assert_eq!(error.code, "E0015");
+    // and nothing of that sandbox was applied
+    assert!(tron.eval("@args();").is_ok());
+    assert!(tron.set_sandbox(Sandbox::strict()).is_ok());
+    assert_eq!(tron.eval("@args();").unwrap_err().code, "E4030");
+}
+
+// the error stops the program, and embedded nothing it guarded happens before `run` returns
+#[test]
+fn strict_sandboxes_refuse_side_effects() {
+    let marker = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("embedding_cmd_ran");
+    let _ = std::fs::remove_file(&marker);
+    let cmd = format!(r#"@cmd("touch {}");"#, marker.display());
+    let library = r#"use "tests/conformance/lib/math.tron";"#;
+
+    let mut tron = Interpreter::new();
+    tron.set_sandbox(Sandbox::strict()).unwrap();
+    for code in [cmd.as_str(), r#"@ask("name?");"#, library] {
+        assert_eq!(tron.run(code).unwrap_err().code, "E4030", "{}", code);
+        assert_eq!(Vm::new().run(code).unwrap_err().code, "E4030", "{}", code);
+    }
+    assert!(!marker.exists());
+    assert_eq!(tron.get_global("square"), None);
+    let mut vm = Vm::new();
+    assert!(vm.run(library).is_err());
+    assert_eq!(vm.get_global("square"), None);
+
+    // as opposed to without the sandbox
+    tron.set_sandbox(Sandbox::default()).unwrap();
+    tron.run(library).unwrap();
+    assert!(tron.get_global("square").is_some());
+}
+
+// natives registered from plain Rust fns take their arity and argument types from the signature
+#[test]
+fn natives_check_their_arguments() {
+    let mut tron = Interpreter::new();
+    tron.register_native("@add", |a: f64, b: f64| a + b);
+    tron.register_native("@pad", |text: String, width: Option<f64>| {
+        format!("{:>1$}", text, width.unwrap_or(0.0) as usize)
+    });
+    assert_eq!(tron.eval("@add(1, 2);").unwrap().as_number(), Some(3.0));
+    assert_eq!(tron.eval(r#"@pad("x", 3);"#).unwrap().as_str(), Some("  x"));
+    assert_eq!(tron.eval(r#"@pad("x");"#).unwrap().as_str(), Some("x"));
+
+    let error = tron.eval(r#"@add(1, "2");"#).unwrap_err();
+    assert_eq!(error.code, "E4021");
+    assert!(
+        error.message.contains("number type as second argument"),
+        "{}",
+        error.message
+    );
+    assert_eq!(tron.eval(r#"@pad(1);"#).unwrap_err().code, "E4021");
+    assert_eq!(tron.eval("@add(1);").unwrap_err().code, "E4020");
+    assert_eq!(tron.eval("@add(1, 2, 3);").unwrap_err().code, "E4020");
+    assert_eq!(tron.eval(r#"@pad();"#).unwrap_err().code, "E4018");
+    assert_eq!(tron.eval(r#"@pad("x", 1, 2);"#).unwrap_err().code, "E4023");
+}
+
+#[test]
+fn natives_convert_containers() {
+    let mut tron = Interpreter::new();
+    tron.register_native("@first", |values: Vec<String>| values.into_iter().next());
+    tron.register_native("@doubled", |fields: HashMap<String, f64>| {
+        fields
+            .into_iter()
+            .map(|(key, value)| (key, value * 2.0))
+            .collect::<HashMap<_, _>>()
+    });
+    tron.register_native("@words", |text: String| {
+        text.split(' ').map(str::to_string).collect::<Vec<_>>()
+    });
+    assert_eq!(
+        tron.eval(r#"@first(["a", "b"]);"#).unwrap().as_str(),
+        Some("a")
+    );
+    assert_eq!(tron.eval("@first([]);").unwrap(), TronType::Null);
+    let doubled = tron.eval("@doubled({ a: 1, b: 2.5 });").unwrap();
+    let doubled = doubled.as_object().unwrap();
+    assert_eq!(doubled["a"].as_number(), Some(2.0));
+    assert_eq!(doubled["b"].as_number(), Some(5.0));
+    let words = tron.eval(r#"@words("a b");"#).unwrap();
+    let words: Vec<_> = words
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| w.as_str())
+        .collect();
+    assert_eq!(words, [Some("a"), Some("b")]);
+    // every element is checked, not just the container
+    assert_eq!(tron.eval(r#"@first(["a", 1]);"#).unwrap_err().code, "E4021");
+    assert_eq!(
+        tron.eval(r#"@doubled({ a: "1" });"#).unwrap_err().code,
+        "E4021"
+    );
+}
+
+// tron numbers are f32, so an f64 result is rounded to the nearest one
+#[test]
+fn native_f64_results_are_narrowed() {
+    let mut tron = Interpreter::new();
+    tron.register_native("@tenth", || 0.1f64);
+    tron.register_native("@huge", || 1e300f64);
+    assert_eq!(tron.eval("@tenth();").unwrap().as_number(), Some(0.1f32));
+    assert_eq!(
+        tron.eval("@huge();").unwrap().as_number(),
+        Some(f32::INFINITY)
+    );
+}
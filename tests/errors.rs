@@ -0,0 +1,196 @@
+// the machine-readable `--error-format`s: each diagnostic's code, severity, message, file and span
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tron_lang::utils::json::Json;
+
+fn tron(dir: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn program(name: &str, source: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("errors")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.tron"), source).unwrap();
+    dir
+}
+
+#[test]
+fn json() {
+    let dir = program("json", "fn f(): number {\n    return 1;\n}\nbreak;\n");
+    assert_eq!(
+        tron(&dir, &["--error-format", "json", "run", "main.tron"]),
+        (
+            1,
+            concat!(
+                r#"{"code":"E3007","severity":"error","message":"break isn't allowed outside of a loop","#,
+                r#""file":"main.tron","line":4,"#,
+                r#""span":{"start":{"line":4,"column":1},"end":{"line":4,"column":6}}}"#,
+                "\n"
+            )
+            .to_string()
+        )
+    );
+
+    // errors about tron itself aren't in a file
+    assert_eq!(
+        tron(&dir, &["--error-format", "json", "run", "missing.tron"]),
+        (
+            1,
+            r#"{"code":"E0001","severity":"error","message":"failed to run file","file":null,"line":0,"span":null}"#
+                .to_string()
+                + "\n"
+        )
+    );
+}
+
+#[test]
+fn sarif() {
+    let dir = program(
+        "sarif",
+        "let name: string = \"tron\";\n@print(name.size);\n",
+    );
+    for engine in ["tree", "vm"] {
+        let (code, stderr) = tron(
+            &dir,
+            &[
+                "--engine",
+                engine,
+                "--error-format",
+                "sarif",
+                "run",
+                "main.tron",
+            ],
+        );
+        assert_eq!(code, 1, "--engine={}", engine);
+        let log = Json::parse(stderr.trim()).unwrap();
+        assert_eq!(log.get("version").and_then(Json::as_str), Some("2.1.0"));
+        let run = &log.get("runs").and_then(Json::as_array).unwrap()[0];
+        let driver = run.get("tool").and_then(|tool| tool.get("driver")).unwrap();
+        assert_eq!(driver.get("name").and_then(Json::as_str), Some("tron"));
+        assert_eq!(
+            run.get("results").map(Json::to_string),
+            Some(
+                concat!(
+                    r#"[{"ruleId":"E4032","level":"error","#,
+                    r#""message":{"text":"can't read key size of a value that isn't an object"},"#,
+                    r#""locations":[{"physicalLocation":{"artifactLocation":{"uri":"main.tron"},"#,
+                    r#""region":{"startLine":2}}}]}]"#
+                )
+                .to_string()
+            ),
+            "--engine={}",
+            engine
+        );
+    }
+}
+
+#[test]
+fn sarif_with_spans_and_traces() {
+    let dir = program(
+        "traces",
+        "fn f(): number {\n    return @length(1);\n}\nf();\nlet x: number = 1 +;\n",
+    );
+    let (code, stderr) = tron(&dir, &["--error-format", "sarif", "run", "main.tron"]);
+    assert_eq!(code, 1);
+    let log = Json::parse(stderr.trim()).unwrap();
+    let run = &log.get("runs").and_then(Json::as_array).unwrap()[0];
+    let results = run.get("results").and_then(Json::as_array).unwrap();
+    assert_eq!(
+        results[0].get("locations").map(Json::to_string),
+        Some(
+            concat!(
+                r#"[{"physicalLocation":{"artifactLocation":{"uri":"main.tron"},"#,
+                r#""region":{"startLine":5,"startColumn":20,"endLine":5,"endColumn":21}}}]"#
+            )
+            .to_string()
+        )
+    );
+    assert_eq!(
+        results.last().unwrap().get("stacks").map(Json::to_string),
+        Some(
+            concat!(
+                r#"[{"frames":[{"location":{"logicalLocations":[{"name":"@length"}]}},"#,
+                r#"{"location":{"physicalLocation":{"artifactLocation":{"uri":"main.tron"},"#,
+                r#""region":{"startLine":2}},"logicalLocations":[{"name":"f"}]}},"#,
+                r#"{"location":{"physicalLocation":{"artifactLocation":{"uri":"main.tron"},"#,
+                r#""region":{"startLine":4}},"logicalLocations":[{"name":"<script>"}]}}]}]"#
+            )
+            .to_string()
+        )
+    );
+}
+
+#[test]
+fn sarif_without_a_line() {
+    // assignments don't say which line failed their type check
+    let dir = program(
+        "lineless",
+        "fn f(): number {\n    let n: number = 1;\n    n = \"one\";\n    return n;\n}\nf();\n",
+    );
+    for engine in ["tree", "vm"] {
+        let (_, stderr) = tron(
+            &dir,
+            &[
+                "--engine",
+                engine,
+                "--error-format",
+                "sarif",
+                "run",
+                "main.tron",
+            ],
+        );
+        let log = Json::parse(stderr.trim()).unwrap();
+        let run = &log.get("runs").and_then(Json::as_array).unwrap()[0];
+        let result = &run.get("results").and_then(Json::as_array).unwrap()[0];
+        // a region has to start at line 1 or later, so there is none
+        assert_eq!(
+            result.get("locations").map(Json::to_string),
+            Some(r#"[{"physicalLocation":{"artifactLocation":{"uri":"main.tron"}}}]"#.to_string()),
+            "--engine={}",
+            engine
+        );
+        assert_eq!(
+            result.get("stacks").map(Json::to_string),
+            Some(
+                concat!(
+                    r#"[{"frames":[{"location":{"physicalLocation":{"#,
+                    r#""artifactLocation":{"uri":"main.tron"}},"logicalLocations":[{"name":"f"}]}},"#,
+                    r#"{"location":{"physicalLocation":{"artifactLocation":{"uri":"main.tron"},"#,
+                    r#""region":{"startLine":6}},"logicalLocations":[{"name":"<script>"}]}}]}]"#
+                )
+                .to_string()
+            ),
+            "--engine={}",
+            engine
+        );
+    }
+}
+
+#[test]
+fn no_colors_when_asked_or_not_a_terminal() {
+    let dir = program("colors", "break;\n");
+    let expected = (
+        1,
+        "[E3007] break isn't allowed outside of a loop (line 1)\n".to_string(),
+    );
+    assert_eq!(tron(&dir, &["run", "main.tron"]), expected);
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(&dir)
+        .env("NO_COLOR", "1")
+        .args(["run", "main.tron"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stderr), expected.1);
+}
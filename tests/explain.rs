@@ -0,0 +1,143 @@
+// the error registry: `errors.md` is generated from it, `TRON_BLESS=1` rewrites it, and every
+// example program reports its error while its fix runs without one
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tron_lang::utils::errors::{is_command, markdown, message, ERRORS};
+
+fn tron(dir: &Path, args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(dir)
+        .args(["--no-color"])
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[test]
+fn errors_md() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("errors.md");
+    if std::env::var_os("TRON_BLESS").is_some() {
+        fs::write(&path, markdown()).unwrap();
+    }
+    assert!(
+        fs::read_to_string(&path).unwrap() == markdown(),
+        "errors.md is out of date; run `TRON_BLESS=1 cargo test --test explain`"
+    );
+}
+
+#[test]
+fn codes_are_unique_and_in_order() {
+    for pair in ERRORS.windows(2) {
+        assert!(
+            pair[0].code < pair[1].code,
+            "{} before {}",
+            pair[0].code,
+            pair[1].code
+        );
+    }
+}
+
+#[test]
+fn messages() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        message("E4020", &args(&["add", "2 arguments"])),
+        "add requires exactly 2 arguments"
+    );
+    assert_eq!(
+        message("E1004", &args(&["number too large"])),
+        "failed to scan tokens: \n number too large"
+    );
+    assert_eq!(message("E4020", &args(&["add"])), "unknown error");
+    assert_eq!(message("E9999", &[]), "unknown error");
+}
+
+// what `main.tron` holds for the examples that name it
+const MAIN: &str = "let count: number = 0;
+while count < 100 {
+    count = count + 1;
+}
+@print(@args());
+";
+
+// runs an example in `dir`, a program as `wrong.tron` or `fixed.tron` and a command as it is
+fn example(dir: &Path, name: &str, example: &str) -> (i32, String) {
+    let (code, _, stderr) = match example.strip_prefix("$ tron ") {
+        Some(command) => {
+            let args: Vec<&str> = command.split(' ').collect();
+            tron(
+                dir,
+                &[&["--error-format", "json"], args.as_slice()].concat(),
+            )
+        }
+        None => {
+            let file = format!("{}.tron", name);
+            fs::write(dir.join(&file), example).unwrap();
+            tron(dir, &["--error-format", "json", "run", &file])
+        }
+    };
+    (code, stderr)
+}
+
+#[test]
+fn examples() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("explain");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for error in ERRORS {
+        let (Some(wrong), Some(fixed)) = (error.wrong, error.fixed) else {
+            continue;
+        };
+        assert_eq!(is_command(wrong), is_command(fixed), "{}", error.code);
+        fs::write(dir.join("main.tron"), MAIN).unwrap();
+        let (_, stderr) = example(&dir, "wrong", wrong);
+        // errors in parsing the command line come before `--error-format` is known
+        assert!(
+            stderr.lines().any(|line| {
+                line.starts_with(&format!("{{\"code\":\"{}\"", error.code))
+                    || line.starts_with(&format!("[{}]", error.code))
+            }),
+            "{}: {}",
+            error.code,
+            stderr
+        );
+        assert_eq!(
+            example(&dir, "fixed", fixed),
+            (0, String::new()),
+            "{}",
+            error.code
+        );
+    }
+}
+
+#[test]
+fn cli() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(
+        tron(dir, &["explain", "e3006"]),
+        (
+            0,
+            "E3006: return isn't allowed outside of a function\n\n\
+             `return` ends a function call, so it can only be used in a function body. A program \
+             ends after its last statement.\n\n\
+             wrong:\n    return 1;\n\n\
+             fixed:\n    fn one(): number {\n        return 1;\n    }\n"
+                .to_string(),
+            String::new()
+        )
+    );
+    assert_eq!(
+        tron(dir, &["explain", "E9999"]),
+        (
+            1,
+            String::new(),
+            "[E0013] unknown error code E9999\n".to_string()
+        )
+    );
+}
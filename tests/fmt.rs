@@ -0,0 +1,68 @@
+// formats every `tests/fmt/*.tron` file and compares the result with the `.out` file next to it;
+// `TRON_BLESS=1` rewrites them
+mod common;
+
+use common::{inputs, Expected};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tron_lang::formatter::format;
+
+#[test]
+fn formatted_sources() {
+    let mut expected = Expected::new();
+    for source in inputs("fmt", "tron") {
+        let actual = format(&fs::read_to_string(&source).unwrap()).unwrap();
+        expected.check(source.display(), &source.with_extension("out"), &actual);
+        assert_eq!(
+            format(&actual).unwrap(),
+            actual,
+            "formatting {} again changed it",
+            source.display()
+        );
+    }
+    expected.assert();
+}
+
+fn fmt(args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--no-color", "fmt"])
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+    )
+}
+
+#[test]
+fn check_fails_until_formatted() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fmt");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("layout.tron");
+    fs::copy("tests/fmt/layout.tron", &file).unwrap();
+    let path = file.to_str().unwrap();
+
+    assert_eq!(fmt(&["--check", path]), (1, format!("{}\n", path)));
+    assert_eq!(fmt(&["--quiet", path]), (0, String::new()));
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        fs::read_to_string("tests/fmt/layout.out").unwrap()
+    );
+    assert_eq!(fmt(&["--check", dir.to_str().unwrap()]), (0, String::new()));
+}
+
+#[test]
+fn syntax_errors_are_left_alone() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fmt_errors");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("broken.tron");
+    fs::write(&file, "let x: number = ;\n").unwrap();
+
+    assert_eq!(fmt(&[file.to_str().unwrap()]).0, 1);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "let x: number = ;\n");
+}
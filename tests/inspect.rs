@@ -0,0 +1,103 @@
+// `tron tokens`, `tron ast` and `tron resolve` on `tests/inspect/program.tron`, compared with the
+// file of each one's output next to it; `TRON_BLESS=1` rewrites them
+mod common;
+
+use common::Expected;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tron_lang::utils::json::Json;
+
+const PROGRAM: &str = "tests/inspect/program.tron";
+
+fn tron(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("--no-color")
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+#[test]
+fn outputs() {
+    let mut expected = Expected::new();
+    for (args, extension) in [
+        (&["tokens"][..], "tokens"),
+        (&["ast"][..], "ast"),
+        (&["ast", "--json"][..], "json"),
+        (&["resolve"][..], "resolve"),
+    ] {
+        let (code, actual, stderr) = tron(&[args, &[PROGRAM]].concat());
+        assert_eq!((code, stderr.as_str()), (0, ""), "{}", args.join(" "));
+        let expected_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(PROGRAM)
+            .with_extension(extension);
+        expected.check(format!("tron {}", args.join(" ")), &expected_path, &actual);
+    }
+    expected.assert();
+}
+
+#[test]
+fn json_schema() {
+    let (_, stdout, _) = tron(&["ast", "--json", PROGRAM]);
+    let ast = Json::parse(stdout.trim()).unwrap();
+    assert_eq!(ast.get("version").and_then(Json::as_f64), Some(1.0));
+    let statements = ast.get("statements").and_then(Json::as_array).unwrap();
+    let kinds: Vec<&str> = statements
+        .iter()
+        .filter_map(|statement| statement.get("kind").and_then(Json::as_str))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "UseStatement",
+            "VariableStatement",
+            "FunctionStatement",
+            "VariableStatement",
+            "IfStatement",
+            "SwitchStatement",
+            "WhileStatement"
+        ]
+    );
+    // tokens point back into the source
+    let source = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(PROGRAM)).unwrap();
+    let name = statements[2].get("name").unwrap();
+    let span = |key: &str| name.get(key).and_then(Json::as_f64).unwrap() as usize;
+    assert_eq!(&source[span("start")..span("end")], "scale");
+    assert_eq!(name.get("text").and_then(Json::as_str), Some("scale"));
+}
+
+#[test]
+fn errors() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("inspect");
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("broken.tron");
+    fs::write(&file, "let broken: number = 1 +;\n").unwrap();
+    let file = file.to_string_lossy();
+    assert_eq!(
+        tron(&["ast", &file]),
+        (
+            1,
+            String::new(),
+            concat!(
+                "[E2003] unexpected token: Semicolon (line 1)\n",
+                "[E2003] unexpected token: Expected ';' after variable declaration (line 1)\n"
+            )
+            .to_string()
+        )
+    );
+    assert_eq!(
+        tron(&["tokens", "missing.tron"]),
+        (
+            1,
+            String::new(),
+            "[E0014] failed to read missing.tron\n".to_string()
+        )
+    );
+}
@@ -0,0 +1,190 @@
+// `tron update --from`, `tron update --rollback` and `tron uninstall` against an install in
+// `TRON_HOME`, and the sha-256 that release bundles are verified with
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tron_lang::utils::sha256::sha256_hex;
+
+#[test]
+fn sha256_known_answers() {
+    // padding is the tricky part: 55 bytes still fit the length in one block, 56 and 64 don't
+    let vectors: &[(&[u8], &str)] = &[
+        (
+            b"",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            &[b'a'; 55],
+            "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+        ),
+        (
+            &[b'a'; 56],
+            "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+        ),
+        (
+            &[b'a'; 64],
+            "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+        ),
+        (
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+        (
+            &[b'a'; 1000],
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3",
+        ),
+    ];
+    for (data, digest) in vectors {
+        assert_eq!(sha256_hex(data), *digest, "{} bytes", data.len());
+    }
+}
+
+fn tron(home: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .env("TRON_HOME", home)
+        .args(["--no-color"])
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("install")
+        .join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// a release bundle with the given files and their SHA256SUMS
+fn bundle(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = dir(name);
+    let mut sums = String::new();
+    for (path, contents) in files {
+        fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+        fs::write(dir.join(path), contents).unwrap();
+        sums += &format!("{}  {}\n", sha256_hex(contents.as_bytes()), path);
+    }
+    fs::write(dir.join("SHA256SUMS"), sums).unwrap();
+    dir
+}
+
+fn read(path: PathBuf) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+#[test]
+fn update_rollback_and_uninstall() {
+    let home = dir("home");
+    let v1 = bundle("v1", &[("bin/tron", "v1"), ("lib/old.tron", "old")]);
+    let v2 = bundle("v2", &[("bin/tron", "v2"), ("lib/new.tron", "new")]);
+
+    assert_eq!(
+        tron(&home, &["update", "--from", v1.to_str().unwrap()]).0,
+        0
+    );
+    assert_eq!(read(home.join("bin/tron")).as_deref(), Some("v1"));
+
+    assert_eq!(
+        tron(&home, &["update", "--from", v2.to_str().unwrap()]).0,
+        0
+    );
+    assert_eq!(read(home.join("bin/tron")).as_deref(), Some("v2"));
+    assert_eq!(read(home.join("lib/new.tron")).as_deref(), Some("new"));
+    assert_eq!(read(home.join("lib/old.tron")), None);
+
+    assert_eq!(tron(&home, &["update", "--rollback"]).0, 0);
+    assert_eq!(read(home.join("bin/tron")).as_deref(), Some("v1"));
+    assert_eq!(read(home.join("lib/old.tron")).as_deref(), Some("old"));
+    assert_eq!(read(home.join("lib/new.tron")), None);
+    let (code, stderr) = tron(&home, &["update", "--rollback"]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("[E0006]"), "{}", stderr);
+
+    // files that aren't tron's are left alone
+    fs::write(home.join("notes.txt"), "mine").unwrap();
+    assert_eq!(tron(&home, &["uninstall"]).0, 0);
+    assert_eq!(read(home.join("bin/tron")), None);
+    assert_eq!(read(home.join("lib/old.tron")), None);
+    assert_eq!(read(home.join(".tron-manifest")), None);
+    assert_eq!(read(home.join("notes.txt")).as_deref(), Some("mine"));
+    let (code, stderr) = tron(&home, &["uninstall"]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("[E0007]"), "{}", stderr);
+}
+
+#[test]
+fn bundles_that_fail_verification_install_nothing() {
+    let home = dir("tampered_home");
+    let tampered = bundle("tampered", &[("bin/tron", "v1"), ("lib/std.tron", "std")]);
+    fs::write(tampered.join("lib/std.tron"), "changed").unwrap();
+    let (code, stderr) = tron(&home, &["update", "--from", tampered.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(
+        stderr.contains("[E0005] checksum mismatch: lib/std.tron"),
+        "{}",
+        stderr
+    );
+    assert_eq!(read(home.join("bin/tron")), None);
+
+    // an archive is unpacked, and a single top level directory in it is looked into
+    let release = bundle("release/tron-2.0.0", &[("bin/tron", "v2")]);
+    let archive = release.parent().unwrap().with_extension("tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(release.parent().unwrap())
+        .arg("tron-2.0.0")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        tron(&home, &["update", "--from", archive.to_str().unwrap()]).0,
+        0
+    );
+    assert_eq!(read(home.join("bin/tron")).as_deref(), Some("v2"));
+}
+
+// an update that stops part way puts the installed version back
+#[test]
+fn failed_installs_are_undone() {
+    let home = dir("interrupted_home");
+    let v1 = bundle(
+        "interrupted_v1",
+        &[("bin/tron", "v1"), ("lib/old.tron", "old")],
+    );
+    let v2 = bundle(
+        "interrupted_v2",
+        &[
+            ("bin/tron", "v2"),
+            ("lib/new.tron", "new"),
+            ("lib/std.tron", "std"),
+        ],
+    );
+    assert_eq!(
+        tron(&home, &["update", "--from", v1.to_str().unwrap()]).0,
+        0
+    );
+    // a directory where the update puts a file stops it after bin/tron and lib/new.tron
+    fs::create_dir_all(home.join("lib/std.tron/mine")).unwrap();
+
+    let (code, stderr) = tron(&home, &["update", "--from", v2.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("[E0004]"), "{}", stderr);
+    assert_eq!(read(home.join("bin/tron")).as_deref(), Some("v1"));
+    assert_eq!(read(home.join("lib/old.tron")).as_deref(), Some("old"));
+    assert_eq!(read(home.join("lib/new.tron")), None);
+    assert!(home.join("lib/std.tron/mine").is_dir());
+    let mut leftovers: Vec<_> = fs::read_dir(home.join("lib"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    leftovers.sort();
+    assert_eq!(leftovers, ["old.tron", "std.tron"]);
+}
@@ -0,0 +1,134 @@
+// lints every `tests/lint/*.tron` file and compares what the rules find, and the file with their
+// fixes applied, with the `.out` file next to it; `TRON_BLESS=1` rewrites them
+mod common;
+
+use common::{inputs, Expected};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tron_lang::lint::{apply_fixes, lint, Config};
+
+#[test]
+fn findings() {
+    let mut expected = Expected::new();
+    for source in inputs("lint", "tron") {
+        let text = fs::read_to_string(&source).unwrap();
+        let findings = lint(&text, &Config::default(), |_| None).unwrap();
+        let mut actual = String::new();
+        for finding in &findings {
+            actual.push_str(&format!(
+                "{}: {}[{}] {}{}\n",
+                finding.line,
+                finding.level.name(),
+                finding.rule,
+                finding.message,
+                if finding.fix.is_some() {
+                    " (fixable)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        let fixed = apply_fixes(&text, &findings);
+        if fixed != text {
+            actual.push_str(&format!("--- fixed\n{}", fixed));
+            let again = lint(&fixed, &Config::default(), |_| None).unwrap();
+            assert!(
+                again.iter().all(|finding| finding.fix.is_none()),
+                "fixing {} left fixable findings",
+                source.display()
+            );
+        }
+        expected.check(source.display(), &source.with_extension("out"), &actual);
+    }
+    expected.assert();
+}
+
+#[test]
+fn config() {
+    let config = Config::parse(
+        "# project settings\n[package]\nname = \"demo\"\n\n[lint]\nshadowing = \"error\" # strict\nunused_variable = \"allow\"\n",
+    )
+    .unwrap();
+    let findings = lint(
+        "fn f(x: number): number {\n    let x: number = 1;\n    let y: number = 2;\n    return x;\n}\nf(1);\n",
+        &config,
+        |_| None,
+    )
+    .unwrap();
+    let found: Vec<(&str, &str)> = findings
+        .iter()
+        .map(|finding| (finding.rule, finding.level.name()))
+        .collect();
+    assert_eq!(
+        found,
+        [("unused_parameter", "warning"), ("shadowing", "error")]
+    );
+
+    assert_eq!(
+        Config::parse("[lint]\nshadowed = \"allow\"\n").unwrap_err(),
+        "line 2: unknown lint rule `shadowed`"
+    );
+    assert_eq!(
+        Config::parse("[lint]\nshadowing = \"off\"\n").unwrap_err(),
+        "line 2: expected \"allow\", \"warn\" or \"error\" for `shadowing`, found \"off\""
+    );
+}
+
+fn tron_lint(dir: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(dir)
+        .args(["--no-color", "lint"])
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+    )
+}
+
+#[test]
+fn cli() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lint");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("main.tron"),
+        "fn f(): number {\n    let unused: number = 1;\n    return 1;\n    @print(2);\n}\n@print(f());\n",
+    )
+    .unwrap();
+
+    // warnings don't fail
+    assert_eq!(
+        tron_lint(&dir, &[]),
+        (
+            0,
+            "./main.tron:2:9: warning[unused_variable] variable `unused` is never used\n\
+             ./main.tron:4:5: warning[unreachable_code] unreachable code after `return`\n"
+                .to_string()
+        )
+    );
+
+    fs::write(
+        dir.join("tron.toml"),
+        "[lint]\nunused_variable = \"error\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        tron_lint(&dir, &["--fix", "main.tron"]),
+        (
+            1,
+            "fixed 1 problem in main.tron\n\
+             main.tron:2:9: error[unused_variable] variable `unused` is never used\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("main.tron")).unwrap(),
+        "fn f(): number {\n    let unused: number = 1;\n    return 1;\n}\n@print(f());\n"
+    );
+
+    fs::write(dir.join("tron.toml"), "[lint]\nunused = \"error\"\n").unwrap();
+    assert_eq!(tron_lint(&dir, &["main.tron"]).0, 1);
+}
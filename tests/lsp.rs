@@ -0,0 +1,80 @@
+// drives `tron lsp` with the client messages recorded in each `tests/lsp/*.in` file and compares
+// everything the server sends with the `.out` file next to it; `TRON_BLESS=1` rewrites them
+//
+// After each request the harness waits for its response, and a `{"wait":"<method>"}` line waits
+// for that notification. Paths under the repository are written as `<root>`, and a `didOpen`
+// without `text` sends the contents of the file it names.
+mod common;
+
+use common::{inputs, read_until, transcript, Expected};
+use std::fs;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tron_lang::utils::json::Json;
+use tron_lang::utils::protocol::write_message;
+
+// fills in the text of a `didOpen` from the file its uri names
+fn with_text(line: &str) -> String {
+    let message = Json::parse(line).unwrap();
+    if message.get("method").and_then(Json::as_str) != Some("textDocument/didOpen") {
+        return line.to_string();
+    }
+    let uri = message
+        .get("params")
+        .and_then(|params| params.get("textDocument"))
+        .and_then(|document| document.get("uri"))
+        .and_then(Json::as_str)
+        .unwrap();
+    let text = fs::read_to_string(uri.trim_start_matches("file://")).unwrap();
+    let text = Json::from(text).to_string();
+    line.replacen(
+        r#""version":1"#,
+        &format!(r#""version":1,"text":{}"#, text),
+        1,
+    )
+}
+
+fn run(session: &Path) -> String {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let mut server = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(root)
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = server.stdin.take().unwrap();
+    let mut output = BufReader::new(server.stdout.take().unwrap());
+    let mut messages = vec![];
+    for line in fs::read_to_string(session).unwrap().lines() {
+        let line = with_text(&line.replace("<root>", root));
+        let message = Json::parse(&line).unwrap();
+        if let Some(method) = message.get("wait").cloned() {
+            read_until(&mut output, &mut messages, |message| {
+                message.get("method") == Some(&method)
+            });
+            continue;
+        }
+        write_message(&mut input, &message).unwrap();
+        if let Some(id) = message.get("id").cloned() {
+            read_until(&mut output, &mut messages, |message| {
+                message.get("id") == Some(&id) && message.get("method").is_none()
+            });
+        }
+    }
+    input.flush().unwrap();
+    drop(input);
+    assert!(server.wait().unwrap().success());
+    transcript(&messages)
+}
+
+#[test]
+fn recorded_sessions() {
+    let mut expected = Expected::new();
+    for session in inputs("lsp", "in") {
+        let actual = run(&session);
+        expected.check(session.display(), &session.with_extension("out"), &actual);
+    }
+    expected.assert();
+}
@@ -0,0 +1,27 @@
+// optimizes every program in tests/optimizer and compares the statements it ends up with to the
+// `.out` file next to it, which `TRON_BLESS=1` rewrites; that programs behave the same optimized
+// or not is checked by running tests/conformance both ways
+mod common;
+
+use common::{inputs, Expected};
+use std::fs;
+use tron_lang::ast::{dump, to_json};
+use tron_lang::optimizer::optimize;
+use tron_lang::parser::Parser;
+use tron_lang::scanner::Scanner;
+
+fn optimized(source: &str) -> String {
+    let tokens = Scanner::new(source).scan_tokens().unwrap();
+    let statements = Parser::new(tokens).parse().unwrap();
+    dump(&to_json(&optimize(statements)))
+}
+
+#[test]
+fn optimized_programs() {
+    let mut expected = Expected::new();
+    for program in inputs("optimizer", "tron") {
+        let actual = optimized(&fs::read_to_string(&program).unwrap());
+        expected.check(program.display(), &program.with_extension("out"), &actual);
+    }
+    expected.assert();
+}
@@ -0,0 +1,83 @@
+// limits and capabilities of `tron -e` on both engines: a program that hits one stops with exit
+// code 1 and the E-code of the limit on stderr
+use std::process::Command;
+
+const ENGINES: &[&str] = &["tree", "vm"];
+
+// exit code and stderr
+fn run(engine: &str, flags: &[&str], code: &str) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--no-color", "--engine", engine])
+        .args(flags)
+        .args(["-e", code])
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap_or(-1),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
+
+fn assert_stopped(flags: &[&str], code: &str, error: &str) {
+    for engine in ENGINES {
+        let (status, stderr) = run(engine, flags, code);
+        assert_eq!(status, 1, "--engine={} {:?}: {}", engine, flags, stderr);
+        assert!(
+            stderr.contains(error),
+            "--engine={} {:?}: expected {} in {}",
+            engine,
+            flags,
+            error,
+            stderr
+        );
+    }
+}
+
+const LOOP: &str = "let i: number = 0; while true { i = i + 1; }";
+
+#[test]
+fn sandbox_denies_capabilities() {
+    assert_stopped(&["--sandbox"], r#"@cmd("echo hi");"#, "[E4030] @cmd");
+    assert_stopped(&["--sandbox"], r#"@ask("name?");"#, "[E4030] @ask");
+    assert_stopped(&["--sandbox"], "@args();", "[E4030] @args");
+    assert_stopped(
+        &["--sandbox"],
+        r#"use "tests/conformance/lib/math.tron";"#,
+        "[E4030] use",
+    );
+}
+
+#[test]
+fn try_doesnt_catch_the_sandbox() {
+    assert_stopped(
+        &["--sandbox"],
+        r#"try { @cmd("echo hi"); } catch (e) { @print("caught"); }"#,
+        "[E4030] @cmd",
+    );
+    assert_stopped(
+        &["--max-steps", "100"],
+        "try { let i: number = 0; while true { i = i + 1; } } catch (e) {}",
+        "[E4022]",
+    );
+}
+
+#[test]
+fn allow_grants_capabilities_back() {
+    for engine in ENGINES {
+        let (status, stderr) = run(engine, &["--sandbox", "--allow", "env"], "@args();");
+        assert_eq!(status, 0, "--engine={}: {}", engine, stderr);
+    }
+}
+
+#[test]
+fn limits_stop_the_program() {
+    assert_stopped(&["--max-steps", "100"], LOOP, "[E4022]");
+    assert_stopped(&["--timeout", "200ms"], LOOP, "[E4029]");
+    assert_stopped(&["--timeout", "200ms"], "@sleep(60000);", "[E4029]");
+    assert_stopped(
+        &["--max-memory", "16m"],
+        r#"let text: string = "tron"; while true { text = text + text; }"#,
+        "[E4027]",
+    );
+}
@@ -0,0 +1,104 @@
+// scans sources with non-ASCII text, whose spans are byte offsets that must stay on character
+// boundaries, and a large generated source, which must scan in time linear in its size
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tron_lang::scanner::{LiteralValue, Scanner, Span, Token, TokenType};
+
+fn tokens(source: &str) -> Vec<Token> {
+    Scanner::new(source).scan_tokens().unwrap()
+}
+
+// every token's span slices the source to its lexeme
+fn assert_spans(source: &str, tokens: &[Token]) {
+    for token in tokens {
+        assert_eq!(
+            &source[token.span.start..token.span.end],
+            token.lexeme,
+            "{:?}",
+            token
+        );
+    }
+}
+
+#[test]
+fn non_ascii_identifiers() {
+    let source = "let größe: number = 1; let 名前 = größe;";
+    let tokens = tokens(source);
+    assert_spans(source, &tokens);
+    let identifiers: Vec<_> = tokens
+        .iter()
+        .filter(|token| token.token_type == TokenType::Identifier)
+        .map(|token| (token.lexeme.as_str(), token.span))
+        .collect();
+    assert_eq!(
+        identifiers,
+        [
+            ("größe", Span { start: 4, end: 11 }),
+            ("number", Span { start: 13, end: 19 }),
+            ("名前", Span { start: 29, end: 35 }),
+            ("größe", Span { start: 38, end: 45 }),
+        ]
+    );
+    // the `;` after the last one starts right after its final byte
+    assert_eq!(tokens[tokens.len() - 2].span, Span { start: 45, end: 46 });
+}
+
+#[test]
+fn non_ascii_strings() {
+    let source = "\"héllo, 世界 🚀\" + 'ñ';";
+    let tokens = tokens(source);
+    assert_spans(source, &tokens);
+    assert_eq!(
+        tokens[0].literal,
+        Some(LiteralValue::StringValue("héllo, 世界 🚀".to_string()))
+    );
+    assert_eq!(tokens[0].span, Span { start: 0, end: 21 });
+    assert_eq!(tokens[1].span, Span { start: 22, end: 23 });
+    assert_eq!(
+        tokens[2].literal,
+        Some(LiteralValue::StringValue("ñ".to_string()))
+    );
+    assert_eq!(tokens[2].span, Span { start: 24, end: 28 });
+}
+
+#[test]
+fn non_ascii_comments() {
+    let source = "// ünïcødé ✓\nlet x = 1; // 終わり\nx;";
+    let (tokens, comments) = Scanner::new(source).scan_with_comments().unwrap();
+    assert_spans(source, &tokens);
+    assert_spans(source, &comments);
+    let comments: Vec<_> = comments
+        .iter()
+        .map(|comment| (comment.lexeme.as_str(), comment.line_number))
+        .collect();
+    assert_eq!(comments, [("// ünïcødé ✓", 1), ("// 終わり", 2)]);
+    assert_eq!(tokens[0].span, Span { start: 19, end: 22 });
+    let last = &tokens[tokens.len() - 3];
+    assert_eq!((last.lexeme.as_str(), last.line_number), ("x", 3));
+    // and nothing is lost around them
+    let lossless = Scanner::new(source).scan_lossless().unwrap();
+    let text: String = lossless.iter().map(|token| token.lexeme.as_str()).collect();
+    assert_eq!(text, source);
+}
+
+// scanning 16 times the source may take at most 64 times as long; quadratic scanning, as slicing
+// the source by characters did, takes 256 times as long, and the large scan is given up on rather
+// than waited for
+#[test]
+fn scanning_is_linear() {
+    let line = "let ñame_1: string = \"größe 世界\"; // ✓ comment\n";
+    let scan = |lines: usize| {
+        let source = line.repeat(lines);
+        let start = Instant::now();
+        let tokens = tokens(&source);
+        assert_eq!(tokens.len(), lines * 7 + 1);
+        assert_eq!(tokens[tokens.len() - 1].span.start, source.len());
+        start.elapsed()
+    };
+    let small = scan(250).max(Duration::from_millis(1));
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || sender.send(scan(4_000)));
+    let large = receiver.recv_timeout(small * 64);
+    assert!(large.is_ok(), "{:?}, then over {:?}", small, small * 64);
+}
@@ -0,0 +1,92 @@
+// parses every `.tron` file under `tests/` into a syntax tree and checks that the tree holds the
+// file byte for byte and gives back the statements the parser makes; `tests/syntax/*.tron` are
+// also dumped and compared with the `.out` file next to them, which `TRON_BLESS=1` rewrites
+mod common;
+
+use common::{inputs, Expected};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tron_lang::parser::Parser;
+use tron_lang::scanner::Scanner;
+use tron_lang::syntax::SyntaxTree;
+use tron_lang::utils::collect_errors;
+
+fn sources(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            sources(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "tron") {
+            found.push(path);
+        }
+    }
+    found.sort();
+}
+
+fn all_sources() -> Vec<PathBuf> {
+    let mut found = vec![];
+    sources(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"),
+        &mut found,
+    );
+    found
+}
+
+#[test]
+fn round_trips() {
+    for path in all_sources() {
+        let source = fs::read_to_string(&path).unwrap();
+        // every prefix, so unfinished code is covered too
+        for end in (0..=source.len()).filter(|&end| source.is_char_boundary(end)) {
+            let tree = SyntaxTree::parse(&source[..end]);
+            assert_eq!(
+                tree.root.text(),
+                &source[..end],
+                "{} cut at {}",
+                path.display(),
+                end
+            );
+            let mut offset = 0;
+            for token in tree.root.tokens() {
+                assert_eq!(
+                    token.span.start,
+                    offset,
+                    "{} cut at {}",
+                    path.display(),
+                    end
+                );
+                offset = token.span.end;
+            }
+        }
+    }
+}
+
+#[test]
+fn derives_the_ast() {
+    for path in all_sources() {
+        let source = fs::read_to_string(&path).unwrap();
+        let (expected, _) = collect_errors(|| {
+            let tokens = Scanner::new(&source).scan_tokens().unwrap();
+            Parser::new(tokens).parse().unwrap()
+        });
+        let tree = SyntaxTree::parse(&source);
+        assert_eq!(
+            format!("{:?}", tree.statements()),
+            format!("{:?}", expected),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn trees() {
+    let mut expected = Expected::new();
+    for source in inputs("syntax", "tron") {
+        let actual = SyntaxTree::parse(&fs::read_to_string(&source).unwrap())
+            .root
+            .dump();
+        expected.check(source.display(), &source.with_extension("out"), &actual);
+    }
+    expected.assert();
+}
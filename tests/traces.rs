@@ -0,0 +1,213 @@
+// stack traces of runtime errors, which both engines print the same way
+use std::process::Command;
+
+const ENGINES: &[&str] = &["tree", "vm"];
+
+fn stderr(engine: &str, flags: &[&str], code: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_tron-lang"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--no-color", "--engine", engine])
+        .args(flags)
+        .args(["-e", code])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+fn assert_stderr(flags: &[&str], code: &str, expected: &str) {
+    for engine in ENGINES {
+        assert_eq!(stderr(engine, flags, code), expected, "--engine={}", engine);
+    }
+}
+
+#[test]
+fn traces_name_functions_files_and_lines() {
+    assert_stderr(
+        &[],
+        r#"use "tests/traces/shapes.tron";
+fn total(shapes: array): number {
+    let first: number = area(shapes[0]);
+    return first;
+}
+let square: object = { width: 2 };
+total([square]);"#,
+        "[E4031] object has no key height (line 2)
+  at area (tests/traces/shapes.tron:2)
+  at total (<eval>:3)
+  at <script> (<eval>:7)
+",
+    );
+}
+
+#[test]
+fn traces_include_builtins_and_fold_recursion() {
+    let code = "fn down(n: number): number {
+    if n == 0 { return @length(5); }
+    let length: number = down(n - 1);
+    return length;
+}
+down(3);";
+    assert_stderr(
+        &[],
+        code,
+        "[E4021] @length expects array or string type as first argument
+  at @length (native)
+  at down (<eval>:2)
+  at down (<eval>:3), 3 times
+  at <script> (<eval>:6)
+",
+    );
+    assert_stderr(
+        &["--error-format", "json"],
+        code,
+        concat!(
+            r#"{"code":"E4021","severity":"error","message":"@length expects array or string type as first argument","#,
+            r#""file":"<eval>","line":0,"span":null,"trace":["#,
+            r#"{"function":"@length","file":null,"line":0,"times":1},"#,
+            r#"{"function":"down","file":"<eval>","line":2,"times":1},"#,
+            r#"{"function":"down","file":"<eval>","line":3,"times":3},"#,
+            r#"{"function":"<script>","file":"<eval>","line":6,"times":1}]}"#,
+            "\n"
+        ),
+    );
+}
+
+#[test]
+fn errors_outside_calls_have_no_trace() {
+    assert_stderr(
+        &[],
+        "let point: object = { x: 1 };\n@print(point.y);",
+        "[E4031] object has no key y (line 2)\n",
+    );
+}
+
+#[test]
+fn uncaught_errors_keep_the_trace_of_their_throw() {
+    assert_stderr(
+        &[],
+        r#"fn check(n: number): number {
+    if n < 0 { throw @error("negative", "E_RANGE"); }
+    return n;
+}
+try { check(-1); } finally { @print("cleanup"); }"#,
+        "[E_RANGE] negative (line 2)
+  at check (<eval>:2)
+  at <script> (<eval>:5)
+",
+    );
+    assert_stderr(
+        &[],
+        r#"try { @panic("stop"); } catch (e) { @print("caught"); }"#,
+        "[E4035] panic: \"stop\"
+  at @panic (native)
+  at <script> (<eval>:1)
+",
+    );
+}
+
+#[test]
+fn argument_errors_are_on_the_line_of_the_call() {
+    assert_stderr(
+        &[],
+        r#"fn greet(name: string, greeting: string = "hi"): string = greeting + " " + name;
+greet(nmae: "ada");"#,
+        "[E4024] greet has no parameter named nmae (line 2)
+  at greet (<eval>:2)
+  at <script> (<eval>:2)
+",
+    );
+    assert_stderr(
+        &[],
+        r#"fn greet(name: string, greeting: string = "hi"): string = greeting + " " + name;
+greet("ada",
+    name: "bob");"#,
+        "[E4025] greet got more than one value for name (line 3)
+  at greet (<eval>:3)
+  at <script> (<eval>:3)
+",
+    );
+    assert_stderr(
+        &[],
+        r#"fn greet(name: string, greeting: string = "hi"): string = greeting + " " + name;
+greet(greeting: "hey");"#,
+        "[E4026] greet is missing a value for name (line 2)
+  at greet (<eval>:2)
+  at <script> (<eval>:2)
+",
+    );
+    // after the parse error a positional call can leave the required parameter without a value
+    assert_stderr(
+        &[],
+        "fn add(a: number = 1, b: number): number = a + b;\nadd(5);",
+        "[E2003] unexpected token: parameter b without a default follows one with a default (line 1)
+[E4026] add is missing a value for b (line 2)
+  at add (<eval>:2)
+  at <script> (<eval>:2)
+",
+    );
+    // a wrong number of arguments is found before the call is entered
+    assert_stderr(
+        &[],
+        "fn add(a: number, b: number): number = a + b;\nfn twice(): number {\n    return add(1);\n}\ntwice();",
+        "[E4020] add requires exactly 2 arguments (line 3)
+  at twice (<eval>:3)
+  at <script> (<eval>:5)
+",
+    );
+}
+
+#[test]
+fn operator_and_index_errors_are_on_the_line_of_the_token() {
+    assert_stderr(
+        &[],
+        r#"fn half(x: number): number {
+    @print(x
+        / "two");
+    return 0;
+}
+half(1);"#,
+        "[E4015] number and string is not implemented for Slash (line 3)
+  at half (<eval>:3)
+  at <script> (<eval>:6)
+",
+    );
+    assert_stderr(
+        &[],
+        "fn third(items: array): number {\n    return items[2];\n}\nthird([1]);",
+        "[E4009] array index is out of bounds (line 2)
+  at third (<eval>:2)
+  at <script> (<eval>:4)
+",
+    );
+}
+
+#[test]
+fn output_type_errors_are_on_the_line_of_the_return() {
+    assert_stderr(
+        &[],
+        r#"fn label(n: number): string {
+    if n > 0 {
+        return n;
+    }
+    return "none";
+}
+@print(label(1));"#,
+        "[E4017] invalid function output type (line 3)
+  at label (<eval>:3)
+  at <script> (<eval>:7)
+",
+    );
+    // falling off the end of the body is about the declaration, and a tail call reports the
+    // function it ran
+    assert_stderr(
+        &[],
+        "fn nothing(): number {\n    @length(\"\");\n}\nfn name(): string {\n    return other();\n}\nfn other(): string = 1;\nnothing();\nname();",
+        "[E4017] invalid function output type (line 1)
+  at nothing (<eval>:1)
+  at <script> (<eval>:8)
+[E4017] invalid function output type (line 7)
+  at other (<eval>:7)
+  at <script> (<eval>:9)
+",
+    );
+}
@@ -1,65 +0,0 @@
-"use strict";
-var __createBinding = (this && this.__createBinding) || (Object.create ? (function(o, m, k, k2) {
-    if (k2 === undefined) k2 = k;
-    var desc = Object.getOwnPropertyDescriptor(m, k);
-    if (!desc || ("get" in desc ? !m.__esModule : desc.writable || desc.configurable)) {
-      desc = { enumerable: true, get: function() { return m[k]; } };
-    }
-    Object.defineProperty(o, k2, desc);
-}) : (function(o, m, k, k2) {
-    if (k2 === undefined) k2 = k;
-    o[k2] = m[k];
-}));
-var __setModuleDefault = (this && this.__setModuleDefault) || (Object.create ? (function(o, v) {
-    Object.defineProperty(o, "default", { enumerable: true, value: v });
-}) : function(o, v) {
-    o["default"] = v;
-});
-var __importStar = (this && this.__importStar) || function (mod) {
-    if (mod && mod.__esModule) return mod;
-    var result = {};
-    if (mod != null) for (var k in mod) if (k !== "default" && Object.prototype.hasOwnProperty.call(mod, k)) __createBinding(result, mod, k);
-    __setModuleDefault(result, mod);
-    return result;
-};
-Object.defineProperty(exports, "__esModule", { value: true });
-exports.deactivate = exports.activate = void 0;
-const path = __importStar(require("path"));
-const vscode_1 = require("vscode");
-const node_1 = require("vscode-languageclient/node");
-let client;
-function activate(context) {
-    const serverModule = context.asAbsolutePath(path.join("server", "out", "server.js"));
-    const serverOptions = {
-        run: { module: serverModule, transport: node_1.TransportKind.ipc },
-        debug: {
-            module: serverModule,
-            transport: node_1.TransportKind.ipc,
-        },
-    };
-    const clientOptions = {
-        documentSelector: [{ scheme: "file", language: "tron" }],
-        synchronize: {
-            fileEvents: vscode_1.workspace.createFileSystemWatcher("**/.clientrc"),
-        },
-    };
-    client = new node_1.LanguageClient("languageServerExample", "Language Server Example", serverOptions, clientOptions);
-    client.start().then(() => {
-        context.subscriptions.push(vscode_1.languages.registerDocumentFormattingEditProvider("tron", {
-            provideDocumentFormattingEdits(document, options, token) {
-                return client.sendRequest("textDocument/formatting", {
-                    textDocument: { uri: document.uri.toString() },
-                    options: options,
-                }, token);
-            },
-        }));
-    });
-}
-exports.activate = activate;
-function deactivate() {
-    if (!client) {
-        return undefined;
-    }
-    return client.stop();
-}
-exports.deactivate = deactivate;
@@ -1,68 +1,34 @@
-import * as path from "path";
 import {
   workspace,
+  debug,
+  DebugAdapterExecutable,
   ExtensionContext,
-  languages,
-  DocumentFormattingEditProvider,
-  TextDocument,
-  FormattingOptions,
-  CancellationToken,
-  ProviderResult,
-  TextEdit,
 } from "vscode";
 import {
   LanguageClient,
   LanguageClientOptions,
   ServerOptions,
-  TransportKind,
 } from "vscode-languageclient/node";
 
 let client: LanguageClient;
 export function activate(context: ExtensionContext) {
-  const serverModule = context.asAbsolutePath(
-    path.join("server", "out", "server.js")
+  const tron = workspace.getConfiguration("tron").get<string>("path", "tron");
+  // programs are debugged by `tron dap`, which speaks the Debug Adapter Protocol over stdio
+  context.subscriptions.push(
+    debug.registerDebugAdapterDescriptorFactory("tron", {
+      createDebugAdapterDescriptor() {
+        return new DebugAdapterExecutable(tron, ["dap"]);
+      },
+    })
   );
-  const serverOptions: ServerOptions = {
-    run: { module: serverModule, transport: TransportKind.ipc },
-    debug: {
-      module: serverModule,
-      transport: TransportKind.ipc,
-    },
-  };
-
+  // and code is checked by `tron lsp`, which speaks the Language Server Protocol over stdio
+  const serverOptions: ServerOptions = { command: tron, args: ["lsp"] };
   const clientOptions: LanguageClientOptions = {
     documentSelector: [{ scheme: "file", language: "tron" }],
-    synchronize: {
-      fileEvents: workspace.createFileSystemWatcher("**/.clientrc"),
-    },
   };
 
-  client = new LanguageClient(
-    "languageServerExample",
-    "Language Server Example",
-    serverOptions,
-    clientOptions
-  );
-  client.start().then(() => {
-    context.subscriptions.push(
-      languages.registerDocumentFormattingEditProvider("tron", {
-        provideDocumentFormattingEdits(
-          document: TextDocument,
-          options: FormattingOptions,
-          token: CancellationToken
-        ): ProviderResult<TextEdit[]> {
-          return client.sendRequest(
-            "textDocument/formatting",
-            {
-              textDocument: { uri: document.uri.toString() },
-              options: options,
-            },
-            token
-          );
-        },
-      })
-    );
-  });
+  client = new LanguageClient("tron", "Tron", serverOptions, clientOptions);
+  client.start();
 }
 
 export function deactivate(): Thenable<void> | undefined {